@@ -0,0 +1,51 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::env;
+
+fn main() {
+    // libthemis-sys detects Themis version and tells us which APIs are available.
+    let split = |var: &str| -> Vec<String> {
+        env::var(var)
+            .unwrap_or_default()
+            .split(',')
+            .filter(|cfg| !cfg.is_empty())
+            .map(String::from)
+            .collect()
+    };
+    for cfg in split("DEP_THEMIS_KNOWN_CFGS") {
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+    }
+    let cfgs = split("DEP_THEMIS_CFGS");
+    for cfg in &cfgs {
+        println!("cargo:rustc-cfg={}", cfg);
+    }
+
+    // APIs which are requested explicitly must be available, fail early if they are not.
+    let has_cfg = |name: &str| cfgs.iter().any(|cfg| cfg == name);
+    if env::var_os("CARGO_FEATURE_PASSPHRASE").is_some()
+        && !has_cfg("themis_secure_cell_passphrase")
+    {
+        panic!(
+            "
+
+`passphrase` feature requires Secure Cell passphrase API, but Themis
+library in your system is too old and does not provide it. Please upgrade
+Themis to 0.13 or newer, or use `vendored` feature to build it from source
+(set THEMIS_SRC_VERSION=0.13.0).
+
+"
+        );
+    }
+}
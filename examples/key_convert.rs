@@ -0,0 +1,88 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process;
+
+use clap::clap_app;
+use themis::keys::convert::{self, KeyFormat};
+
+fn main() {
+    let matches = clap_app!(key_convert =>
+        (version: env!("CARGO_PKG_VERSION"))
+        (about: "Converting Themis keys between formats.")
+        (@arg from: --from [format] "Input format: raw, base64, pem, der (default: detect)")
+        (@arg to: --to <format> "Output format: raw, base64, pem, der")
+        (@arg input: "Input file (default: stdin)")
+        (@arg output: "Output file (default: stdout)")
+    )
+    .get_matches();
+
+    let from = matches.value_of("from").map(parse_format);
+    let to = parse_format(matches.value_of("to").unwrap());
+
+    let input = match matches.value_of("input") {
+        Some(path) => read_file(path),
+        None => read_stdin(),
+    };
+    let input = input.unwrap_or_else(|e| fail(&format!("failed to read input: {}", e)));
+
+    let key = match from {
+        Some(format) => convert::decode(&input, format),
+        None => convert::detect(&input).map(|(format, key)| {
+            eprintln!("detected {} {:?} key", format, key.kind());
+            key
+        }),
+    };
+    let key = key.unwrap_or_else(|e| fail(&format!("failed to decode key: {}", e)));
+
+    let output =
+        convert::encode(&key, to).unwrap_or_else(|e| fail(&format!("failed to encode key: {}", e)));
+
+    let result = match matches.value_of("output") {
+        Some(path) => write_file(path, &output),
+        None => io::stdout().write_all(&output),
+    };
+    result.unwrap_or_else(|e| fail(&format!("failed to write output: {}", e)));
+}
+
+fn parse_format(name: &str) -> KeyFormat {
+    name.parse()
+        .unwrap_or_else(|_| fail(&format!("unknown key format: {}", name)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn read_stdin() -> Result<Vec<u8>, io::Error> {
+    let mut content = Vec::new();
+    io::stdin().read_to_end(&mut content)?;
+    Ok(content)
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, io::Error> {
+    let mut file = File::open(path)?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    Ok(content)
+}
+
+fn write_file(path: &str, data: &[u8]) -> Result<(), io::Error> {
+    let mut file = File::create(path)?;
+    file.write_all(data)?;
+    Ok(())
+}
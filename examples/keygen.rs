@@ -12,37 +12,48 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::fs::File;
-use std::io::{self, Write};
+use std::process;
 
 use clap::clap_app;
-use themis::keygen::gen_ec_key_pair;
+use themis::keygen::{gen_ec_key_pair, gen_rsa_key_pair_with_size, RsaKeySize};
+use themis::keys::KeyPair;
 
 fn main() {
     let matches = clap_app!(keygen =>
         (version: env!("CARGO_PKG_VERSION"))
-        (about: "Generating ECDSA key pairs.")
+        (about: "Generating ECDSA and RSA key pairs.")
         (@arg secret: --secret [path] "Secret key file (default: secret.key)")
         (@arg public: --public [path] "Public key file (default: public.key)")
+        (@arg rsa: --rsa "Generate RSA keys instead of ECDSA")
+        (@arg bits: --bits [number] requires[rsa] "RSA key length in bits (default: 2048)")
     )
     .get_matches();
     let secret_path = matches.value_of("secret").unwrap_or("secret.key");
     let public_path = matches.value_of("public").unwrap_or("public.key");
 
-    let (secret_key, public_key) = gen_ec_key_pair().split();
+    let key_pair = if matches.is_present("rsa") {
+        let size = match matches.value_of("bits").unwrap_or("2048") {
+            "1024" => RsaKeySize::Bits1024,
+            "2048" => RsaKeySize::Bits2048,
+            "4096" => RsaKeySize::Bits4096,
+            "8192" => RsaKeySize::Bits8192,
+            other => {
+                eprintln!("unsupported RSA key length: {}", other);
+                process::exit(1);
+            }
+        };
+        KeyPair::from(gen_rsa_key_pair_with_size(size))
+    } else {
+        KeyPair::from(gen_ec_key_pair())
+    };
+    let (secret_key, public_key) = key_pair.split();
 
-    match write_file(&secret_key, &secret_path) {
+    match secret_key.write_to_file(secret_path) {
         Ok(_) => eprintln!("wrote secret key to {}", secret_path),
         Err(e) => eprintln!("failed to write secret key to {}: {}", secret_path, e),
     }
-    match write_file(&public_key, &public_path) {
+    match public_key.write_to_file(public_path) {
         Ok(_) => eprintln!("wrote public key to {}", public_path),
         Err(e) => eprintln!("failed to write public key to {}: {}", public_path, e),
     }
 }
-
-fn write_file<K: AsRef<[u8]>>(key: K, path: &str) -> io::Result<()> {
-    let mut file = File::create(path)?;
-    file.write_all(key.as_ref())?;
-    Ok(())
-}
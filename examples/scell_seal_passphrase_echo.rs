@@ -0,0 +1,60 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// This tool is compatible with `scell_seal_string_echo_pw` tools used in integration tests
+// of other Themis wrappers: it accepts the same arguments and produces the same output.
+
+use std::env;
+use std::process;
+
+use themis::secure_cell::SecureCell;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 4 && args.len() != 5 {
+        eprintln!(
+            "Usage: {} <command: enc | dec> <passphrase> <message> <context (optional)>",
+            args[0]
+        );
+        process::exit(1);
+    }
+
+    let command = &args[1];
+    let passphrase = &args[2];
+    let message = &args[3];
+    let context = args.get(4).map(String::as_bytes).unwrap_or_default();
+
+    let cell = SecureCell::with_passphrase_and_context(passphrase, context).seal();
+
+    match command.as_str() {
+        "enc" => {
+            let encrypted = cell.encrypt(message).unwrap_or_else(|e| fail(e));
+            println!("{}", base64::encode(&encrypted));
+        }
+        "dec" => {
+            let encrypted = base64::decode(message).unwrap_or_else(|e| fail(e));
+            let decrypted = cell.decrypt(&encrypted).unwrap_or_else(|e| fail(e));
+            println!("{}", String::from_utf8_lossy(&decrypted));
+        }
+        _ => {
+            eprintln!("Wrong command, use <enc | dec>");
+            process::exit(1);
+        }
+    }
+}
+
+fn fail<E: std::fmt::Display>(error: E) -> ! {
+    eprintln!("{}", error);
+    process::exit(1);
+}
@@ -0,0 +1,95 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{self, BufRead, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process;
+
+use clap::clap_app;
+use themis::secure_comparator::{Role, SecureComparator};
+use zeroize::Zeroize;
+
+// Exit codes which can be checked by scripts.
+const EXIT_MATCH: i32 = 0;
+const EXIT_NO_MATCH: i32 = 1;
+const EXIT_FAILURE: i32 = 2;
+
+fn main() {
+    let matches = clap_app!(scomparator =>
+        (version: env!("CARGO_PKG_VERSION"))
+        (about: "Secure Comparator over TCP.")
+        (after_help:
+            "Exits with status 0 if the secrets match, 1 if they do not, and 2 on errors.\n\
+             The secret is read from the terminal if it is not given on command line.\n\
+             Messages are framed with 4-byte little-endian length prefix.")
+        (@group mode +required =>
+            (@arg listen: -l --listen [address] "Wait for the peer at this address")
+            (@arg connect: -c --connect [address] "Connect to the peer at this address")
+        )
+        (@arg secret: -s --secret [string] "Secret to compare (default: read from terminal)")
+    )
+    .get_matches();
+
+    let mut secret = match matches.value_of("secret") {
+        Some(secret) => secret.to_owned(),
+        None => prompt_secret().unwrap_or_else(|e| fail("failed to read secret", e)),
+    };
+
+    let mut comparison = SecureComparator::new();
+    comparison
+        .append_secret(&secret)
+        .unwrap_or_else(|e| fail("failed to append secret", e));
+    secret.zeroize();
+
+    let result = if let Some(address) = matches.value_of("listen") {
+        let listener = TcpListener::bind(address).unwrap_or_else(|e| fail("failed to listen", e));
+        let (mut peer, _) = listener
+            .accept()
+            .unwrap_or_else(|e| fail("failed to accept", e));
+        comparison.compare_over(&mut peer, Role::Server)
+    } else {
+        let address = matches.value_of("connect").unwrap();
+        let mut peer = TcpStream::connect(address).unwrap_or_else(|e| fail("failed to connect", e));
+        comparison.compare_over(&mut peer, Role::Client)
+    };
+
+    match result {
+        Ok(true) => {
+            println!("[+] match OK");
+            process::exit(EXIT_MATCH);
+        }
+        Ok(false) => {
+            println!("[-] no match");
+            process::exit(EXIT_NO_MATCH);
+        }
+        Err(e) => fail("comparison failed", e),
+    }
+}
+
+fn prompt_secret() -> io::Result<String> {
+    eprint!("Secret: ");
+    io::stderr().flush()?;
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    // Remove the line terminator, but keep any other whitespace which may be significant.
+    while line.ends_with('\n') || line.ends_with('\r') {
+        line.pop();
+    }
+    Ok(line)
+}
+
+fn fail<E: std::fmt::Display>(message: &str, error: E) -> ! {
+    eprintln!("{}: {}", message, error);
+    process::exit(EXIT_FAILURE);
+}
@@ -12,12 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::io::{self, Read, Write};
+use std::io::{self, Read};
 use std::net::{TcpListener, TcpStream};
 
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use clap::clap_app;
-use themis::secure_comparator::SecureComparator;
+use themis::secure_comparator::{Role, SecureComparator};
+use zeroize::Zeroize;
 
 fn main() {
     let matches = clap_app!(secure_compare =>
@@ -51,21 +51,16 @@ fn main() {
             .append_secret(&buffer[..bytes])
             .expect("append secret");
     }
+    buffer.zeroize();
 
-    if let Some(matches) = matches.subcommand_matches("client") {
+    let result = if let Some(matches) = matches.subcommand_matches("client") {
         let address = matches.value_of("address").unwrap_or("[::1]:7575");
         let mut server = TcpStream::connect(address).expect("client connect");
 
-        let mut request = comparison.begin_compare().expect("begin");
-
-        while !comparison.is_complete() {
-            send_msg(&request, &mut server).expect("send");
-            let reply = receive_msg(&mut server).expect("receive");
-            request = comparison.proceed_compare(&reply).expect("proceed");
-        }
-    }
-
-    if let Some(matches) = matches.subcommand_matches("server") {
+        comparison
+            .compare_over(&mut server, Role::Client)
+            .expect("compare")
+    } else if let Some(matches) = matches.subcommand_matches("server") {
         let port = matches
             .value_of("port")
             .unwrap_or("7575")
@@ -76,37 +71,17 @@ fn main() {
             .accept()
             .expect("client accept");
 
-        while !comparison.is_complete() {
-            let request = receive_msg(&mut client).expect("receive");
-            let reply = comparison.proceed_compare(&request).expect("proceed");
-            send_msg(&reply, &mut client).expect("send");
-        }
-    }
+        comparison
+            .compare_over(&mut client, Role::Server)
+            .expect("compare")
+    } else {
+        eprintln!("{}", matches.usage());
+        return;
+    };
 
-    if comparison.get_result().expect("result") {
+    if result {
         println!("[+] match OK");
     } else {
         println!("[-] no match");
     }
 }
-
-// TCP is a streaming protocol so we'll need to frame our messages. We use a simple approach:
-// prefix each message with its length in bytes serialized as a 4-byte value in little endian.
-
-fn send_msg(message: &[u8], peer: &mut TcpStream) -> io::Result<()> {
-    if message.len() > u32::max_value() as usize {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "too big message",
-        ));
-    }
-    peer.write_u32::<LittleEndian>(message.len() as u32)?;
-    peer.write_all(&message)
-}
-
-fn receive_msg(peer: &mut TcpStream) -> io::Result<Vec<u8>> {
-    let len = peer.read_u32::<LittleEndian>()?;
-    let mut msg = vec![0; len as usize];
-    peer.read_exact(&mut msg)?;
-    Ok(msg)
-}
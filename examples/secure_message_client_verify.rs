@@ -15,8 +15,7 @@
 #[macro_use]
 extern crate log;
 
-use std::fs::File;
-use std::io::{self, Read, Write};
+use std::io::{self, Write};
 use std::net::UdpSocket;
 use std::thread;
 
@@ -40,10 +39,8 @@ fn main() {
     let public_path = matches.value_of("public").unwrap_or("public.key");
     let remote_addr = matches.value_of("address").unwrap_or("localhost:7573");
 
-    let secret_key = read_file(&secret_path).expect("read secret key");
-    let secret_key = SecretKey::try_from_slice(secret_key).expect("parse secret key");
-    let public_key = read_file(&public_path).expect("read public key");
-    let public_key = PublicKey::try_from_slice(public_key).expect("parse public key");
+    let secret_key = SecretKey::read_from_file(secret_path).expect("read secret key");
+    let public_key = PublicKey::read_from_file(public_path).expect("read public key");
 
     let socket = UdpSocket::bind("localhost:0").expect("client socket");
     socket.connect(&remote_addr).expect("client connection");
@@ -59,7 +56,7 @@ fn main() {
     let receive = thread::spawn(move || {
         let receive_message = || -> io::Result<()> {
             let buffer = recv(&receive_socket)?;
-            let message = receive_secure.verify(&buffer).map_err(themis_as_io_error)?;
+            let message = receive_secure.verify(&buffer)?;
             io::stdout().write_all(&message)?;
             Ok(())
         };
@@ -75,7 +72,7 @@ fn main() {
         let relay_message = || -> io::Result<()> {
             let mut buffer = String::new();
             io::stdin().read_line(&mut buffer)?;
-            let message = relay_secure.sign(&buffer).map_err(themis_as_io_error)?;
+            let message = relay_secure.sign(&buffer)?;
             relay_socket.send(&message)?;
             Ok(())
         };
@@ -91,20 +88,9 @@ fn main() {
     relay.join().unwrap();
 }
 
-fn read_file(path: &str) -> io::Result<Vec<u8>> {
-    let mut file = File::open(path)?;
-    let mut content = Vec::new();
-    file.read_to_end(&mut content)?;
-    Ok(content)
-}
-
 fn recv(socket: &UdpSocket) -> io::Result<Vec<u8>> {
     let mut message = vec![0; 65536];
     let length = socket.recv(&mut message)?;
     message.truncate(length);
     Ok(message)
 }
-
-fn themis_as_io_error(e: themis::Error) -> io::Error {
-    io::Error::new(io::ErrorKind::Other, e)
-}
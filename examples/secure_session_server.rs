@@ -0,0 +1,162 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[macro_use]
+extern crate log;
+
+use std::io::{self, BufRead, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+
+use clap::clap_app;
+use themis::keys::{EcdsaPublicKey, EcdsaSecretKey};
+use themis::secure_session::{
+    read_frame, write_frame, SecureSession, StaticKeyResolver, TcpTransport,
+};
+
+fn main() {
+    env_logger::init();
+
+    let matches = clap_app!(secure_session_server =>
+        (version: env!("CARGO_PKG_VERSION"))
+        (about: "Secure Session chat server.")
+        (@arg id: --id [id] "Server ID (default: server)")
+        (@arg secret: --secret [path] "Server secret key file (default: server.key)")
+        (@arg peer_id: --("peer-id") [id] "Client ID (default: client)")
+        (@arg peer_public: --("peer-public") [path] "Client public key file (default: client.pub)")
+        (@arg port: -p --port [number] "Listening port (default: 7575)")
+    )
+    .get_matches();
+
+    let id = matches.value_of("id").unwrap_or("server");
+    let secret_path = matches.value_of("secret").unwrap_or("server.key");
+    let peer_id = matches.value_of("peer_id").unwrap_or("client");
+    let peer_public_path = matches.value_of("peer_public").unwrap_or("client.pub");
+    let port = matches.value_of("port").unwrap_or("7575").parse().unwrap();
+    let listen_addr = SocketAddr::new([0; 16].into(), port);
+
+    let secret_key = EcdsaSecretKey::read_from_file(secret_path).expect("read secret key");
+    let peer_public_key =
+        EcdsaPublicKey::read_from_file(peer_public_path).expect("read peer public key");
+
+    // Secure Session asks the transport for public keys of the peers which it talks to.
+    // The server knows only one client, other clients will fail to connect.
+    let mut keys = StaticKeyResolver::new();
+    keys.insert(peer_id, peer_public_key);
+
+    let listener = TcpListener::bind(listen_addr).expect("server listen");
+    info!("listening on port {}", port);
+
+    // Keep it simple and chat with only one client.
+    let (stream, remote_addr) = listener.accept().expect("accept connection");
+    info!("new peer: {}", remote_addr);
+
+    let receive_stream = stream.try_clone().unwrap();
+    let transport = TcpTransport::new(stream, keys);
+
+    let mut session =
+        SecureSession::with_transport(id, &secret_key, transport).expect("Secure Session");
+
+    // The server waits for the client to initiate the connection, then both peers exchange
+    // messages until the session is established.
+    while !session.is_established() {
+        session.negotiate_transport().expect("negotiate");
+    }
+    info!("established session with {}", peer_id);
+
+    chat(session, receive_stream, peer_id);
+}
+
+enum Event {
+    Input(String),
+    EndOfInput,
+    Message(io::Result<Vec<u8>>),
+}
+
+fn chat(mut session: SecureSession<TcpTransport>, mut receive_stream: TcpStream, peer_id: &str) {
+    // SecureSession objects are stateful and cannot be shared between threads. Let the main
+    // thread own the session while helper threads read the standard input and the socket.
+    let (events, main_loop) = mpsc::channel();
+
+    let input_events = events.clone();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            match line {
+                Ok(line) => {
+                    if input_events.send(Event::Input(line)).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    error!("failed to read input: {}", e);
+                    break;
+                }
+            }
+        }
+        let _ = input_events.send(Event::EndOfInput);
+    });
+
+    let message_events = events;
+    thread::spawn(move || loop {
+        let message = read_frame(&mut receive_stream);
+        let failed = message.is_err();
+        if message_events.send(Event::Message(message)).is_err() || failed {
+            break;
+        }
+    });
+
+    for event in main_loop {
+        let process_event = || -> io::Result<bool> {
+            match event {
+                // SecureSession::send() cannot tell short messages from errors, so wrap
+                // the messages and write them into the stream explicitly.
+                Event::Input(line) => {
+                    if !line.is_empty() {
+                        let wrapped = session.wrap(&line)?;
+                        let stream = session.transport_mut().get_mut();
+                        write_frame(stream, &wrapped)?;
+                    }
+                }
+                // Closing the connection will make the peer disconnect as well.
+                Event::EndOfInput => {
+                    let stream = session.transport().get_ref();
+                    stream.shutdown(Shutdown::Both)?;
+                    return Ok(false);
+                }
+                Event::Message(wrapped) => {
+                    let message = session.unwrap(&wrapped?)?;
+                    let mut stdout = io::stdout();
+                    write!(stdout, "{}: ", peer_id)?;
+                    stdout.write_all(&message)?;
+                    writeln!(stdout)?;
+                }
+            }
+            Ok(true)
+        };
+        match process_event() {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                info!("{} disconnected", peer_id);
+                break;
+            }
+            Err(e) => {
+                error!("failed to process message: {}", e);
+                break;
+            }
+        }
+    }
+}
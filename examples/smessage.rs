@@ -0,0 +1,127 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process;
+
+use clap::clap_app;
+use themis::keys::{KeyPair, PublicKey, SecretKey};
+use themis::secure_message::{SecureMessage, SecureSign, SecureVerify};
+
+fn main() {
+    let matches = clap_app!(smessage =>
+        (version: env!("CARGO_PKG_VERSION"))
+        (about: "Processing files with Secure Message.")
+        (@group mode +required =>
+            (@arg sign: --sign "Sign the input (needs --secret)")
+            (@arg verify: --verify "Verify signed input (needs --public)")
+            (@arg encrypt: --encrypt "Encrypt the input (needs --secret and --public)")
+            (@arg decrypt: --decrypt "Decrypt the input (needs --secret and --public)")
+        )
+        (@arg secret: --secret [path] "Own secret key file")
+        (@arg public: --public [path] "Peer public key file")
+        (@arg binary: --binary "Do not use base64 for signed and encrypted messages")
+        (@arg input: "Input file (default: standard input)")
+        (@arg output: "Output file (default: standard output)")
+    )
+    .get_matches();
+
+    let secret_path = matches.value_of("secret");
+    let public_path = matches.value_of("public");
+    let input_path = matches.value_of("input").unwrap_or("-");
+    let output_path = matches.value_of("output").unwrap_or("-");
+    let base64 = !matches.is_present("binary");
+
+    let secret_key = || -> SecretKey {
+        let path = secret_path.unwrap_or_else(|| fail("--secret key is required"));
+        SecretKey::read_from_file(path)
+            .unwrap_or_else(|e| fail(&format!("failed to read secret key {}: {}", path, e)))
+    };
+    let public_key = || -> PublicKey {
+        let path = public_path.unwrap_or_else(|| fail("--public key is required"));
+        PublicKey::read_from_file(path)
+            .unwrap_or_else(|e| fail(&format!("failed to read public key {}: {}", path, e)))
+    };
+
+    let input = read_input(input_path)
+        .unwrap_or_else(|e| fail(&format!("failed to read {}: {}", input_path, e)));
+
+    // Signed and encrypted messages are binary. Use base64 for them by default so that
+    // the messages can be easily copied around and compared with other Themis wrappers.
+    let output = if matches.is_present("sign") || matches.is_present("encrypt") {
+        let result = if matches.is_present("sign") {
+            SecureSign::new(secret_key()).sign(&input)
+        } else {
+            secure_message(secret_key(), public_key()).wrap(&input)
+        };
+        let message = result.unwrap_or_else(|e| fail(&format!("failed to process: {}", e)));
+        if base64 {
+            let mut encoded = base64::encode(&message).into_bytes();
+            encoded.push(b'\n');
+            encoded
+        } else {
+            message
+        }
+    } else {
+        let message = if base64 {
+            let encoded: Vec<u8> = input
+                .into_iter()
+                .filter(|b| !b.is_ascii_whitespace())
+                .collect();
+            base64::decode(&encoded)
+                .unwrap_or_else(|e| fail(&format!("invalid base64 input: {}", e)))
+        } else {
+            input
+        };
+        let result = if matches.is_present("verify") {
+            SecureVerify::new(public_key()).verify(&message)
+        } else {
+            secure_message(secret_key(), public_key()).unwrap(&message)
+        };
+        result.unwrap_or_else(|e| fail(&format!("failed to process: {}", e)))
+    };
+
+    write_output(output_path, &output)
+        .unwrap_or_else(|e| fail(&format!("failed to write {}: {}", output_path, e)));
+}
+
+fn secure_message(secret_key: SecretKey, public_key: PublicKey) -> SecureMessage {
+    let key_pair = KeyPair::try_join(secret_key, public_key)
+        .unwrap_or_else(|e| fail(&format!("keys cannot be used together: {}", e)));
+    SecureMessage::new(key_pair)
+}
+
+fn read_input(path: &str) -> io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    if path == "-" {
+        io::stdin().read_to_end(&mut content)?;
+    } else {
+        File::open(path)?.read_to_end(&mut content)?;
+    }
+    Ok(content)
+}
+
+fn write_output(path: &str, data: &[u8]) -> io::Result<()> {
+    if path == "-" {
+        io::stdout().write_all(data)
+    } else {
+        File::create(path)?.write_all(data)
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
@@ -25,10 +25,54 @@
 //!   - GNU Make
 //!   - OpenSSL, LibreSSL, or BoringSSL
 //!
+//! By default Themis is built with OpenSSL or LibreSSL installed in the system. BoringSSL engine
+//! can be selected by setting `ENGINE=boringssl` environment variable. If `ENGINE_INCLUDE_PATH`
+//! and `ENGINE_LIB_PATH` are not set then BoringSSL is built from source as well, which
+//! additionally requires CMake and Go. Use OpenSSL or prebuilt BoringSSL if you cannot install
+//! Go on your build machine.
+//!
 //! Please refer to [the official documentation][docs] on installing and configuring dependencies.
 //!
 //! [docs]: https://github.com/cossacklabs/themis/wiki/Building-and-installing
 //!
+//! # Source code
+//!
+//! Themis source code is normally bundled with this crate, including everything needed to build
+//! embedded BoringSSL, so no network or git access is needed (e.g., with `cargo vendor`).
+//! If the source code is missing, the pinned Themis
+//! release is downloaded with `curl` (or `wget`) and verified before use, unless Cargo is in
+//! offline mode. Set `THEMIS_SRC_URL` to download it from a mirror, and `THEMIS_SRC_SHA256` to
+//! the expected SHA-256 checksum.
+//!
+//! Themis 0.10.0 is bundled. Other Themis releases (0.11, 0.12, 0.13) can be selected with
+//! [`Build::version`] or `THEMIS_SRC_VERSION` environment variable, they are downloaded.
+//!
+//! [`Build::version`]: struct.Build.html#method.version
+//!
+//! # Cross-compilation
+//!
+//! When the target differs from the host (e.g., when building for Android), the C compiler and
+//! archiver for the target are looked up in `CC_<target>` and `AR_<target>` environment
+//! variables (or `TARGET_CC` and `TARGET_AR`), the same way as the `cc` crate does it. For
+//! Android these should point to the NDK toolchain, like `aarch64-linux-android21-clang` and
+//! `llvm-ar`. If the compiler is not set explicitly, it is resolved by the `cc` crate so that
+//! host compiler is never used for the target. `SYSROOT_<target>` (or `TARGET_SYSROOT`) can be
+//! set to pass `--sysroot` to the compiler. Crypto library built for the target must be provided
+//! via `ENGINE`, `ENGINE_INCLUDE_PATH` and `ENGINE_LIB_PATH` environment variables understood
+//! by Themis build. If embedded BoringSSL is used, it is built with a CMake toolchain file
+//! describing the same compiler, archiver (`AR_<target>`, `RANLIB_<target>`), and sysroot.
+//!
+//! iOS and iOS simulator targets use the SDK and compiler provided by Xcode. Only static
+//! libraries are built for them. Minimum supported iOS version can be set with
+//! `IPHONEOS_DEPLOYMENT_TARGET` environment variable.
+//!
+//! Only static libraries are built for musl targets as well. Use `musl-gcc` as the compiler
+//! and make sure that the crypto library is built with musl too.
+//!
+//! `wasm32-unknown-emscripten` target is built with `emcc` and `emar` from Emscripten SDK,
+//! producing static libraries with WebAssembly code. Crypto library compiled with Emscripten
+//! must be provided via `ENGINE_INCLUDE_PATH` and `ENGINE_LIB_PATH` as usual.
+//!
 //! # Examples
 //!
 //! Typical usage from a `*-sys` crate looks like this:
@@ -44,8 +88,12 @@
 //! }
 //! ```
 
+use std::collections::hash_map::DefaultHasher;
 use std::env;
+use std::ffi::OsStr;
 use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
@@ -55,6 +103,52 @@ use std::process::{Command, Stdio};
 #[derive(Default)]
 pub struct Build {
     out_dir: Option<PathBuf>,
+    cflags: Vec<String>,
+    make_vars: Vec<(String, String)>,
+    symbol_prefix: Option<SymbolPrefix>,
+    use_cmake: bool,
+    version: Option<String>,
+    sanitizer: Option<Sanitizer>,
+    run_tests: bool,
+}
+
+/// Sanitizers which Themis can be instrumented with, see [`Build::sanitizer`].
+///
+/// [`Build::sanitizer`]: struct.Build.html#method.sanitizer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sanitizer {
+    /// AddressSanitizer, detects out-of-bounds accesses, use-after-free, and other memory errors.
+    Address,
+    /// UndefinedBehaviorSanitizer, detects undefined behavior like integer overflows.
+    Undefined,
+}
+
+impl Sanitizer {
+    fn from_env() -> Option<Sanitizer> {
+        match env::var("THEMIS_SANITIZER").ok()?.as_str() {
+            "address" => Some(Sanitizer::Address),
+            "undefined" => Some(Sanitizer::Undefined),
+            "" => None,
+            other => panic!(
+                "unknown THEMIS_SANITIZER \"{}\", expected \"address\" or \"undefined\"",
+                other
+            ),
+        }
+    }
+
+    fn flags(self) -> &'static [&'static str] {
+        match self {
+            Sanitizer::Address => &["-fsanitize=address", "-fno-omit-frame-pointer"],
+            Sanitizer::Undefined => &["-fsanitize=undefined"],
+        }
+    }
+}
+
+/// Symbol prefix for embedded BoringSSL.
+#[derive(Clone)]
+struct SymbolPrefix {
+    prefix: String,
+    symbols: PathBuf,
 }
 
 /// Installed Themis library resulting from a [`Build`].
@@ -70,7 +164,7 @@ pub fn make() {
 }
 
 /// Verifies binary dependencies of Themis build. Panics if dependencies are not satisfied.
-fn check_dependencies() {
+fn check_dependencies(cc: &str) {
     fn fails_to_run(terms: &[&str]) -> bool {
         Command::new(&terms[0])
             .args(&terms[1..])
@@ -80,7 +174,14 @@ fn check_dependencies() {
             .is_err()
     }
 
-    if fails_to_run(&["make", "--version"]) {
+    // GNU make is called "gmake" on BSD systems, where "make" is BSD make.
+    let make_missing = make_cmd::make()
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_err();
+    if make_missing {
         panic!(
             "
 
@@ -88,12 +189,13 @@ It seems your system does not have GNU make installed. Make is required
 to build Themis from source.
 
 Please install \"make\" or \"build-essential\" package and try again.
+On FreeBSD, OpenBSD, and other BSD systems install \"gmake\" package.
 
         "
         );
     }
 
-    if fails_to_run(&["cc", "--version"]) {
+    if fails_to_run(&[cc, "--version"]) {
         panic!(
             "
 
@@ -101,6 +203,37 @@ It seems your system does not have a C compiler installed. C compiler
 is required to build Themis from source.
 
 Please install \"clang\" (or \"gcc\" and \"g++\") package and try again.
+If you are cross-compiling, set CC_<target> environment variable to
+the C compiler for your target (currently using \"{}\").
+
+        ",
+            cc
+        );
+    }
+
+    if uses_embedded_boringssl() && fails_to_run(&["cmake", "--version"]) {
+        panic!(
+            "
+
+Embedded BoringSSL requires CMake to build. Please install \"cmake\"
+package and try again, or use a different crypto engine (see below).
+
+        "
+        );
+    }
+
+    if uses_embedded_boringssl() && fails_to_run(&["go", "version"]) {
+        panic!(
+            "
+
+Embedded BoringSSL requires Go to build, but it seems that Go is not
+installed in your system. You can either install Go, or
+
+  - use OpenSSL or LibreSSL instead: unset ENGINE environment variable
+    or set it to \"openssl\" or \"libressl\"
+
+  - use prebuilt BoringSSL: set ENGINE_INCLUDE_PATH and ENGINE_LIB_PATH
+    environment variables to its headers and libraries
 
         "
         );
@@ -109,11 +242,31 @@ Please install \"clang\" (or \"gcc\" and \"g++\") package and try again.
     // TODO: check for SomethingSSL, it would be nice for the user
 }
 
+/// Checks whether Themis build is going to build BoringSSL from the bundled source code.
+///
+/// That's the case when BoringSSL engine is selected without providing the libraries.
+/// OpenSSL and LibreSSL engines (the default) always use the libraries available in the system.
+fn uses_embedded_boringssl() -> bool {
+    let engine = env::var("ENGINE").unwrap_or_default();
+    let engine_lib_path = env::var_os("ENGINE_LIB_PATH").unwrap_or_default();
+    engine == "boringssl" && engine_lib_path.is_empty()
+}
+
 impl Build {
     /// Prepares a new build.
     pub fn new() -> Build {
         Build {
             out_dir: env::var_os("OUT_DIR").map(|s| PathBuf::from(s).join("themis")),
+            cflags: Vec::new(),
+            make_vars: Vec::new(),
+            symbol_prefix: symbol_prefix_from_env(),
+            use_cmake: env::var("THEMIS_BUILD_SYSTEM").ok() == Some("cmake".to_owned()),
+            version: env::var("THEMIS_SRC_VERSION").ok(),
+            sanitizer: Sanitizer::from_env(),
+            run_tests: match env::var("THEMIS_RUN_TESTS") {
+                Ok(value) => !value.is_empty() && value != "0",
+                Err(_) => false,
+            },
         }
     }
 
@@ -124,21 +277,243 @@ impl Build {
         self
     }
 
+    /// Adds an arbitrary flag for C compiler, like `-fstack-protector-strong`.
+    ///
+    /// Flags are appended to the ones from CFLAGS environment variable.
+    pub fn cflag(&mut self, flag: &str) -> &mut Self {
+        self.cflags.push(flag.to_owned());
+        self
+    }
+
+    /// Defines a preprocessor macro, optionally with a value.
+    pub fn define(&mut self, name: &str, value: Option<&str>) -> &mut Self {
+        match value {
+            Some(value) => self.cflag(&format!("-D{}={}", name, value)),
+            None => self.cflag(&format!("-D{}", name)),
+        }
+    }
+
+    /// Prefixes symbols of embedded BoringSSL.
+    ///
+    /// This avoids duplicate symbols (or worse, mixing them up) when your binary is also linked
+    /// with OpenSSL or another copy of BoringSSL. The symbols file lists symbols to be prefixed,
+    /// one per line. Refer to BoringSSL's `BUILDING.md` on how to generate it.
+    ///
+    /// This is only possible when BoringSSL is built from source (`ENGINE=boringssl` without
+    /// `ENGINE_LIB_PATH`). Prefix can also be set with `THEMIS_BORINGSSL_PREFIX` and
+    /// `THEMIS_BORINGSSL_PREFIX_SYMBOLS` environment variables.
+    pub fn boringssl_symbol_prefix<P: AsRef<Path>>(
+        &mut self,
+        prefix: &str,
+        symbols: P,
+    ) -> &mut Self {
+        self.symbol_prefix = Some(SymbolPrefix {
+            prefix: prefix.to_owned(),
+            symbols: symbols.as_ref().to_path_buf(),
+        });
+        self
+    }
+
+    /// Selects Themis version to build, like `0.13`, `0.13.x`, or `0.13.0`.
+    ///
+    /// The bundled version is used by default. Other versions are downloaded from the pinned
+    /// releases, see the crate documentation. Version can be also selected with
+    /// `THEMIS_SRC_VERSION` environment variable.
+    pub fn version(&mut self, version: &str) -> &mut Self {
+        self.version = Some(version.to_owned());
+        self
+    }
+
+    /// Instruments Themis (and embedded BoringSSL) with a sanitizer.
+    ///
+    /// This helps to debug crashes in native code from Rust tests. Rust code has to be built
+    /// with the same sanitizer too (e.g., `RUSTFLAGS=-Zsanitizer=address` on nightly Rust) so
+    /// that the sanitizer runtime is linked in. Sanitizer can be also selected with
+    /// `THEMIS_SANITIZER` environment variable set to `address` or `undefined`.
+    pub fn sanitizer(&mut self, sanitizer: Sanitizer) -> &mut Self {
+        self.sanitizer = Some(sanitizer);
+        self
+    }
+
+    /// Runs Themis test suite after the build, panics if the tests fail.
+    ///
+    /// This catches broken builds of Themis (e.g., because of an unusual toolchain) early,
+    /// instead of getting runtime errors from Themis later. When cross-compiling, the tests are
+    /// run with the command set in `THEMIS_TEST_RUNNER` environment variable (like `qemu-arm`),
+    /// they are only built if it is not set. Tests can be also enabled with `THEMIS_RUN_TESTS=1`
+    /// environment variable.
+    ///
+    /// Test sources are not included into the `libthemis-src` package to keep it small, so this
+    /// works with Git checkouts and downloaded Themis releases (see [`version`]). Tests are run
+    /// only with GNU Make build.
+    ///
+    /// [`version`]: struct.Build.html#method.version
+    pub fn run_tests(&mut self, enable: bool) -> &mut Self {
+        self.run_tests = enable;
+        self
+    }
+
+    /// Builds Themis with CMake instead of GNU Make.
+    ///
+    /// CMake build is performed out of source, so the source code is not copied into OUT_DIR.
+    /// This requires Themis version with installable CMake build, and CMake itself. CMake can
+    /// be also selected with `THEMIS_BUILD_SYSTEM=cmake` environment variable.
+    ///
+    /// Makefile variables set with [`make_var`] are ignored by CMake build.
+    ///
+    /// [`make_var`]: struct.Build.html#method.make_var
+    pub fn cmake(&mut self, enable: bool) -> &mut Self {
+        self.use_cmake = enable;
+        self
+    }
+
+    /// Sets a variable for Themis Makefile, like `RSA_KEY_LENGTH=4096`.
+    pub fn make_var(&mut self, name: &str, value: &str) -> &mut Self {
+        self.make_vars.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
     /// Builds Themis, panics on any errors.
     pub fn build(&self) -> Library {
-        check_dependencies();
+        let target = env::var("TARGET").unwrap_or_default();
+        let ios = ios_target(&target);
+
+        let mut cc = target_tool("CC");
+        let mut ar = target_tool("AR");
+        let ranlib = target_tool("RANLIB");
+        let sysroot = target_tool("SYSROOT");
+        let mut extra_cflags = Vec::new();
+
+        if let Some(ref ios) = ios {
+            let sdk_path = xcrun(&["--sdk", ios.sdk, "--show-sdk-path"]);
+            if cc.is_none() {
+                cc = Some(xcrun(&["--sdk", ios.sdk, "--find", "clang"]));
+            }
+            extra_cflags.push(format!(
+                "-target {} -isysroot {}",
+                ios.clang_target, sdk_path
+            ));
+        } else if is_emscripten(&target) {
+            // Emscripten provides its own wrappers, the host ones cannot produce WebAssembly.
+            if cc.is_none() {
+                cc = Some("emcc".to_owned());
+            }
+            if ar.is_none() {
+                ar = Some("emar".to_owned());
+            }
+        } else if is_cross_compiling() && cc.is_none() {
+            // Do not let Themis silently use the host compiler, that fails only at link time.
+            // Use the same compiler (and target flags) as the `cc` crate would.
+            let compiler = cc::Build::new().get_compiler();
+            cc = Some(compiler.path().display().to_string());
+            extra_cflags.extend(
+                compiler
+                    .args()
+                    .iter()
+                    .map(|arg| arg.to_string_lossy().into_owned()),
+            );
+        }
+
+        if let Some(ref sysroot) = sysroot {
+            extra_cflags.push(format!("--sysroot={}", sysroot));
+        }
+
+        extra_cflags.extend(self.cflags.iter().cloned());
+
+        if let Some(sanitizer) = self.sanitizer {
+            extra_cflags.extend(sanitizer.flags().iter().map(|&flag| flag.to_owned()));
+        }
+
+        // NIST statistical test of the RNG is slow and fails sporadically by design.
+        // Themis CI skips it with this (misspelled) macro, so do we.
+        if self.run_tests {
+            extra_cflags.push("-DCIRICLE_TEST".to_owned());
+        }
+
+        // Themis code needs to use prefixed names too. BoringSSL headers handle that if
+        // the prefix is defined, using the header generated during BoringSSL build.
+        if let Some(ref symbol_prefix) = self.symbol_prefix {
+            if !uses_embedded_boringssl() {
+                panic!("BoringSSL symbol prefix requires embedded BoringSSL (ENGINE=boringssl)");
+            }
+            let out_dir = self.out_dir.as_ref().expect("OUT_DIR not set");
+            let prefix_include = out_dir.join("build/build/boringssl/symbol_prefix_include");
+            extra_cflags.push(format!("-DBORINGSSL_PREFIX={}", symbol_prefix.prefix));
+            extra_cflags.push(format!("-I{}", prefix_include.display()));
+        }
+
+        // Makefile appends its own flags to the ones from environment.
+        let cflags = if extra_cflags.is_empty() {
+            None
+        } else {
+            let mut flags = env::var("CFLAGS").unwrap_or_default();
+            for flag in &extra_cflags {
+                flags.push(' ');
+                flags.push_str(flag);
+            }
+            Some(flags)
+        };
+
+        // Shared libraries are not useful (or not supported) on some platforms.
+        // musl targets are used for fully static binaries.
+        let static_only = ios.is_some() || target.contains("-musl") || is_emscripten(&target);
+
+        let default_cc = "cc".to_owned();
+        check_dependencies(cc.as_ref().unwrap_or(&default_cc));
 
         let out_dir = self.out_dir.as_ref().expect("OUT_DIR not set");
-        let themis_src_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("themis");
+        let themis_src_dir = themis_source_dir(out_dir, self.version.as_ref());
         let themis_build_dir = out_dir.join("build");
         let themis_install_dir = out_dir.join("install");
 
+        // Rebuild only if the source code or build options have changed since the last build.
+        println!("cargo:rerun-if-changed={}", themis_src_dir.display());
+        for var in TRACKED_ENV_VARS {
+            println!("cargo:rerun-if-env-changed={}", var);
+        }
+        let stamp_path = out_dir.join("themis.stamp");
+        let stamp = build_stamp(
+            &themis_src_dir,
+            &[
+                ("TARGET", Some(target.clone())),
+                ("CC", cc.clone()),
+                ("AR", ar.clone()),
+                ("RANLIB", ranlib.clone()),
+                ("SYSROOT", sysroot.clone()),
+                ("CFLAGS", cflags.clone()),
+                ("STATIC_ONLY", Some(static_only.to_string())),
+                ("MAKE_VARS", Some(format!("{:?}", self.make_vars))),
+                ("CMAKE", Some(self.use_cmake.to_string())),
+                ("RUN_TESTS", Some(self.run_tests.to_string())),
+                (
+                    "BORINGSSL_PREFIX",
+                    self.symbol_prefix.as_ref().map(|symbol_prefix| {
+                        format!(
+                            "{} {}",
+                            symbol_prefix.prefix,
+                            symbol_prefix.symbols.display()
+                        )
+                    }),
+                ),
+            ],
+        );
+        if themis_install_dir.exists()
+            && fs::read_to_string(&stamp_path).ok() == Some(stamp.clone())
+        {
+            return Library {
+                prefix: themis_install_dir,
+            };
+        }
+
         // Themis uses in-source build. Cargo requires build scripts to never write anything
         // outside of OUT_DIR so we just have to copy the source code there.
 
         if !out_dir.exists() {
             fs::create_dir(&out_dir).expect("mkdir themis");
         }
+        if stamp_path.exists() {
+            fs::remove_file(&stamp_path).expect("rm themis.stamp");
+        }
         if themis_build_dir.exists() {
             fs::remove_dir_all(&themis_build_dir).expect("rm -r themis/build");
         }
@@ -146,6 +521,22 @@ impl Build {
             fs::remove_dir_all(&themis_install_dir).expect("rm -r themis/install");
         }
 
+        if self.use_cmake {
+            let build = CMakeBuild {
+                src_dir: &themis_src_dir,
+                install_dir: &themis_install_dir,
+                cc: cc.as_ref(),
+                ar: ar.as_ref(),
+                cflags: cflags.as_ref(),
+                static_only,
+            };
+            build.run();
+            fs::write(&stamp_path, stamp).expect("write themis.stamp");
+            return Library {
+                prefix: themis_install_dir,
+            };
+        }
+
         copy_dir::copy_dir(&themis_src_dir, &themis_build_dir).expect("cp -r src build");
         fs::create_dir(&themis_install_dir).expect("mkdir themis/install");
 
@@ -153,8 +544,78 @@ impl Build {
         let mut themis_build_and_install = make_cmd::make();
         themis_build_and_install
             .current_dir(&themis_build_dir)
-            .env("PREFIX", &themis_install_dir)
-            .arg("install");
+            .env("PREFIX", &themis_install_dir);
+
+        if static_only {
+            themis_build_and_install
+                .arg("themis_static")
+                .arg("themis_pkgconfig")
+                .arg("soter_pkgconfig");
+        } else {
+            themis_build_and_install.arg("install");
+        }
+
+        // Tests are built along with the library to use the same configuration.
+        if self.run_tests {
+            if !themis_build_dir.join("tests/soter/soter_test.c").exists() {
+                panic!(
+                    "Themis test sources are not available in {}, cannot run tests",
+                    themis_src_dir.display()
+                );
+            }
+            themis_build_and_install.arg("prepare_tests_basic");
+        }
+
+        if let Some(ref cflags) = cflags {
+            themis_build_and_install.env("CFLAGS", cflags);
+        }
+
+        // Shared libraries need sanitizer runtime as well.
+        if let Some(sanitizer) = self.sanitizer {
+            let mut ldflags = env::var("LDFLAGS").unwrap_or_default();
+            for flag in sanitizer.flags() {
+                ldflags.push(' ');
+                ldflags.push_str(flag);
+            }
+            themis_build_and_install.env("LDFLAGS", ldflags);
+        }
+
+        // Embedded BoringSSL is built with CMake which needs to be told about the target,
+        // symbol prefix, and sanitizer flags.
+        let cross = is_cross_compiling();
+        let custom_toolchain = cross || self.symbol_prefix.is_some() || self.sanitizer.is_some();
+        if custom_toolchain && uses_embedded_boringssl() {
+            let toolchain = CMakeToolchain {
+                cross,
+                sanitizer: self.sanitizer,
+                symbol_prefix: self.symbol_prefix.clone(),
+                cc: cc.clone(),
+                ar: ar.clone(),
+                ranlib: ranlib.clone(),
+                sysroot: sysroot.clone(),
+                cflags: cflags.clone(),
+            };
+            let toolchain_path = out_dir.join("toolchain.cmake");
+            toolchain.write(&toolchain_path);
+            themis_build_and_install.env("CMAKE_TOOLCHAIN_FILE", &toolchain_path);
+        }
+
+        // Cargo tells us how many jobs can be run in parallel.
+        if let Ok(jobs) = env::var("NUM_JOBS") {
+            themis_build_and_install.arg(format!("-j{}", jobs));
+        }
+
+        for (name, value) in &self.make_vars {
+            themis_build_and_install.arg(format!("{}={}", name, value));
+        }
+
+        // Override the tools only if they are explicitly configured, Themis knows the defaults.
+        if let Some(cc) = cc {
+            themis_build_and_install.arg(format!("CC={}", cc));
+        }
+        if let Some(ar) = ar {
+            themis_build_and_install.arg(format!("AR={}", ar));
+        }
 
         // Cargo sets DEBUG environment variable to zero in release builds, but Themis build simply
         // checks existence of this variable. We need to unset it to get real release builds,
@@ -172,19 +633,641 @@ impl Build {
             panic!("Themis build failed: {}", status);
         }
 
+        // "make install" builds shared libraries too, so we install static ones ourselves.
+        if static_only {
+            install_static(&themis_build_dir, &themis_install_dir);
+        }
+
+        if self.run_tests {
+            run_tests(&themis_build_dir);
+        }
+
+        fs::write(&stamp_path, stamp).expect("write themis.stamp");
+
         Library {
             prefix: themis_install_dir,
         }
     }
 }
 
+/// Runs Themis test suite built in the build directory, panics if any test fails.
+fn run_tests(build_dir: &Path) {
+    let runner = env::var("THEMIS_TEST_RUNNER").unwrap_or_default();
+    let runner: Vec<&str> = runner.split_whitespace().collect();
+    if is_cross_compiling() && runner.is_empty() {
+        println!("cargo:warning=Themis tests are not run: set THEMIS_TEST_RUNNER for the target");
+        return;
+    }
+
+    for test in &["soter_test", "themis_test"] {
+        let test_path = build_dir.join("build/tests").join(test);
+        let mut command = match runner.split_first() {
+            Some((program, args)) => {
+                let mut command = Command::new(program);
+                command.args(args).arg(&test_path);
+                command
+            }
+            None => Command::new(&test_path),
+        };
+        let output = command
+            .current_dir(build_dir)
+            .output()
+            .expect("failed to run Themis tests");
+        if !output.status.success() {
+            panic!(
+                "Themis {} failed: {}\n\n{}",
+                test,
+                output.status,
+                String::from_utf8_lossy(&output.stdout)
+            );
+        }
+    }
+}
+
+/// Themis release pinned for download.
+struct Release {
+    version: &'static str,
+    url: &'static str,
+    /// SHA-256 checksum of the source tarball, if it has been published.
+    sha256: Option<&'static str>,
+}
+
+/// Version of Themis source code bundled with the crate.
+const BUNDLED_VERSION: &str = "0.10.0";
+
+/// Themis releases available for vendored build, from oldest to newest.
+const RELEASES: &[Release] = &[
+    Release {
+        version: "0.10.0",
+        url: "https://github.com/cossacklabs/themis/archive/0.10.0.tar.gz",
+        sha256: None,
+    },
+    Release {
+        version: "0.11.1",
+        url: "https://github.com/cossacklabs/themis/archive/0.11.1.tar.gz",
+        sha256: None,
+    },
+    Release {
+        version: "0.12.0",
+        url: "https://github.com/cossacklabs/themis/archive/0.12.0.tar.gz",
+        sha256: None,
+    },
+    Release {
+        version: "0.13.0",
+        url: "https://github.com/cossacklabs/themis/archive/0.13.0.tar.gz",
+        sha256: None,
+    },
+];
+
+/// Finds the newest release matching requested version, like "0.13", "0.13.x", or "0.13.0".
+fn find_release(requested: &str) -> &'static Release {
+    let requested = requested.trim_end_matches(".x");
+    let prefix = format!("{}.", requested);
+    RELEASES
+        .iter()
+        .rev()
+        .find(|release| release.version == requested || release.version.starts_with(&prefix))
+        .unwrap_or_else(|| {
+            let available: Vec<&str> = RELEASES.iter().map(|release| release.version).collect();
+            panic!(
+                "Themis {} is not available for vendored build, please use one of: {}",
+                requested,
+                available.join(", ")
+            )
+        })
+}
+
+/// Locates Themis source code, downloading it if necessary.
+///
+/// Source code bundled with the crate is used by default. If it is missing (e.g., git
+/// submodules have not been checked out), or another version is requested, the source tarball
+/// of the release is downloaded instead. `THEMIS_SRC_URL` can be set to download the source code
+/// from elsewhere. Its SHA-256 checksum is always verified, use `THEMIS_SRC_SHA256` to provide
+/// the checksum for a custom URL.
+fn themis_source_dir(out_dir: &Path, version: Option<&String>) -> PathBuf {
+    println!("cargo:rerun-if-env-changed=THEMIS_SRC_URL");
+    println!("cargo:rerun-if-env-changed=THEMIS_SRC_SHA256");
+
+    let release = match version {
+        Some(version) => find_release(version),
+        None => find_release(BUNDLED_VERSION),
+    };
+    let bundled = Path::new(env!("CARGO_MANIFEST_DIR")).join("themis");
+    let url = env::var("THEMIS_SRC_URL").ok();
+    if url.is_none() && release.version == BUNDLED_VERSION && bundled.join("Makefile").exists() {
+        return bundled;
+    }
+
+    // Do not try to download anything if Cargo has been told to stay offline.
+    if env::var("CARGO_NET_OFFLINE").ok() == Some("true".to_owned()) {
+        panic!(
+            "Themis {} source code is not bundled and cannot be downloaded in offline mode",
+            release.version
+        );
+    }
+
+    let url = url.unwrap_or_else(|| release.url.to_owned());
+    let sha256 = match env::var("THEMIS_SRC_SHA256") {
+        Ok(sha256) => sha256.to_lowercase(),
+        Err(_) => match release.sha256 {
+            Some(sha256) if url == release.url => sha256.to_owned(),
+            _ => panic!(
+                "
+
+Themis source code is not bundled with libthemis-src and has to be
+downloaded from {}
+
+Please set THEMIS_SRC_SHA256 environment variable to the expected
+SHA-256 checksum of this file. Alternatively, check out git submodules
+if you are building from a git repository.
+
+",
+                url
+            ),
+        },
+    };
+
+    download_source(&url, &sha256, &out_dir.join("download"))
+}
+
+/// Downloads and unpacks source tarball, unless it is already there.
+fn download_source(url: &str, sha256: &str, download_dir: &Path) -> PathBuf {
+    let tarball = download_dir.join("themis.tar.gz");
+    let source_dir = download_dir.join("themis");
+    let checksum_path = download_dir.join("themis.sha256");
+
+    let downloaded_sha256 = fs::read_to_string(&checksum_path).ok();
+    if source_dir.exists() && downloaded_sha256 == Some(sha256.to_owned()) {
+        return source_dir;
+    }
+
+    fs::create_dir_all(download_dir).expect("mkdir -p download");
+    let downloaded = Command::new("curl")
+        .arg("--fail")
+        .arg("--silent")
+        .arg("--show-error")
+        .arg("--location")
+        .arg("--output")
+        .arg(&tarball)
+        .arg(url)
+        .status()
+        .or_else(|_| {
+            Command::new("wget")
+                .arg("--quiet")
+                .arg("--output-document")
+                .arg(&tarball)
+                .arg(url)
+                .status()
+        })
+        .expect("failed to run curl or wget, please install one of them");
+    if !downloaded.success() {
+        panic!("failed to download {}: {}", url, downloaded);
+    }
+
+    let actual = file_sha256(&tarball);
+    if actual != sha256 {
+        fs::remove_file(&tarball).expect("rm themis.tar.gz");
+        panic!(
+            "checksum mismatch for {}: expected {}, got {}",
+            url, sha256, actual
+        );
+    }
+
+    if source_dir.exists() {
+        fs::remove_dir_all(&source_dir).expect("rm -r download/themis");
+    }
+    fs::create_dir(&source_dir).expect("mkdir download/themis");
+    let unpacked = Command::new("tar")
+        .arg("-xzf")
+        .arg(&tarball)
+        .arg("-C")
+        .arg(&source_dir)
+        .arg("--strip-components=1")
+        .status()
+        .expect("failed to run tar");
+    if !unpacked.success() {
+        panic!("failed to unpack {}: {}", tarball.display(), unpacked);
+    }
+
+    fs::write(&checksum_path, sha256).expect("write themis.sha256");
+    source_dir
+}
+
+/// Computes SHA-256 checksum of a file with system tools.
+fn file_sha256(path: &Path) -> String {
+    let output = Command::new("sha256sum")
+        .arg(path)
+        .output()
+        .or_else(|_| {
+            Command::new("shasum")
+                .arg("-a")
+                .arg("256")
+                .arg(path)
+                .output()
+        })
+        .expect("failed to run sha256sum or shasum");
+    if !output.status.success() {
+        panic!("failed to compute checksum of {}", path.display());
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Environment variables affecting Themis build, in addition to the build tools.
+const TRACKED_ENV_VARS: &[&str] = &[
+    "ENGINE",
+    "ENGINE_INCLUDE_PATH",
+    "ENGINE_LIB_PATH",
+    "CFLAGS",
+    "IPHONEOS_DEPLOYMENT_TARGET",
+    "THEMIS_BORINGSSL_PREFIX",
+    "THEMIS_BORINGSSL_PREFIX_SYMBOLS",
+    "THEMIS_SRC_VERSION",
+    "THEMIS_SANITIZER",
+    "THEMIS_RUN_TESTS",
+    "THEMIS_TEST_RUNNER",
+];
+
+/// Describes the build: its options and the state of the source code.
+///
+/// Source code is fingerprinted by file names, sizes, and modification times, there is no need
+/// to read the files themselves.
+fn build_stamp(src_dir: &Path, options: &[(&str, Option<String>)]) -> String {
+    fn hash_dir(dir: &Path, hasher: &mut DefaultHasher) {
+        let mut entries: Vec<PathBuf> = dir
+            .read_dir()
+            .expect("ls themis")
+            .map(|entry| entry.expect("ls themis").path())
+            .collect();
+        entries.sort();
+        for path in entries {
+            let metadata = fs::metadata(&path).expect("stat themis");
+            path.hash(hasher);
+            if metadata.is_dir() {
+                hash_dir(&path, hasher);
+            } else {
+                metadata.len().hash(hasher);
+                metadata.modified().ok().hash(hasher);
+            }
+        }
+    }
+
+    let mut stamp = String::new();
+    for &(name, ref value) in options {
+        stamp.push_str(&format!(
+            "{}={}\n",
+            name,
+            value.as_ref().unwrap_or(&String::new())
+        ));
+    }
+    for name in TRACKED_ENV_VARS {
+        let value = env::var(name).unwrap_or_default();
+        stamp.push_str(&format!("{}={}\n", name, value));
+    }
+    let mut hasher = DefaultHasher::new();
+    hash_dir(src_dir, &mut hasher);
+    stamp.push_str(&format!("SOURCE={:016x}\n", hasher.finish()));
+    stamp
+}
+
+/// Themis build with CMake.
+struct CMakeBuild<'a> {
+    src_dir: &'a Path,
+    install_dir: &'a Path,
+    cc: Option<&'a String>,
+    ar: Option<&'a String>,
+    cflags: Option<&'a String>,
+    static_only: bool,
+}
+
+impl<'a> CMakeBuild<'a> {
+    fn run(&self) {
+        // Older Themis versions have CMakeLists.txt only for IDE support, it cannot install.
+        let cmake_lists =
+            fs::read_to_string(self.src_dir.join("CMakeLists.txt")).unwrap_or_default();
+        if !cmake_lists.contains("install(") {
+            panic!(
+                "Themis source code in {} does not support CMake build, please use GNU Make",
+                self.src_dir.display()
+            );
+        }
+
+        let mut config = cmake::Config::new(self.src_dir);
+        config
+            .out_dir(self.install_dir)
+            .define(
+                "BUILD_SHARED_LIBS",
+                if self.static_only { "OFF" } else { "ON" },
+            )
+            .env("ENGINE", env::var("ENGINE").unwrap_or_default());
+        if let Some(cc) = self.cc {
+            config.define("CMAKE_C_COMPILER", cc);
+        }
+        if let Some(ar) = self.ar {
+            config.define("CMAKE_AR", ar);
+        }
+        if let Some(cflags) = self.cflags {
+            config.cflag(cflags);
+        }
+        config.build();
+
+        // pkg-config files are used by libthemis-sys to locate the library.
+        let pkgconfig_dir = self.install_dir.join("lib/pkgconfig");
+        if !pkgconfig_dir.join("libthemis.pc").exists() {
+            self.write_pkg_config(&pkgconfig_dir);
+        }
+    }
+
+    fn write_pkg_config(&self, pkgconfig_dir: &Path) {
+        fs::create_dir_all(pkgconfig_dir).expect("mkdir -p lib/pkgconfig");
+        let prefix = self.install_dir.display();
+        let soter = format!(
+            "prefix={}\nlibdir=${{prefix}}/lib\nincludedir=${{prefix}}/include\n\n\
+             Name: Soter\nDescription: Cryptographic primitives\nVersion:\n\
+             Cflags: -I${{includedir}}\nLibs: -L${{libdir}} -lsoter\nLibs.private: -lcrypto\n",
+            prefix
+        );
+        let themis = format!(
+            "prefix={}\nlibdir=${{prefix}}/lib\nincludedir=${{prefix}}/include\n\n\
+             Name: Themis\nDescription: High-level cryptographic services\nVersion:\n\
+             Requires.private: libsoter\n\
+             Cflags: -I${{includedir}}\nLibs: -L${{libdir}} -lthemis\n",
+            prefix
+        );
+        fs::write(pkgconfig_dir.join("libsoter.pc"), soter).expect("write libsoter.pc");
+        fs::write(pkgconfig_dir.join("libthemis.pc"), themis).expect("write libthemis.pc");
+    }
+}
+
+/// CMake toolchain file for embedded BoringSSL.
+struct CMakeToolchain {
+    cross: bool,
+    sanitizer: Option<Sanitizer>,
+    symbol_prefix: Option<SymbolPrefix>,
+    cc: Option<String>,
+    ar: Option<String>,
+    ranlib: Option<String>,
+    sysroot: Option<String>,
+    cflags: Option<String>,
+}
+
+impl CMakeToolchain {
+    fn write(&self, path: &Path) {
+        let mut contents = String::new();
+        if self.cross {
+            self.write_target(&mut contents);
+        } else if let Some(sanitizer) = self.sanitizer {
+            let flags = sanitizer.flags().join(" ");
+            contents.push_str(&format!("set(CMAKE_C_FLAGS_INIT \"{}\")\n", flags));
+            contents.push_str(&format!("set(CMAKE_CXX_FLAGS_INIT \"{}\")\n", flags));
+        }
+        if let Some(ref symbol_prefix) = self.symbol_prefix {
+            contents.push_str(&format!("set(BORINGSSL_PREFIX {})\n", symbol_prefix.prefix));
+            contents.push_str(&format!(
+                "set(BORINGSSL_PREFIX_SYMBOLS \"{}\")\n",
+                symbol_prefix.symbols.display()
+            ));
+        }
+        fs::write(path, contents).expect("write toolchain.cmake");
+    }
+
+    fn write_target(&self, contents: &mut String) {
+        let system_name = match env::var("CARGO_CFG_TARGET_OS").unwrap_or_default().as_str() {
+            "android" => "Android",
+            "ios" => "iOS",
+            "linux" => "Linux",
+            "macos" => "Darwin",
+            "windows" => "Windows",
+            "freebsd" => "FreeBSD",
+            _ => "Generic",
+        };
+        let processor = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+        let cxx = target_tool("CXX").unwrap_or_else(|| {
+            let compiler = cc::Build::new().cpp(true).get_compiler();
+            compiler.path().display().to_string()
+        });
+
+        contents.push_str(&format!("set(CMAKE_SYSTEM_NAME {})\n", system_name));
+        contents.push_str(&format!("set(CMAKE_SYSTEM_PROCESSOR {})\n", processor));
+        contents.push_str(&format!("set(CMAKE_CXX_COMPILER \"{}\")\n", cxx));
+        let variables = [
+            ("CMAKE_C_COMPILER", &self.cc),
+            ("CMAKE_AR", &self.ar),
+            ("CMAKE_RANLIB", &self.ranlib),
+            ("CMAKE_SYSROOT", &self.sysroot),
+            ("CMAKE_C_FLAGS_INIT", &self.cflags),
+            ("CMAKE_CXX_FLAGS_INIT", &self.cflags),
+        ];
+        for &(name, value) in &variables {
+            if let Some(value) = value {
+                contents.push_str(&format!("set({} \"{}\")\n", name, value));
+            }
+        }
+    }
+}
+
+fn symbol_prefix_from_env() -> Option<SymbolPrefix> {
+    let prefix = env::var("THEMIS_BORINGSSL_PREFIX").ok()?;
+    let symbols = env::var_os("THEMIS_BORINGSSL_PREFIX_SYMBOLS")
+        .expect("THEMIS_BORINGSSL_PREFIX requires THEMIS_BORINGSSL_PREFIX_SYMBOLS to be set");
+    Some(SymbolPrefix {
+        prefix,
+        symbols: PathBuf::from(symbols),
+    })
+}
+
+/// Copies static libraries, headers, and pkg-config files into installation prefix.
+fn install_static(build_dir: &Path, install_dir: &Path) {
+    let copy_files = |from: &Path, extension: &str, to: &Path| {
+        fs::create_dir_all(to).expect("mkdir -p install");
+        for entry in from.read_dir().expect("ls build") {
+            let path = entry.expect("ls build").path();
+            if path.extension() == Some(OsStr::new(extension)) {
+                fs::copy(&path, to.join(path.file_name().unwrap())).expect("install");
+            }
+        }
+    };
+
+    copy_files(
+        &build_dir.join("src/themis"),
+        "h",
+        &install_dir.join("include/themis"),
+    );
+    copy_files(
+        &build_dir.join("src/soter"),
+        "h",
+        &install_dir.join("include/soter"),
+    );
+    copy_files(&build_dir.join("build"), "a", &install_dir.join("lib"));
+    copy_files(
+        &build_dir.join("build"),
+        "pc",
+        &install_dir.join("lib/pkgconfig"),
+    );
+}
+
+/// Recursively copies directory contents, merging with existing destination directory.
+fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in from.read_dir()? {
+        let entry = entry?;
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+        if path.is_dir() {
+            copy_tree(&path, &target)?;
+        } else {
+            fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// iOS SDK and clang target for iOS and iOS simulator targets.
+struct IosTarget {
+    sdk: &'static str,
+    clang_target: String,
+}
+
+fn ios_target(target: &str) -> Option<IosTarget> {
+    let min_version = env::var("IPHONEOS_DEPLOYMENT_TARGET").unwrap_or_else(|_| "10.0".into());
+    let (sdk, clang_target) = match target {
+        "aarch64-apple-ios" => ("iphoneos", format!("arm64-apple-ios{}", min_version)),
+        "aarch64-apple-ios-sim" => (
+            "iphonesimulator",
+            format!("arm64-apple-ios{}-simulator", min_version),
+        ),
+        "x86_64-apple-ios" => (
+            "iphonesimulator",
+            format!("x86_64-apple-ios{}-simulator", min_version),
+        ),
+        _ => return None,
+    };
+    Some(IosTarget { sdk, clang_target })
+}
+
+/// Queries Xcode for SDK paths and tools.
+fn xcrun(args: &[&str]) -> String {
+    let output = Command::new("xcrun")
+        .args(args)
+        .stderr(Stdio::inherit())
+        .output()
+        .expect("failed to run xcrun, is Xcode installed?");
+    if !output.status.success() {
+        panic!("xcrun {} failed: {}", args.join(" "), output.status);
+    }
+    String::from_utf8(output.stdout)
+        .expect("xcrun output")
+        .trim()
+        .to_owned()
+}
+
+/// Checks whether the target is WebAssembly compiled with Emscripten.
+fn is_emscripten(target: &str) -> bool {
+    target.ends_with("-emscripten")
+}
+
+/// Checks whether we are building for a different platform.
+fn is_cross_compiling() -> bool {
+    match (env::var("TARGET"), env::var("HOST")) {
+        (Ok(target), Ok(host)) => target != host,
+        _ => false,
+    }
+}
+
+/// Looks up a build tool for the target, like the `cc` crate does.
+fn target_tool(name: &str) -> Option<String> {
+    let target = env::var("TARGET").unwrap_or_default();
+    let mut candidates = vec![
+        format!("{}_{}", name, target),
+        format!("{}_{}", name, target.replace("-", "_")),
+    ];
+    if is_cross_compiling() {
+        candidates.push(format!("TARGET_{}", name));
+    } else {
+        candidates.push(format!("HOST_{}", name));
+    }
+    candidates.push(name.to_owned());
+
+    candidates
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .next()
+}
+
 impl Library {
     /// Installation prefix of the Themis library.
     pub fn prefix(&self) -> &Path {
         &self.prefix
     }
 
+    /// Directory with Themis and Soter headers.
+    pub fn include_dir(&self) -> PathBuf {
+        self.prefix.join("include")
+    }
+
+    /// Directory with Themis and Soter libraries.
+    pub fn lib_dir(&self) -> PathBuf {
+        self.prefix.join("lib")
+    }
+
+    /// Prints installed Themis location as Cargo metadata.
+    ///
+    /// If called from a build script of a crate with `links` key, dependent crates can find
+    /// Themis via `DEP_<links>_ROOT`, `DEP_<links>_INCLUDE`, and `DEP_<links>_LIB_DIR`
+    /// environment variables without building or locating it again.
+    pub fn env_metadata(&self) {
+        println!("cargo:root={}", self.prefix.display());
+        println!("cargo:include={}", self.include_dir().display());
+        println!("cargo:lib_dir={}", self.lib_dir().display());
+    }
+
+    /// Installs Themis into a conventional installation prefix.
+    ///
+    /// This is useful outside of Cargo build scripts, e.g., for packaging. Headers are copied
+    /// into `include`, libraries into `lib`, and pkg-config files in `lib/pkgconfig` are updated
+    /// to point to the new prefix. Returns the library installed there.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let library = libthemis_src::Build::new()
+    ///     .out_dir("target/themis")
+    ///     .build()
+    ///     .install_to("/opt/themis")?;
+    ///
+    /// assert!(library.include_dir().join("themis/themis.h").exists());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn install_to<P: AsRef<Path>>(&self, prefix: P) -> io::Result<Library> {
+        let prefix = prefix.as_ref();
+        copy_tree(&self.include_dir(), &prefix.join("include"))?;
+        copy_tree(&self.lib_dir(), &prefix.join("lib"))?;
+
+        let pkgconfig_dir = prefix.join("lib/pkgconfig");
+        if pkgconfig_dir.is_dir() {
+            let old_prefix = self.prefix.display().to_string();
+            let new_prefix = prefix.display().to_string();
+            for entry in pkgconfig_dir.read_dir()? {
+                let path = entry?.path();
+                if path.extension() == Some(OsStr::new("pc")) {
+                    let contents = fs::read_to_string(&path)?;
+                    fs::write(&path, contents.replace(&old_prefix, &new_prefix))?;
+                }
+            }
+        }
+
+        Ok(Library {
+            prefix: prefix.to_owned(),
+        })
+    }
+
     /// Adds installed Themis library location to PKG_CONFIG_PATH environment variable.
+    ///
+    /// When cross-compiling, this also sets PKG_CONFIG_LIBDIR so that host libraries are not
+    /// picked up, and allows pkg-config to be used for the target.
     pub fn set_pkg_config_path(&self) {
         let mut paths = env::var_os("PKG_CONFIG_PATH").unwrap_or_default();
         if !paths.is_empty() {
@@ -193,6 +1276,11 @@ impl Library {
         paths.push(self.prefix.join("lib/pkgconfig"));
 
         env::set_var("PKG_CONFIG_PATH", paths);
+
+        if is_cross_compiling() {
+            env::set_var("PKG_CONFIG_LIBDIR", self.prefix.join("lib/pkgconfig"));
+            env::set_var("PKG_CONFIG_ALLOW_CROSS", "1");
+        }
     }
 }
 
@@ -211,6 +1299,8 @@ mod tests {
         assert!(library.prefix().join("include/themis/themis.h").exists());
         assert!(library.prefix().join("lib/pkgconfig/libthemis.pc").exists());
         assert!(library.prefix().join("lib").read_dir().unwrap().count() > 0);
+        assert!(library.include_dir().join("soter/soter.h").exists());
+        assert!(library.lib_dir().join("libthemis.a").exists());
     }
 
     #[test]
@@ -224,6 +1314,84 @@ mod tests {
         assert!(library.prefix().join("lib").read_dir().unwrap().count() > 0);
     }
 
+    #[test]
+    fn build_with_custom_flags() {
+        let temp_dir = tempfile::tempdir().expect("temporary directory");
+        let library = Build::new()
+            .out_dir(&temp_dir)
+            .cflag("-fstack-protector-strong")
+            .define("SOTER_CUSTOM_BUILD", Some("1"))
+            .make_var("RSA_KEY_LENGTH", "4096")
+            .build();
+
+        assert!(library.lib_dir().join("libthemis.a").exists());
+    }
+
+    #[test]
+    fn build_with_sanitizer() {
+        let temp_dir = tempfile::tempdir().expect("temporary directory");
+        let library = Build::new()
+            .out_dir(&temp_dir)
+            .sanitizer(Sanitizer::Undefined)
+            .build();
+
+        assert!(library.lib_dir().join("libthemis.a").exists());
+    }
+
+    #[test]
+    fn build_and_run_tests() {
+        let temp_dir = tempfile::tempdir().expect("temporary directory");
+        let library = Build::new().out_dir(&temp_dir).run_tests(true).build();
+
+        assert!(library.lib_dir().join("libthemis.a").exists());
+        assert!(temp_dir
+            .path()
+            .join("build/build/tests/themis_test")
+            .exists());
+    }
+
+    #[test]
+    fn rebuild_is_skipped_when_up_to_date() {
+        let temp_dir = tempfile::tempdir().expect("temporary directory");
+        let library = Build::new().out_dir(&temp_dir).build();
+        let pc_file = library.prefix().join("lib/pkgconfig/libthemis.pc");
+        let built_at = pc_file.metadata().unwrap().modified().unwrap();
+
+        let library = Build::new().out_dir(&temp_dir).build();
+        let pc_file = library.prefix().join("lib/pkgconfig/libthemis.pc");
+        assert_eq!(pc_file.metadata().unwrap().modified().unwrap(), built_at);
+    }
+
+    #[test]
+    fn install_to_prefix() {
+        let temp_dir = tempfile::tempdir().expect("temporary directory");
+        let prefix = tempfile::tempdir().expect("temporary directory");
+        let library = Build::new().out_dir(&temp_dir).build();
+
+        let installed = library.install_to(prefix.path()).expect("install");
+
+        assert_eq!(installed.prefix(), prefix.path());
+        assert!(installed.include_dir().join("themis/themis.h").exists());
+        assert!(installed.lib_dir().join("libthemis.a").exists());
+        let pc_file = fs::read_to_string(installed.lib_dir().join("pkgconfig/libthemis.pc"))
+            .expect("libthemis.pc");
+        assert!(pc_file.contains(prefix.path().to_str().expect("prefix")));
+        assert!(!pc_file.contains(library.prefix().to_str().expect("prefix")));
+    }
+
+    #[test]
+    fn release_version_matching() {
+        assert_eq!(find_release("0.10").version, "0.10.0");
+        assert_eq!(find_release("0.13.x").version, "0.13.0");
+        assert_eq!(find_release("0.11.1").version, "0.11.1");
+    }
+
+    #[test]
+    #[should_panic]
+    fn release_version_unknown() {
+        find_release("0.9");
+    }
+
     #[test]
     fn pkg_config_setting() {
         let temp_dir = tempfile::tempdir().expect("temporary directory");
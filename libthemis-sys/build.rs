@@ -13,22 +13,62 @@
 // limitations under the License.
 
 use std::env;
+use std::ffi::OsString;
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
 use pkg_config::Library;
 
+/// Prints a diagnostic message about the probing process if THEMIS_SYS_VERBOSE is set.
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if is_verbose() {
+            println!("cargo:warning=libthemis-sys: {}", format!($($arg)*));
+        }
+    };
+}
+
 fn main() {
+    println!("cargo:rerun-if-env-changed=THEMIS_SYS_VERBOSE");
+    println!("cargo:rerun-if-env-changed=DOCS_RS");
+    if env::var_os("DOCS_RS").is_some() {
+        docs_only();
+        return;
+    }
+
     let themis = get_themis();
+    let function_cfgs = detect_function_cfgs(&themis);
+    emit_cfgs(
+        themis.version.as_ref().unwrap_or(&String::new()),
+        &function_cfgs,
+    );
+    emit_metadata(&themis);
 
     let whitelist = "(THEMIS|themis|secure_(comparator|session)|STATE)_.*";
+    let soter_whitelist = "(soter|SOTER)_(hash|hmac|kdf|rand|sym|asym_cipher|asym_ka|sign|verify|\
+                           container|CONTAINER|update_container|version|VERSION|pbkdf2)(_.*)?";
+    let header = if cfg!(feature = "soter-only") {
+        "src/soter_wrapper.h"
+    } else {
+        "src/wrapper.h"
+    };
     let bindings = bindgen::Builder::default()
         .clang_args(clang_include_paths(&themis))
         .clang_args(clang_library_paths(&themis))
-        .header("src/wrapper.h")
+        .clang_args(clang_target_args())
+        .header(header)
         .whitelist_function(whitelist)
         .whitelist_type(whitelist)
         .whitelist_var(whitelist)
+        .whitelist_function(soter_whitelist)
+        .whitelist_type(soter_whitelist)
+        .whitelist_var(soter_whitelist)
         .rustified_enum("themis_key_kind")
+        .rustified_enum("soter_hash_algo_type")
+        .rustified_enum("soter_asym_cipher_padding_type")
+        .rustified_enum("soter_asym_ka_alg_type")
+        .rustified_enum("soter_sign_alg_type")
         .generate()
         .expect("generating bindings");
 
@@ -37,6 +77,10 @@ fn main() {
         .write_to_file(out_path.join("bindings.rs"))
         .expect("writing bindings!");
 
+    // Shims need Themis headers and are not used by Soter APIs.
+    if cfg!(feature = "soter-only") {
+        return;
+    }
     cc::Build::new()
         .file("src/wrapper.c")
         .include("src")
@@ -44,28 +88,141 @@ fn main() {
         .compile("themis_shims");
 }
 
+/// Prepares documentation build which does not need Themis installation.
+///
+/// docs.rs cannot install Themis, so we do not look for it and do not link with it. Instead,
+/// pregenerated bindings are used, and all APIs are enabled.
+fn docs_only() {
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::copy("src/pregenerated.rs", out_path.join("bindings.rs")).expect("copying bindings");
+
+    let (major, minor) = KNOWN_VERSIONS[KNOWN_VERSIONS.len() - 1];
+    let function_cfgs: Vec<&str> = FUNCTION_CFGS.iter().map(|&(cfg, _, _)| cfg).collect();
+    emit_cfgs(&format!("{}.{}", major, minor), &function_cfgs);
+    println!("cargo:rustc-check-cfg=cfg(docs_only)");
+    println!("cargo:rustc-cfg=docs_only");
+}
+
+/// Location of Themis headers and libraries.
+struct Themis {
+    include_paths: Vec<PathBuf>,
+    link_paths: Vec<PathBuf>,
+    libs: Vec<String>,
+    is_static: bool,
+    version: Option<String>,
+    engine: Option<String>,
+}
+
+impl From<Library> for Themis {
+    fn from(library: Library) -> Themis {
+        Themis {
+            include_paths: library.include_paths,
+            link_paths: library.link_paths,
+            libs: library.libs,
+            is_static: false,
+            version: Some(library.version),
+            engine: None,
+        }
+    }
+}
+
 /// Embarks on an incredible adventure and returns with a suitable Themis (or dies trying).
-fn get_themis() -> Library {
+fn get_themis() -> Themis {
     #[cfg(feature = "vendored")]
-    libthemis_src::make();
+    {
+        verbose!("building vendored Themis");
+        libthemis_src::make();
+    }
+
+    if let Some(themis) = probe_themis_dir() {
+        return themis;
+    }
 
     let mut pkg_config = pkg_config::Config::new();
     pkg_config.env_metadata(true);
-    pkg_config.arg("libsoter"); // TODO: remove this together with themis_shims
+    if !cfg!(feature = "soter-only") {
+        pkg_config.arg("libsoter"); // TODO: remove this together with themis_shims
+    }
 
-    #[cfg(feature = "vendored")]
-    pkg_config.statik(true);
+    // pkg-config links all libraries in the same way. If linkage is selected per library,
+    // we use pkg-config only to find Themis and then link the libraries ourselves.
+    // Vendored build produces only static Themis, its crypto backend is linked by pkg-config.
+    let link_manually = !cfg!(feature = "vendored") && has_per_library_linkage();
+    if link_manually {
+        pkg_config.cargo_metadata(false);
+    }
 
-    match pkg_config.probe("libthemis") {
-        Ok(library) => return library,
-        Err(error) => panic!(format!(
-            "
+    // Vendored build produces static libraries, and some targets support only static linkage.
+    // Otherwise pkg-config decides based on LIBTHEMIS_STATIC and friends.
+    let force_static = cfg!(feature = "vendored") || is_static_only_target();
+    if force_static {
+        pkg_config.statik(true);
+    }
+
+    let error = match pkg_config.probe(&format!("lib{}", main_library())) {
+        Ok(library) if link_manually => {
+            verbose!("pkg-config: found Themis {:?}", library.version);
+            let include_dir = library.include_paths.first().cloned().unwrap_or_default();
+            let found = library
+                .link_paths
+                .iter()
+                .filter_map(|lib_dir| use_dirs(&include_dir, lib_dir))
+                .next();
+            match found {
+                Some(mut themis) => {
+                    themis.version = Some(library.version);
+                    return themis;
+                }
+                None => panic!(
+                    "pkg-config found Themis, but its libraries are not in {:?}",
+                    library.link_paths
+                ),
+            }
+        }
+        Ok(library) => {
+            let mut themis = Themis::from(library);
+            themis.is_static = force_static || pkg_config_prefers_static();
+            if cfg!(feature = "vendored") {
+                themis.engine = Some(env::var("ENGINE").unwrap_or_else(|_| "openssl".into()));
+            }
+            verbose!("pkg-config: found Themis {:?}", themis.version);
+            return themis;
+        }
+        Err(error) => error,
+    };
+    verbose!(
+        "pkg-config: {}",
+        error.to_string().trim().replace("\n", " ")
+    );
+
+    if let Some(themis) = probe_vcpkg().or_else(probe_conda) {
+        return themis;
+    }
+
+    if let Some(themis) = probe_standard_paths() {
+        return themis;
+    }
+
+    if target_env("CARGO_CFG_TARGET_OS") == "macos" {
+        if let Some(themis) = probe_homebrew().or_else(probe_macports) {
+            return themis;
+        }
+    } else {
+        verbose!("Homebrew and MacPorts: skipped, target is not macOS");
+    }
+
+    panic!(
+        "
 
 `libthemis-sys` could not find Themis installation in your system.
 
 Please make sure you have appropriate development package installed.
+Libraries in LIBRARY_PATH, /usr/local, and /usr are used as well.
 On Linux it's called `libthemis-dev`, not just `libthemis`.
-On macOS Homebrew formula is called `themis` or `themis-openssl`.
+On macOS Homebrew formula is called `themis` or `themis-openssl`,
+MacPorts installation in /opt/local is used as well.
+On Windows you can install Themis with vcpkg.
+Installations in conda environments are used too.
 
 Please refer to the documentation for installation instructions:
 
@@ -76,14 +233,738 @@ non-standard installation of Themis then you can help pkg-config
 to locate your library by setting the PKG_CONFIG_PATH environment
 variable to the path where `libthemis.pc` file is located.
 
+Alternatively, set THEMIS_DIR environment variable to the prefix
+where Themis is installed (containing `include` and `lib`), or set
+THEMIS_INCLUDE_DIR and THEMIS_LIB_DIR. Append _<TARGET> suffix to set
+them for a particular target only.
+vcpkg installation is used if VCPKG_ROOT or VCPKG_INSTALLED_DIR is set.
+
+Set THEMIS_SYS_VERBOSE=1 environment variable to see where Themis
+has been looked for.
+
 {}
 ",
-            error
-        )),
+        error
+    );
+}
+
+/// Libraries which can have their linkage selected individually.
+const LINKED_LIBRARIES: &[&str] = &["themis", "soter", "crypto"];
+
+/// Checks whether a library should be linked statically (or dynamically).
+///
+/// `LIBTHEMIS_STATIC` and `LIBTHEMIS_DYNAMIC` can be set to a comma-separated list of libraries
+/// (e.g., `themis,soter` or `crypto`) to select linkage only for them. Any other value applies
+/// to all libraries. Returns `None` if there is no preference.
+fn linkage_preference(name: &str) -> Option<bool> {
+    println!("cargo:rerun-if-env-changed=LIBTHEMIS_STATIC");
+    println!("cargo:rerun-if-env-changed=LIBTHEMIS_DYNAMIC");
+    let applies_to = |var: &str| match env::var(var) {
+        Ok(value) => match parse_library_list(&value) {
+            Some(names) => names.iter().any(|lib| lib == name),
+            None => true,
+        },
+        Err(_) => env::var_os(var).is_some(),
+    };
+    if applies_to("LIBTHEMIS_STATIC") {
+        return Some(true);
+    }
+    if applies_to("LIBTHEMIS_DYNAMIC") {
+        return Some(false);
+    }
+    None
+}
+
+/// Parses a list of library names, returns `None` if the value is not such a list.
+fn parse_library_list(value: &str) -> Option<Vec<String>> {
+    let names: Vec<String> = value
+        .split(',')
+        .map(|name| name.trim().trim_start_matches("lib").to_owned())
+        .filter(|name| !name.is_empty())
+        .collect();
+    if names.is_empty()
+        || !names
+            .iter()
+            .all(|name| LINKED_LIBRARIES.contains(&name.as_str()))
+    {
+        return None;
+    }
+    Some(names)
+}
+
+/// Checks whether linkage is selected for individual libraries rather than all of them.
+fn has_per_library_linkage() -> bool {
+    ["LIBTHEMIS_STATIC", "LIBTHEMIS_DYNAMIC"].iter().any(|var| {
+        env::var(var)
+            .ok()
+            .and_then(|value| parse_library_list(&value))
+            .is_some()
+    })
+}
+
+/// Returns the name of the library which must be present: Themis, or Soter with `soter-only`.
+fn main_library() -> &'static str {
+    if cfg!(feature = "soter-only") {
+        "soter"
+    } else {
+        "themis"
+    }
+}
+
+/// Checks whether pkg-config is going to link Themis statically by default.
+fn pkg_config_prefers_static() -> bool {
+    if env::var_os("LIBTHEMIS_STATIC").is_some() {
+        return true;
+    }
+    if env::var_os("LIBTHEMIS_DYNAMIC").is_some() {
+        return false;
+    }
+    env::var_os("PKG_CONFIG_ALL_STATIC").is_some()
+}
+
+/// Exports Themis location for dependent crates (available as `DEP_THEMIS_*` variables).
+fn emit_metadata(themis: &Themis) {
+    let join_paths = |paths: &[PathBuf]| {
+        env::join_paths(paths)
+            .expect("paths with separators")
+            .to_string_lossy()
+            .into_owned()
+    };
+    println!("cargo:include={}", join_paths(&themis.include_paths));
+    println!("cargo:lib_dir={}", join_paths(&themis.link_paths));
+    println!("cargo:libs={}", themis.libs.join(","));
+    println!(
+        "cargo:linkage={}",
+        if themis.is_static {
+            "static"
+        } else {
+            "dynamic"
+        }
+    );
+    if let Some(ref engine) = themis.engine {
+        println!("cargo:engine={}", engine);
+    }
+}
+
+fn is_verbose() -> bool {
+    match env::var("THEMIS_SYS_VERBOSE") {
+        Ok(value) => !value.is_empty() && value != "0",
+        Err(_) => false,
+    }
+}
+
+/// Checks whether the target needs static linkage.
+///
+/// musl targets are meant for fully static binaries, and Emscripten cannot link dynamically.
+fn is_static_only_target() -> bool {
+    target_env("CARGO_CFG_TARGET_ENV") == "musl"
+        || target_env("CARGO_CFG_TARGET_OS") == "emscripten"
+}
+
+fn target_env(name: &str) -> String {
+    env::var(name).unwrap_or_default()
+}
+
+/// Uses Themis installed into THEMIS_DIR prefix, if set.
+///
+/// Header and library locations can be also set separately with THEMIS_INCLUDE_DIR and
+/// THEMIS_LIB_DIR, taking precedence over THEMIS_DIR. All of them can be set for a particular
+/// target, e.g., THEMIS_DIR_AARCH64_LINUX_ANDROID.
+fn probe_themis_dir() -> Option<Themis> {
+    let prefix = target_var("THEMIS_DIR").map(PathBuf::from);
+    let include_dir = target_var("THEMIS_INCLUDE_DIR").map(PathBuf::from);
+    let lib_dir = target_var("THEMIS_LIB_DIR").map(PathBuf::from);
+    if prefix.is_none() && include_dir.is_none() && lib_dir.is_none() {
+        verbose!("THEMIS_DIR: skipped, not set");
+        return None;
+    }
+
+    let include_dir = include_dir
+        .or_else(|| prefix.as_ref().map(|prefix| prefix.join("include")))
+        .expect("THEMIS_LIB_DIR is set, but THEMIS_INCLUDE_DIR (or THEMIS_DIR) is not");
+    let lib_dir = lib_dir
+        .or_else(|| prefix.as_ref().map(|prefix| prefix_lib_dir(prefix)))
+        .expect("THEMIS_INCLUDE_DIR is set, but THEMIS_LIB_DIR (or THEMIS_DIR) is not");
+
+    Some(use_dirs(&include_dir, &lib_dir).unwrap_or_else(|| {
+        panic!(
+            "Themis libraries are not found in {} (check THEMIS_DIR or THEMIS_LIB_DIR)",
+            lib_dir.display()
+        )
+    }))
+}
+
+/// Reads environment variable for the current target.
+///
+/// Target-specific variable (`NAME_<TARGET>`, like `THEMIS_DIR_X86_64_APPLE_DARWIN`) is used
+/// if it is set, otherwise the generic one (`NAME`) is used. Empty values are ignored.
+fn target_var(name: &str) -> Option<OsString> {
+    let target = target_env("TARGET").replace("-", "_").to_uppercase();
+    let target_name = format!("{}_{}", name, target);
+    println!("cargo:rerun-if-env-changed={}", target_name);
+    println!("cargo:rerun-if-env-changed={}", name);
+    env::var_os(&target_name)
+        .filter(|value| !value.is_empty())
+        .or_else(|| env::var_os(name).filter(|value| !value.is_empty()))
+}
+
+/// Uses Themis installed with vcpkg.
+///
+/// Packages installed in manifest mode are looked up in VCPKG_INSTALLED_DIR, or in
+/// `vcpkg_installed` directory next to Cargo target directory. Classic mode installations
+/// are found in VCPKG_ROOT. VCPKG_DEFAULT_TRIPLET overrides the triplet.
+fn probe_vcpkg() -> Option<Themis> {
+    println!("cargo:rerun-if-env-changed=VCPKG_ROOT");
+    println!("cargo:rerun-if-env-changed=VCPKG_INSTALLED_DIR");
+    println!("cargo:rerun-if-env-changed=VCPKG_DEFAULT_TRIPLET");
+    let triplet = match env::var("VCPKG_DEFAULT_TRIPLET")
+        .ok()
+        .or_else(vcpkg_triplet)
+    {
+        Some(triplet) => triplet,
+        None => {
+            verbose!("vcpkg: skipped, unsupported target");
+            return None;
+        }
+    };
+
+    let mut installed_dirs = Vec::new();
+    if let Some(dir) = env::var_os("VCPKG_INSTALLED_DIR") {
+        installed_dirs.push(PathBuf::from(dir));
+    }
+    // OUT_DIR is usually inside the target directory in the project root.
+    if let Some(out_dir) = env::var_os("OUT_DIR") {
+        let out_dir = PathBuf::from(out_dir);
+        for dir in out_dir.ancestors().skip(1) {
+            let installed = dir.join("vcpkg_installed");
+            if installed.is_dir() {
+                installed_dirs.push(installed);
+                break;
+            }
+        }
+    }
+    if let Some(root) = env::var_os("VCPKG_ROOT") {
+        installed_dirs.push(PathBuf::from(root).join("installed"));
+    }
+    if installed_dirs.is_empty() {
+        verbose!("vcpkg: skipped, neither VCPKG_ROOT nor VCPKG_INSTALLED_DIR is set");
+        return None;
+    }
+
+    installed_dirs
+        .iter()
+        .filter_map(|dir| use_prefix(&dir.join(&triplet)))
+        .next()
+}
+
+/// Uses Themis installed into the active conda environment.
+fn probe_conda() -> Option<Themis> {
+    println!("cargo:rerun-if-env-changed=CONDA_PREFIX");
+    let prefix = match env::var_os("CONDA_PREFIX") {
+        Some(prefix) => PathBuf::from(prefix),
+        None => {
+            verbose!("conda: skipped, CONDA_PREFIX is not set");
+            return None;
+        }
+    };
+    // Conda keeps native libraries in a separate subdirectory on Windows.
+    if target_env("CARGO_CFG_TARGET_OS") == "windows" {
+        use_prefix(&prefix.join("Library"))
+    } else {
+        use_prefix(&prefix)
+    }
+}
+
+/// Uses Themis installed with Homebrew.
+///
+/// Homebrew lives in `/opt/homebrew` on Apple Silicon and in `/usr/local` on Intel Macs.
+/// `brew --prefix` is asked first, but it may be unavailable or fail (e.g., if `brew` is
+/// a shim), so the default locations are checked too.
+fn probe_homebrew() -> Option<Themis> {
+    println!("cargo:rerun-if-env-changed=HOMEBREW_PREFIX");
+    let mut prefixes = Vec::new();
+    for formula in &["themis", "themis-openssl"] {
+        if let Some(prefix) = brew_prefix(formula) {
+            prefixes.push(prefix);
+        }
+    }
+    let mut brew_roots = vec![PathBuf::from("/opt/homebrew"), PathBuf::from("/usr/local")];
+    if let Some(root) = env::var_os("HOMEBREW_PREFIX") {
+        brew_roots.insert(0, PathBuf::from(root));
+    }
+    for root in &brew_roots {
+        for formula in &["themis", "themis-openssl"] {
+            prefixes.push(root.join("opt").join(formula));
+        }
+    }
+    prefixes
+        .iter()
+        .filter_map(|prefix| use_prefix(prefix))
+        .next()
+}
+
+fn brew_prefix(formula: &str) -> Option<PathBuf> {
+    let output = match Command::new("brew")
+        .arg("--prefix")
+        .arg(formula)
+        .stderr(Stdio::null())
+        .output()
+    {
+        Ok(output) => output,
+        Err(error) => {
+            verbose!(
+                "Homebrew: failed to run `brew --prefix {}`: {}",
+                formula,
+                error
+            );
+            return None;
+        }
+    };
+    if !output.status.success() {
+        verbose!(
+            "Homebrew: `brew --prefix {}` failed: {}",
+            formula,
+            output.status
+        );
+        return None;
+    }
+    let prefix = String::from_utf8(output.stdout).ok()?;
+    let prefix = prefix.trim();
+    if prefix.is_empty() {
+        return None;
+    }
+    Some(PathBuf::from(prefix))
+}
+
+/// Uses Themis installed with MacPorts.
+fn probe_macports() -> Option<Themis> {
+    use_prefix(Path::new("/opt/local"))
+}
+
+fn vcpkg_triplet() -> Option<String> {
+    let arch = match target_env("CARGO_CFG_TARGET_ARCH").as_str() {
+        "x86_64" => "x64",
+        "x86" => "x86",
+        "aarch64" => "arm64",
+        _ => return None,
+    };
+    match target_env("CARGO_CFG_TARGET_OS").as_str() {
+        "windows" => {
+            let static_crt = target_env("CARGO_CFG_TARGET_FEATURE")
+                .split(',')
+                .any(|feature| feature == "crt-static");
+            if static_crt {
+                Some(format!("{}-windows-static", arch))
+            } else {
+                Some(format!("{}-windows", arch))
+            }
+        }
+        "linux" => Some(format!("{}-linux", arch)),
+        "macos" => Some(format!("{}-osx", arch)),
+        _ => None,
+    }
+}
+
+/// Uses Themis from library search paths and standard system locations.
+///
+/// LIBRARY_PATH is used by compilers to find libraries, so Themis might be there. Headers are
+/// looked up in CPATH and C_INCLUDE_PATH, or next to the libraries. After that `/usr/local`
+/// and `/usr` prefixes are checked. Host locations are not used when cross-compiling.
+fn probe_standard_paths() -> Option<Themis> {
+    if target_env("TARGET") != target_env("HOST") {
+        verbose!("standard paths: skipped, cross-compiling");
+        return None;
+    }
+    if target_env("CARGO_CFG_TARGET_OS") == "windows" {
+        verbose!("standard paths: skipped, target is Windows");
+        return None;
+    }
+    println!("cargo:rerun-if-env-changed=LIBRARY_PATH");
+    if let Some(paths) = env::var_os("LIBRARY_PATH") {
+        for lib_dir in env::split_paths(&paths) {
+            if let Some(include_dir) = find_include_dir(&lib_dir) {
+                if let Some(themis) = use_dirs(&include_dir, &lib_dir) {
+                    return Some(themis);
+                }
+            }
+        }
+    } else {
+        verbose!("LIBRARY_PATH: skipped, not set");
+    }
+    use_prefix(Path::new("/usr/local")).or_else(|| use_prefix(Path::new("/usr")))
+}
+
+/// Finds Themis headers for a library directory from LIBRARY_PATH.
+fn find_include_dir(lib_dir: &Path) -> Option<PathBuf> {
+    let header = format!("{0}/{0}.h", main_library());
+    let has_headers = |dir: &Path| dir.join(&header).is_file();
+    for var in &["CPATH", "C_INCLUDE_PATH"] {
+        println!("cargo:rerun-if-env-changed={}", var);
+        if let Some(paths) = env::var_os(var) {
+            if let Some(dir) = env::split_paths(&paths).find(|dir| has_headers(dir)) {
+                return Some(dir);
+            }
+        }
+    }
+    let found = lib_dir
+        .ancestors()
+        .map(|dir| dir.join("include"))
+        .find(|dir| has_headers(dir));
+    if found.is_none() {
+        verbose!("no Themis headers found for {}", lib_dir.display());
+    }
+    found
+}
+
+/// Links with Themis installed into the prefix, returns `None` if it is not there.
+///
+/// Libraries are looked up in `lib/<multiarch>` (Debian and Ubuntu), `lib64` (Fedora, CentOS,
+/// etc.), and `lib` subdirectories of the prefix.
+fn use_prefix(prefix: &Path) -> Option<Themis> {
+    let include_dir = prefix.join("include");
+    prefix_lib_dirs(prefix)
+        .iter()
+        .filter_map(|lib_dir| use_dirs(&include_dir, lib_dir))
+        .next()
+}
+
+/// Returns candidate library directories in the prefix.
+fn prefix_lib_dirs(prefix: &Path) -> Vec<PathBuf> {
+    let mut lib_dirs = Vec::new();
+    if let Some(triple) = multiarch_triple() {
+        lib_dirs.push(prefix.join("lib").join(triple));
+    }
+    if target_env("CARGO_CFG_TARGET_POINTER_WIDTH") == "64" {
+        lib_dirs.push(prefix.join("lib64"));
+    }
+    lib_dirs.push(prefix.join("lib"));
+    lib_dirs
+}
+
+/// Returns library directory in the prefix which contains Themis, or just `lib`.
+fn prefix_lib_dir(prefix: &Path) -> PathBuf {
+    prefix_lib_dirs(prefix)
+        .into_iter()
+        .find(|lib_dir| find_library(lib_dir, main_library()).is_some())
+        .unwrap_or_else(|| prefix.join("lib"))
+}
+
+/// Returns Debian multiarch triple for Linux targets, like `x86_64-linux-gnu`.
+fn multiarch_triple() -> Option<String> {
+    let target = target_env("TARGET");
+    let parts: Vec<&str> = target.split('-').collect();
+    if parts.len() != 4 || parts[2] != "linux" {
+        return None;
+    }
+    let arch = match parts[0] {
+        "i586" | "i686" => "i386",
+        arch if arch.starts_with("armv7") => "arm",
+        arch => arch,
+    };
+    Some(format!("{}-linux-{}", arch, parts[3]))
+}
+
+/// Links with Themis from given directories, returns `None` if libraries are not there.
+///
+/// Only Soter is needed with `soter-only` feature.
+fn use_dirs(include_dir: &Path, lib_dir: &Path) -> Option<Themis> {
+    verbose!("looking for libraries in {}", lib_dir.display());
+    let names: &[&str] = if cfg!(feature = "soter-only") {
+        &["soter"]
+    } else {
+        &["themis", "soter"]
+    };
+    let mut found = Vec::new();
+    for name in names {
+        match find_library(lib_dir, name) {
+            Some(library) => found.push(library),
+            None => {
+                verbose!("{} library not found in {}", name, lib_dir.display());
+                return None;
+            }
+        }
+    }
+    let mut libs: Vec<String> = found.iter().map(|library| library.name.clone()).collect();
+    verbose!("using {} from {}", libs.join(" and "), lib_dir.display());
+
+    println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    for library in &found {
+        library.link();
+    }
+    let (main, soter) = (&found[0], &found[found.len() - 1]);
+    if soter.is_static {
+        // Static Soter needs its crypto backend, shared one brings it along.
+        let crypto = link_crypto(lib_dir);
+        libs.push(crypto.name);
+    }
+    if target_env("CARGO_CFG_TARGET_OS") == "windows" {
+        // Soter uses htonl() and friends from Winsock.
+        println!("cargo:rustc-link-lib=ws2_32");
+    }
+
+    Some(Themis {
+        include_paths: vec![include_dir.to_owned()],
+        link_paths: vec![lib_dir.to_owned()],
+        libs,
+        is_static: main.is_static,
+        version: read_installed_version(lib_dir),
+        engine: None,
+    })
+}
+
+/// Links with the crypto backend of static Soter.
+///
+/// OpenSSL, LibreSSL, and BoringSSL all call it `libcrypto`. It is looked up next to Themis,
+/// then in `ENGINE_LIB_PATH` and `OPENSSL_LIB_DIR`. If it is not found there, we hope that
+/// the linker will find it in system directories.
+fn link_crypto(lib_dir: &Path) -> FoundLibrary {
+    let mut lib_dirs = vec![lib_dir.to_owned()];
+    for var in &["ENGINE_LIB_PATH", "OPENSSL_LIB_DIR"] {
+        if let Some(dir) = target_var(var) {
+            lib_dirs.push(PathBuf::from(dir));
+        }
+    }
+    for dir in &lib_dirs {
+        if let Some(crypto) = find_library(dir, "crypto") {
+            verbose!(
+                "using {} crypto library from {}",
+                if crypto.is_static { "static" } else { "shared" },
+                dir.display()
+            );
+            if dir != lib_dir {
+                println!("cargo:rustc-link-search=native={}", dir.display());
+            }
+            crypto.link();
+            return crypto;
+        }
+    }
+    if linkage_preference("crypto") == Some(true) || is_static_only_target() {
+        panic!(
+            "
+
+Themis is linked statically, but static crypto library (libcrypto.a)
+is not found in {:?}.
+
+Static Themis needs the crypto library it has been built with (OpenSSL,
+LibreSSL, or BoringSSL). Set ENGINE_LIB_PATH or OPENSSL_LIB_DIR to the
+directory containing it, or set LIBTHEMIS_STATIC=themis,soter to link
+the crypto library dynamically.
+
+",
+            lib_dirs
+        );
+    }
+    verbose!("crypto library not found, using system one");
+    let crypto = FoundLibrary {
+        name: "crypto".to_owned(),
+        is_static: false,
+    };
+    crypto.link();
+    crypto
+}
+
+/// Reads installed Themis version from pkg-config files next to the library.
+///
+/// BSD packages put pkg-config files into `libdata/pkgconfig` instead of `lib/pkgconfig`.
+fn read_installed_version(lib_dir: &Path) -> Option<String> {
+    let pc_file = format!("lib{}.pc", main_library());
+    read_pc_version(&lib_dir.join("pkgconfig").join(&pc_file)).or_else(|| {
+        let prefix = lib_dir.parent()?;
+        read_pc_version(&prefix.join("libdata/pkgconfig").join(&pc_file))
+    })
+}
+
+/// Reads `Version` field from a pkg-config file, if it's there.
+fn read_pc_version(path: &Path) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    for line in contents.lines() {
+        let mut field = line.splitn(2, ':');
+        if field.next() == Some("Version") {
+            return field.next().map(|version| version.trim().to_owned());
+        }
+    }
+    None
+}
+
+/// Oldest Themis version supported by this crate.
+const MIN_VERSION: (u32, u32) = (0, 10);
+
+/// Themis versions which have their own `cfg` flags.
+const KNOWN_VERSIONS: &[(u32, u32)] = &[(0, 10), (0, 11), (0, 12), (0, 13)];
+
+/// Optional Themis functions which have their own `cfg` flags: flag, header, function name.
+const FUNCTION_CFGS: &[(&str, &str, &str)] = &[
+    (
+        "themis_secure_cell_passphrase",
+        "themis/secure_cell.h",
+        "themis_secure_cell_encrypt_seal_with_passphrase",
+    ),
+    ("soter_pbkdf2", "soter/soter_kdf.h", "soter_pbkdf2_sha256"),
+];
+
+/// Checks which optional functions are available in Themis.
+///
+/// Bindings are generated from headers, so we look for function declarations there. Libraries
+/// with mismatched headers will fail to link anyway.
+fn detect_function_cfgs(themis: &Themis) -> Vec<&'static str> {
+    if cfg!(feature = "soter-only") {
+        return Vec::new();
+    }
+    FUNCTION_CFGS
+        .iter()
+        .filter(|&&(cfg, header, function)| {
+            let declared = themis.include_paths.iter().any(|dir| {
+                fs::read_to_string(dir.join(header))
+                    .map(|contents| contents.contains(function))
+                    .unwrap_or(false)
+            });
+            if !declared {
+                verbose!("{} not found in {}, {} disabled", function, header, cfg);
+            }
+            declared
+        })
+        .map(|&(cfg, _, _)| cfg)
+        .collect()
+}
+
+/// Checks Themis version and tells rustc which APIs are available.
+///
+/// For each known version not newer than the installed one we emit a `themis_X_Y` cfg flag.
+/// Optional functions detected in Themis get their own flags as well. They are also exported
+/// as `DEP_THEMIS_CFGS` for dependent crates, along with the full list of flags in
+/// `DEP_THEMIS_KNOWN_CFGS` and detected version in `DEP_THEMIS_VERSION`.
+fn emit_cfgs(version: &str, function_cfgs: &[&str]) {
+    let (major, minor) = match parse_version(version) {
+        Some(version) => version,
+        None => {
+            // Vendored and some custom builds do not have the version set. Assume the oldest
+            // supported one, the build will fail later if it is actually older.
+            if !version.is_empty() {
+                println!(
+                    "cargo:warning=cannot parse Themis version \"{}\", assuming {}.{}",
+                    version, MIN_VERSION.0, MIN_VERSION.1
+                );
+            }
+            MIN_VERSION
+        }
+    };
+
+    if (major, minor) < MIN_VERSION {
+        panic!(
+            "
+
+Themis {} is installed in your system, but `libthemis-sys` requires
+Themis {}.{} or newer. Please upgrade Themis, or use `vendored` feature
+to build it from source along with your crate.
+
+",
+            version, MIN_VERSION.0, MIN_VERSION.1
+        );
+    }
+
+    let cfg_name = |&(major, minor): &(u32, u32)| format!("themis_{}_{}", major, minor);
+    let mut known_cfgs: Vec<String> = KNOWN_VERSIONS.iter().map(cfg_name).collect();
+    let mut cfgs: Vec<String> = KNOWN_VERSIONS
+        .iter()
+        .filter(|&&known| known <= (major, minor))
+        .map(cfg_name)
+        .collect();
+    known_cfgs.extend(FUNCTION_CFGS.iter().map(|&(cfg, _, _)| cfg.to_owned()));
+    cfgs.extend(function_cfgs.iter().map(|&cfg| cfg.to_owned()));
+
+    for cfg in &known_cfgs {
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+    }
+    for cfg in &cfgs {
+        println!("cargo:rustc-cfg={}", cfg);
     }
+    println!("cargo:version={}.{}", major, minor);
+    println!("cargo:cfgs={}", cfgs.join(","));
+    println!("cargo:known_cfgs={}", known_cfgs.join(","));
 }
 
-fn clang_include_paths(library: &Library) -> Vec<String> {
+/// Parses major and minor version from strings like "0.11.1" or "v0.12.0-42-gcafe".
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let version = version.trim().trim_start_matches('v');
+    let mut parts = version.split(&['.', '-'][..]);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+/// Library found in a directory.
+struct FoundLibrary {
+    name: String,
+    is_static: bool,
+}
+
+impl FoundLibrary {
+    fn link(&self) {
+        let kind = if self.is_static { "static" } else { "dylib" };
+        println!("cargo:rustc-link-lib={}={}", kind, self.name);
+    }
+}
+
+/// Looks for a library in the directory, taking into account platform naming conventions.
+///
+/// Unix libraries are called `libfoo.a` (static) or `libfoo.so`, `libfoo.dylib` (dynamic).
+/// OpenBSD and some other BSD packages install only versioned `libfoo.so.X.Y` without a bare
+/// `libfoo.so` symlink, their linkers can use them as well.
+/// MinGW uses `libfoo.a` and `libfoo.dll.a` import libraries. MSVC libraries are called
+/// `foo.lib` or `libfoo.lib` and may be either static or import libraries: they are linked
+/// dynamically if there is a matching DLL next to them (or in `../bin`). `LIBTHEMIS_STATIC`
+/// and `LIBTHEMIS_DYNAMIC` environment variables can be set to prefer static or dynamic
+/// libraries. musl and Emscripten targets always prefer static libraries.
+fn find_library(dir: &Path, name: &str) -> Option<FoundLibrary> {
+    let preference = linkage_preference(name);
+    let prefer_static = preference == Some(true) || is_static_only_target();
+    let exists = |file: &str| dir.join(file).is_file();
+
+    if target_env("CARGO_CFG_TARGET_ENV") == "msvc" {
+        for lib_name in &[name.to_owned(), format!("lib{}", name)] {
+            if !exists(&format!("{}.lib", lib_name)) {
+                continue;
+            }
+            let dll = format!("{}.dll", lib_name);
+            let has_dll = exists(&dll) || dir.join("../bin").join(&dll).is_file();
+            return Some(FoundLibrary {
+                name: lib_name.clone(),
+                is_static: prefer_static || !has_dll,
+            });
+        }
+        return None;
+    }
+
+    let has_static = exists(&format!("lib{}.a", name));
+    let has_dynamic = ["so", "dylib", "dll.a"]
+        .iter()
+        .any(|ext| exists(&format!("lib{}.{}", name, ext)))
+        || has_versioned_library(dir, name);
+    if !has_static && !has_dynamic {
+        return None;
+    }
+    Some(FoundLibrary {
+        name: name.to_owned(),
+        is_static: has_static && (prefer_static || !has_dynamic),
+    })
+}
+
+/// Checks whether the directory contains versioned shared library like `libfoo.so.1.0`.
+fn has_versioned_library(dir: &Path, name: &str) -> bool {
+    let prefix = format!("lib{}.so.", name);
+    match dir.read_dir() {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).any(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .map(|file| file.starts_with(&prefix))
+                .unwrap_or(false)
+        }),
+        Err(_) => false,
+    }
+}
+
+fn clang_include_paths(library: &Themis) -> Vec<String> {
     library
         .include_paths
         .iter()
@@ -91,7 +972,7 @@ fn clang_include_paths(library: &Library) -> Vec<String> {
         .collect()
 }
 
-fn clang_library_paths(library: &Library) -> Vec<String> {
+fn clang_library_paths(library: &Themis) -> Vec<String> {
     library
         .link_paths
         .iter()
@@ -99,6 +980,16 @@ fn clang_library_paths(library: &Library) -> Vec<String> {
         .collect()
 }
 
+fn clang_target_args() -> Vec<String> {
+    // Make sure that bindings are generated for the target platform when cross-compiling.
+    // Type sizes and alignment may differ, e.g., for 32-bit Android targets.
+    let target = target_env("TARGET");
+    if target.is_empty() || target == target_env("HOST") {
+        return vec![];
+    }
+    vec![format!("--target={}", target)]
+}
+
 trait CCBuildEx {
     fn includes<I>(&mut self, dirs: I) -> &mut Self
     where
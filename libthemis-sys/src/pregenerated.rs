@@ -0,0 +1,856 @@
+/* automatically generated by rust-bindgen */
+
+// Pregenerated bindings used for documentation builds (on docs.rs) where Themis is not
+// available. Regenerate them with bindgen when wrapper.h changes.
+pub const THEMIS_VERSION_TEXT: &'static [u8; 13usize] = b"themis 0.9: \0";
+pub const THEMIS_SUCCESS: u32 = 0;
+pub const THEMIS_SSESSION_SEND_OUTPUT_TO_PEER: u32 = 1;
+pub const THEMIS_FAIL: u32 = 11;
+pub const THEMIS_INVALID_PARAMETER: u32 = 12;
+pub const THEMIS_NO_MEMORY: u32 = 13;
+pub const THEMIS_BUFFER_TOO_SMALL: u32 = 14;
+pub const THEMIS_DATA_CORRUPT: u32 = 15;
+pub const THEMIS_INVALID_SIGNATURE: u32 = 16;
+pub const THEMIS_NOT_SUPPORTED: u32 = 17;
+pub const THEMIS_SSESSION_KA_NOT_FINISHED: u32 = 19;
+pub const THEMIS_SSESSION_TRANSPORT_ERROR: u32 = 20;
+pub const THEMIS_SSESSION_GET_PUB_FOR_ID_CALLBACK_ERROR: u32 = 21;
+pub const THEMIS_SCOMPARE_SEND_OUTPUT_TO_PEER: u32 = 1;
+pub const THEMIS_SCOMPARE_MATCH: u32 = 21;
+pub const THEMIS_SCOMPARE_NO_MATCH: u32 = 22;
+pub const THEMIS_SCOMPARE_NOT_READY: u32 = 0;
+pub const STATE_IDLE: u32 = 0;
+pub const STATE_NEGOTIATING: u32 = 1;
+pub const STATE_ESTABLISHED: u32 = 2;
+pub type __uint8_t = ::std::os::raw::c_uchar;
+pub type __int32_t = ::std::os::raw::c_int;
+pub type __ssize_t = ::std::os::raw::c_long;
+pub type themis_status_t = i32;
+extern "C" {
+    pub fn themis_secure_cell_encrypt_seal(
+        master_key: *const u8,
+        master_key_length: usize,
+        user_context: *const u8,
+        user_context_length: usize,
+        message: *const u8,
+        message_length: usize,
+        encrypted_message: *mut u8,
+        encrypted_message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_cell_decrypt_seal(
+        master_key: *const u8,
+        master_key_length: usize,
+        user_context: *const u8,
+        user_context_length: usize,
+        encrypted_message: *const u8,
+        encrypted_message_length: usize,
+        plain_message: *mut u8,
+        plain_message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_cell_encrypt_seal_with_passphrase(
+        passphrase: *const u8,
+        passphrase_length: usize,
+        user_context: *const u8,
+        user_context_length: usize,
+        message: *const u8,
+        message_length: usize,
+        encrypted_message: *mut u8,
+        encrypted_message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_cell_decrypt_seal_with_passphrase(
+        passphrase: *const u8,
+        passphrase_length: usize,
+        user_context: *const u8,
+        user_context_length: usize,
+        encrypted_message: *const u8,
+        encrypted_message_length: usize,
+        plain_message: *mut u8,
+        plain_message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_cell_encrypt_token_protect(
+        master_key: *const u8,
+        master_key_length: usize,
+        user_context: *const u8,
+        user_context_length: usize,
+        message: *const u8,
+        message_length: usize,
+        context: *mut u8,
+        context_length: *mut usize,
+        encrypted_message: *mut u8,
+        encrypted_message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_cell_decrypt_token_protect(
+        master_key: *const u8,
+        master_key_length: usize,
+        user_context: *const u8,
+        user_context_length: usize,
+        encrypted_message: *const u8,
+        encrypted_message_length: usize,
+        context: *const u8,
+        context_length: usize,
+        plain_message: *mut u8,
+        plain_message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_cell_encrypt_context_imprint(
+        master_key: *const u8,
+        master_key_length: usize,
+        message: *const u8,
+        message_length: usize,
+        context: *const u8,
+        context_length: usize,
+        encrypted_message: *mut u8,
+        encrypted_message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_cell_decrypt_context_imprint(
+        master_key: *const u8,
+        master_key_length: usize,
+        encrypted_message: *const u8,
+        encrypted_message_length: usize,
+        context: *const u8,
+        context_length: usize,
+        plain_message: *mut u8,
+        plain_message_length: *mut usize,
+    ) -> themis_status_t;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct secure_comparator_type {
+    _unused: [u8; 0],
+}
+pub type secure_comparator_t = secure_comparator_type;
+extern "C" {
+    pub fn secure_comparator_create() -> *mut secure_comparator_t;
+}
+extern "C" {
+    pub fn secure_comparator_destroy(comp_ctx: *mut secure_comparator_t) -> themis_status_t;
+}
+extern "C" {
+    pub fn secure_comparator_append_secret(
+        comp_ctx: *mut secure_comparator_t,
+        secret_data: *const ::std::os::raw::c_void,
+        secret_data_length: usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn secure_comparator_begin_compare(
+        comp_ctx: *mut secure_comparator_t,
+        compare_data: *mut ::std::os::raw::c_void,
+        compare_data_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn secure_comparator_proceed_compare(
+        comp_ctx: *mut secure_comparator_t,
+        peer_compare_data: *const ::std::os::raw::c_void,
+        peer_compare_data_length: usize,
+        compare_data: *mut ::std::os::raw::c_void,
+        compare_data_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn secure_comparator_get_result(comp_ctx: *const secure_comparator_t) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_gen_sym_key(key: *mut u8, key_length: *mut usize) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_gen_rsa_key_pair(
+        private_key: *mut u8,
+        private_key_length: *mut usize,
+        public_key: *mut u8,
+        public_key_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_gen_ec_key_pair(
+        private_key: *mut u8,
+        private_key_length: *mut usize,
+        public_key: *mut u8,
+        public_key_length: *mut usize,
+    ) -> themis_status_t;
+}
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum themis_key_kind {
+    THEMIS_KEY_INVALID = 0,
+    THEMIS_KEY_RSA_PRIVATE = 1,
+    THEMIS_KEY_RSA_PUBLIC = 2,
+    THEMIS_KEY_EC_PRIVATE = 3,
+    THEMIS_KEY_EC_PUBLIC = 4,
+}
+pub use self::themis_key_kind as themis_key_kind_t;
+extern "C" {
+    pub fn themis_get_asym_key_kind(key: *const u8, length: usize) -> themis_key_kind_t;
+}
+extern "C" {
+    pub fn themis_is_valid_asym_key(key: *const u8, length: usize) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_message_encrypt(
+        private_key: *const u8,
+        private_key_length: usize,
+        public_key: *const u8,
+        public_key_length: usize,
+        message: *const u8,
+        message_length: usize,
+        encrypted_message: *mut u8,
+        encrypted_message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_message_decrypt(
+        private_key: *const u8,
+        private_key_length: usize,
+        public_key: *const u8,
+        public_key_length: usize,
+        encrypted_message: *const u8,
+        encrypted_message_length: usize,
+        message: *mut u8,
+        message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_message_sign(
+        private_key: *const u8,
+        private_key_length: usize,
+        message: *const u8,
+        message_length: usize,
+        signed_message: *mut u8,
+        signed_message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_message_verify(
+        public_key: *const u8,
+        public_key_length: usize,
+        signed_message: *const u8,
+        signed_message_length: usize,
+        message: *mut u8,
+        message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_message_wrap(
+        private_key: *const u8,
+        private_key_length: usize,
+        public_key: *const u8,
+        public_key_length: usize,
+        message: *const u8,
+        message_length: usize,
+        wrapped_message: *mut u8,
+        wrapped_message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_secure_message_unwrap(
+        private_key: *const u8,
+        private_key_length: usize,
+        public_key: *const u8,
+        public_key_length: usize,
+        wrapped_message: *const u8,
+        wrapped_message_length: usize,
+        message: *mut u8,
+        message_length: *mut usize,
+    ) -> themis_status_t;
+}
+pub type send_protocol_data_callback = ::std::option::Option<
+    unsafe extern "C" fn(
+        data: *const u8,
+        data_length: usize,
+        user_data: *mut ::std::os::raw::c_void,
+    ) -> isize,
+>;
+pub type receive_protocol_data_callback = ::std::option::Option<
+    unsafe extern "C" fn(
+        data: *mut u8,
+        data_length: usize,
+        user_data: *mut ::std::os::raw::c_void,
+    ) -> isize,
+>;
+pub type protocol_state_changed_callback = ::std::option::Option<
+    unsafe extern "C" fn(event: ::std::os::raw::c_int, user_data: *mut ::std::os::raw::c_void),
+>;
+pub type get_public_key_for_id_callback = ::std::option::Option<
+    unsafe extern "C" fn(
+        id: *const ::std::os::raw::c_void,
+        id_length: usize,
+        key_buffer: *mut ::std::os::raw::c_void,
+        key_buffer_length: usize,
+        user_data: *mut ::std::os::raw::c_void,
+    ) -> ::std::os::raw::c_int,
+>;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct secure_session_user_callbacks_type {
+    pub send_data: send_protocol_data_callback,
+    pub receive_data: receive_protocol_data_callback,
+    pub state_changed: protocol_state_changed_callback,
+    pub get_public_key_for_id: get_public_key_for_id_callback,
+    pub user_data: *mut ::std::os::raw::c_void,
+}
+pub type secure_session_user_callbacks_t = secure_session_user_callbacks_type;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct secure_session_type {
+    _unused: [u8; 0],
+}
+pub type secure_session_t = secure_session_type;
+extern "C" {
+    pub fn secure_session_create(
+        id: *const ::std::os::raw::c_void,
+        id_length: usize,
+        sign_key: *const ::std::os::raw::c_void,
+        sign_key_length: usize,
+        user_callbacks: *const secure_session_user_callbacks_t,
+    ) -> *mut secure_session_t;
+}
+extern "C" {
+    pub fn secure_session_destroy(session_ctx: *mut secure_session_t) -> themis_status_t;
+}
+extern "C" {
+    pub fn secure_session_connect(session_ctx: *mut secure_session_t) -> themis_status_t;
+}
+extern "C" {
+    pub fn secure_session_generate_connect_request(
+        session_ctx: *mut secure_session_t,
+        output: *mut ::std::os::raw::c_void,
+        output_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn secure_session_wrap(
+        session_ctx: *mut secure_session_t,
+        message: *const ::std::os::raw::c_void,
+        message_length: usize,
+        wrapped_message: *mut ::std::os::raw::c_void,
+        wrapped_message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn secure_session_unwrap(
+        session_ctx: *mut secure_session_t,
+        wrapped_message: *const ::std::os::raw::c_void,
+        wrapped_message_length: usize,
+        message: *mut ::std::os::raw::c_void,
+        message_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn secure_session_send(
+        session_ctx: *mut secure_session_t,
+        message: *const ::std::os::raw::c_void,
+        message_length: usize,
+    ) -> isize;
+}
+extern "C" {
+    pub fn secure_session_receive(
+        session_ctx: *mut secure_session_t,
+        message: *mut ::std::os::raw::c_void,
+        message_length: usize,
+    ) -> isize;
+}
+extern "C" {
+    pub fn secure_session_save(
+        session_ctx: *const secure_session_t,
+        out: *mut ::std::os::raw::c_void,
+        out_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn secure_session_load(
+        session_ctx: *mut secure_session_t,
+        in_: *const ::std::os::raw::c_void,
+        in_length: usize,
+        user_callbacks: *const secure_session_user_callbacks_t,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn secure_session_is_established(session_ctx: *const secure_session_t) -> bool;
+}
+extern "C" {
+    pub fn secure_session_get_remote_id(
+        session_ctx: *const secure_session_t,
+        id: *mut u8,
+        id_length: *mut usize,
+    ) -> themis_status_t;
+}
+
+extern "C" {
+    pub fn themis_is_valid_key(key: *const u8, length: usize) -> themis_status_t;
+    pub fn themis_get_key_kind(key: *const u8, length: usize) -> themis_key_kind;
+}
+extern "C" {
+    pub fn themis_version() -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn themis_gen_rsa_key_pair_with_size(
+        bits: ::std::os::raw::c_uint,
+        private_key: *mut u8,
+        private_key_length: *mut usize,
+        public_key: *mut u8,
+        public_key_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_get_public_key(
+        private_key: *const u8,
+        private_key_length: usize,
+        public_key: *mut u8,
+        public_key_length: *mut usize,
+    ) -> themis_status_t;
+}
+extern "C" {
+    pub fn themis_update_key_container(key: *mut u8, length: usize) -> themis_status_t;
+}
+pub type soter_status_t = i32;
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum soter_hash_algo_type {
+    SOTER_HASH_SHA256 = 0,
+    SOTER_HASH_SHA512 = 1,
+}
+pub use self::soter_hash_algo_type as soter_hash_algo_t;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct soter_hash_ctx_type {
+    _unused: [u8; 0],
+}
+pub type soter_hash_ctx_t = soter_hash_ctx_type;
+extern "C" {
+    pub fn soter_hash_create(algo: soter_hash_algo_t) -> *mut soter_hash_ctx_t;
+}
+extern "C" {
+    pub fn soter_hash_destroy(hash_ctx: *mut soter_hash_ctx_t) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_hash_cleanup(hash_ctx: *mut soter_hash_ctx_t) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_hash_update(
+        hash_ctx: *mut soter_hash_ctx_t,
+        data: *const ::std::os::raw::c_void,
+        length: usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_hash_final(
+        hash_ctx: *mut soter_hash_ctx_t,
+        hash_value: *mut u8,
+        hash_length: *mut usize,
+    ) -> soter_status_t;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct soter_hmac_ctx_type {
+    _unused: [u8; 0],
+}
+pub type soter_hmac_ctx_t = soter_hmac_ctx_type;
+extern "C" {
+    pub fn soter_hmac_create(
+        algo: soter_hash_algo_t,
+        key: *const u8,
+        key_length: usize,
+    ) -> *mut soter_hmac_ctx_t;
+}
+extern "C" {
+    pub fn soter_hmac_destroy(hmac_ctx: *mut soter_hmac_ctx_t) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_hmac_update(
+        hmac_ctx: *mut soter_hmac_ctx_t,
+        data: *const ::std::os::raw::c_void,
+        length: usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_hmac_final(
+        hmac_ctx: *mut soter_hmac_ctx_t,
+        hmac_value: *mut u8,
+        hmac_length: *mut usize,
+    ) -> soter_status_t;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct soter_kdf_context_buf_type {
+    pub data: *const u8,
+    pub length: usize,
+}
+pub type soter_kdf_context_buf_t = soter_kdf_context_buf_type;
+extern "C" {
+    pub fn soter_kdf(
+        key: *const ::std::os::raw::c_void,
+        key_length: usize,
+        label: *const ::std::os::raw::c_char,
+        context: *const soter_kdf_context_buf_t,
+        context_count: usize,
+        output: *mut ::std::os::raw::c_void,
+        output_length: usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_pbkdf2_sha256(
+        passphrase: *const u8,
+        passphrase_length: usize,
+        salt: *const u8,
+        salt_length: usize,
+        iterations: usize,
+        key: *mut u8,
+        key_length: usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_rand(buffer: *mut u8, length: usize) -> soter_status_t;
+}
+pub const SOTER_VERSION_TEXT: &'static [u8; 12usize] = b"soter 0.9: \0";
+extern "C" {
+    pub fn soter_version() -> *const ::std::os::raw::c_char;
+}
+pub const SOTER_CONTAINER_TAG_LENGTH: u32 = 4;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct soter_container_hdr_type {
+    pub tag: [::std::os::raw::c_char; 4usize],
+    pub size: i32,
+    pub crc: u32,
+}
+pub type soter_container_hdr_t = soter_container_hdr_type;
+extern "C" {
+    pub fn soter_update_container_checksum(hdr: *mut soter_container_hdr_t) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_verify_container_checksum(hdr: *const soter_container_hdr_t) -> soter_status_t;
+}
+pub const SOTER_SYM_AES_ECB_PKCS7: u32 = 268500992;
+pub const SOTER_SYM_AES_CTR: u32 = 536870912;
+pub const SOTER_SYM_AES_XTS: u32 = 805306368;
+pub const SOTER_SYM_AES_GCM: u32 = 1073807360;
+pub const SOTER_SYM_NOKDF: u32 = 0;
+pub const SOTER_SYM_PBKDF2: u32 = 16777216;
+pub const SOTER_SYM_256_KEY_LENGTH: u32 = 256;
+pub const SOTER_SYM_192_KEY_LENGTH: u32 = 192;
+pub const SOTER_SYM_128_KEY_LENGTH: u32 = 128;
+pub const SOTER_SYM_KEY_LENGTH_MASK: u32 = 4095;
+pub const SOTER_SYM_PADDING_MASK: u32 = 983040;
+pub const SOTER_SYM_ALG_MASK: u32 = 4026531840;
+pub const SOTER_SYM_KDF_MASK: u32 = 251658240;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct soter_sym_ctx_type {
+    _unused: [u8; 0],
+}
+pub type soter_sym_ctx_t = soter_sym_ctx_type;
+extern "C" {
+    pub fn soter_sym_encrypt_create(
+        alg: u32,
+        key: *const ::std::os::raw::c_void,
+        key_length: usize,
+        salt: *const ::std::os::raw::c_void,
+        salt_length: usize,
+        iv: *const ::std::os::raw::c_void,
+        iv_length: usize,
+    ) -> *mut soter_sym_ctx_t;
+}
+extern "C" {
+    pub fn soter_sym_encrypt_update(
+        ctx: *mut soter_sym_ctx_t,
+        in_data: *const ::std::os::raw::c_void,
+        data_length: usize,
+        out_data: *mut ::std::os::raw::c_void,
+        out_data_length: *mut usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_encrypt_final(
+        ctx: *mut soter_sym_ctx_t,
+        out_data: *mut ::std::os::raw::c_void,
+        out_data_length: *mut usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_encrypt_destroy(ctx: *mut soter_sym_ctx_t) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_decrypt_create(
+        alg: u32,
+        key: *const ::std::os::raw::c_void,
+        key_length: usize,
+        salt: *const ::std::os::raw::c_void,
+        salt_length: usize,
+        iv: *const ::std::os::raw::c_void,
+        iv_length: usize,
+    ) -> *mut soter_sym_ctx_t;
+}
+extern "C" {
+    pub fn soter_sym_decrypt_update(
+        ctx: *mut soter_sym_ctx_t,
+        in_data: *const ::std::os::raw::c_void,
+        data_length: usize,
+        out_data: *mut ::std::os::raw::c_void,
+        out_data_length: *mut usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_decrypt_final(
+        ctx: *mut soter_sym_ctx_t,
+        out_data: *mut ::std::os::raw::c_void,
+        out_data_length: *mut usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_decrypt_destroy(ctx: *mut soter_sym_ctx_t) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_aead_encrypt_create(
+        alg: u32,
+        key: *const ::std::os::raw::c_void,
+        key_length: usize,
+        salt: *const ::std::os::raw::c_void,
+        salt_length: usize,
+        iv: *const ::std::os::raw::c_void,
+        iv_length: usize,
+    ) -> *mut soter_sym_ctx_t;
+}
+extern "C" {
+    pub fn soter_sym_aead_encrypt_update(
+        ctx: *mut soter_sym_ctx_t,
+        in_data: *const ::std::os::raw::c_void,
+        data_length: usize,
+        out_data: *mut ::std::os::raw::c_void,
+        out_data_length: *mut usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_aead_encrypt_final(
+        ctx: *mut soter_sym_ctx_t,
+        out_data: *mut ::std::os::raw::c_void,
+        out_data_length: *mut usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_aead_encrypt_destroy(ctx: *mut soter_sym_ctx_t) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_aead_encrypt_aad(
+        ctx: *mut soter_sym_ctx_t,
+        plain_data: *const ::std::os::raw::c_void,
+        data_length: usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_aead_decrypt_create(
+        alg: u32,
+        key: *const ::std::os::raw::c_void,
+        key_length: usize,
+        salt: *const ::std::os::raw::c_void,
+        salt_length: usize,
+        iv: *const ::std::os::raw::c_void,
+        iv_length: usize,
+    ) -> *mut soter_sym_ctx_t;
+}
+extern "C" {
+    pub fn soter_sym_aead_decrypt_update(
+        ctx: *mut soter_sym_ctx_t,
+        in_data: *const ::std::os::raw::c_void,
+        data_length: usize,
+        out_data: *mut ::std::os::raw::c_void,
+        out_data_length: *mut usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_aead_decrypt_final(
+        ctx: *mut soter_sym_ctx_t,
+        auth_tag: *const ::std::os::raw::c_void,
+        auth_tag_length: usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_aead_decrypt_destroy(ctx: *mut soter_sym_ctx_t) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sym_aead_decrypt_aad(
+        ctx: *mut soter_sym_ctx_t,
+        plain_data: *const ::std::os::raw::c_void,
+        data_length: usize,
+    ) -> soter_status_t;
+}
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum soter_asym_cipher_padding_type {
+    SOTER_ASYM_CIPHER_NOPAD = 0,
+    SOTER_ASYM_CIPHER_OAEP = 1,
+}
+pub use self::soter_asym_cipher_padding_type as soter_asym_cipher_padding_t;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct soter_asym_cipher_type {
+    _unused: [u8; 0],
+}
+pub type soter_asym_cipher_t = soter_asym_cipher_type;
+extern "C" {
+    pub fn soter_asym_cipher_create(
+        key: *const ::std::os::raw::c_void,
+        key_length: usize,
+        pad: soter_asym_cipher_padding_t,
+    ) -> *mut soter_asym_cipher_t;
+}
+extern "C" {
+    pub fn soter_asym_cipher_encrypt(
+        asym_cipher_ctx: *mut soter_asym_cipher_t,
+        plain_data: *const ::std::os::raw::c_void,
+        plain_data_length: usize,
+        cipher_data: *mut ::std::os::raw::c_void,
+        cipher_data_length: *mut usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_asym_cipher_decrypt(
+        asym_cipher_ctx: *mut soter_asym_cipher_t,
+        cipher_data: *const ::std::os::raw::c_void,
+        cipher_data_length: usize,
+        plain_data: *mut ::std::os::raw::c_void,
+        plain_data_length: *mut usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_asym_cipher_destroy(asym_cipher_ctx: *mut soter_asym_cipher_t) -> soter_status_t;
+}
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum soter_sign_alg_type {
+    SOTER_SIGN_undefined = 0,
+    SOTER_SIGN_rsa_pss_pkcs8 = 1,
+    SOTER_SIGN_ecdsa_none_pkcs8 = 2,
+}
+pub use self::soter_sign_alg_type as soter_sign_alg_t;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct soter_sign_ctx_type {
+    _unused: [u8; 0],
+}
+pub type soter_sign_ctx_t = soter_sign_ctx_type;
+pub type soter_verify_ctx_t = soter_sign_ctx_type;
+extern "C" {
+    pub fn soter_sign_create(
+        alg: soter_sign_alg_t,
+        private_key: *const ::std::os::raw::c_void,
+        private_key_length: usize,
+        public_key: *const ::std::os::raw::c_void,
+        public_key_length: usize,
+    ) -> *mut soter_sign_ctx_t;
+}
+extern "C" {
+    pub fn soter_sign_update(
+        ctx: *mut soter_sign_ctx_t,
+        data: *const ::std::os::raw::c_void,
+        data_length: usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sign_final(
+        ctx: *mut soter_sign_ctx_t,
+        signature: *mut ::std::os::raw::c_void,
+        signature_length: *mut usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sign_export_key(
+        ctx: *mut soter_sign_ctx_t,
+        key: *mut ::std::os::raw::c_void,
+        key_length: *mut usize,
+        isprivate: bool,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sign_destroy(ctx: *mut soter_sign_ctx_t) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_sign_get_alg_id(ctx: *mut soter_sign_ctx_t) -> soter_sign_alg_t;
+}
+extern "C" {
+    pub fn soter_verify_create(
+        alg: soter_sign_alg_t,
+        private_key: *const ::std::os::raw::c_void,
+        private_key_length: usize,
+        public_key: *const ::std::os::raw::c_void,
+        public_key_length: usize,
+    ) -> *mut soter_verify_ctx_t;
+}
+extern "C" {
+    pub fn soter_verify_update(
+        ctx: *mut soter_verify_ctx_t,
+        data: *const ::std::os::raw::c_void,
+        data_length: usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_verify_final(
+        ctx: *mut soter_verify_ctx_t,
+        signature: *const ::std::os::raw::c_void,
+        signature_length: usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_verify_destroy(ctx: *mut soter_verify_ctx_t) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_verify_get_alg_id(ctx: *mut soter_verify_ctx_t) -> soter_sign_alg_t;
+}
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum soter_asym_ka_alg_type {
+    SOTER_ASYM_KA_EC_P256 = 0,
+}
+pub use self::soter_asym_ka_alg_type as soter_asym_ka_alg_t;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct soter_asym_ka_type {
+    _unused: [u8; 0],
+}
+pub type soter_asym_ka_t = soter_asym_ka_type;
+extern "C" {
+    pub fn soter_asym_ka_create(alg: soter_asym_ka_alg_t) -> *mut soter_asym_ka_t;
+}
+extern "C" {
+    pub fn soter_asym_ka_gen_key(asym_ka_ctx: *mut soter_asym_ka_t) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_asym_ka_export_key(
+        asym_ka_ctx: *mut soter_asym_ka_t,
+        key: *mut ::std::os::raw::c_void,
+        key_length: *mut usize,
+        isprivate: bool,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_asym_ka_import_key(
+        asym_ka_ctx: *mut soter_asym_ka_t,
+        key: *const ::std::os::raw::c_void,
+        key_length: usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_asym_ka_derive(
+        asym_ka_ctx: *mut soter_asym_ka_t,
+        peer_key: *const ::std::os::raw::c_void,
+        peer_key_length: usize,
+        shared_secret: *mut ::std::os::raw::c_void,
+        shared_secret_length: *mut usize,
+    ) -> soter_status_t;
+}
+extern "C" {
+    pub fn soter_asym_ka_destroy(asym_ka_ctx: *mut soter_asym_ka_t) -> soter_status_t;
+}
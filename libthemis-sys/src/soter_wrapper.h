@@ -0,0 +1,52 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Wrapper header for bindgen used with `soter-only` feature, without Themis headers.
+
+// Individual headers cannot be included before the umbrella one.
+#include <soter/soter.h>
+
+#include <soter/soter_hash.h>
+#include <soter/soter_hmac.h>
+#include <soter/soter_kdf.h>
+#include <soter/soter_rand.h>
+#include <soter/soter_sym.h>
+#include <soter/soter_asym_cipher.h>
+#include <soter/soter_asym_ka.h>
+#include <soter/soter_asym_sign.h>
+#include <soter/soter_container.h>
+
+// Themis status codes are used by error handling in Rust, they are the same as in
+// <themis/themis_error.h> which we cannot include here.
+
+#include <stdint.h>
+
+typedef int32_t themis_status_t;
+
+#define THEMIS_SUCCESS 0
+#define THEMIS_SSESSION_SEND_OUTPUT_TO_PEER 1
+#define THEMIS_FAIL 11
+#define THEMIS_INVALID_PARAMETER 12
+#define THEMIS_NO_MEMORY 13
+#define THEMIS_BUFFER_TOO_SMALL 14
+#define THEMIS_DATA_CORRUPT 15
+#define THEMIS_INVALID_SIGNATURE 16
+#define THEMIS_NOT_SUPPORTED 17
+#define THEMIS_SSESSION_KA_NOT_FINISHED 19
+#define THEMIS_SSESSION_TRANSPORT_ERROR 20
+#define THEMIS_SSESSION_GET_PUB_FOR_ID_CALLBACK_ERROR 21
+#define THEMIS_SCOMPARE_SEND_OUTPUT_TO_PEER 1
+#define THEMIS_SCOMPARE_MATCH 21
+#define THEMIS_SCOMPARE_NO_MATCH 22
+#define THEMIS_SCOMPARE_NOT_READY 0
@@ -14,13 +14,21 @@
 
 #include "wrapper.h"
 
+#include <stdbool.h>
 #include <string.h>
 
+#ifdef _WIN32
+#include <winsock2.h>
+#else
 #include <arpa/inet.h>
+#endif
 
+#include <soter/soter_asym_ka.h>
+#include <soter/soter_asym_sign.h>
 #include <soter/soter_container.h>
 #include <soter/soter_ec_key.h>
 #include <soter/soter_rsa_key.h>
+#include <soter/soter_rsa_key_pair_gen.h>
 
 themis_status_t themis_is_valid_key(const uint8_t *key, size_t length)
 {
@@ -68,6 +76,22 @@ themis_status_t themis_is_valid_key(const uint8_t *key, size_t length)
     return THEMIS_SUCCESS;
 }
 
+themis_status_t themis_update_key_container(uint8_t *key, size_t length)
+{
+    // See themis_is_valid_key() for a discussion of this cast.
+    soter_container_hdr_t *container = (void*) key;
+
+    if (!key || (length < sizeof(soter_container_hdr_t)))
+    {
+        return THEMIS_INVALID_PARAMETER;
+    }
+
+    container->size = htonl(length);
+    soter_update_container_checksum(container);
+
+    return THEMIS_SUCCESS;
+}
+
 enum themis_key_kind themis_get_key_kind(const uint8_t *key, size_t length)
 {
     if (!key || (length < sizeof(soter_container_hdr_t)))
@@ -94,3 +118,322 @@ enum themis_key_kind themis_get_key_kind(const uint8_t *key, size_t length)
 
     return THEMIS_KEY_INVALID;
 }
+
+themis_status_t themis_gen_rsa_key_pair_with_size(unsigned bits,
+                                                  uint8_t *private_key,
+                                                  size_t *private_key_length,
+                                                  uint8_t *public_key,
+                                                  size_t *public_key_length)
+{
+    unsigned key_length = 0;
+    soter_rsa_key_pair_gen_t *ctx = NULL;
+    soter_status_t private_res = SOTER_FAIL;
+    soter_status_t public_res = SOTER_FAIL;
+
+    // Soter uses its own constants for key lengths, not bit counts.
+    switch (bits)
+    {
+    case 1024:
+        key_length = RSA_KEY_LENGTH_1024;
+        break;
+    case 2048:
+        key_length = RSA_KEY_LENGTH_2048;
+        break;
+    case 4096:
+        key_length = RSA_KEY_LENGTH_4096;
+        break;
+    case 8192:
+        key_length = RSA_KEY_LENGTH_8192;
+        break;
+    default:
+        return THEMIS_INVALID_PARAMETER;
+    }
+
+    ctx = soter_rsa_key_pair_gen_create(key_length);
+    if (!ctx)
+    {
+        return THEMIS_FAIL;
+    }
+
+    private_res = soter_rsa_key_pair_gen_export_key(ctx, private_key, private_key_length, true);
+    if (private_res != SOTER_SUCCESS && private_res != SOTER_BUFFER_TOO_SMALL)
+    {
+        soter_rsa_key_pair_gen_destroy(ctx);
+        return private_res;
+    }
+
+    public_res = soter_rsa_key_pair_gen_export_key(ctx, public_key, public_key_length, false);
+    if (public_res != SOTER_SUCCESS && public_res != SOTER_BUFFER_TOO_SMALL)
+    {
+        soter_rsa_key_pair_gen_destroy(ctx);
+        return public_res;
+    }
+
+    soter_rsa_key_pair_gen_destroy(ctx);
+
+    if (private_res == SOTER_BUFFER_TOO_SMALL || public_res == SOTER_BUFFER_TOO_SMALL)
+    {
+        return THEMIS_BUFFER_TOO_SMALL;
+    }
+
+    return THEMIS_SUCCESS;
+}
+
+// Compressed generator points of supported curves (SEC 2, section 2.4).
+
+static const uint8_t ec_generator_256[] = {
+    0x03,
+    0x6b, 0x17, 0xd1, 0xf2, 0xe1, 0x2c, 0x42, 0x47, 0xf8, 0xbc, 0xe6, 0xe5, 0x63, 0xa4, 0x40, 0xf2,
+    0x77, 0x03, 0x7d, 0x81, 0x2d, 0xeb, 0x33, 0xa0, 0xf4, 0xa1, 0x39, 0x45, 0xd8, 0x98, 0xc2, 0x96,
+};
+
+static const uint8_t ec_generator_384[] = {
+    0x03,
+    0xaa, 0x87, 0xca, 0x22, 0xbe, 0x8b, 0x05, 0x37, 0x8e, 0xb1, 0xc7, 0x1e, 0xf3, 0x20, 0xad, 0x74,
+    0x6e, 0x1d, 0x3b, 0x62, 0x8b, 0xa7, 0x9b, 0x98, 0x59, 0xf7, 0x41, 0xe0, 0x82, 0x54, 0x2a, 0x38,
+    0x55, 0x02, 0xf2, 0x5d, 0xbf, 0x55, 0x29, 0x6c, 0x3a, 0x54, 0x5e, 0x38, 0x72, 0x76, 0x0a, 0xb7,
+};
+
+static const uint8_t ec_generator_521[] = {
+    0x02,
+    0x00, 0xc6, 0x85, 0x8e, 0x06, 0xb7, 0x04, 0x04, 0xe9, 0xcd, 0x9e, 0x3e, 0xcb, 0x66, 0x23, 0x95,
+    0xb4, 0x42, 0x9c, 0x64, 0x81, 0x39, 0x05, 0x3f, 0xb5, 0x21, 0xf8, 0x28, 0xaf, 0x60, 0x6b, 0x4d,
+    0x3d, 0xba, 0xa1, 0x4b, 0x5e, 0x77, 0xef, 0xe7, 0x59, 0x28, 0xfe, 0x1d, 0xc1, 0x27, 0xa2, 0xff,
+    0xa8, 0xde, 0x33, 0x48, 0xb3, 0xc1, 0x85, 0x6a, 0x42, 0x9b, 0xf9, 0x7e, 0x7e, 0x31, 0xc2, 0xe5,
+    0xbd, 0x66,
+};
+
+// Enough for ECDSA signatures over all supported curves (DER-encoded).
+#define EC_MAX_SIGNATURE_LENGTH 160
+
+static const uint8_t ec_test_message[] = "Themis public key derivation";
+
+static void set_container_header(uint8_t *key, const char *prefix, char suffix, size_t length)
+{
+    // See themis_is_valid_key() for a discussion of this cast.
+    soter_container_hdr_t *container = (void*) key;
+
+    memcpy(container->tag, prefix, SOTER_CONTAINER_TAG_LENGTH - 1);
+    container->tag[SOTER_CONTAINER_TAG_LENGTH - 1] = suffix;
+    container->size = htonl(length);
+    soter_update_container_checksum(container);
+}
+
+// RSA private key container includes the modulus and public exponent so we simply copy them.
+static themis_status_t rsa_public_key_from_private(const uint8_t *private_key,
+                                                   size_t private_key_length,
+                                                   uint8_t *public_key,
+                                                   size_t *public_key_length)
+{
+    size_t modulus_length = 0;
+    size_t modulus_offset = 0;
+    size_t expected_length = 0;
+
+    switch (private_key[SOTER_CONTAINER_TAG_LENGTH - 1])
+    {
+    case '1':
+        modulus_length = RSA_BYTE_SIZE(1024);
+        break;
+    case '2':
+        modulus_length = RSA_BYTE_SIZE(2048);
+        break;
+    case '4':
+        modulus_length = RSA_BYTE_SIZE(4096);
+        break;
+    case '8':
+        modulus_length = RSA_BYTE_SIZE(8192);
+        break;
+    default:
+        return THEMIS_INVALID_PARAMETER;
+    }
+
+    // Private key layout: header, private exponent, 5 CRT parameters, modulus, public exponent.
+    modulus_offset = sizeof(soter_container_hdr_t) + modulus_length + 5 * (modulus_length / 2);
+    expected_length = modulus_offset + modulus_length + sizeof(uint32_t);
+    if (private_key_length != expected_length)
+    {
+        return THEMIS_INVALID_PARAMETER;
+    }
+
+    if (!public_key || *public_key_length < sizeof(soter_container_hdr_t) + modulus_length + sizeof(uint32_t))
+    {
+        *public_key_length = sizeof(soter_container_hdr_t) + modulus_length + sizeof(uint32_t);
+        return THEMIS_BUFFER_TOO_SMALL;
+    }
+    *public_key_length = sizeof(soter_container_hdr_t) + modulus_length + sizeof(uint32_t);
+
+    memcpy(public_key + sizeof(soter_container_hdr_t),
+           private_key + modulus_offset,
+           modulus_length + sizeof(uint32_t));
+
+    set_container_header(public_key, RSA_PUB_KEY_PREF,
+                         private_key[SOTER_CONTAINER_TAG_LENGTH - 1], *public_key_length);
+
+    return THEMIS_SUCCESS;
+}
+
+static bool ec_public_key_matches(const uint8_t *signature,
+                                  size_t signature_length,
+                                  const uint8_t *public_key,
+                                  size_t public_key_length)
+{
+    soter_verify_ctx_t *ctx = NULL;
+    soter_status_t res = SOTER_FAIL;
+
+    ctx = soter_verify_create(SOTER_SIGN_ecdsa_none_pkcs8, NULL, 0, public_key, public_key_length);
+    if (!ctx)
+    {
+        return false;
+    }
+
+    res = soter_verify_update(ctx, ec_test_message, sizeof(ec_test_message));
+    if (res == SOTER_SUCCESS)
+    {
+        res = soter_verify_final(ctx, signature, signature_length);
+    }
+
+    soter_verify_destroy(ctx);
+
+    return res == SOTER_SUCCESS;
+}
+
+// Soter does not provide EC point multiplication, so we use a trick: ECDH with the curve
+// generator yields the x-coordinate of the public key point. Then we guess the y-coordinate
+// parity and check the guess by verifying a signature made with the private key.
+static themis_status_t ec_public_key_from_private(const uint8_t *private_key,
+                                                  size_t private_key_length,
+                                                  uint8_t *public_key,
+                                                  size_t *public_key_length)
+{
+    const uint8_t *generator = NULL;
+    size_t point_length = 0;
+    char suffix = private_key[SOTER_CONTAINER_TAG_LENGTH - 1];
+    uint8_t generator_key[sizeof(soter_container_hdr_t) + sizeof(ec_generator_521)];
+    uint8_t signature[EC_MAX_SIGNATURE_LENGTH];
+    size_t signature_length = sizeof(signature);
+    size_t coordinate_length = 0;
+    soter_asym_ka_t *ka = NULL;
+    soter_sign_ctx_t *sign = NULL;
+    soter_status_t res = SOTER_FAIL;
+
+    switch (suffix)
+    {
+    case '2':
+        generator = ec_generator_256;
+        point_length = sizeof(ec_generator_256);
+        break;
+    case '3':
+        generator = ec_generator_384;
+        point_length = sizeof(ec_generator_384);
+        break;
+    case '5':
+        generator = ec_generator_521;
+        point_length = sizeof(ec_generator_521);
+        break;
+    default:
+        return THEMIS_INVALID_PARAMETER;
+    }
+
+    if (!public_key || *public_key_length < sizeof(soter_container_hdr_t) + point_length)
+    {
+        *public_key_length = sizeof(soter_container_hdr_t) + point_length;
+        return THEMIS_BUFFER_TOO_SMALL;
+    }
+    *public_key_length = sizeof(soter_container_hdr_t) + point_length;
+
+    memcpy(generator_key + sizeof(soter_container_hdr_t), generator, point_length);
+    set_container_header(generator_key, EC_PUB_KEY_PREF, suffix,
+                         sizeof(soter_container_hdr_t) + point_length);
+
+    ka = soter_asym_ka_create(SOTER_ASYM_KA_EC_P256);
+    if (!ka)
+    {
+        return THEMIS_FAIL;
+    }
+
+    res = soter_asym_ka_import_key(ka, private_key, private_key_length);
+    if (res == SOTER_SUCCESS)
+    {
+        coordinate_length = point_length - 1;
+        res = soter_asym_ka_derive(ka, generator_key, sizeof(soter_container_hdr_t) + point_length,
+                                   public_key + sizeof(soter_container_hdr_t) + 1,
+                                   &coordinate_length);
+    }
+
+    soter_asym_ka_destroy(ka);
+
+    if (res != SOTER_SUCCESS)
+    {
+        return res;
+    }
+    if (coordinate_length != point_length - 1)
+    {
+        return THEMIS_FAIL;
+    }
+
+    sign = soter_sign_create(SOTER_SIGN_ecdsa_none_pkcs8, private_key, private_key_length, NULL, 0);
+    if (!sign)
+    {
+        return THEMIS_FAIL;
+    }
+
+    res = soter_sign_update(sign, ec_test_message, sizeof(ec_test_message));
+    if (res == SOTER_SUCCESS)
+    {
+        res = soter_sign_final(sign, signature, &signature_length);
+    }
+
+    soter_sign_destroy(sign);
+
+    if (res != SOTER_SUCCESS)
+    {
+        return res;
+    }
+
+    public_key[sizeof(soter_container_hdr_t)] = 0x02;
+    set_container_header(public_key, EC_PUB_KEY_PREF, suffix, *public_key_length);
+    if (ec_public_key_matches(signature, signature_length, public_key, *public_key_length))
+    {
+        return THEMIS_SUCCESS;
+    }
+
+    public_key[sizeof(soter_container_hdr_t)] = 0x03;
+    set_container_header(public_key, EC_PUB_KEY_PREF, suffix, *public_key_length);
+    if (ec_public_key_matches(signature, signature_length, public_key, *public_key_length))
+    {
+        return THEMIS_SUCCESS;
+    }
+
+    return THEMIS_FAIL;
+}
+
+themis_status_t themis_get_public_key(const uint8_t *private_key,
+                                      size_t private_key_length,
+                                      uint8_t *public_key,
+                                      size_t *public_key_length)
+{
+    themis_status_t res = THEMIS_FAIL;
+
+    if (!public_key_length)
+    {
+        return THEMIS_INVALID_PARAMETER;
+    }
+
+    res = themis_is_valid_key(private_key, private_key_length);
+    if (res != THEMIS_SUCCESS)
+    {
+        return res;
+    }
+
+    switch (themis_get_key_kind(private_key, private_key_length))
+    {
+    case THEMIS_KEY_RSA_PRIVATE:
+        return rsa_public_key_from_private(private_key, private_key_length,
+                                           public_key, public_key_length);
+    case THEMIS_KEY_EC_PRIVATE:
+        return ec_public_key_from_private(private_key, private_key_length,
+                                          public_key, public_key_length);
+    default:
+        return THEMIS_INVALID_PARAMETER;
+    }
+}
@@ -16,6 +16,18 @@
 
 #include <themis/themis.h>
 
+// Soter primitives which are useful on their own but not exported by Themis API.
+
+#include <soter/soter_hash.h>
+#include <soter/soter_hmac.h>
+#include <soter/soter_kdf.h>
+#include <soter/soter_rand.h>
+#include <soter/soter_sym.h>
+#include <soter/soter_asym_cipher.h>
+#include <soter/soter_asym_ka.h>
+#include <soter/soter_asym_sign.h>
+#include <soter/soter_container.h>
+
 // TODO: move shims into Themis core
 //
 // These shims are here because they use C macros which are not exported by bindgen.
@@ -39,3 +51,19 @@ themis_status_t themis_is_valid_key(const uint8_t *key, size_t length);
 
 /// Extracts the presumed key kind from the buffer.
 enum themis_key_kind themis_get_key_kind(const uint8_t *key, size_t length);
+
+/// Generates RSA key pair of specified length (in bits): 1024, 2048, 4096, or 8192.
+themis_status_t themis_gen_rsa_key_pair_with_size(unsigned bits,
+                                                  uint8_t *private_key,
+                                                  size_t *private_key_length,
+                                                  uint8_t *public_key,
+                                                  size_t *public_key_length);
+
+/// Derives public key corresponding to the given private key.
+themis_status_t themis_get_public_key(const uint8_t *private_key,
+                                      size_t private_key_length,
+                                      uint8_t *public_key,
+                                      size_t *public_key_length);
+
+/// Updates size and checksum in the header of a key container.
+themis_status_t themis_update_key_container(uint8_t *key, size_t length);
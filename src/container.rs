@@ -0,0 +1,225 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Soter containers.
+//!
+//! Themis keys and messages are stored in **Soter containers**: a 12-byte header followed by
+//! payload. The header consists of
+//!
+//!   - 4-byte tag identifying the contents (e.g., `UEC2` for ECDSA public keys),
+//!   - 4-byte size of the whole container, including the header (big-endian),
+//!   - 4-byte CRC-32C checksum of the whole container, computed with this field zeroed.
+//!
+//! This module allows to read and write containers. This is useful for inspecting data
+//! produced by Themis, debugging, and building tools which work with Themis data.
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> Result<(), themis::Error> {
+//! use themis::container::{self, Container};
+//!
+//! let bytes = container::build(b"TEST", b"payload")?;
+//!
+//! let container = Container::parse(&bytes)?;
+//!
+//! assert_eq!(container.tag(), b"TEST");
+//! assert_eq!(container.payload(), b"payload");
+//! # Ok(())
+//! # }
+//! ```
+
+use bindings::{themis_is_valid_key, themis_update_key_container};
+
+use crate::error::{Error, ErrorKind, Result, Status};
+use crate::utils::into_raw_parts;
+
+/// Length of container tag in bytes.
+pub const TAG_LEN: usize = 4;
+
+/// Length of container header in bytes.
+pub const HEADER_LEN: usize = 12;
+
+// Size is stored as a signed 32-bit integer.
+const MAX_CONTAINER_LEN: usize = 0x7FFF_FFFF;
+
+/// Soter container header.
+///
+/// Headers can be read without access to the whole container. Use [`Container`] to make sure
+/// that the container is complete and has not been corrupted.
+///
+/// [`Container`]: struct.Container.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    tag: [u8; TAG_LEN],
+    size: usize,
+    checksum: u32,
+}
+
+impl Header {
+    /// Reads container header from the start of the buffer.
+    ///
+    /// Returns [`InvalidParameter`] error if the buffer is too short to contain a header or if
+    /// the declared container size is invalid. This method does not check whether the buffer
+    /// contains the whole container.
+    ///
+    /// [`InvalidParameter`]: ../enum.ErrorKind.html#variant.InvalidParameter
+    pub fn read<B: AsRef<[u8]>>(bytes: B) -> Result<Header> {
+        let bytes = bytes.as_ref();
+        if bytes.len() < HEADER_LEN {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+
+        let mut tag = [0; TAG_LEN];
+        tag.copy_from_slice(&bytes[0..TAG_LEN]);
+        let size = read_u32(&bytes[4..8]) as usize;
+        let checksum = read_u32(&bytes[8..12]);
+
+        // Size includes the header and must fit into a signed 32-bit integer.
+        if size < HEADER_LEN {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+        if size > MAX_CONTAINER_LEN {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+
+        Ok(Header {
+            tag,
+            size,
+            checksum,
+        })
+    }
+
+    /// Returns container tag.
+    pub fn tag(&self) -> &[u8] {
+        &self.tag
+    }
+
+    /// Returns size of the whole container, including the header.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns size of the container payload.
+    pub fn payload_len(&self) -> usize {
+        self.size - HEADER_LEN
+    }
+
+    /// Returns container checksum, as stored in the header.
+    pub fn checksum(&self) -> u32 {
+        self.checksum
+    }
+}
+
+pub(crate) fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from(bytes[0]) << 24
+        | u32::from(bytes[1]) << 16
+        | u32::from(bytes[2]) << 8
+        | u32::from(bytes[3])
+}
+
+/// Complete Soter container with verified checksum.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Container<'a> {
+    header: Header,
+    bytes: &'a [u8],
+}
+
+impl<'a> Container<'a> {
+    /// Parses a container occupying the whole buffer.
+    ///
+    /// Returns [`InvalidParameter`] error if the buffer does not contain exactly one container,
+    /// or [`DataCorrupt`] error if the container checksum is invalid.
+    ///
+    /// [`InvalidParameter`]: ../enum.ErrorKind.html#variant.InvalidParameter
+    /// [`DataCorrupt`]: ../enum.ErrorKind.html#variant.DataCorrupt
+    pub fn parse(bytes: &'a [u8]) -> Result<Container<'a>> {
+        let (container, rest) = Container::parse_prefix(bytes)?;
+        if !rest.is_empty() {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+        Ok(container)
+    }
+
+    /// Parses a container at the start of the buffer.
+    ///
+    /// Returns the container and the rest of the buffer following it. Errors are the same as
+    /// for [`parse`].
+    ///
+    /// [`parse`]: struct.Container.html#method.parse
+    pub fn parse_prefix(bytes: &'a [u8]) -> Result<(Container<'a>, &'a [u8])> {
+        let header = Header::read(bytes)?;
+        if bytes.len() < header.size() {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+        let (bytes, rest) = bytes.split_at(header.size());
+
+        // Copy the container to make sure that the header is properly aligned for C code.
+        let aligned = bytes.to_vec();
+        let (ptr, len) = into_raw_parts(&aligned);
+        let status = unsafe { themis_is_valid_key(ptr, len) };
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
+        }
+
+        Ok((Container { header, bytes }, rest))
+    }
+
+    /// Returns container header.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Returns container tag.
+    pub fn tag(&self) -> &[u8] {
+        self.header.tag()
+    }
+
+    /// Returns container payload.
+    pub fn payload(&self) -> &'a [u8] {
+        &self.bytes[HEADER_LEN..]
+    }
+
+    /// Returns the whole container, including the header.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+/// Builds a new container with given tag and payload.
+///
+/// Returns [`InvalidParameter`] error if the payload is too long to fit into a container.
+///
+/// [`InvalidParameter`]: ../enum.ErrorKind.html#variant.InvalidParameter
+pub fn build<P: AsRef<[u8]>>(tag: &[u8; TAG_LEN], payload: P) -> Result<Vec<u8>> {
+    let payload = payload.as_ref();
+    if payload.len() > MAX_CONTAINER_LEN - HEADER_LEN {
+        return Err(Error::with_kind(ErrorKind::InvalidParameter));
+    }
+
+    let mut container = Vec::with_capacity(HEADER_LEN + payload.len());
+    container.extend_from_slice(tag);
+    container.resize(HEADER_LEN, 0);
+    container.extend_from_slice(payload);
+
+    // This fills in both the size and the checksum.
+    let status = unsafe { themis_update_key_container(container.as_mut_ptr(), container.len()) };
+    let status = Status::from_themis(status);
+    if status != Status::Success {
+        return Err(status.into());
+    }
+
+    Ok(container)
+}
@@ -0,0 +1,806 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion of keys to and from standard DER encodings.
+//!
+//! Themis keys are stored in Soter containers: a 12-byte header (tag, size, checksum) followed
+//! by fixed-width big-endian numbers. Standard encodings supported here are:
+//!
+//!   - SEC 1 `ECPrivateKey` (RFC 5915) for ECDSA secret keys
+//!   - PKCS #1 `RSAPrivateKey` and `RSAPublicKey` (RFC 8017) for RSA keys
+//!   - PKCS #8 `PrivateKeyInfo` (RFC 5208) for secret keys of either kind
+//!   - X.509 `SubjectPublicKeyInfo` (RFC 5280) for public keys of either kind
+//!
+//! This is the same DER that `openssl pkey -outform DER` produces and consumes. Only what
+//! Themis can actually use is supported: NIST P-256, P-384, P-521 curves; 1024, 2048, 4096,
+//! 8192-bit RSA keys with public exponent 3 or 65537. Other keys are rejected with
+//! `ErrorKind::NotSupported`, malformed DER is rejected with `ErrorKind::InvalidParameter`.
+
+use std::ops::{Deref, DerefMut};
+
+use bindings::themis_update_key_container;
+use zeroize::Zeroize;
+
+use crate::error::{Error, ErrorKind, Result, Status};
+use crate::keys::{
+    EcdsaPublicKey, EcdsaSecretKey, KeyKind, PublicKey, RsaPublicKey, RsaSecretKey, SecretKey,
+};
+
+// Soter container header: 4-byte tag, 4-byte size, 4-byte checksum.
+const HEADER_LENGTH: usize = 12;
+
+// Extra capacity for DER headers, enough for all supported keys.
+const DER_OVERHEAD: usize = 128;
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_CONTEXT_0: u8 = 0xA0;
+const TAG_CONTEXT_1: u8 = 0xA1;
+
+// Object identifiers, DER-encoded content only.
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01];
+const OID_PRIME256V1: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07];
+const OID_SECP384R1: &[u8] = &[0x2B, 0x81, 0x04, 0x00, 0x22];
+const OID_SECP521R1: &[u8] = &[0x2B, 0x81, 0x04, 0x00, 0x23];
+
+const RSA_EXPONENTS: &[u32] = &[3, 65537];
+
+fn invalid() -> Error {
+    Error::with_kind(ErrorKind::InvalidParameter)
+}
+
+fn not_supported() -> Error {
+    Error::with_kind(ErrorKind::NotSupported)
+}
+
+/// Byte buffer which is wiped on drop, for intermediate copies of secret keys.
+struct SecretBuffer(Vec<u8>);
+
+impl SecretBuffer {
+    /// Makes an empty buffer. Capacity should be large enough to avoid reallocations
+    /// which would leave unwiped copies of secrets.
+    fn with_capacity(capacity: usize) -> Self {
+        SecretBuffer(Vec::with_capacity(capacity))
+    }
+
+    // std::mem::take() is not available in Rust 1.31
+    #[allow(clippy::mem_replace_with_default)]
+    fn into_vec(mut self) -> Vec<u8> {
+        std::mem::replace(&mut self.0, Vec::new())
+    }
+}
+
+impl Deref for SecretBuffer {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.0
+    }
+}
+
+impl DerefMut for SecretBuffer {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.0
+    }
+}
+
+impl Drop for SecretBuffer {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+//
+// Elliptic curves
+//
+
+struct Curve {
+    /// Last byte of Soter container tag.
+    suffix: u8,
+    oid: &'static [u8],
+    /// Size of field elements in bytes.
+    size: usize,
+}
+
+const CURVES: &[Curve] = &[
+    Curve {
+        suffix: b'2',
+        oid: OID_PRIME256V1,
+        size: 32,
+    },
+    Curve {
+        suffix: b'3',
+        oid: OID_SECP384R1,
+        size: 48,
+    },
+    Curve {
+        suffix: b'5',
+        oid: OID_SECP521R1,
+        size: 66,
+    },
+];
+
+/// Returns curve of an ECDSA key, checking that the key has expected length.
+fn ec_key_curve(key: &[u8]) -> Result<&'static Curve> {
+    let curve = CURVES
+        .iter()
+        .find(|curve| curve.suffix == key[3])
+        .ok_or_else(invalid)?;
+    // Both secret and public keys have an extra byte: zero padding or point format.
+    if key.len() != HEADER_LENGTH + curve.size + 1 {
+        return Err(invalid());
+    }
+    Ok(curve)
+}
+
+fn curve_by_oid(oid: &[u8]) -> Result<&'static Curve> {
+    CURVES
+        .iter()
+        .find(|curve| curve.oid == oid)
+        .ok_or_else(not_supported)
+}
+
+//
+// RSA key sizes
+//
+
+/// Returns RSA modulus length in bytes, checking that the key has expected length.
+///
+/// Themis RSA secret key layout: header, private exponent, p, q, dp, dq, qp, modulus, public
+/// exponent. Public key layout: header, modulus, public exponent.
+fn rsa_modulus_length(key: &[u8], secret: bool) -> Result<usize> {
+    let n = match key[3] {
+        b'1' => 128,
+        b'2' => 256,
+        b'4' => 512,
+        b'8' => 1024,
+        _ => return Err(invalid()),
+    };
+    let expected_length = if secret {
+        HEADER_LENGTH + n + 5 * (n / 2) + n + 4
+    } else {
+        HEADER_LENGTH + n + 4
+    };
+    if key.len() != expected_length {
+        return Err(invalid());
+    }
+    Ok(n)
+}
+
+/// Returns Soter container tag suffix for RSA modulus of given length in bytes.
+fn rsa_suffix(modulus_length: usize) -> Result<u8> {
+    match modulus_length {
+        128 => Ok(b'1'),
+        256 => Ok(b'2'),
+        512 => Ok(b'4'),
+        1024 => Ok(b'8'),
+        _ => Err(not_supported()),
+    }
+}
+
+fn rsa_exponent(exponent: &[u8]) -> Result<[u8; 4]> {
+    if exponent.len() > 4 {
+        return Err(not_supported());
+    }
+    let mut bytes = [0; 4];
+    bytes[4 - exponent.len()..].copy_from_slice(exponent);
+    let value = u32::from(bytes[0]) << 24
+        | u32::from(bytes[1]) << 16
+        | u32::from(bytes[2]) << 8
+        | u32::from(bytes[3]);
+    if !RSA_EXPONENTS.contains(&value) {
+        return Err(not_supported());
+    }
+    Ok(bytes)
+}
+
+//
+// DER writer
+//
+
+fn write_length(output: &mut Vec<u8>, length: usize) {
+    if length < 0x80 {
+        output.push(length as u8);
+    } else if length <= 0xFF {
+        output.push(0x81);
+        output.push(length as u8);
+    } else {
+        debug_assert!(length <= 0xFFFF);
+        output.push(0x82);
+        output.push((length >> 8) as u8);
+        output.push(length as u8);
+    }
+}
+
+fn write_tlv(output: &mut Vec<u8>, tag: u8, content: &[u8]) {
+    output.push(tag);
+    write_length(output, content.len());
+    output.extend_from_slice(content);
+}
+
+/// Writes a non-negative INTEGER from its big-endian representation.
+fn write_unsigned(output: &mut Vec<u8>, value: &[u8]) {
+    let value = strip_leading_zeros(value);
+    output.push(TAG_INTEGER);
+    if value.is_empty() {
+        write_length(output, 1);
+        output.push(0);
+    } else if value[0] & 0x80 != 0 {
+        write_length(output, value.len() + 1);
+        output.push(0);
+        output.extend_from_slice(value);
+    } else {
+        write_length(output, value.len());
+        output.extend_from_slice(value);
+    }
+}
+
+fn write_bit_string(output: &mut Vec<u8>, content: &[u8]) {
+    output.push(TAG_BIT_STRING);
+    write_length(output, content.len() + 1);
+    output.push(0); // no unused bits
+    output.extend_from_slice(content);
+}
+
+fn write_algorithm(output: &mut Vec<u8>, algorithm: &[u8], curve: Option<&Curve>) {
+    let mut content = Vec::new();
+    write_tlv(&mut content, TAG_OID, algorithm);
+    match curve {
+        Some(curve) => write_tlv(&mut content, TAG_OID, curve.oid),
+        None => write_tlv(&mut content, TAG_NULL, &[]),
+    }
+    write_tlv(output, TAG_SEQUENCE, &content);
+}
+
+fn strip_leading_zeros(value: &[u8]) -> &[u8] {
+    let zeros = value.iter().take_while(|&&byte| byte == 0).count();
+    &value[zeros..]
+}
+
+//
+// DER reader
+//
+
+struct Reader<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Reader { input }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    fn peek_tag(&self) -> Option<u8> {
+        self.input.first().cloned()
+    }
+
+    fn read(&mut self, tag: u8) -> Result<&'a [u8]> {
+        if self.peek_tag() != Some(tag) {
+            return Err(invalid());
+        }
+        let (length, header) = match self.input.get(1) {
+            Some(&length) if length < 0x80 => (usize::from(length), 2),
+            Some(&0x81) => match self.input.get(2) {
+                Some(&length) if length >= 0x80 => (usize::from(length), 3),
+                _ => return Err(invalid()),
+            },
+            Some(&0x82) => match (self.input.get(2), self.input.get(3)) {
+                (Some(&high), Some(&low)) if high != 0 => {
+                    (usize::from(high) << 8 | usize::from(low), 4)
+                }
+                _ => return Err(invalid()),
+            },
+            _ => return Err(invalid()),
+        };
+        if self.input.len() < header + length {
+            return Err(invalid());
+        }
+        let content = &self.input[header..header + length];
+        self.input = &self.input[header + length..];
+        Ok(content)
+    }
+
+    fn read_optional(&mut self, tag: u8) -> Result<Option<&'a [u8]>> {
+        if self.peek_tag() == Some(tag) {
+            self.read(tag).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Reads a non-negative INTEGER and returns its minimal big-endian representation.
+    fn read_unsigned(&mut self) -> Result<&'a [u8]> {
+        let value = self.read(TAG_INTEGER)?;
+        match value.first() {
+            None => Err(invalid()),
+            Some(&byte) if byte & 0x80 != 0 => Err(invalid()),
+            Some(_) => Ok(strip_leading_zeros(value)),
+        }
+    }
+
+    fn read_version(&mut self, expected: &[u8]) -> Result<()> {
+        let version = self.read_unsigned()?;
+        if version.is_empty() && expected.contains(&0) {
+            return Ok(());
+        }
+        if version.len() == 1 && expected.contains(&version[0]) {
+            return Ok(());
+        }
+        Err(not_supported())
+    }
+
+    fn read_bit_string(&mut self) -> Result<&'a [u8]> {
+        match self.read(TAG_BIT_STRING)?.split_first() {
+            Some((&0, content)) => Ok(content),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Reads AlgorithmIdentifier and returns the curve for EC keys, or `None` for RSA keys.
+    fn read_algorithm(&mut self) -> Result<Option<&'static Curve>> {
+        let mut algorithm = Reader::new(self.read(TAG_SEQUENCE)?);
+        let oid = algorithm.read(TAG_OID)?;
+        let curve = if oid == OID_EC_PUBLIC_KEY {
+            Some(curve_by_oid(algorithm.read(TAG_OID)?)?)
+        } else if oid == OID_RSA_ENCRYPTION {
+            algorithm.read_optional(TAG_NULL)?;
+            None
+        } else {
+            return Err(not_supported());
+        };
+        algorithm.finish()?;
+        Ok(curve)
+    }
+
+    fn finish(self) -> Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+/// Appends a number left-padded with zeros to fixed width.
+fn put_fixed(output: &mut Vec<u8>, value: &[u8], width: usize) -> Result<()> {
+    let value = strip_leading_zeros(value);
+    if value.len() > width {
+        return Err(invalid());
+    }
+    output.resize(output.len() + width - value.len(), 0);
+    output.extend_from_slice(value);
+    Ok(())
+}
+
+/// Starts a Soter container with given tag. Size and checksum are filled in by `seal()`.
+fn start_container(output: &mut Vec<u8>, prefix: &[u8], suffix: u8) {
+    output.extend_from_slice(prefix);
+    output.push(suffix);
+    output.resize(HEADER_LENGTH, 0);
+}
+
+fn seal(container: &mut Vec<u8>) -> Result<()> {
+    let status = unsafe { themis_update_key_container(container.as_mut_ptr(), container.len()) };
+    let status = Status::from_themis(status);
+    if status != Status::Success {
+        return Err(status.into());
+    }
+    Ok(())
+}
+
+//
+// ECDSA keys
+//
+
+fn encode_ec_private_key(secret_key: &[u8], with_parameters: bool) -> Result<SecretBuffer> {
+    let curve = ec_key_curve(secret_key)?;
+    // Soter stores the private scalar with an extra leading zero byte.
+    let scalar = &secret_key[HEADER_LENGTH + 1..];
+
+    let mut content = SecretBuffer::with_capacity(secret_key.len() + DER_OVERHEAD);
+    write_unsigned(&mut content, &[1]);
+    write_tlv(&mut content, TAG_OCTET_STRING, scalar);
+    if with_parameters {
+        let mut parameters = Vec::new();
+        write_tlv(&mut parameters, TAG_OID, curve.oid);
+        write_tlv(&mut content, TAG_CONTEXT_0, &parameters);
+    }
+
+    let mut output = SecretBuffer::with_capacity(secret_key.len() + DER_OVERHEAD);
+    write_tlv(&mut output, TAG_SEQUENCE, &content);
+    Ok(output)
+}
+
+fn decode_ec_private_key(der: &[u8], curve: Option<&'static Curve>) -> Result<SecretBuffer> {
+    let mut outer = Reader::new(der);
+    let mut key = Reader::new(outer.read(TAG_SEQUENCE)?);
+    outer.finish()?;
+
+    key.read_version(&[1])?;
+    let scalar = key.read(TAG_OCTET_STRING)?;
+    let curve = match (key.read_optional(TAG_CONTEXT_0)?, curve) {
+        (Some(parameters), expected) => {
+            let mut parameters = Reader::new(parameters);
+            let curve = curve_by_oid(parameters.read(TAG_OID)?)?;
+            parameters.finish()?;
+            if let Some(expected) = expected {
+                if expected.suffix != curve.suffix {
+                    return Err(invalid());
+                }
+            }
+            curve
+        }
+        (None, Some(curve)) => curve,
+        (None, None) => return Err(invalid()),
+    };
+    // Embedded public key is not needed, Themis does not store it.
+    key.read_optional(TAG_CONTEXT_1)?;
+    key.finish()?;
+
+    let mut container = SecretBuffer::with_capacity(der.len() + DER_OVERHEAD);
+    start_container(&mut container, b"REC", curve.suffix);
+    put_fixed(&mut container, scalar, curve.size + 1)?;
+    seal(&mut container)?;
+    Ok(container)
+}
+
+fn encode_ec_public_key(public_key: &[u8]) -> Result<Vec<u8>> {
+    let curve = ec_key_curve(public_key)?;
+    let point = &public_key[HEADER_LENGTH..];
+
+    let mut content = Vec::new();
+    write_algorithm(&mut content, OID_EC_PUBLIC_KEY, Some(curve));
+    write_bit_string(&mut content, point);
+
+    let mut output = Vec::new();
+    write_tlv(&mut output, TAG_SEQUENCE, &content);
+    Ok(output)
+}
+
+fn decode_ec_public_key(point: &[u8], curve: &Curve) -> Result<Vec<u8>> {
+    let mut container = Vec::new();
+    start_container(&mut container, b"UEC", curve.suffix);
+    match point.first() {
+        // Compressed point, Themis uses this form.
+        Some(&0x02) | Some(&0x03) if point.len() == 1 + curve.size => {
+            container.extend_from_slice(point);
+        }
+        // Uncompressed point, used by OpenSSL by default. Keep only y-coordinate parity.
+        Some(&0x04) if point.len() == 1 + 2 * curve.size => {
+            let (x, y) = point[1..].split_at(curve.size);
+            container.push(0x02 | (y[curve.size - 1] & 1));
+            container.extend_from_slice(x);
+        }
+        _ => return Err(invalid()),
+    }
+    seal(&mut container)?;
+    Ok(container)
+}
+
+//
+// RSA keys
+//
+
+fn encode_rsa_private_key(secret_key: &[u8]) -> Result<SecretBuffer> {
+    let n = rsa_modulus_length(secret_key, true)?;
+    let half = n / 2;
+    let data = &secret_key[HEADER_LENGTH..];
+    let (private_exponent, data) = data.split_at(n);
+    let (p, data) = data.split_at(half);
+    let (q, data) = data.split_at(half);
+    let (dp, data) = data.split_at(half);
+    let (dq, data) = data.split_at(half);
+    let (qp, data) = data.split_at(half);
+    let (modulus, public_exponent) = data.split_at(n);
+
+    let mut content = SecretBuffer::with_capacity(secret_key.len() + DER_OVERHEAD);
+    write_unsigned(&mut content, &[0]);
+    for value in &[modulus, public_exponent, private_exponent, p, q, dp, dq, qp] {
+        write_unsigned(&mut content, value);
+    }
+
+    let mut output = SecretBuffer::with_capacity(secret_key.len() + DER_OVERHEAD);
+    write_tlv(&mut output, TAG_SEQUENCE, &content);
+    Ok(output)
+}
+
+fn decode_rsa_private_key(der: &[u8]) -> Result<SecretBuffer> {
+    let mut outer = Reader::new(der);
+    let mut key = Reader::new(outer.read(TAG_SEQUENCE)?);
+    outer.finish()?;
+
+    // Multi-prime keys (version 1) are not supported by Themis.
+    key.read_version(&[0])?;
+    let modulus = key.read_unsigned()?;
+    let public_exponent = rsa_exponent(key.read_unsigned()?)?;
+    let private_exponent = key.read_unsigned()?;
+    let p = key.read_unsigned()?;
+    let q = key.read_unsigned()?;
+    let dp = key.read_unsigned()?;
+    let dq = key.read_unsigned()?;
+    let qp = key.read_unsigned()?;
+    key.finish()?;
+
+    let n = modulus.len();
+    let mut container = SecretBuffer::with_capacity(der.len() + DER_OVERHEAD);
+    start_container(&mut container, b"RRA", rsa_suffix(n)?);
+    put_fixed(&mut container, private_exponent, n)?;
+    for value in &[p, q, dp, dq, qp] {
+        put_fixed(&mut container, value, n / 2)?;
+    }
+    container.extend_from_slice(modulus);
+    container.extend_from_slice(&public_exponent);
+    seal(&mut container)?;
+    Ok(container)
+}
+
+fn encode_rsa_public_key(public_key: &[u8]) -> Result<Vec<u8>> {
+    let n = rsa_modulus_length(public_key, false)?;
+    let (modulus, public_exponent) = public_key[HEADER_LENGTH..].split_at(n);
+
+    let mut content = Vec::new();
+    write_unsigned(&mut content, modulus);
+    write_unsigned(&mut content, public_exponent);
+
+    let mut output = Vec::new();
+    write_tlv(&mut output, TAG_SEQUENCE, &content);
+    Ok(output)
+}
+
+fn decode_rsa_public_key(der: &[u8]) -> Result<Vec<u8>> {
+    let mut outer = Reader::new(der);
+    let mut key = Reader::new(outer.read(TAG_SEQUENCE)?);
+    outer.finish()?;
+
+    let modulus = key.read_unsigned()?;
+    let public_exponent = rsa_exponent(key.read_unsigned()?)?;
+    key.finish()?;
+
+    let mut container = Vec::new();
+    start_container(&mut container, b"URA", rsa_suffix(modulus.len())?);
+    container.extend_from_slice(modulus);
+    container.extend_from_slice(&public_exponent);
+    seal(&mut container)?;
+    Ok(container)
+}
+
+//
+// PKCS #8 and SubjectPublicKeyInfo
+//
+
+fn encode_private_key_info(secret_key: &[u8], kind: KeyKind) -> Result<SecretBuffer> {
+    let mut content = SecretBuffer::with_capacity(secret_key.len() + DER_OVERHEAD);
+    write_unsigned(&mut content, &[0]);
+    match kind {
+        KeyKind::EcdsaSecret => {
+            let curve = ec_key_curve(secret_key)?;
+            write_algorithm(&mut content, OID_EC_PUBLIC_KEY, Some(curve));
+            let key = encode_ec_private_key(secret_key, false)?;
+            write_tlv(&mut content, TAG_OCTET_STRING, &key);
+        }
+        KeyKind::RsaSecret => {
+            write_algorithm(&mut content, OID_RSA_ENCRYPTION, None);
+            let key = encode_rsa_private_key(secret_key)?;
+            write_tlv(&mut content, TAG_OCTET_STRING, &key);
+        }
+        KeyKind::EcdsaPublic | KeyKind::RsaPublic => unreachable!("secret key expected"),
+    }
+
+    let mut output = SecretBuffer::with_capacity(secret_key.len() + DER_OVERHEAD);
+    write_tlv(&mut output, TAG_SEQUENCE, &content);
+    Ok(output)
+}
+
+fn decode_private_key_info(der: &[u8]) -> Result<SecretBuffer> {
+    let mut outer = Reader::new(der);
+    let mut info = Reader::new(outer.read(TAG_SEQUENCE)?);
+    outer.finish()?;
+
+    // Version 1 (RFC 5958) may additionally contain the public key which we ignore.
+    info.read_version(&[0, 1])?;
+    let curve = info.read_algorithm()?;
+    let key = info.read(TAG_OCTET_STRING)?;
+    info.read_optional(TAG_CONTEXT_0)?;
+    info.read_optional(0x81)?;
+    info.finish()?;
+
+    match curve {
+        Some(curve) => decode_ec_private_key(key, Some(curve)),
+        None => decode_rsa_private_key(key),
+    }
+}
+
+fn encode_public_key_info(public_key: &[u8], kind: KeyKind) -> Result<Vec<u8>> {
+    match kind {
+        KeyKind::EcdsaPublic => encode_ec_public_key(public_key),
+        KeyKind::RsaPublic => {
+            let mut content = Vec::new();
+            write_algorithm(&mut content, OID_RSA_ENCRYPTION, None);
+            write_bit_string(&mut content, &encode_rsa_public_key(public_key)?);
+
+            let mut output = Vec::new();
+            write_tlv(&mut output, TAG_SEQUENCE, &content);
+            Ok(output)
+        }
+        KeyKind::EcdsaSecret | KeyKind::RsaSecret => unreachable!("public key expected"),
+    }
+}
+
+fn decode_public_key_info(der: &[u8]) -> Result<Vec<u8>> {
+    let mut outer = Reader::new(der);
+    let mut info = Reader::new(outer.read(TAG_SEQUENCE)?);
+    outer.finish()?;
+
+    let curve = info.read_algorithm()?;
+    let key = info.read_bit_string()?;
+    info.finish()?;
+
+    match curve {
+        Some(curve) => decode_ec_public_key(key, curve),
+        None => decode_rsa_public_key(key),
+    }
+}
+
+//
+// Public API
+//
+
+impl EcdsaSecretKey {
+    /// Encodes this key as SEC 1 `ECPrivateKey` structure in DER.
+    ///
+    /// This is the format of `openssl ec -outform DER`. The output contains secret key material,
+    /// consider wiping it after use.
+    pub fn to_sec1_der(&self) -> Result<Vec<u8>> {
+        encode_ec_private_key(self.as_ref(), true).map(SecretBuffer::into_vec)
+    }
+
+    /// Parses a key from SEC 1 `ECPrivateKey` structure in DER.
+    ///
+    /// The key must specify its curve.
+    pub fn from_sec1_der<T: AsRef<[u8]>>(der: T) -> Result<Self> {
+        let container = decode_ec_private_key(der.as_ref(), None)?;
+        Self::try_from_slice(&*container)
+    }
+
+    /// Encodes this key as PKCS #8 `PrivateKeyInfo` structure in DER.
+    ///
+    /// The output contains secret key material, consider wiping it after use.
+    pub fn to_pkcs8_der(&self) -> Result<Vec<u8>> {
+        encode_private_key_info(self.as_ref(), KeyKind::EcdsaSecret).map(SecretBuffer::into_vec)
+    }
+
+    /// Parses a key from PKCS #8 `PrivateKeyInfo` structure in DER.
+    ///
+    /// Only unencrypted keys are supported.
+    pub fn from_pkcs8_der<T: AsRef<[u8]>>(der: T) -> Result<Self> {
+        let container = decode_private_key_info(der.as_ref())?;
+        Self::try_from_slice(&*container)
+    }
+}
+
+impl EcdsaPublicKey {
+    /// Encodes this key as X.509 `SubjectPublicKeyInfo` structure in DER.
+    ///
+    /// This is the format of `openssl pkey -pubout -outform DER`.
+    pub fn to_spki_der(&self) -> Result<Vec<u8>> {
+        encode_public_key_info(self.as_ref(), KeyKind::EcdsaPublic)
+    }
+
+    /// Parses a key from X.509 `SubjectPublicKeyInfo` structure in DER.
+    ///
+    /// Both compressed and uncompressed points are accepted.
+    pub fn from_spki_der<T: AsRef<[u8]>>(der: T) -> Result<Self> {
+        let container = decode_public_key_info(der.as_ref())?;
+        Self::try_from_slice(&container)
+    }
+}
+
+impl RsaSecretKey {
+    /// Encodes this key as PKCS #1 `RSAPrivateKey` structure in DER.
+    ///
+    /// This is the format of `openssl rsa -outform DER`. The output contains secret key
+    /// material, consider wiping it after use.
+    pub fn to_pkcs1_der(&self) -> Result<Vec<u8>> {
+        encode_rsa_private_key(self.as_ref()).map(SecretBuffer::into_vec)
+    }
+
+    /// Parses a key from PKCS #1 `RSAPrivateKey` structure in DER.
+    pub fn from_pkcs1_der<T: AsRef<[u8]>>(der: T) -> Result<Self> {
+        let container = decode_rsa_private_key(der.as_ref())?;
+        Self::try_from_slice(&*container)
+    }
+
+    /// Encodes this key as PKCS #8 `PrivateKeyInfo` structure in DER.
+    ///
+    /// The output contains secret key material, consider wiping it after use.
+    pub fn to_pkcs8_der(&self) -> Result<Vec<u8>> {
+        encode_private_key_info(self.as_ref(), KeyKind::RsaSecret).map(SecretBuffer::into_vec)
+    }
+
+    /// Parses a key from PKCS #8 `PrivateKeyInfo` structure in DER.
+    ///
+    /// Only unencrypted keys are supported.
+    pub fn from_pkcs8_der<T: AsRef<[u8]>>(der: T) -> Result<Self> {
+        let container = decode_private_key_info(der.as_ref())?;
+        Self::try_from_slice(&*container)
+    }
+}
+
+impl RsaPublicKey {
+    /// Encodes this key as PKCS #1 `RSAPublicKey` structure in DER.
+    pub fn to_pkcs1_der(&self) -> Result<Vec<u8>> {
+        encode_rsa_public_key(self.as_ref())
+    }
+
+    /// Parses a key from PKCS #1 `RSAPublicKey` structure in DER.
+    pub fn from_pkcs1_der<T: AsRef<[u8]>>(der: T) -> Result<Self> {
+        let container = decode_rsa_public_key(der.as_ref())?;
+        Self::try_from_slice(&container)
+    }
+
+    /// Encodes this key as X.509 `SubjectPublicKeyInfo` structure in DER.
+    ///
+    /// This is the format of `openssl pkey -pubout -outform DER`.
+    pub fn to_spki_der(&self) -> Result<Vec<u8>> {
+        encode_public_key_info(self.as_ref(), KeyKind::RsaPublic)
+    }
+
+    /// Parses a key from X.509 `SubjectPublicKeyInfo` structure in DER.
+    pub fn from_spki_der<T: AsRef<[u8]>>(der: T) -> Result<Self> {
+        let container = decode_public_key_info(der.as_ref())?;
+        Self::try_from_slice(&container)
+    }
+}
+
+impl SecretKey {
+    /// Encodes this key as PKCS #8 `PrivateKeyInfo` structure in DER.
+    ///
+    /// This is the format of `openssl pkey -outform DER`. The output contains secret key
+    /// material, consider wiping it after use.
+    pub fn to_pkcs8_der(&self) -> Result<Vec<u8>> {
+        encode_private_key_info(self.as_ref(), self.kind()).map(SecretBuffer::into_vec)
+    }
+
+    /// Parses an RSA or ECDSA key from PKCS #8 `PrivateKeyInfo` structure in DER.
+    ///
+    /// Only unencrypted keys are supported.
+    pub fn from_pkcs8_der<T: AsRef<[u8]>>(der: T) -> Result<Self> {
+        let container = decode_private_key_info(der.as_ref())?;
+        Self::try_from_slice(&*container)
+    }
+}
+
+impl PublicKey {
+    /// Encodes this key as X.509 `SubjectPublicKeyInfo` structure in DER.
+    ///
+    /// This is the format of `openssl pkey -pubout -outform DER`.
+    pub fn to_spki_der(&self) -> Result<Vec<u8>> {
+        encode_public_key_info(self.as_ref(), self.kind())
+    }
+
+    /// Parses an RSA or ECDSA key from X.509 `SubjectPublicKeyInfo` structure in DER.
+    pub fn from_spki_der<T: AsRef<[u8]>>(der: T) -> Result<Self> {
+        let container = decode_public_key_info(der.as_ref())?;
+        Self::try_from_slice(&container)
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Elliptic curve key agreement.
+//!
+//! This module provides the key agreement step of Secure Session on its own (**ECDH**). It is
+//! useful if you are building a custom protocol and need two parties to arrive at a shared
+//! secret key. Each party uses its own ECDSA secret key and the public key of its peer.
+//!
+//! Raw ECDH output is never returned directly: it is passed through the [key derivation
+//! function], so you get a uniformly random key bound to the label and context you provide.
+//! Note that key agreement alone does not authenticate the peer: make sure that you use
+//! the correct public key.
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> Result<(), themis::Error> {
+//! use themis::ecdh::derive_shared_key;
+//! use themis::keygen::gen_ec_key_pair;
+//!
+//! let (secret_alice, public_alice) = gen_ec_key_pair().split();
+//! let (secret_bob, public_bob) = gen_ec_key_pair().split();
+//!
+//! let key_alice = derive_shared_key(&secret_alice, &public_bob, "file transfer", &[b"42"])?;
+//! let key_bob = derive_shared_key(&secret_bob, &public_alice, "file transfer", &[b"42"])?;
+//!
+//! assert_eq!(key_alice, key_bob);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [key derivation function]: ../kdf/index.html
+
+use std::os::raw::c_void;
+use std::ptr;
+
+use bindings::{
+    soter_asym_ka_alg_t, soter_asym_ka_create, soter_asym_ka_derive, soter_asym_ka_destroy,
+    soter_asym_ka_import_key, soter_asym_ka_t,
+};
+use zeroize::Zeroize;
+
+use crate::container::TAG_LEN;
+use crate::error::{Error, ErrorKind, Result, Status};
+use crate::kdf::{derive_key, SecretBytes};
+use crate::keys::{EcdsaPublicKey, EcdsaSecretKey};
+use crate::utils::into_raw_parts;
+
+/// Derives a shared key from our secret key and peer's public key.
+///
+/// Both keys must use the same elliptic curve, otherwise an [`InvalidParameter`] error is
+/// returned. Label and context are used for key derivation as described in [`derive_key`].
+/// The resulting key is [`DERIVED_KEY_LEN`] bytes long and is wiped from memory when dropped.
+///
+/// [`InvalidParameter`]: ../enum.ErrorKind.html#variant.InvalidParameter
+/// [`derive_key`]: ../kdf/fn.derive_key.html
+/// [`DERIVED_KEY_LEN`]: ../kdf/constant.DERIVED_KEY_LEN.html
+pub fn derive_shared_key<C: AsRef<[u8]>>(
+    secret_key: &EcdsaSecretKey,
+    peer_public_key: &EcdsaPublicKey,
+    label: &str,
+    context: &[C],
+) -> Result<SecretBytes> {
+    let mut shared_secret = agree(secret_key.as_ref(), peer_public_key.as_ref())?;
+    let key = derive_key(&shared_secret, label, context);
+    shared_secret.zeroize();
+    key
+}
+
+fn agree(secret_key: &[u8], public_key: &[u8]) -> Result<Vec<u8>> {
+    // The last byte of the tag indicates the curve.
+    if secret_key.get(TAG_LEN - 1) != public_key.get(TAG_LEN - 1) {
+        return Err(Error::with_kind(ErrorKind::InvalidParameter));
+    }
+
+    // The algorithm is just a default, imported key determines the actual curve.
+    let ka_ctx = unsafe { soter_asym_ka_create(soter_asym_ka_alg_t::SOTER_ASYM_KA_EC_P256) };
+    if ka_ctx.is_null() {
+        return Err(Error::with_kind(ErrorKind::NoMemory));
+    }
+    let ka_ctx = KeyAgreement(ka_ctx);
+
+    let (secret_ptr, secret_len) = into_raw_parts(secret_key);
+    let (public_ptr, public_len) = into_raw_parts(public_key);
+
+    unsafe {
+        let status = soter_asym_ka_import_key(ka_ctx.0, secret_ptr as *const c_void, secret_len);
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
+        }
+    }
+
+    let mut shared_secret = Vec::new();
+    let mut shared_secret_len = 0;
+
+    unsafe {
+        let status = soter_asym_ka_derive(
+            ka_ctx.0,
+            public_ptr as *const c_void,
+            public_len,
+            ptr::null_mut(),
+            &mut shared_secret_len,
+        );
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
+        }
+    }
+
+    shared_secret.reserve(shared_secret_len);
+
+    unsafe {
+        let status = soter_asym_ka_derive(
+            ka_ctx.0,
+            public_ptr as *const c_void,
+            public_len,
+            shared_secret.as_mut_ptr() as *mut c_void,
+            &mut shared_secret_len,
+        );
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
+        }
+        debug_assert!(shared_secret_len <= shared_secret.capacity());
+        shared_secret.set_len(shared_secret_len);
+    }
+
+    Ok(shared_secret)
+}
+
+struct KeyAgreement(*mut soter_asym_ka_t);
+
+impl Drop for KeyAgreement {
+    fn drop(&mut self) {
+        unsafe {
+            let status = soter_asym_ka_destroy(self.0);
+            let status = Status::from_themis(status);
+            if cfg!(debug_assertions) && status != Status::Success {
+                panic!("soter_asym_ka_destroy() failed: {}", Error::from(status));
+            }
+        }
+    }
+}
@@ -16,7 +16,8 @@
 //!
 //! This module wraps Themis error types and provides useful Rust API for them.
 
-use std::{error, fmt, result};
+use std::sync::Arc;
+use std::{error, fmt, io, result};
 
 use bindings::{
     THEMIS_BUFFER_TOO_SMALL, THEMIS_DATA_CORRUPT, THEMIS_FAIL, THEMIS_INVALID_PARAMETER,
@@ -38,82 +39,203 @@ pub type Result<T> = result::Result<T, Error>;
 /// of the library. However, they may also result from underlying OS errors. See [`ErrorKind`] for
 /// details.
 ///
+/// Errors are `Send + Sync + 'static` and can be converted into `std::io::Error`, so they can
+/// be propagated with `?` operator through I/O code.
+///
+/// Some errors are caused by other errors, like I/O errors of Secure Session transport. The
+/// original error is available via `source()` method of `std::error::Error` trait. Errors are
+/// compared only by their kind, ignoring the source.
+///
 /// [`ErrorKind`]: enum.ErrorKind.html
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Error {
     kind: ErrorKind,
+    /// Original status code if it is not known to this binding.
+    unknown_status: Option<themis_status_t>,
+    /// Underlying cause of this error, if any.
+    source: Option<Arc<dyn error::Error + Send + Sync>>,
 }
 
 impl Error {
     /// Constructs a new error of given kind.
     pub(crate) fn with_kind(kind: ErrorKind) -> Error {
-        Error { kind }
+        Error {
+            kind,
+            unknown_status: None,
+            source: None,
+        }
+    }
+
+    /// Attaches the underlying cause to this error.
+    pub(crate) fn caused_by<E>(mut self, source: E) -> Error
+    where
+        E: error::Error + Send + Sync + 'static,
+    {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    /// Returns the corresponding `ErrorKind` for this error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
     }
+}
 
+/// Status code returned by Themis functions.
+///
+/// Apart from success and errors, some Themis functions return statuses which direct further
+/// processing, like sending data to the peer. These are handled internally by the library and
+/// are never reported as errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Status {
+    /// Operation has completed successfully.
+    Success,
+    /// Output buffer is too small, its required length has been returned.
+    BufferTooSmall,
+    /// Send output to the peer.
+    SendOutputToPeer,
+    /// Compared data matches.
+    Match,
+    /// Compared data does not match.
+    NoMatch,
+    /// Operation failed.
+    Error(ErrorKind),
+    /// Status code not known to this binding.
+    Unknown(themis_status_t),
+}
+
+impl Status {
     /// Converts generic Themis status codes.
-    pub(crate) fn from_themis_status(status: themis_status_t) -> Error {
+    pub(crate) fn from_themis(status: themis_status_t) -> Status {
         let kind = match status as u32 {
-            THEMIS_SUCCESS => ErrorKind::Success,
+            THEMIS_SUCCESS => return Status::Success,
+            THEMIS_BUFFER_TOO_SMALL => return Status::BufferTooSmall,
             THEMIS_FAIL => ErrorKind::Fail,
             THEMIS_INVALID_PARAMETER => ErrorKind::InvalidParameter,
             THEMIS_NO_MEMORY => ErrorKind::NoMemory,
-            THEMIS_BUFFER_TOO_SMALL => ErrorKind::BufferTooSmall,
             THEMIS_DATA_CORRUPT => ErrorKind::DataCorrupt,
             THEMIS_INVALID_SIGNATURE => ErrorKind::InvalidSignature,
             THEMIS_NOT_SUPPORTED => ErrorKind::NotSupported,
-            _ => ErrorKind::UnknownError(status),
+            _ => return Status::Unknown(status),
         };
-        Error { kind }
+        Status::Error(kind)
     }
 
     /// Converts status codes returned by Secure Session.
-    pub(crate) fn from_session_status(status: themis_status_t) -> Error {
+    pub(crate) fn from_session(status: themis_status_t) -> Status {
         let kind = match status as u32 {
-            THEMIS_SSESSION_SEND_OUTPUT_TO_PEER => ErrorKind::SessionSendOutputToPeer,
+            THEMIS_SSESSION_SEND_OUTPUT_TO_PEER => return Status::SendOutputToPeer,
             THEMIS_SSESSION_KA_NOT_FINISHED => ErrorKind::SessionKeyAgreementNotFinished,
             THEMIS_SSESSION_TRANSPORT_ERROR => ErrorKind::SessionTransportError,
             THEMIS_SSESSION_GET_PUB_FOR_ID_CALLBACK_ERROR => {
                 ErrorKind::SessionGetPublicKeyForIdError
             }
-            _ => return Error::from_themis_status(status),
+            _ => return Status::from_themis(status),
         };
-        Error { kind }
+        Status::Error(kind)
     }
 
     /// Converts status codes returned by Secure Comparator data exchange.
-    pub(crate) fn from_compare_status(status: themis_status_t) -> Error {
-        let kind = match status as u32 {
-            THEMIS_SCOMPARE_SEND_OUTPUT_TO_PEER => ErrorKind::CompareSendOutputToPeer,
-            _ => return Error::from_themis_status(status),
-        };
-        Error { kind }
+    pub(crate) fn from_compare(status: themis_status_t) -> Status {
+        match status as u32 {
+            THEMIS_SCOMPARE_SEND_OUTPUT_TO_PEER => Status::SendOutputToPeer,
+            _ => Status::from_themis(status),
+        }
     }
 
     /// Converts status codes returned by Secure Comparator status query.
-    pub(crate) fn from_match_status(status: themis_status_t) -> Error {
-        let kind = match status as u32 {
-            THEMIS_SCOMPARE_NOT_READY => ErrorKind::CompareNotReady,
-            THEMIS_SCOMPARE_MATCH => ErrorKind::CompareMatch,
-            THEMIS_SCOMPARE_NO_MATCH => ErrorKind::CompareNoMatch,
-            _ => return Error::from_themis_status(status),
-        };
-        Error { kind }
+    pub(crate) fn from_match(status: themis_status_t) -> Status {
+        match status as u32 {
+            THEMIS_SCOMPARE_NOT_READY => Status::Error(ErrorKind::CompareNotReady),
+            THEMIS_SCOMPARE_MATCH => Status::Match,
+            THEMIS_SCOMPARE_NO_MATCH => Status::NoMatch,
+            _ => Status::from_themis(status),
+        }
     }
+}
 
-    /// Returns the corresponding `ErrorKind` for this error.
-    pub fn kind(&self) -> ErrorKind {
-        self.kind
+impl From<Status> for Error {
+    /// Converts a status which turned out to be an error.
+    ///
+    /// Statuses which are not expected at the call site are reported as general failures.
+    fn from(status: Status) -> Error {
+        match status {
+            Status::Error(kind) => Error::with_kind(kind),
+            Status::BufferTooSmall => Error::with_kind(ErrorKind::BufferTooSmall),
+            Status::Unknown(status) => Error {
+                kind: ErrorKind::Fail,
+                unknown_status: Some(status),
+                source: None,
+            },
+            Status::Success | Status::SendOutputToPeer | Status::Match | Status::NoMatch => {
+                Error::with_kind(ErrorKind::Fail)
+            }
+        }
     }
 }
 
-impl error::Error for Error {}
+impl From<ErrorKind> for Error {
+    /// Constructs an error of given kind.
+    ///
+    /// This is useful for implementing traits like [`PrivateKeyHandle`] outside of Themis.
+    ///
+    /// [`PrivateKeyHandle`]: keys/trait.PrivateKeyHandle.html
+    fn from(kind: ErrorKind) -> Error {
+        Error::with_kind(kind)
+    }
+}
+
+impl PartialEq for Error {
+    fn eq(&self, other: &Error) -> bool {
+        self.kind == other.kind && self.unknown_status == other.unknown_status
+    }
+}
+
+impl Eq for Error {}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self.source {
+            Some(ref source) => Some(&**source),
+            None => None,
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    /// Wraps Themis error into an I/O error of appropriate kind.
+    ///
+    /// This allows to use `?` operator for Themis errors in functions returning `io::Result`.
+    /// The original error is available via `io::Error::get_ref()`. Errors caused by I/O errors
+    /// keep the kind of the original I/O error.
+    fn from(error: Error) -> io::Error {
+        let source_kind = error
+            .source
+            .as_ref()
+            .and_then(|source| source.downcast_ref::<io::Error>())
+            .map(io::Error::kind);
+        if let Some(kind) = source_kind {
+            return io::Error::new(kind, error);
+        }
+        let kind = match error.kind {
+            ErrorKind::InvalidParameter => io::ErrorKind::InvalidInput,
+            ErrorKind::DataCorrupt
+            | ErrorKind::InvalidSignature
+            | ErrorKind::KeyRingUnknownVersion => io::ErrorKind::InvalidData,
+            ErrorKind::SessionKeyAgreementNotFinished => io::ErrorKind::NotConnected,
+            ErrorKind::SessionHandshakeTimeout => io::ErrorKind::TimedOut,
+            _ => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, error)
+    }
+}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(status) = self.unknown_status {
+            return write!(f, "unknown error: {}", status);
+        }
         match self.kind {
-            ErrorKind::UnknownError(status) => write!(f, "unknown error: {}", status),
-            ErrorKind::Success => write!(f, "success"),
-
             ErrorKind::Fail => write!(f, "failure"),
             ErrorKind::InvalidParameter => write!(f, "invalid parameter"),
             ErrorKind::NoMemory => write!(f, "out of memory"),
@@ -121,18 +243,22 @@ impl fmt::Display for Error {
             ErrorKind::DataCorrupt => write!(f, "corrupted data"),
             ErrorKind::InvalidSignature => write!(f, "invalid signature"),
             ErrorKind::NotSupported => write!(f, "operation not supported"),
+            ErrorKind::SelfTestFailed => write!(f, "self-test failed"),
 
-            ErrorKind::SessionSendOutputToPeer => write!(f, "send key agreement data to peer"),
             ErrorKind::SessionKeyAgreementNotFinished => write!(f, "key agreement not finished"),
             ErrorKind::SessionTransportError => write!(f, "transport layer error"),
             ErrorKind::SessionGetPublicKeyForIdError => {
                 write!(f, "failed to get public key for ID")
             }
+            ErrorKind::SessionLimitExceeded => write!(f, "too many Secure Sessions"),
+            ErrorKind::SessionHandshakeTimeout => write!(f, "negotiation timed out"),
+
+            ErrorKind::KeyRingNoCurrentKey => write!(f, "no current key in key ring"),
+            ErrorKind::KeyRingUnknownVersion => write!(f, "unknown key version"),
 
-            ErrorKind::CompareSendOutputToPeer => write!(f, "send comparison data to peer"),
-            ErrorKind::CompareMatch => write!(f, "data matches"),
-            ErrorKind::CompareNoMatch => write!(f, "data does not match"),
             ErrorKind::CompareNotReady => write!(f, "comparator not ready"),
+
+            ErrorKind::__Nonexhaustive => write!(f, "unknown error"),
         }
     }
 }
@@ -140,24 +266,17 @@ impl fmt::Display for Error {
 /// A list of Themis error categories.
 ///
 /// This enumeration is used by [`Error`] type, returned by most Themis functions. Some error kinds
-/// are specific to particular functions, and some are used internally by the library.
+/// are specific to particular functions.
+///
+/// New error kinds may be added in future versions, so matching on `ErrorKind` must include
+/// a wildcard arm. Errors reported by the core library but unknown to this binding are reported
+/// as `Fail`.
 ///
 /// [`Error`]: struct.Error.html
+// #[non_exhaustive] requires Rust 1.40 so we use a hidden variant instead.
+#[allow(clippy::manual_non_exhaustive)]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum ErrorKind {
-    /// Catch-all generic error.
-    ///
-    /// If you encounter this error kind then the Themis binding is likely to be out of sync with
-    /// the core library. The contained error code has not been mapped onto `ErrorKind` value.
-    #[doc(hidden)]
-    UnknownError(i32),
-    /// "Fatal error: success!"
-    ///
-    /// This value is used internally to distinguish successful function calls conveniently.
-    /// End-users should never encounter it.
-    #[doc(hidden)]
-    Success,
-
     /// General failure.
     Fail,
     /// Some input parameter has incorrect value.
@@ -172,34 +291,28 @@ pub enum ErrorKind {
     InvalidSignature,
     /// Operation not supported.
     NotSupported,
+    /// Self-test has detected malfunction of the library or random number generator.
+    SelfTestFailed,
 
-    /// Send output with internal data of Secure Session to the peer.
-    ///
-    /// This is not actually an error and the end-user should never see it.
-    #[doc(hidden)]
-    SessionSendOutputToPeer,
     /// Attempt to use Secure Session before completing key exchange.
     SessionKeyAgreementNotFinished,
     /// Transport layer returned error.
     SessionTransportError,
     /// Could not retrieve a public key corresponding to peer ID.
     SessionGetPublicKeyForIdError,
+    /// Secure Session registry cannot accept more sessions.
+    SessionLimitExceeded,
+    /// Secure Session has not completed negotiation in time.
+    SessionHandshakeTimeout,
+
+    /// Key ring is empty or all its keys have expired.
+    KeyRingNoCurrentKey,
+    /// Data is protected with a key version which is not present in the key ring.
+    KeyRingUnknownVersion,
 
-    /// Send output with internal data of Secure Comparator to the peer.
-    ///
-    /// This is not actually an error and the end-user should never see it.
-    #[doc(hidden)]
-    CompareSendOutputToPeer,
-    /// Indicates that compared data matches.
-    ///
-    /// This is not actually an error and the end-user should never see it.
-    #[doc(hidden)]
-    CompareMatch,
-    /// Indicates that compared data does not match.
-    ///
-    /// This is not actually an error and the end-user should never see it.
-    #[doc(hidden)]
-    CompareNoMatch,
     /// Attempt to use Secure Comparator before completing nonce exchange.
     CompareNotReady,
+
+    #[doc(hidden)]
+    __Nonexhaustive,
 }
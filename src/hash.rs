@@ -0,0 +1,278 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cryptographic hash functions.
+//!
+//! Themis uses hash functions of its underlying crypto library (**Soter**) internally. This
+//! module makes them available to applications, so that you do not need another crypto crate
+//! to compute fingerprints of keys or data protected with Themis.
+//!
+//! # Examples
+//!
+//! Data can be hashed incrementally:
+//!
+//! ```
+//! # fn main() -> Result<(), themis::Error> {
+//! use themis::hash::SecureHash;
+//!
+//! let mut hash = SecureHash::sha256();
+//! hash.update(b"some data")?;
+//! hash.update(b"and some more data")?;
+//! let digest = hash.finalize()?;
+//!
+//! assert_eq!(digest.len(), 32);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`HashWriter`] computes hash of data while it is being written somewhere else:
+//!
+//! ```
+//! # fn main() -> std::io::Result<()> {
+//! use std::io::Write;
+//!
+//! use themis::hash::{HashWriter, SecureHash};
+//!
+//! let mut writer = HashWriter::new(Vec::new(), SecureHash::sha256());
+//! writer.write_all(b"some data")?;
+//! let (output, digest) = writer.finalize()?;
+//!
+//! assert_eq!(output, b"some data");
+//! assert_eq!(digest.len(), 32);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`HashWriter`]: struct.HashWriter.html
+
+use std::io::{self, Write};
+use std::os::raw::c_void;
+
+use bindings::{
+    soter_hash_algo_t, soter_hash_create, soter_hash_ctx_t, soter_hash_destroy, soter_hash_final,
+    soter_hash_update,
+};
+
+use crate::error::{Error, ErrorKind, Result, Status};
+use crate::utils::into_raw_parts;
+
+/// Hash algorithms supported by Themis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    /// SHA-256 with 32-byte output.
+    Sha256,
+    /// SHA-512 with 64-byte output.
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// Returns the length of the hash value in bytes.
+    pub fn output_len(self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha512 => 64,
+        }
+    }
+
+    pub(crate) fn to_soter(self) -> soter_hash_algo_t {
+        match self {
+            HashAlgorithm::Sha256 => soter_hash_algo_t::SOTER_HASH_SHA256,
+            HashAlgorithm::Sha512 => soter_hash_algo_t::SOTER_HASH_SHA512,
+        }
+    }
+}
+
+/// Incremental hash computation.
+///
+/// Feed the data with [`update`] and get the hash value with [`finalize`].
+///
+/// [`update`]: struct.SecureHash.html#method.update
+/// [`finalize`]: struct.SecureHash.html#method.finalize
+pub struct SecureHash {
+    hash_ctx: *mut soter_hash_ctx_t,
+    algorithm: HashAlgorithm,
+}
+
+// Hash context is not tied to any particular thread, it is just some memory.
+unsafe impl Send for SecureHash {}
+
+impl SecureHash {
+    /// Prepares a new SHA-256 hash computation.
+    ///
+    /// # Panics
+    ///
+    /// May panic on internal unrecoverable errors (e.g., out-of-memory).
+    pub fn sha256() -> Self {
+        SecureHash::new(HashAlgorithm::Sha256)
+    }
+
+    /// Prepares a new SHA-512 hash computation.
+    ///
+    /// # Panics
+    ///
+    /// May panic on internal unrecoverable errors (e.g., out-of-memory).
+    pub fn sha512() -> Self {
+        SecureHash::new(HashAlgorithm::Sha512)
+    }
+
+    /// Prepares a new hash computation with given algorithm.
+    ///
+    /// # Panics
+    ///
+    /// May panic on internal unrecoverable errors (e.g., out-of-memory).
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        match SecureHash::try_new(algorithm) {
+            Ok(hash) => hash,
+            Err(e) => panic!("soter_hash_create() failed: {}", e),
+        }
+    }
+
+    fn try_new(algorithm: HashAlgorithm) -> Result<Self> {
+        let hash_ctx = unsafe { soter_hash_create(algorithm.to_soter()) };
+
+        if hash_ctx.is_null() {
+            return Err(Error::with_kind(ErrorKind::NoMemory));
+        }
+
+        Ok(Self {
+            hash_ctx,
+            algorithm,
+        })
+    }
+
+    /// Returns the algorithm used by this hash.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Hashes more data.
+    pub fn update<D: AsRef<[u8]>>(&mut self, data: D) -> Result<()> {
+        // Soter does not accept empty data, but hashing nothing does not change anything.
+        if data.as_ref().is_empty() {
+            return Ok(());
+        }
+        let (data_ptr, data_len) = into_raw_parts(data.as_ref());
+
+        unsafe {
+            let status = soter_hash_update(self.hash_ctx, data_ptr as *const c_void, data_len);
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                return Err(status.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Completes hash computation and returns the hash value.
+    pub fn finalize(self) -> Result<Vec<u8>> {
+        let mut hash = vec![0; self.algorithm.output_len()];
+        let mut hash_len = hash.len();
+
+        unsafe {
+            let status = soter_hash_final(self.hash_ctx, hash.as_mut_ptr(), &mut hash_len);
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                return Err(status.into());
+            }
+        }
+
+        hash.truncate(hash_len);
+        Ok(hash)
+    }
+
+    /// Computes hash of the data in one go.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), themis::Error> {
+    /// use themis::hash::{HashAlgorithm, SecureHash};
+    ///
+    /// let digest = SecureHash::digest(HashAlgorithm::Sha512, b"some data")?;
+    ///
+    /// assert_eq!(digest.len(), 64);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn digest<D: AsRef<[u8]>>(algorithm: HashAlgorithm, data: D) -> Result<Vec<u8>> {
+        let mut hash = SecureHash::new(algorithm);
+        hash.update(data)?;
+        hash.finalize()
+    }
+}
+
+#[doc(hidden)]
+impl Drop for SecureHash {
+    fn drop(&mut self) {
+        unsafe {
+            let status = soter_hash_destroy(self.hash_ctx);
+            let status = Status::from_themis(status);
+            if cfg!(debug_assertions) && status != Status::Success {
+                panic!("soter_hash_destroy() failed: {}", Error::from(status));
+            }
+        }
+    }
+}
+
+/// Writer which hashes all data written through it.
+///
+/// Data is passed to the underlying writer and only the bytes that it has accepted are hashed.
+/// Use [`io::sink()`] as the underlying writer if you need only the hash.
+///
+/// [`io::sink()`]: https://doc.rust-lang.org/std/io/fn.sink.html
+pub struct HashWriter<W> {
+    inner: W,
+    hash: SecureHash,
+}
+
+impl<W: Write> HashWriter<W> {
+    /// Makes a new writer hashing data with given hash.
+    pub fn new(inner: W, hash: SecureHash) -> Self {
+        Self { inner, hash }
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    ///
+    /// Data written directly to the underlying writer is not hashed.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Flushes the underlying writer and completes hash computation.
+    ///
+    /// Returns the underlying writer and the hash value.
+    pub fn finalize(mut self) -> io::Result<(W, Vec<u8>)> {
+        self.inner.flush()?;
+        let hash = self.hash.finalize()?;
+        Ok((self.inner, hash))
+    }
+}
+
+impl<W: Write> Write for HashWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hash.update(&buf[..written])?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Message authentication codes.
+//!
+//! This module provides HMAC based on [hash functions] of Themis. It is useful for
+//! authenticating data which does not need to be encrypted, like metadata stored alongside
+//! data protected with [Secure Cell].
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> Result<(), themis::Error> {
+//! use themis::hmac::SecureHmac;
+//!
+//! let mut hmac = SecureHmac::sha256(b"secret key")?;
+//! hmac.update(b"public metadata")?;
+//! let tag = hmac.finalize()?;
+//!
+//! let mut hmac = SecureHmac::sha256(b"secret key")?;
+//! hmac.update(b"public metadata")?;
+//! hmac.verify(&tag)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Use [`HmacWriter`] to authenticate data while it is being written somewhere else.
+//!
+//! [hash functions]: ../hash/index.html
+//! [Secure Cell]: ../secure_cell/index.html
+//! [`HmacWriter`]: struct.HmacWriter.html
+
+use std::io::{self, Write};
+use std::os::raw::c_void;
+
+use bindings::{
+    soter_hmac_create, soter_hmac_ctx_t, soter_hmac_destroy, soter_hmac_final, soter_hmac_update,
+};
+
+use crate::error::{Error, ErrorKind, Result, Status};
+use crate::hash::HashAlgorithm;
+use crate::utils::{constant_time_eq, into_raw_parts};
+
+/// Incremental HMAC computation.
+///
+/// Feed the data with [`update`], then get the authentication tag with [`finalize`] or check
+/// the expected one with [`verify`].
+///
+/// [`update`]: struct.SecureHmac.html#method.update
+/// [`finalize`]: struct.SecureHmac.html#method.finalize
+/// [`verify`]: struct.SecureHmac.html#method.verify
+pub struct SecureHmac {
+    hmac_ctx: *mut soter_hmac_ctx_t,
+    algorithm: HashAlgorithm,
+}
+
+// HMAC context is not tied to any particular thread, it is just some memory.
+unsafe impl Send for SecureHmac {}
+
+impl SecureHmac {
+    /// Prepares a new HMAC-SHA-256 computation with given key.
+    ///
+    /// The key must not be empty.
+    pub fn sha256<K: AsRef<[u8]>>(key: K) -> Result<Self> {
+        SecureHmac::new(HashAlgorithm::Sha256, key)
+    }
+
+    /// Prepares a new HMAC-SHA-512 computation with given key.
+    ///
+    /// The key must not be empty.
+    pub fn sha512<K: AsRef<[u8]>>(key: K) -> Result<Self> {
+        SecureHmac::new(HashAlgorithm::Sha512, key)
+    }
+
+    /// Prepares a new HMAC computation with given hash algorithm and key.
+    ///
+    /// The key must not be empty.
+    pub fn new<K: AsRef<[u8]>>(algorithm: HashAlgorithm, key: K) -> Result<Self> {
+        if key.as_ref().is_empty() {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+        let (key_ptr, key_len) = into_raw_parts(key.as_ref());
+
+        let hmac_ctx = unsafe { soter_hmac_create(algorithm.to_soter(), key_ptr, key_len) };
+
+        if hmac_ctx.is_null() {
+            // With valid parameters this function is most likely to fail on memory allocation.
+            return Err(Error::with_kind(ErrorKind::NoMemory));
+        }
+
+        Ok(Self {
+            hmac_ctx,
+            algorithm,
+        })
+    }
+
+    /// Returns the hash algorithm used by this HMAC.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Authenticates more data.
+    pub fn update<D: AsRef<[u8]>>(&mut self, data: D) -> Result<()> {
+        // Soter does not accept empty data, but authenticating nothing does not change anything.
+        if data.as_ref().is_empty() {
+            return Ok(());
+        }
+        let (data_ptr, data_len) = into_raw_parts(data.as_ref());
+
+        unsafe {
+            let status = soter_hmac_update(self.hmac_ctx, data_ptr as *const c_void, data_len);
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                return Err(status.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Completes HMAC computation and returns the authentication tag.
+    pub fn finalize(self) -> Result<Vec<u8>> {
+        let mut tag = vec![0; self.algorithm.output_len()];
+        let mut tag_len = tag.len();
+
+        unsafe {
+            let status = soter_hmac_final(self.hmac_ctx, tag.as_mut_ptr(), &mut tag_len);
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                return Err(status.into());
+            }
+        }
+
+        tag.truncate(tag_len);
+        Ok(tag)
+    }
+
+    /// Completes HMAC computation and checks that it matches the expected tag.
+    ///
+    /// Tags are compared in constant time. Returns [`InvalidSignature`] error if they differ.
+    ///
+    /// [`InvalidSignature`]: ../enum.ErrorKind.html#variant.InvalidSignature
+    pub fn verify<T: AsRef<[u8]>>(self, expected: T) -> Result<()> {
+        let tag = self.finalize()?;
+        if !constant_time_eq(&tag, expected.as_ref()) {
+            return Err(Error::with_kind(ErrorKind::InvalidSignature));
+        }
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+impl Drop for SecureHmac {
+    fn drop(&mut self) {
+        // This also wipes the key material held in the context.
+        unsafe {
+            let status = soter_hmac_destroy(self.hmac_ctx);
+            let status = Status::from_themis(status);
+            if cfg!(debug_assertions) && status != Status::Success {
+                panic!("soter_hmac_destroy() failed: {}", Error::from(status));
+            }
+        }
+    }
+}
+
+/// Writer which authenticates all data written through it.
+///
+/// Data is passed to the underlying writer and only the bytes that it has accepted are
+/// authenticated. Use [`io::sink()`] as the underlying writer if you need only the tag.
+///
+/// [`io::sink()`]: https://doc.rust-lang.org/std/io/fn.sink.html
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> std::io::Result<()> {
+/// use std::io::{self, Write};
+///
+/// use themis::hmac::{HmacWriter, SecureHmac};
+///
+/// let mut writer = HmacWriter::new(io::sink(), SecureHmac::sha256(b"secret key")?);
+/// writer.write_all(b"public metadata")?;
+/// let (_, tag) = writer.finalize()?;
+///
+/// assert_eq!(tag.len(), 32);
+/// # Ok(())
+/// # }
+/// ```
+pub struct HmacWriter<W> {
+    inner: W,
+    hmac: SecureHmac,
+}
+
+impl<W: Write> HmacWriter<W> {
+    /// Makes a new writer authenticating data with given HMAC.
+    pub fn new(inner: W, hmac: SecureHmac) -> Self {
+        Self { inner, hmac }
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    ///
+    /// Data written directly to the underlying writer is not authenticated.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Flushes the underlying writer and completes HMAC computation.
+    ///
+    /// Returns the underlying writer and the authentication tag.
+    pub fn finalize(mut self) -> io::Result<(W, Vec<u8>)> {
+        self.inner.flush()?;
+        let tag = self.hmac.finalize()?;
+        Ok((self.inner, tag))
+    }
+}
+
+impl<W: Write> Write for HmacWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hmac.update(&buf[..written])?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
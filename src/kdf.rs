@@ -0,0 +1,375 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Key derivation function.
+//!
+//! This module exposes the key derivation function used internally by Themis (**Soter KDF**,
+//! based on HMAC-SHA-256). It allows applications to derive independent subkeys for different
+//! purposes from a single master secret. Derived keys are compatible with other Themis wrappers
+//! which provide Soter KDF.
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> Result<(), themis::Error> {
+//! use themis::kdf::derive_key;
+//!
+//! let master_key = b"master secret";
+//!
+//! let encryption_key = derive_key(master_key, "encryption", &[b"user 42"])?;
+//! let signing_key = derive_key(master_key, "signing", &[b"user 42"])?;
+//!
+//! assert_ne!(encryption_key, signing_key);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`KeyDerivation`] produces typed keys bound to their purpose (not available with `soter-only`
+//! feature):
+//!
+//! ```
+//! # fn main() -> Result<(), themis::Error> {
+//! use themis::kdf::KeyDerivation;
+//! use themis::keys::SymmetricKey;
+//! use themis::secure_cell::SecureCell;
+//!
+//! let master = KeyDerivation::new(b"master secret")?;
+//! let storage_key: SymmetricKey = master.derive("storage")?;
+//!
+//! let cell = SecureCell::with_key(&storage_key).seal();
+//! let encrypted = cell.encrypt(b"data at rest")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`KeyDerivation`]: struct.KeyDerivation.html
+//!
+//! Neither of these is suitable for passwords and other low-entropy secrets. Use
+//! [`SymmetricKey::derive_from_passphrase`] to turn a passphrase into a key.
+//!
+//! [`SymmetricKey::derive_from_passphrase`]: ../keys/struct.SymmetricKey.html#method.derive_from_passphrase
+
+use std::ffi::CString;
+use std::fmt;
+#[cfg(not(feature = "soter-only"))]
+use std::mem;
+use std::ops::Deref;
+use std::os::raw::c_void;
+use std::ptr;
+
+#[cfg(all(soter_pbkdf2, not(feature = "soter-only")))]
+use bindings::soter_pbkdf2_sha256;
+use bindings::{soter_kdf, soter_kdf_context_buf_t};
+
+use crate::error::{Error, ErrorKind, Result, Status};
+#[cfg(not(feature = "soter-only"))]
+use crate::keys::{KeyBytes, SymmetricKey};
+use crate::utils::into_raw_parts;
+use zeroize::Zeroize;
+
+/// Length of keys produced by [`derive_key`] in bytes.
+///
+/// If you need a shorter key, take a prefix of the derived one. This is how shorter keys are
+/// derived by Soter KDF.
+///
+/// [`derive_key`]: fn.derive_key.html
+pub const DERIVED_KEY_LEN: usize = 32;
+
+/// Derives a key from input key material.
+///
+/// Label describes the purpose of the derived key, it must not contain zero bytes. Context
+/// is a list of additional data items bound to the derived key (like user or session IDs).
+/// Different labels or contexts produce unrelated keys.
+///
+/// If the input key material is empty then a key is derived from the label and context alone.
+/// Such keys are not secret and can be used only for deriving identifiers.
+///
+/// The key is returned in a buffer which is wiped from memory when dropped.
+pub fn derive_key<K, C>(input: K, label: &str, context: &[C]) -> Result<SecretBytes>
+where
+    K: AsRef<[u8]>,
+    C: AsRef<[u8]>,
+{
+    let label = CString::new(label).map_err(|_| Error::with_kind(ErrorKind::InvalidParameter))?;
+    let context: Vec<soter_kdf_context_buf_t> = context
+        .iter()
+        .map(|item| {
+            let (data, length) = into_raw_parts(item.as_ref());
+            soter_kdf_context_buf_t { data, length }
+        })
+        .collect();
+    let (input_ptr, input_len) = into_raw_parts(input.as_ref());
+    let (context_ptr, context_len) = if context.is_empty() {
+        (ptr::null(), 0)
+    } else {
+        (context.as_ptr(), context.len())
+    };
+
+    let mut key = vec![0; DERIVED_KEY_LEN];
+
+    unsafe {
+        let status = soter_kdf(
+            input_ptr as *const c_void,
+            input_len,
+            label.as_ptr(),
+            context_ptr,
+            context_len,
+            key.as_mut_ptr() as *mut c_void,
+            key.len(),
+        );
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            key.zeroize();
+            return Err(status.into());
+        }
+    }
+
+    Ok(SecretBytes(key))
+}
+
+/// Secret bytes which are wiped from memory when dropped.
+///
+/// This is used for derived keys and for raw keys which have not been parsed yet, such as keys
+/// of a loaded [`KeyRing`]. `Debug` output shows only the length.
+///
+/// [`KeyRing`]: ../keyring/struct.KeyRing.html
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretBytes(Vec<u8>);
+
+#[cfg(not(feature = "soter-only"))]
+impl SecretBytes {
+    /// Takes the bytes out, leaving the buffer empty.
+    // std::mem::take() is not available in Rust 1.31
+    #[allow(clippy::mem_replace_with_default)]
+    pub(crate) fn into_vec(mut self) -> Vec<u8> {
+        mem::replace(&mut self.0, Vec::new())
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        SecretBytes(bytes)
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretBytes({} bytes)", self.0.len())
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Keys which can be produced by [`KeyDerivation`].
+///
+/// [`KeyDerivation`]: struct.KeyDerivation.html
+pub trait DerivedKey: Sized {
+    /// Name of the key type, used for domain separation.
+    ///
+    /// Keys of different types derived with the same label are unrelated. This name is a part
+    /// of the derivation and must never change, otherwise previously derived keys are lost.
+    const KEY_TYPE: &'static str;
+
+    /// Makes a key out of derived bytes.
+    fn from_derived_bytes(bytes: SecretBytes) -> Self;
+}
+
+#[cfg(not(feature = "soter-only"))]
+impl DerivedKey for SymmetricKey {
+    const KEY_TYPE: &'static str = "SymmetricKey";
+
+    fn from_derived_bytes(bytes: SecretBytes) -> Self {
+        SymmetricKey::from_vec(bytes.into_vec())
+    }
+}
+
+/// Derivation of purpose-bound keys from a master key.
+///
+/// Use [`derive`] to obtain independent keys for different purposes, identified by labels.
+/// This is a convenient way to build key hierarchies without storing every key separately.
+///
+/// Derived keys are stable: given the same master key, label, and key type you will always
+/// get the same key, in all versions of Themis. A key of type `T` is computed as
+///
+/// ```text
+/// derive_key(master, label, &[T::KEY_TYPE])
+/// ```
+///
+/// using the [`derive_key`] function.
+///
+/// [`derive`]: struct.KeyDerivation.html#method.derive
+/// [`derive_key`]: fn.derive_key.html
+#[cfg(not(feature = "soter-only"))]
+pub struct KeyDerivation {
+    master: KeyBytes,
+}
+
+#[cfg(not(feature = "soter-only"))]
+impl KeyDerivation {
+    /// Prepares derivation from given master key.
+    ///
+    /// Returns an error if the master key is empty.
+    pub fn new<K: AsRef<[u8]>>(master: K) -> Result<Self> {
+        if master.as_ref().is_empty() {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+        Ok(Self {
+            master: KeyBytes::copy_slice(master.as_ref()),
+        })
+    }
+
+    /// Derives a key for the purpose identified by the label.
+    ///
+    /// Label must not contain zero bytes.
+    pub fn derive<T: DerivedKey>(&self, label: &str) -> Result<T> {
+        let key = derive_key(self.master.as_bytes(), label, &[T::KEY_TYPE])?;
+        Ok(T::from_derived_bytes(key))
+    }
+}
+
+#[cfg(not(feature = "soter-only"))]
+impl fmt::Debug for KeyDerivation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("KeyDerivation")
+    }
+}
+
+/// Parameters of passphrase-based key derivation.
+///
+/// Used by [`SymmetricKey::derive_from_passphrase`]. The number of iterations is the work factor
+/// of PBKDF2: higher values make brute-force attacks on the passphrase slower, along with the
+/// legitimate derivation. Defaults match the ones used by Secure Cell in passphrase mode.
+///
+/// [`SymmetricKey::derive_from_passphrase`]: ../keys/struct.SymmetricKey.html#method.derive_from_passphrase
+#[cfg(all(soter_pbkdf2, not(feature = "soter-only")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PassphraseParams {
+    iterations: usize,
+}
+
+#[cfg(all(soter_pbkdf2, not(feature = "soter-only")))]
+impl PassphraseParams {
+    /// Default number of PBKDF2 iterations.
+    pub const DEFAULT_ITERATIONS: usize = 200_000;
+
+    /// Makes parameters with given number of PBKDF2 iterations.
+    pub fn with_iterations(iterations: usize) -> Self {
+        Self { iterations }
+    }
+
+    /// Returns the number of PBKDF2 iterations.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+}
+
+#[cfg(all(soter_pbkdf2, not(feature = "soter-only")))]
+impl Default for PassphraseParams {
+    fn default() -> Self {
+        Self::with_iterations(Self::DEFAULT_ITERATIONS)
+    }
+}
+
+#[cfg(all(soter_pbkdf2, not(feature = "soter-only")))]
+impl SymmetricKey {
+    /// Derives a key from a passphrase.
+    ///
+    /// The key is computed with PBKDF2-HMAC-SHA-256 as implemented by Soter, so the same
+    /// passphrase, salt, and parameters produce the same key in any PBKDF2 implementation.
+    /// The key is [`DERIVED_KEY_LEN`] bytes long, which is suitable for Secure Cell.
+    ///
+    /// Salt should be random and unique for each passphrase; store it along with the data.
+    /// It does not need to be secret. Returns an error if the passphrase or salt is empty,
+    /// or if the number of iterations is zero.
+    ///
+    /// This function is available if Themis provides PBKDF2 (Themis 0.13+), which is detected
+    /// as `soter_pbkdf2` cfg flag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), themis::Error> {
+    /// use themis::kdf::PassphraseParams;
+    /// use themis::keys::SymmetricKey;
+    /// use themis::secure_cell::SecureCell;
+    ///
+    /// let salt = b"random salt value";
+    /// let params = PassphraseParams::with_iterations(1000);
+    /// let key = SymmetricKey::derive_from_passphrase("correct horse battery staple", salt, params)?;
+    ///
+    /// let cell = SecureCell::with_key(&key).seal();
+    /// let encrypted = cell.encrypt(b"source data")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`DERIVED_KEY_LEN`]: ../kdf/constant.DERIVED_KEY_LEN.html
+    pub fn derive_from_passphrase<P, S>(
+        passphrase: P,
+        salt: S,
+        params: PassphraseParams,
+    ) -> Result<Self>
+    where
+        P: AsRef<str>,
+        S: AsRef<[u8]>,
+    {
+        let passphrase = passphrase.as_ref().as_bytes();
+        let salt = salt.as_ref();
+        if passphrase.is_empty() || salt.is_empty() || params.iterations == 0 {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+
+        let (passphrase_ptr, passphrase_len) = into_raw_parts(passphrase);
+        let (salt_ptr, salt_len) = into_raw_parts(salt);
+
+        let mut key = vec![0; DERIVED_KEY_LEN];
+
+        unsafe {
+            let status = soter_pbkdf2_sha256(
+                passphrase_ptr,
+                passphrase_len,
+                salt_ptr,
+                salt_len,
+                params.iterations,
+                key.as_mut_ptr(),
+                key.len(),
+            );
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                key.zeroize();
+                return Err(status.into());
+            }
+        }
+
+        Ok(SymmetricKey::from_vec(key))
+    }
+}
@@ -47,12 +47,17 @@
 
 use std::ptr;
 
-use bindings::{themis_gen_ec_key_pair, themis_gen_rsa_key_pair};
+use bindings::{
+    themis_gen_ec_key_pair, themis_gen_rsa_key_pair, themis_gen_rsa_key_pair_with_size,
+};
+use zeroize::Zeroize;
 
-use crate::error::{Error, ErrorKind, Result};
+use crate::container::HEADER_LEN;
+use crate::error::{Error, ErrorKind, Result, Status};
 use crate::keys::{
     EcdsaKeyPair, EcdsaPublicKey, EcdsaSecretKey, RsaKeyPair, RsaPublicKey, RsaSecretKey,
 };
+use crate::secure_message::{unwrap, wrap};
 
 /// Generates a pair of RSA keys.
 ///
@@ -63,49 +68,113 @@ use crate::keys::{
 /// This function may panic in case of unrecoverable errors inside the library (e.g., out-of-memory
 /// or assertion violations).
 pub fn gen_rsa_key_pair() -> RsaKeyPair {
-    match try_gen_rsa_key_pair() {
+    match try_gen_rsa_key_pair(None) {
         Ok(keys) => keys,
         Err(e) => panic!("themis_gen_rsa_key_pair() failed: {}", e),
     }
 }
 
-/// Generates a secret-public pair of RSA keys.
-fn try_gen_rsa_key_pair() -> Result<RsaKeyPair> {
+/// Length of RSA keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RsaKeySize {
+    /// 1024-bit key.
+    Bits1024,
+    /// 2048-bit key. This is the default used by [`gen_rsa_key_pair`].
+    ///
+    /// [`gen_rsa_key_pair`]: fn.gen_rsa_key_pair.html
+    Bits2048,
+    /// 4096-bit key.
+    Bits4096,
+    /// 8192-bit key.
+    Bits8192,
+}
+
+impl RsaKeySize {
+    /// Returns key length in bits.
+    pub fn bits(self) -> u32 {
+        match self {
+            RsaKeySize::Bits1024 => 1024,
+            RsaKeySize::Bits2048 => 2048,
+            RsaKeySize::Bits4096 => 4096,
+            RsaKeySize::Bits8192 => 8192,
+        }
+    }
+}
+
+/// Generates a pair of RSA keys of specified size.
+///
+/// Use this function if you need keys of particular length. Note that generation of longer
+/// keys takes noticeably more time.
+///
+/// # Examples
+///
+/// ```
+/// use themis::keygen::{gen_rsa_key_pair_with_size, RsaKeySize};
+///
+/// let key_pair = gen_rsa_key_pair_with_size(RsaKeySize::Bits4096);
+/// ```
+///
+/// # Panics
+///
+/// This function may panic in case of unrecoverable errors inside the library (e.g., out-of-memory
+/// or assertion violations).
+pub fn gen_rsa_key_pair_with_size(size: RsaKeySize) -> RsaKeyPair {
+    match try_gen_rsa_key_pair(Some(size)) {
+        Ok(keys) => keys,
+        Err(e) => panic!("themis_gen_rsa_key_pair_with_size() failed: {}", e),
+    }
+}
+
+/// Generates a secret-public pair of RSA keys, of default size if not specified.
+fn try_gen_rsa_key_pair(size: Option<RsaKeySize>) -> Result<RsaKeyPair> {
     let mut secret_key = Vec::new();
     let mut public_key = Vec::new();
     let mut secret_key_len = 0;
     let mut public_key_len = 0;
 
-    unsafe {
-        let status = themis_gen_rsa_key_pair(
-            ptr::null_mut(),
-            &mut secret_key_len,
-            ptr::null_mut(),
-            &mut public_key_len,
-        );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::BufferTooSmall {
-            return Err(error);
+    let generate = |secret_key: *mut u8, secret_key_len, public_key: *mut u8, public_key_len| unsafe {
+        match size {
+            Some(size) => themis_gen_rsa_key_pair_with_size(
+                size.bits(),
+                secret_key,
+                secret_key_len,
+                public_key,
+                public_key_len,
+            ),
+            None => themis_gen_rsa_key_pair(secret_key, secret_key_len, public_key, public_key_len),
         }
+    };
+
+    let status = generate(
+        ptr::null_mut(),
+        &mut secret_key_len,
+        ptr::null_mut(),
+        &mut public_key_len,
+    );
+    let status = Status::from_themis(status);
+    if status != Status::BufferTooSmall {
+        return Err(status.into());
     }
 
-    secret_key.reserve(secret_key_len);
+    // Secret key buffer is initialized so that it can be wiped if anything goes wrong.
+    secret_key.resize(secret_key_len, 0);
     public_key.reserve(secret_key_len);
 
     unsafe {
-        let status = themis_gen_rsa_key_pair(
+        let status = generate(
             secret_key.as_mut_ptr(),
             &mut secret_key_len,
             public_key.as_mut_ptr(),
             &mut public_key_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::Success {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            secret_key.zeroize();
+            return Err(status.into());
         }
-        debug_assert!(secret_key_len <= secret_key.capacity());
+        debug_assert!(secret_key_len <= secret_key.len());
         debug_assert!(public_key_len <= public_key.capacity());
-        secret_key.set_len(secret_key_len as usize);
+        secret_key.truncate(secret_key_len as usize);
         public_key.set_len(public_key_len as usize);
     }
 
@@ -143,13 +212,14 @@ fn try_gen_ec_key_pair() -> Result<EcdsaKeyPair> {
             ptr::null_mut(),
             &mut public_key_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::BufferTooSmall {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
         }
     }
 
-    secret_key.reserve(secret_key_len);
+    // Secret key buffer is initialized so that it can be wiped if anything goes wrong.
+    secret_key.resize(secret_key_len, 0);
     public_key.reserve(secret_key_len);
 
     unsafe {
@@ -159,13 +229,14 @@ fn try_gen_ec_key_pair() -> Result<EcdsaKeyPair> {
             public_key.as_mut_ptr(),
             &mut public_key_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::Success {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            secret_key.zeroize();
+            return Err(status.into());
         }
-        debug_assert!(secret_key_len <= secret_key.capacity());
+        debug_assert!(secret_key_len <= secret_key.len());
         debug_assert!(public_key_len <= public_key.capacity());
-        secret_key.set_len(secret_key_len as usize);
+        secret_key.truncate(secret_key_len as usize);
         public_key.set_len(public_key_len as usize);
     }
 
@@ -173,3 +244,81 @@ fn try_gen_ec_key_pair() -> Result<EcdsaKeyPair> {
     let public_key = EcdsaPublicKey::from_vec(public_key);
     Ok(EcdsaKeyPair::join(secret_key, public_key))
 }
+
+/// Checks that key generation and basic cryptographic operations work correctly.
+///
+/// This function generates a couple of ECDSA key pairs, makes sure that they do not look
+/// degenerate, then performs sign/verify and encrypt/decrypt round-trips with them. Run it
+/// before trusting generated keys on platforms where the quality of random number generator
+/// is not guaranteed, such as embedded devices right after boot.
+///
+/// Returns `ErrorKind::SelfTestFailed` if any of the checks fails.
+///
+/// # Examples
+///
+/// ```
+/// use themis::keygen::{gen_ec_key_pair, self_test};
+///
+/// self_test().expect("Themis self-test");
+///
+/// let identity = gen_ec_key_pair();
+/// ```
+pub fn self_test() -> Result<()> {
+    let alice = try_gen_ec_key_pair()?;
+    let bob = try_gen_ec_key_pair()?;
+
+    check_key_entropy(alice.secret_key_bytes())?;
+    check_key_entropy(bob.secret_key_bytes())?;
+    check(alice.secret_key_bytes() != bob.secret_key_bytes())?;
+
+    let message: &[u8] = b"Themis self-test";
+
+    let mut signed = wrap(alice.secret_key_bytes(), &[], message).map_err(self_test_failed)?;
+    let verified = unwrap(&[], alice.public_key_bytes(), &signed).map_err(self_test_failed)?;
+    check(verified == message)?;
+    check(unwrap(&[], bob.public_key_bytes(), &signed).is_err())?;
+    let last = signed.len() - 1;
+    signed[last] ^= 0xFF;
+    check(unwrap(&[], alice.public_key_bytes(), &signed).is_err())?;
+
+    let encrypt = || {
+        wrap(alice.secret_key_bytes(), bob.public_key_bytes(), message).map_err(self_test_failed)
+    };
+    let encrypted_1 = encrypt()?;
+    let encrypted_2 = encrypt()?;
+    check(encrypted_1 != encrypted_2)?;
+    let decrypted = unwrap(
+        bob.secret_key_bytes(),
+        alice.public_key_bytes(),
+        &encrypted_1,
+    )
+    .map_err(self_test_failed)?;
+    check(decrypted == message)?;
+
+    Ok(())
+}
+
+/// Checks that key data is not obviously degenerate (e.g., all zeros or a repeating pattern).
+///
+/// Random data of a typical key length has almost all bytes distinct.
+fn check_key_entropy(key: &[u8]) -> Result<()> {
+    let data = key.get(HEADER_LEN..).unwrap_or(&[]);
+    let mut seen = [false; 256];
+    for &byte in data {
+        seen[byte as usize] = true;
+    }
+    let distinct = seen.iter().filter(|&&seen| seen).count();
+    check(!data.is_empty() && 2 * distinct >= data.len())
+}
+
+fn check(condition: bool) -> Result<()> {
+    if condition {
+        Ok(())
+    } else {
+        Err(Error::with_kind(ErrorKind::SelfTestFailed))
+    }
+}
+
+fn self_test_failed(_: Error) -> Error {
+    Error::with_kind(ErrorKind::SelfTestFailed)
+}
@@ -0,0 +1,370 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Versioned keys for rotation.
+//!
+//! Keys should be replaced from time to time, but data protected with older keys still needs
+//! to be processed. [`KeyRing`] keeps several numbered versions of a key. New data is always
+//! protected with the newest key which has not expired yet, and is prefixed with the version
+//! of that key. Older keys are used only to process data protected with them.
+//!
+//! Key rings are accepted by the following wrappers:
+//!
+//!   - [`SecureCellRing`] for Secure Cell in sealing mode
+//!   - [`SecureMessageRing`] for encrypted Secure Messages
+//!   - [`SecureSignRing`] and [`SecureVerifyRing`] for signed Secure Messages
+//!
+//! Rings of keys can also be saved into [`KeyStorage`] with [`store`] and loaded back
+//! with [`load`].
+//!
+//! [`KeyRing`]: struct.KeyRing.html
+//! [`SecureCellRing`]: ../secure_cell/struct.SecureCellRing.html
+//! [`SecureMessageRing`]: ../secure_message/struct.SecureMessageRing.html
+//! [`SecureSignRing`]: ../secure_message/struct.SecureSignRing.html
+//! [`SecureVerifyRing`]: ../secure_message/struct.SecureVerifyRing.html
+//! [`KeyStorage`]: ../keystore/trait.KeyStorage.html
+//! [`store`]: struct.KeyRing.html#method.store
+//! [`load`]: struct.KeyRing.html#method.load
+//!
+//! # Examples
+//!
+//! Rotating the key is a matter of adding a new version to the ring:
+//!
+//! ```
+//! # fn main() -> Result<(), themis::Error> {
+//! use themis::keyring::KeyRing;
+//! use themis::keystore::ManagedKey;
+//! use themis::secure_cell::SecureCellRing;
+//!
+//! let mut ring = KeyRing::new();
+//! ring.add(ManagedKey::new("2018", b"old password".to_vec()));
+//!
+//! let mut cell = SecureCellRing::new(ring);
+//! let old_data = cell.encrypt(b"old data")?;
+//!
+//! cell.key_ring_mut().add(ManagedKey::new("2019", b"new password".to_vec()));
+//! let new_data = cell.encrypt(b"new data")?;
+//!
+//! assert_eq!(cell.decrypt(&old_data)?, b"old data");
+//! assert_eq!(cell.decrypt(&new_data)?, b"new data");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::result;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use zeroize::Zeroize;
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::kdf::SecretBytes;
+use crate::keystore::{read_chunk, write_chunk, KeyStorage, ManagedKey};
+
+/// Length of key version prefix in protected data.
+const VERSION_LENGTH: usize = 4;
+
+/// Versioned collection of keys.
+///
+/// Versions are positive numbers, newer keys have greater versions. The _current_ key is the
+/// newest one which has not expired yet. Expired keys are still available for processing older
+/// data until they are explicitly removed.
+#[derive(Clone)]
+pub struct KeyRing<K> {
+    keys: BTreeMap<u32, ManagedKey<K>>,
+}
+
+// ManagedKey prints only key metadata, never the keys.
+impl<K> fmt::Debug for KeyRing<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyRing").field("keys", &self.keys).finish()
+    }
+}
+
+impl<K> Default for KeyRing<K> {
+    fn default() -> Self {
+        Self {
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K> KeyRing<K> {
+    /// Makes a new empty key ring.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a new key to the ring, making it the current one unless it is expired.
+    ///
+    /// Returns the version assigned to the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ring has run out of version numbers.
+    pub fn add(&mut self, key: ManagedKey<K>) -> u32 {
+        let version = match self.keys.keys().next_back() {
+            Some(&last) => last.checked_add(1).expect("key version overflow"),
+            None => 1,
+        };
+        self.keys.insert(version, key);
+        version
+    }
+
+    /// Puts a key with given version into the ring.
+    ///
+    /// This is useful for restoring previously saved key rings. Returns the key previously
+    /// stored with this version, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the version is zero.
+    pub fn insert(&mut self, version: u32, key: ManagedKey<K>) -> Option<ManagedKey<K>> {
+        assert!(version != 0, "key version must be positive");
+        self.keys.insert(version, key)
+    }
+
+    /// Returns a key with given version.
+    pub fn get(&self, version: u32) -> Option<&ManagedKey<K>> {
+        self.keys.get(&version)
+    }
+
+    /// Returns the current key and its version.
+    ///
+    /// Returns `None` if the ring is empty or all keys have expired.
+    pub fn current(&self) -> Option<(u32, &ManagedKey<K>)> {
+        let now = SystemTime::now();
+        self.keys
+            .iter()
+            .rev()
+            .find(|(_, key)| !key.is_expired_at(now))
+            .map(|(&version, key)| (version, key))
+    }
+
+    /// Removes a key with given version from the ring.
+    ///
+    /// Data protected with this key will not be accessible anymore.
+    pub fn remove(&mut self, version: u32) -> Option<ManagedKey<K>> {
+        self.keys.remove(&version)
+    }
+
+    /// Removes all expired keys from the ring.
+    ///
+    /// Data protected with these keys will not be accessible anymore. Returns the number of
+    /// removed keys.
+    pub fn remove_expired(&mut self) -> usize {
+        let now = SystemTime::now();
+        let expired: Vec<u32> = self
+            .keys
+            .iter()
+            .filter(|(_, key)| key.is_expired_at(now))
+            .map(|(&version, _)| version)
+            .collect();
+        for version in &expired {
+            self.keys.remove(version);
+        }
+        expired.len()
+    }
+
+    /// Returns an iterator over keys and their versions, from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &ManagedKey<K>)> {
+        self.keys.iter().map(|(&version, key)| (version, key))
+    }
+
+    /// Returns the number of keys in the ring.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Checks whether the ring is empty.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Converts all keys in the ring, keeping their versions and metadata.
+    ///
+    /// This is useful for parsing keys loaded from storage:
+    ///
+    /// ```
+    /// # fn main() -> Result<(), themis::Error> {
+    /// use themis::keygen::gen_ec_key_pair;
+    /// use themis::keyring::KeyRing;
+    /// use themis::keys::SecretKey;
+    /// use themis::keystore::ManagedKey;
+    ///
+    /// let mut ring = KeyRing::new();
+    /// ring.add(ManagedKey::new("key", gen_ec_key_pair().split().0.as_ref().to_vec()));
+    ///
+    /// let ring = ring.try_map(|bytes| SecretKey::try_from_slice(bytes))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_map<L, E, F>(&self, mut f: F) -> result::Result<KeyRing<L>, E>
+    where
+        F: FnMut(&K) -> result::Result<L, E>,
+    {
+        let mut keys = BTreeMap::new();
+        for (&version, key) in &self.keys {
+            let mut mapped = ManagedKey::with_creation_time(key.id(), f(key.key())?, key.created());
+            if let Some(expires) = key.expires() {
+                mapped = mapped.expires_at(expires);
+            }
+            keys.insert(version, mapped);
+        }
+        Ok(KeyRing { keys })
+    }
+
+    /// Returns the current key and its version, or an error if there is none.
+    pub(crate) fn current_key(&self) -> Result<(u32, &K)> {
+        self.current()
+            .map(|(version, key)| (version, key.key()))
+            .ok_or_else(|| Error::with_kind(ErrorKind::KeyRingNoCurrentKey))
+    }
+
+    /// Finds the key for versioned data, returning the key and the unversioned data.
+    pub(crate) fn key_for<'a>(&self, data: &'a [u8]) -> Result<(&K, &'a [u8])> {
+        if data.len() < VERSION_LENGTH {
+            return Err(Error::with_kind(ErrorKind::DataCorrupt));
+        }
+        let version = (data[0] as u32) << 24
+            | (data[1] as u32) << 16
+            | (data[2] as u32) << 8
+            | (data[3] as u32);
+        match self.keys.get(&version) {
+            Some(key) => Ok((key.key(), &data[VERSION_LENGTH..])),
+            None => Err(Error::with_kind(ErrorKind::KeyRingUnknownVersion)),
+        }
+    }
+}
+
+/// Prefixes data with key version.
+pub(crate) fn with_version(version: u32, data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(VERSION_LENGTH + data.len());
+    output.extend_from_slice(&[
+        (version >> 24) as u8,
+        (version >> 16) as u8,
+        (version >> 8) as u8,
+        version as u8,
+    ]);
+    output.extend_from_slice(data);
+    output
+}
+
+// Key ring storage format: format tag followed by a sequence of entries. Each entry contains
+// key version (32-bit), creation and expiration time (64-bit seconds since UNIX epoch, zero
+// expiration time means none), key ID and key data. Integers are big-endian, ID and key data
+// are length-prefixed chunks.
+
+const KEY_RING_TAG: &[u8] = b"TKR1";
+
+impl<K: AsRef<[u8]>> KeyRing<K> {
+    /// Saves the key ring into storage under given ID.
+    ///
+    /// All versions and their metadata are saved as a single stored item.
+    pub fn store<S: KeyStorage + ?Sized>(&self, storage: &mut S, id: &str) -> io::Result<()> {
+        let mut encoded = Vec::new();
+        encoded.extend_from_slice(KEY_RING_TAG);
+        for (&version, key) in &self.keys {
+            write_u64(&mut encoded, u64::from(version), 4);
+            write_u64(&mut encoded, unix_seconds(key.created()), 8);
+            write_u64(
+                &mut encoded,
+                key.expires().map(unix_seconds).unwrap_or(0),
+                8,
+            );
+            write_chunk(&mut encoded, key.id().as_bytes());
+            write_chunk(&mut encoded, key.key().as_ref());
+        }
+        let result = storage.store(id, &encoded);
+        encoded.zeroize();
+        result
+    }
+}
+
+impl KeyRing<SecretBytes> {
+    /// Loads a key ring saved into storage with [`store`].
+    ///
+    /// Keys are returned as raw bytes which are wiped when dropped, use [`try_map`] to parse
+    /// them. Returns `None` if there
+    /// is no such key ring and an error of kind `InvalidData` if the stored data is corrupted.
+    ///
+    /// [`store`]: struct.KeyRing.html#method.store
+    /// [`try_map`]: struct.KeyRing.html#method.try_map
+    pub fn load<S: KeyStorage + ?Sized>(storage: &mut S, id: &str) -> io::Result<Option<Self>> {
+        let mut encoded = match storage.load(id)? {
+            Some(encoded) => encoded,
+            None => return Ok(None),
+        };
+        let ring = decode_key_ring(&encoded);
+        encoded.zeroize();
+        ring.map(Some)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "corrupted key ring"))
+    }
+}
+
+fn decode_key_ring(input: &[u8]) -> Option<KeyRing<SecretBytes>> {
+    if !input.starts_with(KEY_RING_TAG) {
+        return None;
+    }
+    let mut input = &input[KEY_RING_TAG.len()..];
+    let mut ring = KeyRing::new();
+    while !input.is_empty() {
+        match decode_key_ring_entry(&mut input) {
+            Some((version, key)) if version != 0 => {
+                ring.keys.insert(version, key);
+            }
+            _ => return None,
+        }
+    }
+    Some(ring)
+}
+
+fn decode_key_ring_entry(input: &mut &[u8]) -> Option<(u32, ManagedKey<SecretBytes>)> {
+    let version = read_u64(input, 4)? as u32;
+    let created = read_u64(input, 8)?;
+    let expires = read_u64(input, 8)?;
+    let id = String::from_utf8(read_chunk(input)?.to_vec()).ok()?;
+    let key = SecretBytes::from(read_chunk(input)?.to_vec());
+    let created = UNIX_EPOCH + Duration::from_secs(created);
+    let mut key = ManagedKey::with_creation_time(id, key, created);
+    if expires != 0 {
+        key = key.expires_at(UNIX_EPOCH + Duration::from_secs(expires));
+    }
+    Some((version, key))
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn write_u64(output: &mut Vec<u8>, value: u64, length: usize) {
+    for i in (0..length).rev() {
+        output.push((value >> (8 * i)) as u8);
+    }
+}
+
+fn read_u64(input: &mut &[u8], length: usize) -> Option<u64> {
+    if input.len() < length {
+        return None;
+    }
+    let value = input[..length]
+        .iter()
+        .fold(0, |value, &byte| value << 8 | u64::from(byte));
+    *input = &input[length..];
+    Some(value)
+}
@@ -35,6 +35,7 @@
 //! [`KeyPair`]: struct.KeyPair.html
 //! [`PublicKey`]: struct.PublicKey.html
 //! [`SecretKey`]: struct.SecretKey.html
+//! [`KeyInfo`]: struct.KeyInfo.html
 //!
 //! # Examples
 //!
@@ -108,7 +109,11 @@
 //! ```
 //!
 //! You can also restore the keys from raw bytes using `try_from_slice` methods. They check that
-//! the byte slice indeed contains a valid Themis key of the specified kind:
+//! the byte slice indeed contains a valid Themis key of the specified kind: the key container
+//! must have an expected tag, correct length, and matching checksum. Wrong kind or length is
+//! reported as `ErrorKind::InvalidParameter`, while checksum mismatch (e.g., due to a damaged
+//! file) is reported as `ErrorKind::DataCorrupt`. Use [`KeyInfo`] to find out what exactly is
+//! wrong with a rejected key.
 //!
 //! ```
 //! # fn main() -> Result<(), themis::Error> {
@@ -127,15 +132,107 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! ## Text encoding
+//!
+//! Keys can be encoded with base64 which is the usual way to ship keys around in other
+//! Themis wrappers:
+//!
+//! ```
+//! # fn main() -> Result<(), themis::Error> {
+//! use themis::keys::EcdsaPublicKey;
+//!
+//! let public = EcdsaPublicKey::decode_base64("VUVDMgAAAC0Ti98MAh8JiDnZczqEj6hQ2SvtPTjPHdDO9K7bz6/La6VKCBEh")?;
+//!
+//! assert_eq!(
+//!     public.encode_base64(),
+//!     "VUVDMgAAAC0Ti98MAh8JiDnZczqEj6hQ2SvtPTjPHdDO9K7bz6/La6VKCBEh"
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## PEM encoding
+//!
+//! Keys can be converted into PEM format which is more suitable for copying around by humans,
+//! and parsed back from it:
+//!
+//! ```
+//! # fn main() -> Result<(), themis::Error> {
+//! use themis::keygen::gen_ec_key_pair;
+//! use themis::keys::EcdsaPublicKey;
+//!
+//! let (_, public) = gen_ec_key_pair().split();
+//!
+//! let pem = public.to_pem();
+//! assert!(pem.starts_with("-----BEGIN THEMIS ECDSA PUBLIC KEY-----"));
+//!
+//! let parsed = EcdsaPublicKey::from_pem(&pem)?;
+//! assert_eq!(parsed, public);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Key files
+//!
+//! Keys can be saved into files and loaded back. Secret key files are created readable only
+//! by their owner, and loading a secret key from a file accessible by other users fails.
+//!
+//! ```no_run
+//! # fn main() -> std::io::Result<()> {
+//! use themis::keygen::gen_ec_key_pair;
+//! use themis::keys::EcdsaSecretKey;
+//!
+//! let (secret, public) = gen_ec_key_pair().split();
+//!
+//! secret.write_to_file("secret.key")?;
+//! public.write_to_file("public.key")?;
+//!
+//! let secret = EcdsaSecretKey::read_from_file("secret.key")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Serialization
+//!
+//! With crate feature `serde` enabled all key and key pair types implement `Serialize` and
+//! `Deserialize` traits. Keys are represented as structures with format version, key kind,
+//! and key data. Key data is encoded with base64 for human-readable formats (like JSON)
+//! and kept as is for binary formats. Deserialized keys are validated as with `try_from_slice`.
+//!
+//! ## Standard formats
+//!
+//! With crate feature `der` enabled keys can be converted to and from DER encodings used by
+//! OpenSSL and other PKI tooling: SEC 1 and PKCS #1 for ECDSA and RSA keys respectively,
+//! PKCS #8 for secret keys of either kind, and X.509 SubjectPublicKeyInfo for public keys.
+//! See `to_pkcs8_der()`, `from_pkcs8_der()`, `to_spki_der()`, `from_spki_der()` and others.
+//! Themis supports only NIST P-256, P-384, P-521 curves and RSA keys of certain sizes,
+//! other keys are rejected with `ErrorKind::NotSupported`.
+//!
+//! ## Format conversion
+//!
+//! If the kind or format of a key is not known in advance, use [`convert`] module to detect
+//! and normalize it.
+//!
+//! [`convert`]: convert/index.html
 
 use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::ptr;
 
-use bindings::{themis_get_key_kind, themis_is_valid_key};
+use bindings::{themis_get_key_kind, themis_get_public_key, themis_is_valid_key};
 use zeroize::Zeroize;
 
-use crate::error::{Error, ErrorKind, Result};
+use crate::container::{self, HEADER_LEN, TAG_LEN};
+use crate::error::{Error, ErrorKind, Result, Status};
+use crate::hash::{HashAlgorithm, SecureHash};
+use crate::secure_message;
 use crate::utils::into_raw_parts;
 
+pub mod convert;
+
 /// Key material.
 #[derive(Clone, Eq, PartialEq, Hash)]
 pub(crate) struct KeyBytes(Vec<u8>);
@@ -160,6 +257,32 @@ impl KeyBytes {
     pub fn as_bytes(&self) -> &[u8] {
         &self.0
     }
+
+    /// Returns key fingerprint: a prefix of SHA-256 hash of the key.
+    ///
+    /// Use this only for public keys. Secret key material must not leak in any form,
+    /// not even as a hash.
+    fn fingerprint(&self) -> Fingerprint {
+        let digest = SecureHash::digest(HashAlgorithm::Sha256, &self.0).unwrap_or_default();
+        Fingerprint(digest.into_iter().take(FINGERPRINT_LEN).collect())
+    }
+}
+
+/// Length of public key fingerprints in bytes.
+const FINGERPRINT_LEN: usize = 8;
+
+struct Fingerprint(Vec<u8>);
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(":")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Debug for KeyBytes {
@@ -171,6 +294,10 @@ impl fmt::Debug for KeyBytes {
 // Make sure that sensitive key material is removed from memory as soon as it is no longer needed.
 impl Drop for KeyBytes {
     fn drop(&mut self) {
+        // Wipe the whole allocated buffer, not only its initialized part. This does not
+        // reallocate, and the spare capacity might contain leftovers of key material.
+        let capacity = self.0.capacity();
+        self.0.resize(capacity, 0);
         self.0.zeroize();
     }
 }
@@ -180,38 +307,38 @@ impl Drop for KeyBytes {
 //
 
 /// RSA secret key.
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct RsaSecretKey {
     inner: KeyBytes,
 }
 
 /// RSA public key.
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct RsaPublicKey {
     inner: KeyBytes,
 }
 
 /// RSA key pair.
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct RsaKeyPair {
     secret_key: KeyBytes,
     public_key: KeyBytes,
 }
 
 /// ECDSA secret key.
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct EcdsaSecretKey {
     inner: KeyBytes,
 }
 
 /// ECDSA public key.
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct EcdsaPublicKey {
     inner: KeyBytes,
 }
 
 /// ECDSA key pair.
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct EcdsaKeyPair {
     secret_key: KeyBytes,
     public_key: KeyBytes,
@@ -224,7 +351,7 @@ pub struct EcdsaKeyPair {
 ///
 /// [`RsaSecretKey`]: struct.RsaSecretKey.html
 /// [`EcdsaSecretKey`]: struct.EcdsaSecretKey.html
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct SecretKey {
     inner: KeyBytes,
 }
@@ -236,7 +363,7 @@ pub struct SecretKey {
 ///
 /// [`RsaPublicKey`]: struct.RsaPublicKey.html
 /// [`EcdsaPublicKey`]: struct.EcdsaPublicKey.html
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct PublicKey {
     inner: KeyBytes,
 }
@@ -252,12 +379,23 @@ pub struct PublicKey {
 /// [`EcdsaKeyPair`]: struct.EcdsaKeyPair.html
 /// [`SecretKey`]: struct.SecretKey.html
 /// [`PublicKey`]: struct.PublicKey.html
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct KeyPair {
     secret_key: KeyBytes,
     public_key: KeyBytes,
 }
 
+/// A symmetric key.
+///
+/// Symmetric keys are arbitrary non-empty byte strings, they can be used with [Secure Cell].
+/// Unlike plain byte vectors, key material is wiped from memory when the key is dropped.
+///
+/// [Secure Cell]: ../secure_cell/index.html
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct SymmetricKey {
+    inner: KeyBytes,
+}
+
 /// Kind of an asymmetric key.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum KeyKind {
@@ -271,11 +409,196 @@ pub enum KeyKind {
     EcdsaPublic,
 }
 
+/// Information about a key container.
+///
+/// Use [`KeyInfo::inspect`] to examine an arbitrary byte blob which is supposed to contain
+/// a key. This may help to find out why a key is rejected: whether it is truncated, corrupted,
+/// of the wrong kind, or not a key at all.
+///
+/// [`KeyInfo::inspect`]: struct.KeyInfo.html#method.inspect
+///
+/// # Examples
+///
+/// ```
+/// use themis::keygen::gen_ec_key_pair;
+/// use themis::keys::{KeyInfo, KeyKind};
+///
+/// let (_, public) = gen_ec_key_pair().split();
+/// let mut bytes = public.as_ref().to_vec();
+/// bytes.truncate(bytes.len() - 5);
+///
+/// let info = KeyInfo::inspect(&bytes).expect("key header");
+///
+/// assert_eq!(info.tag(), b"UEC2");
+/// assert_eq!(info.kind(), Some(KeyKind::EcdsaPublic));
+/// assert!(info.is_truncated());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyInfo {
+    tag: [u8; TAG_LEN],
+    declared_size: usize,
+    actual_size: usize,
+    checksum_valid: Option<bool>,
+    kind: Option<KeyKind>,
+}
+
+impl KeyInfo {
+    /// Examines key container header.
+    ///
+    /// Returns `None` if the buffer is too short to contain a header.
+    pub fn inspect<T: AsRef<[u8]>>(bytes: T) -> Option<KeyInfo> {
+        let bytes = bytes.as_ref();
+        if bytes.len() < HEADER_LEN {
+            return None;
+        }
+
+        let mut tag = [0; TAG_LEN];
+        tag.copy_from_slice(&bytes[0..TAG_LEN]);
+        let declared_size = container::read_u32(&bytes[4..8]) as usize;
+
+        // Checksum can be verified only if the whole container is available.
+        let checksum_valid = if declared_size >= HEADER_LEN && declared_size <= bytes.len() {
+            let key = KeyBytes::copy_slice(&bytes[..declared_size]);
+            Some(is_valid_themis_key(&key).is_ok())
+        } else {
+            None
+        };
+
+        let kind = try_get_key_kind(&KeyBytes::copy_slice(&bytes[..HEADER_LEN])).ok();
+
+        Some(KeyInfo {
+            tag,
+            declared_size,
+            actual_size: bytes.len(),
+            checksum_valid,
+            kind,
+        })
+    }
+
+    /// Returns container tag.
+    pub fn tag(&self) -> &[u8] {
+        &self.tag
+    }
+
+    /// Returns container size as declared in its header.
+    pub fn declared_size(&self) -> usize {
+        self.declared_size
+    }
+
+    /// Returns actual size of inspected buffer.
+    pub fn actual_size(&self) -> usize {
+        self.actual_size
+    }
+
+    /// Checks whether the buffer is shorter than the declared size.
+    pub fn is_truncated(&self) -> bool {
+        self.actual_size < self.declared_size
+    }
+
+    /// Checks whether the container checksum is valid.
+    ///
+    /// Returns `None` if the checksum cannot be verified because the container is truncated
+    /// or its declared size is invalid.
+    pub fn is_checksum_valid(&self) -> Option<bool> {
+        self.checksum_valid
+    }
+
+    /// Returns key kind indicated by the container tag, if it is a known one.
+    pub fn kind(&self) -> Option<KeyKind> {
+        self.kind
+    }
+}
+
+//
+// Key handles
+//
+
+/// Source of secret key material.
+///
+/// Key handles allow secret keys to be kept somewhere else than in application memory,
+/// for example, in a hardware token accessed via PKCS #11 or in a key management service.
+/// Themis performs all cryptographic operations itself, so it still needs the key bytes:
+/// a handle is asked for the secret key right before it is used, and the key is dropped
+/// (and wiped) afterwards. This allows handles to control when and for how long keys
+/// stay in memory, perform access checks, audit key usage, etc.
+///
+/// All secret key types implement this trait by simply returning a copy of themselves.
+/// Handles may report their own failures by converting an `ErrorKind` into `Error`.
+///
+/// Key handles are accepted by [`SecureSign::with_key_handle`] and
+/// [`SecureSession::with_key_handle`].
+///
+/// [`SecureSign::with_key_handle`]: ../secure_message/struct.SecureSign.html#method.with_key_handle
+/// [`SecureSession::with_key_handle`]: ../secure_session/struct.SecureSession.html#method.with_key_handle
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), themis::Error> {
+/// use themis::keygen::gen_ec_key_pair;
+/// use themis::keys::{PrivateKeyHandle, SecretKey};
+/// use themis::secure_message::{SecureSign, SecureVerify};
+///
+/// struct Token {
+///     wrapped_key: Vec<u8>,
+/// }
+///
+/// impl PrivateKeyHandle for Token {
+///     fn secret_key(&self) -> themis::Result<SecretKey> {
+///         // Real token would unwrap the key here.
+///         SecretKey::try_from_slice(&self.wrapped_key)
+///     }
+/// }
+///
+/// let (secret, public) = gen_ec_key_pair().split();
+/// let token = Token { wrapped_key: secret.as_ref().to_vec() };
+///
+/// let signed = SecureSign::with_key_handle(token).sign(b"message")?;
+///
+/// assert_eq!(SecureVerify::new(public).verify(&signed)?, b"message");
+/// # Ok(())
+/// # }
+/// ```
+pub trait PrivateKeyHandle {
+    /// Retrieves the secret key for a single operation.
+    fn secret_key(&self) -> Result<SecretKey>;
+}
+
+impl PrivateKeyHandle for SecretKey {
+    fn secret_key(&self) -> Result<SecretKey> {
+        Ok(self.clone())
+    }
+}
+
+impl PrivateKeyHandle for RsaSecretKey {
+    fn secret_key(&self) -> Result<SecretKey> {
+        Ok(self.clone().into())
+    }
+}
+
+impl PrivateKeyHandle for EcdsaSecretKey {
+    fn secret_key(&self) -> Result<SecretKey> {
+        Ok(self.clone().into())
+    }
+}
+
 //
 // Key pairs
 //
 
 impl RsaKeyPair {
+    /// Access bytes of the secret key.
+    #[cfg(feature = "serde")]
+    pub(crate) fn secret_key_bytes(&self) -> &[u8] {
+        self.secret_key.as_bytes()
+    }
+
+    /// Access bytes of the public key.
+    #[cfg(feature = "serde")]
+    pub(crate) fn public_key_bytes(&self) -> &[u8] {
+        self.public_key.as_bytes()
+    }
+
     /// Splits this key pair into secret and public keys.
     pub fn split(self) -> (RsaSecretKey, RsaPublicKey) {
         (
@@ -291,16 +614,37 @@ impl RsaKeyPair {
     /// Joins a pair of secret and public keys.
     ///
     /// Note that this method _does not_ verify that the keys match: i.e., that it is possible
-    /// to use the secret key to decrypt data encrypted with the public key.
+    /// to use the secret key to decrypt data encrypted with the public key. Use [`verify_matching`]
+    /// if you need to check that.
+    ///
+    /// [`verify_matching`]: #method.verify_matching
     pub fn join(secret_key: RsaSecretKey, public_key: RsaPublicKey) -> RsaKeyPair {
         RsaKeyPair {
             secret_key: secret_key.inner,
             public_key: public_key.inner,
         }
     }
+
+    /// Verifies that the secret and public keys of this pair match.
+    ///
+    /// This is done by signing a message with the secret key and verifying the signature with
+    /// the public key. Returns `ErrorKind::InvalidParameter` if the keys do not match.
+    pub fn verify_matching(&self) -> Result<()> {
+        verify_matching(&self.secret_key, &self.public_key)
+    }
 }
 
 impl EcdsaKeyPair {
+    /// Access bytes of the secret key.
+    pub(crate) fn secret_key_bytes(&self) -> &[u8] {
+        self.secret_key.as_bytes()
+    }
+
+    /// Access bytes of the public key.
+    pub(crate) fn public_key_bytes(&self) -> &[u8] {
+        self.public_key.as_bytes()
+    }
+
     /// Splits this key pair into secret and public keys.
     pub fn split(self) -> (EcdsaSecretKey, EcdsaPublicKey) {
         (
@@ -316,16 +660,33 @@ impl EcdsaKeyPair {
     /// Joins a pair of secret and public keys.
     ///
     /// Note that this method _does not_ verify that the keys match: i.e., that it is possible
-    /// to use the secret key to decrypt data encrypted with the public key.
+    /// to use the secret key to decrypt data encrypted with the public key. Use [`verify_matching`]
+    /// if you need to check that.
+    ///
+    /// [`verify_matching`]: #method.verify_matching
     pub fn join(secret_key: EcdsaSecretKey, public_key: EcdsaPublicKey) -> EcdsaKeyPair {
         EcdsaKeyPair {
             secret_key: secret_key.inner,
             public_key: public_key.inner,
         }
     }
+
+    /// Verifies that the secret and public keys of this pair match.
+    ///
+    /// This is done by signing a message with the secret key and verifying the signature with
+    /// the public key. Returns `ErrorKind::InvalidParameter` if the keys do not match.
+    pub fn verify_matching(&self) -> Result<()> {
+        verify_matching(&self.secret_key, &self.public_key)
+    }
 }
 
 impl KeyPair {
+    /// Retrieves actual kind of the stored secret key.
+    #[cfg(feature = "serde")]
+    pub(crate) fn kind(&self) -> KeyKind {
+        get_key_kind_trusted(&self.secret_key)
+    }
+
     /// Access bytes of the secret key.
     pub(crate) fn secret_key_bytes(&self) -> &[u8] {
         self.secret_key.as_bytes()
@@ -351,11 +712,14 @@ impl KeyPair {
     /// Joins a pair of secret and public keys.
     ///
     /// Note that this method _does not_ verify that the keys match: i.e., that it is possible
-    /// to use the secret key to decrypt data encrypted with the public key.
+    /// to use the secret key to decrypt data encrypted with the public key. Use [`verify_matching`]
+    /// if you need to check that.
     ///
     /// However, it does verify that _the kinds_ of the keys match: i.e., that they are both
     /// either RSA or ECDSA keys. An error is returned if that’s not the case. You can check
     /// the kind of the key beforehand via its `kind()` method.
+    ///
+    /// [`verify_matching`]: #method.verify_matching
     pub fn try_join<S, P>(secret_key: S, public_key: P) -> Result<KeyPair>
     where
         S: Into<SecretKey>,
@@ -374,6 +738,41 @@ impl KeyPair {
             public_key: public_key.inner,
         })
     }
+
+    /// Verifies that the secret and public keys of this pair match.
+    ///
+    /// This is done by signing a message with the secret key and verifying the signature with
+    /// the public key. Returns `ErrorKind::InvalidParameter` if the keys do not match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use themis::keygen::gen_ec_key_pair;
+    /// use themis::keys::KeyPair;
+    ///
+    /// let (secret_a, public_a) = gen_ec_key_pair().split();
+    /// let (_, public_b) = gen_ec_key_pair().split();
+    ///
+    /// let key_pair = KeyPair::try_join(secret_a.clone(), public_a).unwrap();
+    /// assert!(key_pair.verify_matching().is_ok());
+    ///
+    /// let key_pair = KeyPair::try_join(secret_a, public_b).unwrap();
+    /// assert!(key_pair.verify_matching().is_err());
+    /// ```
+    pub fn verify_matching(&self) -> Result<()> {
+        verify_matching(&self.secret_key, &self.public_key)
+    }
+}
+
+/// Checks that the keys match by a sign-verify round-trip.
+fn verify_matching(secret_key: &KeyBytes, public_key: &KeyBytes) -> Result<()> {
+    const MESSAGE: &[u8] = b"Themis key pair verification";
+
+    let signed = secure_message::wrap(secret_key.as_bytes(), &[], MESSAGE)?;
+    match secure_message::unwrap(&[], public_key.as_bytes(), &signed) {
+        Ok(ref verified) if verified == MESSAGE => Ok(()),
+        _ => Err(Error::with_kind(ErrorKind::InvalidParameter)),
+    }
 }
 
 //
@@ -498,6 +897,28 @@ impl PublicKey {
     }
 }
 
+impl SymmetricKey {
+    /// Makes a key from a copy of a byte slice.
+    ///
+    /// Returns an error if the slice is empty.
+    pub fn try_from_slice<T: AsRef<[u8]>>(bytes: T) -> Result<Self> {
+        if bytes.as_ref().is_empty() {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+        Ok(Self {
+            inner: KeyBytes::copy_slice(bytes.as_ref()),
+        })
+    }
+
+    /// Wraps an existing trusted byte vector into a key.
+    pub(crate) fn from_vec(bytes: Vec<u8>) -> Self {
+        debug_assert!(!bytes.is_empty());
+        Self {
+            inner: KeyBytes::from_vec(bytes),
+        }
+    }
+}
+
 // The following functions have to be called in a particular sequence in order to be safe to use.
 // That's why they are free functions, not methods of KeyBytes.
 //
@@ -520,9 +941,9 @@ fn get_key_kind_trusted(key: &KeyBytes) -> KeyKind {
 fn is_valid_themis_key(key: &KeyBytes) -> Result<()> {
     let (ptr, len) = into_raw_parts(key.as_bytes());
     let status = unsafe { themis_is_valid_key(ptr, len) };
-    let error = Error::from_themis_status(status);
-    if error.kind() != ErrorKind::Success {
-        return Err(error);
+    let status = Status::from_themis(status);
+    if status != Status::Success {
+        return Err(status.into());
     }
     Ok(())
 }
@@ -540,6 +961,85 @@ fn try_get_key_kind(key: &KeyBytes) -> Result<KeyKind> {
     }
 }
 
+//
+// Public key derivation
+//
+
+impl RsaSecretKey {
+    /// Computes the public key corresponding to this secret key.
+    ///
+    /// RSA secret key contains all components of the public key so this is a cheap operation.
+    pub fn public_key(&self) -> Result<RsaPublicKey> {
+        derive_public_key(&self.inner).map(|inner| RsaPublicKey { inner })
+    }
+}
+
+impl EcdsaSecretKey {
+    /// Computes the public key corresponding to this secret key.
+    ///
+    /// This is useful if you have kept only the secret key and need to distribute
+    /// the public one again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), themis::Error> {
+    /// use themis::keygen::gen_ec_key_pair;
+    ///
+    /// let (secret, public) = gen_ec_key_pair().split();
+    ///
+    /// assert_eq!(secret.public_key()?, public);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn public_key(&self) -> Result<EcdsaPublicKey> {
+        derive_public_key(&self.inner).map(|inner| EcdsaPublicKey { inner })
+    }
+}
+
+impl SecretKey {
+    /// Computes the public key corresponding to this secret key.
+    ///
+    /// The resulting public key is of the same kind as the secret key.
+    pub fn public_key(&self) -> Result<PublicKey> {
+        derive_public_key(&self.inner).map(|inner| PublicKey { inner })
+    }
+}
+
+fn derive_public_key(secret_key: &KeyBytes) -> Result<KeyBytes> {
+    let (secret_ptr, secret_len) = into_raw_parts(secret_key.as_bytes());
+    let mut public_key = Vec::new();
+    let mut public_key_len = 0;
+
+    unsafe {
+        let status =
+            themis_get_public_key(secret_ptr, secret_len, ptr::null_mut(), &mut public_key_len);
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
+        }
+    }
+
+    public_key.reserve(public_key_len);
+
+    unsafe {
+        let status = themis_get_public_key(
+            secret_ptr,
+            secret_len,
+            public_key.as_mut_ptr(),
+            &mut public_key_len,
+        );
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
+        }
+        debug_assert!(public_key_len <= public_key.capacity());
+        public_key.set_len(public_key_len);
+    }
+
+    Ok(KeyBytes::from_vec(public_key))
+}
+
 //
 // AsRef<[u8]> casts
 //
@@ -580,6 +1080,593 @@ impl AsRef<[u8]> for PublicKey {
     }
 }
 
+impl AsRef<[u8]> for SymmetricKey {
+    fn as_ref(&self) -> &[u8] {
+        self.inner.as_bytes()
+    }
+}
+
+//
+// Base64 encoding
+//
+
+impl RsaSecretKey {
+    /// Encodes this key with base64.
+    pub fn encode_base64(&self) -> String {
+        base64::encode(self.inner.as_bytes())
+    }
+
+    /// Parses a base64-encoded key.
+    ///
+    /// Returns an error if the string does not contain a valid RSA secret key.
+    pub fn decode_base64(encoded: &str) -> Result<Self> {
+        Self::try_from_slice(decode_base64(encoded)?.as_bytes())
+    }
+}
+
+impl RsaPublicKey {
+    /// Encodes this key with base64.
+    pub fn encode_base64(&self) -> String {
+        base64::encode(self.inner.as_bytes())
+    }
+
+    /// Parses a base64-encoded key.
+    ///
+    /// Returns an error if the string does not contain a valid RSA public key.
+    pub fn decode_base64(encoded: &str) -> Result<Self> {
+        Self::try_from_slice(decode_base64(encoded)?.as_bytes())
+    }
+}
+
+impl EcdsaSecretKey {
+    /// Encodes this key with base64.
+    pub fn encode_base64(&self) -> String {
+        base64::encode(self.inner.as_bytes())
+    }
+
+    /// Parses a base64-encoded key.
+    ///
+    /// Returns an error if the string does not contain a valid ECDSA secret key.
+    pub fn decode_base64(encoded: &str) -> Result<Self> {
+        Self::try_from_slice(decode_base64(encoded)?.as_bytes())
+    }
+}
+
+impl EcdsaPublicKey {
+    /// Encodes this key with base64.
+    pub fn encode_base64(&self) -> String {
+        base64::encode(self.inner.as_bytes())
+    }
+
+    /// Parses a base64-encoded key.
+    ///
+    /// Returns an error if the string does not contain a valid ECDSA public key.
+    pub fn decode_base64(encoded: &str) -> Result<Self> {
+        Self::try_from_slice(decode_base64(encoded)?.as_bytes())
+    }
+}
+
+impl SecretKey {
+    /// Encodes this key with base64.
+    pub fn encode_base64(&self) -> String {
+        base64::encode(self.inner.as_bytes())
+    }
+
+    /// Parses a base64-encoded key.
+    ///
+    /// Returns an error if the string does not contain a valid RSA or ECDSA secret key.
+    pub fn decode_base64(encoded: &str) -> Result<Self> {
+        Self::try_from_slice(decode_base64(encoded)?.as_bytes())
+    }
+}
+
+impl PublicKey {
+    /// Encodes this key with base64.
+    pub fn encode_base64(&self) -> String {
+        base64::encode(self.inner.as_bytes())
+    }
+
+    /// Parses a base64-encoded key.
+    ///
+    /// Returns an error if the string does not contain a valid RSA or ECDSA public key.
+    pub fn decode_base64(encoded: &str) -> Result<Self> {
+        Self::try_from_slice(decode_base64(encoded)?.as_bytes())
+    }
+}
+
+fn decode_base64(encoded: &str) -> Result<KeyBytes> {
+    match base64::decode(encoded.trim()) {
+        Ok(bytes) => Ok(KeyBytes::from_vec(bytes)),
+        Err(_) => Err(Error::with_kind(ErrorKind::InvalidParameter)),
+    }
+}
+
+//
+// PEM encoding
+//
+
+impl RsaSecretKey {
+    /// Encodes this key in PEM format.
+    pub fn to_pem(&self) -> String {
+        encode_pem(KeyKind::RsaSecret, &self.inner)
+    }
+
+    /// Parses a key in PEM format.
+    ///
+    /// Returns an error if the string does not contain a valid RSA secret key.
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        match decode_pem(pem)? {
+            (KeyKind::RsaSecret, key) => Ok(Self { inner: key }),
+            _ => Err(Error::with_kind(ErrorKind::InvalidParameter)),
+        }
+    }
+}
+
+impl RsaPublicKey {
+    /// Encodes this key in PEM format.
+    pub fn to_pem(&self) -> String {
+        encode_pem(KeyKind::RsaPublic, &self.inner)
+    }
+
+    /// Parses a key in PEM format.
+    ///
+    /// Returns an error if the string does not contain a valid RSA public key.
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        match decode_pem(pem)? {
+            (KeyKind::RsaPublic, key) => Ok(Self { inner: key }),
+            _ => Err(Error::with_kind(ErrorKind::InvalidParameter)),
+        }
+    }
+}
+
+impl EcdsaSecretKey {
+    /// Encodes this key in PEM format.
+    pub fn to_pem(&self) -> String {
+        encode_pem(KeyKind::EcdsaSecret, &self.inner)
+    }
+
+    /// Parses a key in PEM format.
+    ///
+    /// Returns an error if the string does not contain a valid ECDSA secret key.
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        match decode_pem(pem)? {
+            (KeyKind::EcdsaSecret, key) => Ok(Self { inner: key }),
+            _ => Err(Error::with_kind(ErrorKind::InvalidParameter)),
+        }
+    }
+}
+
+impl EcdsaPublicKey {
+    /// Encodes this key in PEM format.
+    pub fn to_pem(&self) -> String {
+        encode_pem(KeyKind::EcdsaPublic, &self.inner)
+    }
+
+    /// Parses a key in PEM format.
+    ///
+    /// Returns an error if the string does not contain a valid ECDSA public key.
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        match decode_pem(pem)? {
+            (KeyKind::EcdsaPublic, key) => Ok(Self { inner: key }),
+            _ => Err(Error::with_kind(ErrorKind::InvalidParameter)),
+        }
+    }
+}
+
+impl SecretKey {
+    /// Encodes this key in PEM format.
+    pub fn to_pem(&self) -> String {
+        encode_pem(self.kind(), &self.inner)
+    }
+
+    /// Parses a key in PEM format.
+    ///
+    /// Returns an error if the string does not contain a valid RSA or ECDSA secret key.
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        match decode_pem(pem)? {
+            (KeyKind::RsaSecret, key) => Ok(Self { inner: key }),
+            (KeyKind::EcdsaSecret, key) => Ok(Self { inner: key }),
+            _ => Err(Error::with_kind(ErrorKind::InvalidParameter)),
+        }
+    }
+}
+
+impl PublicKey {
+    /// Encodes this key in PEM format.
+    pub fn to_pem(&self) -> String {
+        encode_pem(self.kind(), &self.inner)
+    }
+
+    /// Parses a key in PEM format.
+    ///
+    /// Returns an error if the string does not contain a valid RSA or ECDSA public key.
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        match decode_pem(pem)? {
+            (KeyKind::RsaPublic, key) => Ok(Self { inner: key }),
+            (KeyKind::EcdsaPublic, key) => Ok(Self { inner: key }),
+            _ => Err(Error::with_kind(ErrorKind::InvalidParameter)),
+        }
+    }
+}
+
+// PEM labels identify the kind of the key. They must match the kind stored in the key itself.
+
+fn pem_label(kind: KeyKind) -> &'static str {
+    match kind {
+        KeyKind::RsaSecret => "THEMIS RSA PRIVATE KEY",
+        KeyKind::RsaPublic => "THEMIS RSA PUBLIC KEY",
+        KeyKind::EcdsaSecret => "THEMIS ECDSA PRIVATE KEY",
+        KeyKind::EcdsaPublic => "THEMIS ECDSA PUBLIC KEY",
+    }
+}
+
+const PEM_KINDS: [KeyKind; 4] = [
+    KeyKind::RsaSecret,
+    KeyKind::RsaPublic,
+    KeyKind::EcdsaSecret,
+    KeyKind::EcdsaPublic,
+];
+
+const PEM_LINE_LENGTH: usize = 64;
+
+fn encode_pem(kind: KeyKind, key: &KeyBytes) -> String {
+    let label = pem_label(kind);
+    let mut encoded = base64::encode(key.as_bytes());
+
+    let mut pem = String::with_capacity(encoded.len() + encoded.len() / PEM_LINE_LENGTH + 64);
+    pem.push_str("-----BEGIN ");
+    pem.push_str(label);
+    pem.push_str("-----\n");
+    // Base64 alphabet is ASCII so it is safe to split the string at any byte.
+    for line in encoded.as_bytes().chunks(PEM_LINE_LENGTH) {
+        pem.push_str(std::str::from_utf8(line).expect("base64 is ASCII"));
+        pem.push('\n');
+    }
+    pem.push_str("-----END ");
+    pem.push_str(label);
+    pem.push_str("-----\n");
+
+    encoded.zeroize();
+    pem
+}
+
+fn pem_boundary_label<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    let suffix = "-----";
+    if line.len() >= prefix.len() + suffix.len()
+        && line.starts_with(prefix)
+        && line.ends_with(suffix)
+    {
+        Some(&line[prefix.len()..line.len() - suffix.len()])
+    } else {
+        None
+    }
+}
+
+fn decode_pem(pem: &str) -> Result<(KeyKind, KeyBytes)> {
+    let invalid = || Error::with_kind(ErrorKind::InvalidParameter);
+
+    let mut lines = pem.trim().lines().map(str::trim);
+    let begin = lines.next().ok_or_else(invalid)?;
+    let end = lines.next_back().ok_or_else(invalid)?;
+
+    let label = pem_boundary_label(begin, "-----BEGIN ").ok_or_else(invalid)?;
+    if pem_boundary_label(end, "-----END ") != Some(label) {
+        return Err(invalid());
+    }
+    let kind = *PEM_KINDS
+        .iter()
+        .find(|&&kind| pem_label(kind) == label)
+        .ok_or_else(invalid)?;
+
+    let mut encoded: String = lines.collect();
+    let decoded = base64::decode(&encoded);
+    encoded.zeroize();
+    let key = KeyBytes::from_vec(decoded.map_err(|_| invalid())?);
+
+    if get_key_kind(&key)? != kind {
+        return Err(invalid());
+    }
+    Ok((kind, key))
+}
+
+//
+// Key files
+//
+
+impl RsaSecretKey {
+    /// Writes this key into a new file.
+    ///
+    /// Fails if the file already exists. On Unix the file is created with permissions
+    /// restricted to reading by the owner only (0400).
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        write_key_file(path.as_ref(), &self.inner, true)
+    }
+
+    /// Reads a key from a file.
+    ///
+    /// Returns an error of kind `InvalidData` if the file does not contain a valid RSA secret key.
+    /// On Unix an error of kind `PermissionDenied` is returned if the file is accessible by
+    /// the group or other users.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let key = read_key_file(path.as_ref(), true)?;
+        Self::try_from_slice(key.as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl RsaPublicKey {
+    /// Writes this key into a new file.
+    ///
+    /// Fails if the file already exists.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        write_key_file(path.as_ref(), &self.inner, false)
+    }
+
+    /// Reads a key from a file.
+    ///
+    /// Returns an error of kind `InvalidData` if the file does not contain a valid RSA public key.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let key = read_key_file(path.as_ref(), false)?;
+        Self::try_from_slice(key.as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl EcdsaSecretKey {
+    /// Writes this key into a new file.
+    ///
+    /// Fails if the file already exists. On Unix the file is created with permissions
+    /// restricted to reading by the owner only (0400).
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        write_key_file(path.as_ref(), &self.inner, true)
+    }
+
+    /// Reads a key from a file.
+    ///
+    /// Returns an error of kind `InvalidData` if the file does not contain a valid ECDSA secret key.
+    /// On Unix an error of kind `PermissionDenied` is returned if the file is accessible by
+    /// the group or other users.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let key = read_key_file(path.as_ref(), true)?;
+        Self::try_from_slice(key.as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl EcdsaPublicKey {
+    /// Writes this key into a new file.
+    ///
+    /// Fails if the file already exists.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        write_key_file(path.as_ref(), &self.inner, false)
+    }
+
+    /// Reads a key from a file.
+    ///
+    /// Returns an error of kind `InvalidData` if the file does not contain a valid ECDSA public key.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let key = read_key_file(path.as_ref(), false)?;
+        Self::try_from_slice(key.as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl SecretKey {
+    /// Writes this key into a new file.
+    ///
+    /// Fails if the file already exists. On Unix the file is created with permissions
+    /// restricted to reading by the owner only (0400).
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        write_key_file(path.as_ref(), &self.inner, true)
+    }
+
+    /// Reads a key from a file.
+    ///
+    /// Returns an error of kind `InvalidData` if the file does not contain a valid RSA or ECDSA secret key.
+    /// On Unix an error of kind `PermissionDenied` is returned if the file is accessible by
+    /// the group or other users.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let key = read_key_file(path.as_ref(), true)?;
+        Self::try_from_slice(key.as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl PublicKey {
+    /// Writes this key into a new file.
+    ///
+    /// Fails if the file already exists.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        write_key_file(path.as_ref(), &self.inner, false)
+    }
+
+    /// Reads a key from a file.
+    ///
+    /// Returns an error of kind `InvalidData` if the file does not contain a valid RSA or ECDSA public key.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let key = read_key_file(path.as_ref(), false)?;
+        Self::try_from_slice(key.as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+fn write_key_file(path: &Path, key: &KeyBytes, secret: bool) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        if secret {
+            options.mode(0o400);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = secret;
+
+    let mut file = options.open(path)?;
+    file.write_all(key.as_bytes())?;
+    file.sync_all()
+}
+
+/// Maximum size of key files, well above the largest RSA keys.
+const MAX_KEY_FILE_LENGTH: u64 = 64 * 1024;
+
+fn read_key_file(path: &Path, secret: bool) -> io::Result<KeyBytes> {
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.permissions().mode();
+        if secret && (mode & 0o077 != 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "secret key file is accessible by other users (mode {:o})",
+                    mode & 0o777
+                ),
+            ));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = secret;
+
+    if metadata.len() > MAX_KEY_FILE_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "key file is too large",
+        ));
+    }
+    // Read into a buffer of exact size. Growing it would leave copies of the key in memory.
+    let mut key = KeyBytes::from_vec(vec![0; metadata.len() as usize]);
+    file.read_exact(&mut key.0)?;
+    Ok(key)
+}
+
+//
+// Debug output
+//
+// Never print key bytes. Secret keys show only kind and length, public keys add a fingerprint.
+//
+
+impl fmt::Debug for RsaSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_secret_key(f, "RsaSecretKey", "RSA", &self.inner)
+    }
+}
+
+impl fmt::Debug for RsaPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_public_key(f, "RsaPublicKey", "RSA", &self.inner)
+    }
+}
+
+impl fmt::Debug for EcdsaSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_secret_key(f, "EcdsaSecretKey", "ECDSA", &self.inner)
+    }
+}
+
+impl fmt::Debug for EcdsaPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_public_key(f, "EcdsaPublicKey", "ECDSA", &self.inner)
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_secret_key(f, "SecretKey", key_algorithm(self.kind()), &self.inner)
+    }
+}
+
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_public_key(f, "PublicKey", key_algorithm(self.kind()), &self.inner)
+    }
+}
+
+impl fmt::Debug for SymmetricKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SymmetricKey({} bytes)", self.inner.as_bytes().len())
+    }
+}
+
+impl fmt::Debug for RsaKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_key_pair(f, "RsaKeyPair", "RSA", &self.public_key)
+    }
+}
+
+impl fmt::Debug for EcdsaKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_key_pair(f, "EcdsaKeyPair", "ECDSA", &self.public_key)
+    }
+}
+
+impl fmt::Debug for KeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_key_pair(
+            f,
+            "KeyPair",
+            key_algorithm(get_key_kind_trusted(&self.secret_key)),
+            &self.public_key,
+        )
+    }
+}
+
+fn key_algorithm(kind: KeyKind) -> &'static str {
+    match kind {
+        KeyKind::RsaSecret | KeyKind::RsaPublic => "RSA",
+        KeyKind::EcdsaSecret | KeyKind::EcdsaPublic => "ECDSA",
+    }
+}
+
+fn fmt_secret_key(
+    f: &mut fmt::Formatter,
+    name: &str,
+    algorithm: &str,
+    key: &KeyBytes,
+) -> fmt::Result {
+    write!(
+        f,
+        "{}(kind={}, {} bytes)",
+        name,
+        algorithm,
+        key.as_bytes().len()
+    )
+}
+
+fn fmt_public_key(
+    f: &mut fmt::Formatter,
+    name: &str,
+    algorithm: &str,
+    key: &KeyBytes,
+) -> fmt::Result {
+    write!(
+        f,
+        "{}(kind={}, fp={}, {} bytes)",
+        name,
+        algorithm,
+        key.fingerprint(),
+        key.as_bytes().len()
+    )
+}
+
+fn fmt_key_pair(
+    f: &mut fmt::Formatter,
+    name: &str,
+    algorithm: &str,
+    public_key: &KeyBytes,
+) -> fmt::Result {
+    write!(
+        f,
+        "{}(kind={}, public fp={})",
+        name,
+        algorithm,
+        public_key.fingerprint()
+    )
+}
+
 //
 // From/Into conversions
 //
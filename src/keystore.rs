@@ -0,0 +1,653 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Key storage.
+//!
+//! Applications need a place to keep their keys between runs. [`KeyStorage`] trait provides
+//! a common interface for key storage backends which keep keys under string identifiers.
+//! The following implementations are available:
+//!
+//!   - [`MemoryKeyStorage`] keeps keys in memory, useful for testing
+//!   - [`FileKeyStore`] keeps keys in a single file encrypted with Secure Cell
+//!   - [`KeychainStorage`] uses macOS Keychain, available with crate feature `keychain`
+//!
+//! Other platform keystores (e.g., Android Keystore which is accessible only via Java API)
+//! can be integrated by implementing `KeyStorage` on the application side.
+//!
+//! [`ManagedKey`] attaches identifier, creation and expiration time to keys.
+//!
+//! [`KeyStorage`]: trait.KeyStorage.html
+//! [`MemoryKeyStorage`]: struct.MemoryKeyStorage.html
+//! [`FileKeyStore`]: struct.FileKeyStore.html
+//! [`KeychainStorage`]: struct.KeychainStorage.html
+//! [`ManagedKey`]: struct.ManagedKey.html
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> std::io::Result<()> {
+//! use themis::keygen::gen_ec_key_pair;
+//! use themis::keystore::{KeyStorage, MemoryKeyStorage};
+//!
+//! let mut storage = MemoryKeyStorage::new();
+//!
+//! let (secret, _) = gen_ec_key_pair().split();
+//! storage.store("identity", secret.as_ref())?;
+//!
+//! let loaded = storage.load_secret_key("identity")?.expect("stored key");
+//! assert_eq!(loaded.as_ref(), secret.as_ref());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use zeroize::Zeroize;
+
+use crate::keys::{PublicKey, SecretKey};
+use crate::secure_cell::{SecureCell, SecureCellSeal};
+
+/// Storage of keys identified by strings.
+///
+/// Keys are stored as byte strings and are returned as they were stored. Typed accessors
+/// like [`load_secret_key`] parse and validate the keys.
+///
+/// [`load_secret_key`]: trait.KeyStorage.html#method.load_secret_key
+pub trait KeyStorage {
+    /// Loads a key with given ID.
+    ///
+    /// Returns `None` if there is no such key.
+    fn load(&mut self, id: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Stores a key with given ID, replacing any previously stored key.
+    fn store(&mut self, id: &str, key: &[u8]) -> io::Result<()>;
+
+    /// Deletes a key with given ID.
+    ///
+    /// Returns `true` if the key has been deleted and `false` if there was no such key.
+    fn delete(&mut self, id: &str) -> io::Result<bool>;
+
+    /// Loads a secret key with given ID.
+    ///
+    /// Returns `None` if there is no such key and an error of kind `InvalidData`
+    /// if the stored data is not a valid secret key.
+    fn load_secret_key(&mut self, id: &str) -> io::Result<Option<SecretKey>> {
+        match self.load(id)? {
+            Some(mut bytes) => {
+                let key = SecretKey::try_from_slice(&bytes);
+                bytes.zeroize();
+                key.map(Some)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Loads a public key with given ID.
+    ///
+    /// Returns `None` if there is no such key and an error of kind `InvalidData`
+    /// if the stored data is not a valid public key.
+    fn load_public_key(&mut self, id: &str) -> io::Result<Option<PublicKey>> {
+        match self.load(id)? {
+            Some(bytes) => PublicKey::try_from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// In-memory key storage.
+///
+/// Keys are wiped from memory when they are deleted or replaced, and when the storage
+/// is dropped.
+#[derive(Default)]
+pub struct MemoryKeyStorage {
+    keys: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryKeyStorage {
+    /// Makes a new empty storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of stored keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Checks whether the storage is empty.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl KeyStorage for MemoryKeyStorage {
+    fn load(&mut self, id: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.keys.get(id).cloned())
+    }
+
+    fn store(&mut self, id: &str, key: &[u8]) -> io::Result<()> {
+        if let Some(mut previous) = self.keys.insert(id.to_owned(), key.to_vec()) {
+            previous.zeroize();
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, id: &str) -> io::Result<bool> {
+        match self.keys.remove(id) {
+            Some(mut key) => {
+                key.zeroize();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+impl Drop for MemoryKeyStorage {
+    fn drop(&mut self) {
+        for key in self.keys.values_mut() {
+            key.zeroize();
+        }
+    }
+}
+
+/// Encrypted file key storage.
+///
+/// All keys are kept in a single file encrypted with Secure Cell in Seal mode using the master
+/// key. The file is rewritten atomically on each modification: new content is written into
+/// a temporary file which then replaces the original one. On Unix the file is accessible only
+/// by its owner.
+///
+/// Keys are kept decrypted in memory while the storage is open and are wiped when the storage
+/// is dropped.
+///
+/// The master key is used by Secure Cell as is. It should be a random key (e.g., 32 bytes from
+/// a cryptographically secure generator) or at least a long high-entropy passphrase.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> {
+/// use themis::keygen::gen_ec_key_pair;
+/// use themis::keystore::FileKeyStore;
+///
+/// let mut store = FileKeyStore::create("keys.bin", b"master key")?;
+///
+/// let (secret, public) = gen_ec_key_pair().split();
+/// store.add("identity", secret.as_ref())?;
+/// store.add("identity.pub", public.as_ref())?;
+///
+/// let store = FileKeyStore::open("keys.bin", b"master key")?;
+///
+/// for id in store.list() {
+///     println!("{}", id);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct FileKeyStore {
+    path: PathBuf,
+    cell: SecureCellSeal,
+    keys: BTreeMap<String, Vec<u8>>,
+}
+
+// Secure Cell context binds the encrypted data to this file format.
+const FILE_KEY_STORE_CONTEXT: &[u8] = b"themis key store v1";
+
+impl FileKeyStore {
+    /// Creates a new empty key store file.
+    ///
+    /// Fails if the file already exists.
+    pub fn create<P, K>(path: P, master_key: K) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+        K: AsRef<[u8]>,
+    {
+        let store = Self {
+            path: path.as_ref().to_owned(),
+            cell: Self::make_cell(master_key.as_ref()),
+            keys: BTreeMap::new(),
+        };
+        write_new_file(&store.path, &store.encrypt()?)?;
+        Ok(store)
+    }
+
+    /// Opens an existing key store file.
+    ///
+    /// Returns an error of kind `InvalidData` if the file cannot be decrypted with given
+    /// master key or if it is corrupted.
+    pub fn open<P, K>(path: P, master_key: K) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+        K: AsRef<[u8]>,
+    {
+        let path = path.as_ref();
+        let cell = Self::make_cell(master_key.as_ref());
+
+        let mut encrypted = Vec::new();
+        File::open(path)?.read_to_end(&mut encrypted)?;
+        let mut decrypted = cell
+            .decrypt(&encrypted)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let keys = decode_key_map(&decrypted);
+        decrypted.zeroize();
+
+        Ok(Self {
+            path: path.to_owned(),
+            cell,
+            keys: keys?,
+        })
+    }
+
+    fn make_cell(master_key: &[u8]) -> SecureCellSeal {
+        SecureCell::with_key_and_context(master_key, FILE_KEY_STORE_CONTEXT).seal()
+    }
+
+    /// Adds a new key.
+    ///
+    /// Returns an error of kind `AlreadyExists` if there is already a key with given ID.
+    pub fn add(&mut self, id: &str, key: &[u8]) -> io::Result<()> {
+        if self.keys.contains_key(id) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("key {:?} already exists", id),
+            ));
+        }
+        self.keys.insert(id.to_owned(), key.to_vec());
+        self.save_or_rollback(id, None)
+    }
+
+    /// Returns a key with given ID.
+    pub fn get(&self, id: &str) -> Option<&[u8]> {
+        self.keys.get(id).map(|key| key.as_slice())
+    }
+
+    /// Lists IDs of all stored keys in alphabetical order.
+    pub fn list(&self) -> impl Iterator<Item = &str> {
+        self.keys.keys().map(|id| id.as_str())
+    }
+
+    /// Removes a key with given ID.
+    ///
+    /// Returns `true` if the key has been removed and `false` if there was no such key.
+    pub fn remove(&mut self, id: &str) -> io::Result<bool> {
+        match self.keys.remove(id) {
+            Some(key) => {
+                self.save_or_rollback(id, Some(key))?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Saves the store after modification of `id` key, restoring its `previous` value
+    /// in memory if the file cannot be written.
+    fn save_or_rollback(&mut self, id: &str, previous: Option<Vec<u8>>) -> io::Result<()> {
+        match self.save() {
+            Ok(()) => {
+                if let Some(mut previous) = previous {
+                    previous.zeroize();
+                }
+                Ok(())
+            }
+            Err(error) => {
+                let current = match previous {
+                    Some(previous) => self.keys.insert(id.to_owned(), previous),
+                    None => self.keys.remove(id),
+                };
+                if let Some(mut current) = current {
+                    current.zeroize();
+                }
+                Err(error)
+            }
+        }
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let encrypted = self.encrypt()?;
+
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        let temp_path = PathBuf::from(temp_path);
+
+        // Leftover of a failed save. Do not reuse it, it might be accessible by others.
+        if let Err(error) = fs::remove_file(&temp_path) {
+            if error.kind() != io::ErrorKind::NotFound {
+                return Err(error);
+            }
+        }
+        write_new_file(&temp_path, &encrypted)?;
+        let result = fs::rename(&temp_path, &self.path);
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    fn encrypt(&self) -> io::Result<Vec<u8>> {
+        let mut plaintext = encode_key_map(&self.keys);
+        let encrypted = self.cell.encrypt(&plaintext);
+        plaintext.zeroize();
+        // Secure Cell fails only with invalid master key here, e.g., an empty one.
+        encrypted.map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+}
+
+/// Writes data into a new file accessible only by its owner, removing the file on failure.
+fn write_new_file(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    let result = file.write_all(data).and_then(|_| file.sync_all());
+    if result.is_err() {
+        drop(file);
+        let _ = fs::remove_file(path);
+    }
+    result
+}
+
+impl KeyStorage for FileKeyStore {
+    fn load(&mut self, id: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.get(id).map(|key| key.to_vec()))
+    }
+
+    fn store(&mut self, id: &str, key: &[u8]) -> io::Result<()> {
+        let previous = self.keys.insert(id.to_owned(), key.to_vec());
+        self.save_or_rollback(id, previous)
+    }
+
+    fn delete(&mut self, id: &str) -> io::Result<bool> {
+        self.remove(id)
+    }
+}
+
+impl Drop for FileKeyStore {
+    fn drop(&mut self) {
+        for key in self.keys.values_mut() {
+            key.zeroize();
+        }
+    }
+}
+
+// Key store content format: format tag followed by a sequence of (ID, key) pairs, each is
+// length-prefixed with 32-bit big-endian integer. Note that Secure Cell cannot encrypt empty
+// messages so the tag is necessary for empty stores.
+
+const FILE_KEY_STORE_TAG: &[u8] = b"TKS1";
+
+fn encode_key_map(keys: &BTreeMap<String, Vec<u8>>) -> Vec<u8> {
+    let length = keys
+        .iter()
+        .map(|(id, key)| 8 + id.len() + key.len())
+        .sum::<usize>();
+    let mut output = Vec::with_capacity(FILE_KEY_STORE_TAG.len() + length);
+    output.extend_from_slice(FILE_KEY_STORE_TAG);
+    for (id, key) in keys {
+        write_chunk(&mut output, id.as_bytes());
+        write_chunk(&mut output, key);
+    }
+    output
+}
+
+pub(crate) fn write_chunk(output: &mut Vec<u8>, chunk: &[u8]) {
+    let length = chunk.len() as u32;
+    output.extend_from_slice(&[
+        (length >> 24) as u8,
+        (length >> 16) as u8,
+        (length >> 8) as u8,
+        length as u8,
+    ]);
+    output.extend_from_slice(chunk);
+}
+
+fn decode_key_map(input: &[u8]) -> io::Result<BTreeMap<String, Vec<u8>>> {
+    let corrupted = || io::Error::new(io::ErrorKind::InvalidData, "corrupted key store");
+    if !input.starts_with(FILE_KEY_STORE_TAG) {
+        return Err(corrupted());
+    }
+    let mut input = &input[FILE_KEY_STORE_TAG.len()..];
+    let mut keys = BTreeMap::new();
+    while !input.is_empty() {
+        let id = read_chunk(&mut input).and_then(|id| String::from_utf8(id.to_vec()).ok());
+        let key = read_chunk(&mut input);
+        match (id, key) {
+            (Some(id), Some(key)) => {
+                keys.insert(id, key.to_vec());
+            }
+            _ => {
+                for key in keys.values_mut() {
+                    key.zeroize();
+                }
+                return Err(corrupted());
+            }
+        }
+    }
+    Ok(keys)
+}
+
+pub(crate) fn read_chunk<'a>(input: &mut &'a [u8]) -> Option<&'a [u8]> {
+    if input.len() < 4 {
+        return None;
+    }
+    let length = (input[0] as usize) << 24
+        | (input[1] as usize) << 16
+        | (input[2] as usize) << 8
+        | (input[3] as usize);
+    if input.len() < 4 + length {
+        return None;
+    }
+    let chunk = &input[4..4 + length];
+    *input = &input[4 + length..];
+    Some(chunk)
+}
+
+/// Key storage in macOS Keychain.
+///
+/// Keys are stored as generic passwords with the given service name and key ID as account name.
+/// Available on macOS with crate feature `keychain`.
+#[cfg(all(feature = "keychain", target_os = "macos"))]
+pub struct KeychainStorage {
+    service: String,
+}
+
+#[cfg(all(feature = "keychain", target_os = "macos"))]
+mod keychain {
+    use std::io;
+
+    use security_framework::base::Error;
+    use security_framework::passwords::{
+        delete_generic_password, get_generic_password, set_generic_password,
+    };
+
+    use super::{KeyStorage, KeychainStorage};
+
+    // errSecItemNotFound from <Security/SecBase.h>
+    const ITEM_NOT_FOUND: i32 = -25300;
+
+    fn into_io_error(error: Error) -> io::Error {
+        io::Error::new(io::ErrorKind::Other, error)
+    }
+
+    impl KeychainStorage {
+        /// Makes a new storage using given Keychain service name.
+        pub fn new<S: Into<String>>(service: S) -> Self {
+            Self {
+                service: service.into(),
+            }
+        }
+    }
+
+    impl KeyStorage for KeychainStorage {
+        fn load(&mut self, id: &str) -> io::Result<Option<Vec<u8>>> {
+            match get_generic_password(&self.service, id) {
+                Ok(key) => Ok(Some(key)),
+                Err(ref e) if e.code() == ITEM_NOT_FOUND => Ok(None),
+                Err(e) => Err(into_io_error(e)),
+            }
+        }
+
+        fn store(&mut self, id: &str, key: &[u8]) -> io::Result<()> {
+            set_generic_password(&self.service, id, key).map_err(into_io_error)
+        }
+
+        fn delete(&mut self, id: &str) -> io::Result<bool> {
+            match delete_generic_password(&self.service, id) {
+                Ok(()) => Ok(true),
+                Err(ref e) if e.code() == ITEM_NOT_FOUND => Ok(false),
+                Err(e) => Err(into_io_error(e)),
+            }
+        }
+    }
+}
+
+/// Key with management metadata.
+///
+/// Long-lived keys need to be identified, rotated, and eventually retired. `ManagedKey` wraps
+/// a key (or a key pair) together with its identifier, creation time, and optional expiration
+/// time. Timestamps are kept with one-second precision.
+///
+/// Expired keys are not used to protect new data when they are kept in a [`KeyRing`].
+/// Managed keys can be serialized with crate feature `serde`.
+///
+/// [`KeyRing`]: ../keyring/struct.KeyRing.html
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use themis::keygen::gen_ec_key_pair;
+/// use themis::keystore::ManagedKey;
+///
+/// let key = ManagedKey::new("signing-2019", gen_ec_key_pair())
+///     .expires_after(Duration::from_secs(90 * 24 * 60 * 60));
+///
+/// assert_eq!(key.id(), "signing-2019");
+/// assert!(!key.is_expired());
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct ManagedKey<K> {
+    id: String,
+    created: SystemTime,
+    expires: Option<SystemTime>,
+    key: K,
+}
+
+impl<K> ManagedKey<K> {
+    /// Wraps a key created right now, without expiration time.
+    pub fn new<S: Into<String>>(id: S, key: K) -> Self {
+        Self::with_creation_time(id, key, SystemTime::now())
+    }
+
+    /// Wraps a key created at given time, without expiration time.
+    pub fn with_creation_time<S: Into<String>>(id: S, key: K, created: SystemTime) -> Self {
+        Self {
+            id: id.into(),
+            created: truncate_to_seconds(created),
+            expires: None,
+            key,
+        }
+    }
+
+    /// Sets the time when the key expires.
+    pub fn expires_at(mut self, expires: SystemTime) -> Self {
+        self.expires = Some(truncate_to_seconds(expires));
+        self
+    }
+
+    /// Sets the key to expire after given duration since its creation.
+    pub fn expires_after(self, lifetime: Duration) -> Self {
+        let expires = self.created + lifetime;
+        self.expires_at(expires)
+    }
+
+    /// Returns the identifier of the key.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the time when the key was created.
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+
+    /// Returns the time when the key expires, if any.
+    pub fn expires(&self) -> Option<SystemTime> {
+        self.expires
+    }
+
+    /// Returns a reference to the key.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns a mutable reference to the key.
+    pub fn key_mut(&mut self) -> &mut K {
+        &mut self.key
+    }
+
+    /// Unwraps the key, dropping its metadata.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Checks whether the key has expired by now.
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(SystemTime::now())
+    }
+
+    /// Checks whether the key has expired by given time.
+    ///
+    /// Keys without expiration time never expire.
+    pub fn is_expired_at(&self, time: SystemTime) -> bool {
+        match self.expires {
+            Some(expires) => time >= expires,
+            None => false,
+        }
+    }
+}
+
+// Keys may be plain byte vectors, so never print them, only metadata.
+impl<K> fmt::Debug for ManagedKey<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ManagedKey")
+            .field("id", &self.id)
+            .field("created", &self.created)
+            .field("expires", &self.expires)
+            .finish()
+    }
+}
+
+/// Drops the fractional part of the timestamp. Times before UNIX epoch are clamped to it.
+fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    UNIX_EPOCH + Duration::from_secs(seconds)
+}
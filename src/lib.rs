@@ -40,20 +40,60 @@
 //! [Secure Session]: secure_session/index.html
 //! [Secure Cell]: secure_cell/index.html
 //! [Secure Comparator]: secure_comparator/index.html
+//!
+//! # Soter-only build
+//!
+//! Applications which need only cryptographic primitives ([hashes], [HMAC], [key derivation],
+//! and [random numbers]) can enable `soter-only` feature. Then only `libsoter` is linked and
+//! Themis headers are not required, while all other modules are not available.
+//!
+//! [hashes]: hash/index.html
+//! [HMAC]: hmac/index.html
+//! [key derivation]: kdf/index.html
+//! [random numbers]: rand/index.html
 
 #![warn(missing_docs)]
 #![doc(html_no_source)]
 #![doc(html_logo_url = "https://rust-themis.ilammy.net/images/logo.png")]
 #![doc(html_favicon_url = "https://rust-themis.ilammy.net/images/favicon.png")]
 
+#[cfg(not(feature = "soter-only"))]
+pub mod container;
+#[cfg(not(feature = "soter-only"))]
+pub mod ecdh;
+pub mod hash;
+pub mod hmac;
+pub mod kdf;
+#[cfg(not(feature = "soter-only"))]
 pub mod keygen;
+#[cfg(not(feature = "soter-only"))]
+pub mod keyring;
+#[cfg(not(feature = "soter-only"))]
 pub mod keys;
+#[cfg(not(feature = "soter-only"))]
+pub mod keystore;
+#[cfg(not(feature = "soter-only"))]
+pub mod low_level;
+pub mod rand;
+#[cfg(not(feature = "soter-only"))]
 pub mod secure_cell;
+#[cfg(not(feature = "soter-only"))]
 pub mod secure_comparator;
+#[cfg(not(feature = "soter-only"))]
 pub mod secure_message;
+#[cfg(not(feature = "soter-only"))]
 pub mod secure_session;
+pub mod version;
 
+#[cfg(all(feature = "der", not(feature = "soter-only")))]
+mod der;
+// Secure Session and Secure Comparator statuses are not used by Soter.
+#[cfg_attr(feature = "soter-only", allow(dead_code))]
 mod error;
+#[cfg(all(feature = "serde", not(feature = "soter-only")))]
+mod serialization;
+#[cfg(not(feature = "soter-only"))]
+mod trace;
 mod utils;
 
 pub use crate::error::{Error, ErrorKind, Result};
@@ -0,0 +1,33 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Low-level cryptographic primitives.
+//!
+//! **Do not use this module unless you know exactly what you are doing.**
+//!
+//! Themis cryptosystems like [Secure Cell] and [Secure Message] combine primitives of the
+//! underlying crypto library (**Soter**) into well-defined containers, taking care of key
+//! derivation, nonce generation, authentication, and data formats. This module exposes these
+//! primitives directly for advanced use cases, like interoperability with existing data formats.
+//!
+//! It is *your* responsibility to use the primitives correctly: never reuse nonces with the same
+//! key, authenticate encrypted data, keep track of algorithm parameters. Mistakes will not be
+//! caught by the library and will likely compromise security of your data. Data produced with
+//! these primitives is not compatible with Themis cryptosystems.
+//!
+//! [Secure Cell]: ../secure_cell/index.html
+//! [Secure Message]: ../secure_message/index.html
+
+pub mod asym;
+pub mod sym;
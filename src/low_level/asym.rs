@@ -0,0 +1,369 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Asymmetric encryption and signatures.
+//!
+//! This module provides raw public key operations with Themis keys:
+//!
+//!   - [`rsa_encrypt`] and [`rsa_decrypt`] use RSA with OAEP padding (SHA-1 and MGF1).
+//!     The message must be short: no longer than 42 bytes less than the key size.
+//!
+//!   - [`Signer`] and [`Verifier`] produce and check signatures of data hashed with SHA-256.
+//!     ECDSA keys produce DER-encoded ECDSA signatures, RSA keys produce RSA-PSS signatures.
+//!
+//! Unlike [Secure Message], these functions do not add any headers, so their output can be
+//! processed by other software. They also do not protect you from misuse.
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> Result<(), themis::Error> {
+//! use themis::keygen::gen_ec_key_pair;
+//! use themis::low_level::asym::{Signer, Verifier};
+//!
+//! let (secret, public) = gen_ec_key_pair().split();
+//!
+//! let mut signer = Signer::new(secret)?;
+//! signer.update(b"some data")?;
+//! let signature = signer.finalize()?;
+//!
+//! let mut verifier = Verifier::new(public)?;
+//! verifier.update(b"some data")?;
+//! verifier.finalize(&signature)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`rsa_encrypt`]: fn.rsa_encrypt.html
+//! [`rsa_decrypt`]: fn.rsa_decrypt.html
+//! [`Signer`]: struct.Signer.html
+//! [`Verifier`]: struct.Verifier.html
+//! [Secure Message]: ../../secure_message/index.html
+
+use std::os::raw::c_void;
+use std::ptr;
+
+use bindings::{
+    soter_asym_cipher_create, soter_asym_cipher_decrypt, soter_asym_cipher_destroy,
+    soter_asym_cipher_encrypt, soter_asym_cipher_padding_t, soter_asym_cipher_t, soter_sign_alg_t,
+    soter_sign_create, soter_sign_ctx_t, soter_sign_destroy, soter_sign_final, soter_sign_update,
+    soter_status_t, soter_verify_create, soter_verify_ctx_t, soter_verify_destroy,
+    soter_verify_final, soter_verify_update,
+};
+
+use crate::error::{Error, ErrorKind, Result, Status};
+use crate::keys::{KeyKind, PublicKey, RsaPublicKey, RsaSecretKey, SecretKey};
+use crate::utils::into_raw_parts;
+
+type CipherFn = unsafe extern "C" fn(
+    *mut soter_asym_cipher_t,
+    *const c_void,
+    usize,
+    *mut c_void,
+    *mut usize,
+) -> soter_status_t;
+
+/// Encrypts a short message with RSA-OAEP.
+pub fn rsa_encrypt<M: AsRef<[u8]>>(public_key: &RsaPublicKey, message: M) -> Result<Vec<u8>> {
+    rsa_cipher(
+        public_key.as_ref(),
+        soter_asym_cipher_encrypt,
+        message.as_ref(),
+    )
+}
+
+/// Decrypts a message encrypted with RSA-OAEP.
+pub fn rsa_decrypt<M: AsRef<[u8]>>(secret_key: &RsaSecretKey, message: M) -> Result<Vec<u8>> {
+    rsa_cipher(
+        secret_key.as_ref(),
+        soter_asym_cipher_decrypt,
+        message.as_ref(),
+    )
+}
+
+fn rsa_cipher(key: &[u8], cipher: CipherFn, input: &[u8]) -> Result<Vec<u8>> {
+    if input.is_empty() {
+        return Err(Error::with_kind(ErrorKind::InvalidParameter));
+    }
+    let (key_ptr, key_len) = into_raw_parts(key);
+    let (input_ptr, input_len) = into_raw_parts(input);
+
+    let ctx = unsafe {
+        soter_asym_cipher_create(
+            key_ptr as *const c_void,
+            key_len,
+            soter_asym_cipher_padding_t::SOTER_ASYM_CIPHER_OAEP,
+        )
+    };
+    if ctx.is_null() {
+        return Err(Error::with_kind(ErrorKind::InvalidParameter));
+    }
+    let ctx = CipherContext(ctx);
+
+    let mut output = Vec::new();
+    let mut output_len = 0;
+
+    unsafe {
+        let status = cipher(
+            ctx.0,
+            input_ptr as *const c_void,
+            input_len,
+            ptr::null_mut(),
+            &mut output_len,
+        );
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
+        }
+    }
+
+    output.reserve(output_len);
+
+    unsafe {
+        let status = cipher(
+            ctx.0,
+            input_ptr as *const c_void,
+            input_len,
+            output.as_mut_ptr() as *mut c_void,
+            &mut output_len,
+        );
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
+        }
+        debug_assert!(output_len <= output.capacity());
+        output.set_len(output_len);
+    }
+
+    Ok(output)
+}
+
+struct CipherContext(*mut soter_asym_cipher_t);
+
+impl Drop for CipherContext {
+    fn drop(&mut self) {
+        unsafe {
+            let status = soter_asym_cipher_destroy(self.0);
+            let status = Status::from_themis(status);
+            if cfg!(debug_assertions) && status != Status::Success {
+                panic!(
+                    "soter_asym_cipher_destroy() failed: {}",
+                    Error::from(status)
+                );
+            }
+        }
+    }
+}
+
+fn sign_algorithm(kind: KeyKind) -> soter_sign_alg_t {
+    match kind {
+        KeyKind::RsaSecret | KeyKind::RsaPublic => soter_sign_alg_t::SOTER_SIGN_rsa_pss_pkcs8,
+        KeyKind::EcdsaSecret | KeyKind::EcdsaPublic => {
+            soter_sign_alg_t::SOTER_SIGN_ecdsa_none_pkcs8
+        }
+    }
+}
+
+/// Incremental signature computation.
+///
+/// Feed the data with [`update`] and get the signature with [`finalize`].
+///
+/// [`update`]: struct.Signer.html#method.update
+/// [`finalize`]: struct.Signer.html#method.finalize
+pub struct Signer {
+    sign_ctx: *mut soter_sign_ctx_t,
+}
+
+// Signature context is not tied to any particular thread, it is just some memory.
+unsafe impl Send for Signer {}
+
+impl Signer {
+    /// Prepares a new signature with given secret key.
+    pub fn new<S: Into<SecretKey>>(secret_key: S) -> Result<Self> {
+        let secret_key = secret_key.into();
+        let (key_ptr, key_len) = into_raw_parts(secret_key.as_ref());
+
+        let sign_ctx = unsafe {
+            soter_sign_create(
+                sign_algorithm(secret_key.kind()),
+                key_ptr as *const c_void,
+                key_len,
+                ptr::null(),
+                0,
+            )
+        };
+        if sign_ctx.is_null() {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+
+        Ok(Self { sign_ctx })
+    }
+
+    /// Signs more data.
+    pub fn update<D: AsRef<[u8]>>(&mut self, data: D) -> Result<()> {
+        if data.as_ref().is_empty() {
+            return Ok(());
+        }
+        let (data_ptr, data_len) = into_raw_parts(data.as_ref());
+
+        unsafe {
+            let status = soter_sign_update(self.sign_ctx, data_ptr as *const c_void, data_len);
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                return Err(status.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Completes signature computation and returns the signature.
+    pub fn finalize(self) -> Result<Vec<u8>> {
+        let mut signature = Vec::new();
+        let mut signature_len = 0;
+
+        unsafe {
+            let status = soter_sign_final(self.sign_ctx, ptr::null_mut(), &mut signature_len);
+            let status = Status::from_themis(status);
+            if status != Status::BufferTooSmall {
+                return Err(status.into());
+            }
+        }
+
+        signature.reserve(signature_len);
+
+        unsafe {
+            let status = soter_sign_final(
+                self.sign_ctx,
+                signature.as_mut_ptr() as *mut c_void,
+                &mut signature_len,
+            );
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                return Err(status.into());
+            }
+            debug_assert!(signature_len <= signature.capacity());
+            signature.set_len(signature_len);
+        }
+
+        Ok(signature)
+    }
+}
+
+#[doc(hidden)]
+impl Drop for Signer {
+    fn drop(&mut self) {
+        unsafe {
+            let status = soter_sign_destroy(self.sign_ctx);
+            let status = Status::from_themis(status);
+            if cfg!(debug_assertions) && status != Status::Success {
+                panic!("soter_sign_destroy() failed: {}", Error::from(status));
+            }
+        }
+    }
+}
+
+/// Incremental signature verification.
+///
+/// Feed the data with [`update`] and check the signature with [`finalize`].
+///
+/// [`update`]: struct.Verifier.html#method.update
+/// [`finalize`]: struct.Verifier.html#method.finalize
+pub struct Verifier {
+    verify_ctx: *mut soter_verify_ctx_t,
+}
+
+// Verification context is not tied to any particular thread, it is just some memory.
+unsafe impl Send for Verifier {}
+
+impl Verifier {
+    /// Prepares a new verification with given public key.
+    pub fn new<P: Into<PublicKey>>(public_key: P) -> Result<Self> {
+        let public_key = public_key.into();
+        let (key_ptr, key_len) = into_raw_parts(public_key.as_ref());
+
+        let verify_ctx = unsafe {
+            soter_verify_create(
+                sign_algorithm(public_key.kind()),
+                ptr::null(),
+                0,
+                key_ptr as *const c_void,
+                key_len,
+            )
+        };
+        if verify_ctx.is_null() {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+
+        Ok(Self { verify_ctx })
+    }
+
+    /// Verifies more data.
+    pub fn update<D: AsRef<[u8]>>(&mut self, data: D) -> Result<()> {
+        if data.as_ref().is_empty() {
+            return Ok(());
+        }
+        let (data_ptr, data_len) = into_raw_parts(data.as_ref());
+
+        unsafe {
+            let status = soter_verify_update(self.verify_ctx, data_ptr as *const c_void, data_len);
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                return Err(status.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Completes verification and checks the signature.
+    ///
+    /// Returns [`InvalidSignature`] error if the signature does not match the data.
+    ///
+    /// [`InvalidSignature`]: ../../enum.ErrorKind.html#variant.InvalidSignature
+    pub fn finalize<S: AsRef<[u8]>>(self, signature: S) -> Result<()> {
+        let (signature_ptr, signature_len) = into_raw_parts(signature.as_ref());
+
+        unsafe {
+            let status = soter_verify_final(
+                self.verify_ctx,
+                signature_ptr as *const c_void,
+                signature_len,
+            );
+            let status = Status::from_themis(status);
+            // RSA-PSS verification reports mismatched signatures as a generic failure.
+            if status == Status::Error(ErrorKind::Fail) {
+                return Err(Error::with_kind(ErrorKind::InvalidSignature));
+            }
+            if status != Status::Success {
+                return Err(status.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+impl Drop for Verifier {
+    fn drop(&mut self) {
+        unsafe {
+            let status = soter_verify_destroy(self.verify_ctx);
+            let status = Status::from_themis(status);
+            if cfg!(debug_assertions) && status != Status::Success {
+                panic!("soter_verify_destroy() failed: {}", Error::from(status));
+            }
+        }
+    }
+}
@@ -0,0 +1,381 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Symmetric ciphers.
+//!
+//! This module provides raw AES in GCM and CTR modes. Key size (128, 192, or 256 bits) is
+//! determined by the length of the key. Keys are used as is, without any key derivation.
+//!
+//! **AES-GCM** is an authenticated cipher. It requires a unique 12-byte nonce for each message
+//! encrypted with the same key. Reusing a nonce reveals the plaintext and allows forgeries.
+//! Decrypted data must not be used until [`finalize`] verifies the authentication tag.
+//!
+//! **AES-CTR** does not provide any authentication, you need to add it yourself (e.g., with
+//! [HMAC]). It requires a unique 16-byte initial counter block for each message.
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> Result<(), themis::Error> {
+//! use themis::low_level::sym::{AesGcmDecryptor, AesGcmEncryptor, GCM_NONCE_LEN};
+//! use themis::rand;
+//!
+//! let key = rand::secure_bytes(32)?;
+//! let nonce = rand::secure_bytes(GCM_NONCE_LEN)?;
+//!
+//! let mut encryptor = AesGcmEncryptor::new(&key, &nonce)?;
+//! encryptor.update_aad(b"header")?;
+//! let ciphertext = encryptor.update(b"secret message")?;
+//! let tag = encryptor.finalize()?;
+//!
+//! let mut decryptor = AesGcmDecryptor::new(&key, &nonce)?;
+//! decryptor.update_aad(b"header")?;
+//! let plaintext = decryptor.update(&ciphertext)?;
+//! decryptor.finalize(&tag)?;
+//!
+//! assert_eq!(plaintext, b"secret message");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`finalize`]: struct.AesGcmDecryptor.html#method.finalize
+//! [HMAC]: ../../hmac/index.html
+
+use std::os::raw::c_void;
+use std::ptr;
+
+use bindings::{
+    soter_status_t, soter_sym_aead_decrypt_aad, soter_sym_aead_decrypt_create,
+    soter_sym_aead_decrypt_destroy, soter_sym_aead_decrypt_final, soter_sym_aead_decrypt_update,
+    soter_sym_aead_encrypt_aad, soter_sym_aead_encrypt_create, soter_sym_aead_encrypt_destroy,
+    soter_sym_aead_encrypt_final, soter_sym_aead_encrypt_update, soter_sym_ctx_t,
+    soter_sym_encrypt_create, soter_sym_encrypt_destroy, soter_sym_encrypt_update,
+    SOTER_SYM_128_KEY_LENGTH, SOTER_SYM_192_KEY_LENGTH, SOTER_SYM_256_KEY_LENGTH,
+    SOTER_SYM_AES_CTR, SOTER_SYM_AES_GCM, SOTER_SYM_NOKDF,
+};
+
+use crate::error::{Error, ErrorKind, Result, Status};
+use crate::utils::into_raw_parts;
+
+/// Length of AES-GCM nonce in bytes.
+pub const GCM_NONCE_LEN: usize = 12;
+
+/// Length of AES-GCM authentication tag in bytes.
+pub const GCM_TAG_LEN: usize = 16;
+
+/// Length of AES-CTR initial counter block in bytes.
+pub const CTR_IV_LEN: usize = 16;
+
+// AES block length. Ciphers may buffer up to a block of data internally.
+const AES_BLOCK_LEN: usize = 16;
+
+type CreateFn = unsafe extern "C" fn(
+    u32,
+    *const c_void,
+    usize,
+    *const c_void,
+    usize,
+    *const c_void,
+    usize,
+) -> *mut soter_sym_ctx_t;
+
+type UpdateFn = unsafe extern "C" fn(
+    *mut soter_sym_ctx_t,
+    *const c_void,
+    usize,
+    *mut c_void,
+    *mut usize,
+) -> soter_status_t;
+
+type AadFn = unsafe extern "C" fn(*mut soter_sym_ctx_t, *const c_void, usize) -> soter_status_t;
+
+type DestroyFn = unsafe extern "C" fn(*mut soter_sym_ctx_t) -> soter_status_t;
+
+/// Soter cipher context along with its destructor.
+struct Context {
+    ctx: *mut soter_sym_ctx_t,
+    destroy: DestroyFn,
+}
+
+// Cipher context is not tied to any particular thread, it is just some memory.
+unsafe impl Send for Context {}
+
+impl Context {
+    fn new(
+        create: CreateFn,
+        destroy: DestroyFn,
+        mode: u32,
+        key: &[u8],
+        iv: &[u8],
+        iv_len: usize,
+    ) -> Result<Self> {
+        let key_length = match key.len() {
+            16 => SOTER_SYM_128_KEY_LENGTH,
+            24 => SOTER_SYM_192_KEY_LENGTH,
+            32 => SOTER_SYM_256_KEY_LENGTH,
+            _ => return Err(Error::with_kind(ErrorKind::InvalidParameter)),
+        };
+        if iv.len() != iv_len {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+        let algorithm = mode | SOTER_SYM_NOKDF | key_length;
+
+        let ctx = unsafe {
+            create(
+                algorithm,
+                key.as_ptr() as *const c_void,
+                key.len(),
+                ptr::null(),
+                0,
+                iv.as_ptr() as *const c_void,
+                iv.len(),
+            )
+        };
+
+        if ctx.is_null() {
+            // Parameters have been checked so this is most likely a memory allocation failure.
+            return Err(Error::with_kind(ErrorKind::NoMemory));
+        }
+
+        Ok(Context { ctx, destroy })
+    }
+
+    fn update(&mut self, update: UpdateFn, input: &[u8]) -> Result<Vec<u8>> {
+        if input.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (input_ptr, input_len) = into_raw_parts(input);
+        let mut output = vec![0; input_len + AES_BLOCK_LEN];
+        let mut output_len = output.len();
+
+        unsafe {
+            let status = update(
+                self.ctx,
+                input_ptr as *const c_void,
+                input_len,
+                output.as_mut_ptr() as *mut c_void,
+                &mut output_len,
+            );
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                return Err(status.into());
+            }
+        }
+
+        debug_assert!(output_len <= output.len());
+        output.truncate(output_len);
+        Ok(output)
+    }
+
+    fn aad(&mut self, aad: AadFn, data: &[u8]) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let (data_ptr, data_len) = into_raw_parts(data);
+
+        unsafe {
+            let status = aad(self.ctx, data_ptr as *const c_void, data_len);
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                return Err(status.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe {
+            let status = (self.destroy)(self.ctx);
+            let status = Status::from_themis(status);
+            if cfg!(debug_assertions) && status != Status::Success {
+                panic!("soter_sym_*_destroy() failed: {}", Error::from(status));
+            }
+        }
+    }
+}
+
+/// AES-GCM encryption.
+///
+/// Add associated data with [`update_aad`] (before encrypting any data), encrypt the message
+/// with [`update`], and get the authentication tag with [`finalize`].
+///
+/// [`update_aad`]: struct.AesGcmEncryptor.html#method.update_aad
+/// [`update`]: struct.AesGcmEncryptor.html#method.update
+/// [`finalize`]: struct.AesGcmEncryptor.html#method.finalize
+pub struct AesGcmEncryptor {
+    context: Context,
+}
+
+impl AesGcmEncryptor {
+    /// Prepares encryption with given key and nonce.
+    ///
+    /// The key must be 16, 24, or 32 bytes long. The nonce must be [`GCM_NONCE_LEN`] bytes long
+    /// and must never be reused with the same key.
+    ///
+    /// [`GCM_NONCE_LEN`]: constant.GCM_NONCE_LEN.html
+    pub fn new<K: AsRef<[u8]>, N: AsRef<[u8]>>(key: K, nonce: N) -> Result<Self> {
+        let context = Context::new(
+            soter_sym_aead_encrypt_create,
+            soter_sym_aead_encrypt_destroy,
+            SOTER_SYM_AES_GCM,
+            key.as_ref(),
+            nonce.as_ref(),
+            GCM_NONCE_LEN,
+        )?;
+        Ok(Self { context })
+    }
+
+    /// Authenticates associated data which is not encrypted.
+    pub fn update_aad<D: AsRef<[u8]>>(&mut self, data: D) -> Result<()> {
+        self.context.aad(soter_sym_aead_encrypt_aad, data.as_ref())
+    }
+
+    /// Encrypts more data, returning the ciphertext.
+    pub fn update<D: AsRef<[u8]>>(&mut self, plaintext: D) -> Result<Vec<u8>> {
+        self.context
+            .update(soter_sym_aead_encrypt_update, plaintext.as_ref())
+    }
+
+    /// Completes encryption and returns the authentication tag.
+    pub fn finalize(self) -> Result<Vec<u8>> {
+        let mut tag = vec![0; GCM_TAG_LEN];
+        let mut tag_len = tag.len();
+
+        unsafe {
+            let status = soter_sym_aead_encrypt_final(
+                self.context.ctx,
+                tag.as_mut_ptr() as *mut c_void,
+                &mut tag_len,
+            );
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                return Err(status.into());
+            }
+        }
+
+        tag.truncate(tag_len);
+        Ok(tag)
+    }
+}
+
+/// AES-GCM decryption.
+///
+/// Add associated data with [`update_aad`] (before decrypting any data), decrypt the message
+/// with [`update`], and verify the authentication tag with [`finalize`]. Decrypted data must
+/// not be used if the verification fails.
+///
+/// [`update_aad`]: struct.AesGcmDecryptor.html#method.update_aad
+/// [`update`]: struct.AesGcmDecryptor.html#method.update
+/// [`finalize`]: struct.AesGcmDecryptor.html#method.finalize
+pub struct AesGcmDecryptor {
+    context: Context,
+}
+
+impl AesGcmDecryptor {
+    /// Prepares decryption with given key and nonce.
+    ///
+    /// The key must be 16, 24, or 32 bytes long. The nonce must be [`GCM_NONCE_LEN`] bytes long.
+    ///
+    /// [`GCM_NONCE_LEN`]: constant.GCM_NONCE_LEN.html
+    pub fn new<K: AsRef<[u8]>, N: AsRef<[u8]>>(key: K, nonce: N) -> Result<Self> {
+        let context = Context::new(
+            soter_sym_aead_decrypt_create,
+            soter_sym_aead_decrypt_destroy,
+            SOTER_SYM_AES_GCM,
+            key.as_ref(),
+            nonce.as_ref(),
+            GCM_NONCE_LEN,
+        )?;
+        Ok(Self { context })
+    }
+
+    /// Authenticates associated data which is not encrypted.
+    pub fn update_aad<D: AsRef<[u8]>>(&mut self, data: D) -> Result<()> {
+        self.context.aad(soter_sym_aead_decrypt_aad, data.as_ref())
+    }
+
+    /// Decrypts more data, returning the plaintext.
+    ///
+    /// The plaintext is not authenticated until [`finalize`] succeeds.
+    ///
+    /// [`finalize`]: struct.AesGcmDecryptor.html#method.finalize
+    pub fn update<D: AsRef<[u8]>>(&mut self, ciphertext: D) -> Result<Vec<u8>> {
+        self.context
+            .update(soter_sym_aead_decrypt_update, ciphertext.as_ref())
+    }
+
+    /// Completes decryption and verifies the authentication tag.
+    ///
+    /// Returns an error if the data or the tag has been corrupted.
+    pub fn finalize<T: AsRef<[u8]>>(self, tag: T) -> Result<()> {
+        let tag = tag.as_ref();
+        if tag.len() != GCM_TAG_LEN {
+            return Err(Error::with_kind(ErrorKind::InvalidParameter));
+        }
+
+        // Soter destroys the context if it fails to set the tag, but that cannot happen
+        // for GCM tags of correct length.
+        unsafe {
+            let status = soter_sym_aead_decrypt_final(
+                self.context.ctx,
+                tag.as_ptr() as *const c_void,
+                tag.len(),
+            );
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                return Err(status.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// AES-CTR keystream cipher.
+///
+/// Encryption and decryption are the same operation in CTR mode: [`apply`] the cipher to
+/// plaintext to encrypt it, apply it to ciphertext to decrypt.
+///
+/// [`apply`]: struct.AesCtr.html#method.apply
+pub struct AesCtr {
+    context: Context,
+}
+
+impl AesCtr {
+    /// Prepares the cipher with given key and initial counter block.
+    ///
+    /// The key must be 16, 24, or 32 bytes long. The initial counter block must be
+    /// [`CTR_IV_LEN`] bytes long and must never be reused with the same key.
+    ///
+    /// [`CTR_IV_LEN`]: constant.CTR_IV_LEN.html
+    pub fn new<K: AsRef<[u8]>, I: AsRef<[u8]>>(key: K, iv: I) -> Result<Self> {
+        let context = Context::new(
+            soter_sym_encrypt_create,
+            soter_sym_encrypt_destroy,
+            SOTER_SYM_AES_CTR,
+            key.as_ref(),
+            iv.as_ref(),
+            CTR_IV_LEN,
+        )?;
+        Ok(Self { context })
+    }
+
+    /// Encrypts or decrypts more data.
+    pub fn apply<D: AsRef<[u8]>>(&mut self, data: D) -> Result<Vec<u8>> {
+        self.context.update(soter_sym_encrypt_update, data.as_ref())
+    }
+}
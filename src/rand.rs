@@ -0,0 +1,109 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cryptographically secure random numbers.
+//!
+//! This module provides access to the random number generator used by Themis for key
+//! generation and encryption. Use it for salts, nonces, tokens and other random values
+//! which need to be unpredictable.
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> Result<(), themis::Error> {
+//! use themis::rand;
+//!
+//! let token = rand::secure_bytes(16)?;
+//!
+//! let mut nonce = [0; 12];
+//! rand::fill(&mut nonce)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With `rand` crate feature enabled, [`SoterRng`] allows other crates to use this generator
+//! via `rand_core::RngCore` trait.
+//!
+//! [`SoterRng`]: struct.SoterRng.html
+
+use bindings::soter_rand;
+
+use crate::error::{Result, Status};
+
+// Soter passes the length to crypto backend as C int, so large buffers are filled in chunks
+// which are no longer than INT_MAX (for 32-bit int).
+const MAX_CHUNK_LEN: usize = 0x7FFF_FFFF;
+
+/// Returns a vector of random bytes of given length.
+pub fn secure_bytes(len: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![0; len];
+    fill(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Fills the buffer with random bytes.
+pub fn fill(buffer: &mut [u8]) -> Result<()> {
+    for chunk in buffer.chunks_mut(MAX_CHUNK_LEN) {
+        unsafe {
+            let status = soter_rand(chunk.as_mut_ptr(), chunk.len());
+            let status = Status::from_themis(status);
+            if status != Status::Success {
+                return Err(status.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Random number generator used by Themis.
+///
+/// This is an adapter for `rand_core::RngCore` trait, it allows to use the same generator
+/// in other crates which accept `RngCore + CryptoRng`. There is no state to seed or share:
+/// all instances return bytes from the same source as [`fill`].
+///
+/// This generator is available with `rand` crate feature.
+///
+/// [`fill`]: fn.fill.html
+///
+/// # Panics
+///
+/// Infallible methods of `RngCore` panic if the generator fails. Use `try_fill_bytes` if you
+/// need to handle errors.
+#[cfg(feature = "rand")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SoterRng;
+
+#[cfg(feature = "rand")]
+impl rand_core::RngCore for SoterRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(e) = fill(dest) {
+            panic!("soter_rand() failed: {}", e);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand_core::Error> {
+        fill(dest).map_err(rand_core::Error::new)
+    }
+}
+
+#[cfg(feature = "rand")]
+impl rand_core::CryptoRng for SoterRng {}
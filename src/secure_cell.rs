@@ -91,8 +91,14 @@ use bindings::{
     themis_secure_cell_decrypt_token_protect, themis_secure_cell_encrypt_context_imprint,
     themis_secure_cell_encrypt_seal, themis_secure_cell_encrypt_token_protect,
 };
+#[cfg(themis_secure_cell_passphrase)]
+use bindings::{
+    themis_secure_cell_decrypt_seal_with_passphrase,
+    themis_secure_cell_encrypt_seal_with_passphrase,
+};
 
-use crate::error::{Error, ErrorKind, Result};
+use crate::error::{Result, Status};
+use crate::keyring::{with_version, KeyRing};
 use crate::keys::KeyBytes;
 use crate::utils::into_raw_parts;
 
@@ -181,6 +187,58 @@ impl SecureCell {
         }
     }
 
+    /// Constructs a new cell secured by a passphrase.
+    ///
+    /// Unlike master keys, passphrases are expected to be short and human-readable. Themis
+    /// derives the encryption key from the passphrase with PBKDF2, which is intentionally slow.
+    /// Only _sealing_ mode supports passphrases.
+    ///
+    /// This method is available only with Themis 0.13 or newer. Enable `passphrase` crate
+    /// feature to get a clear build error if the installed Themis is older.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), themis::Error> {
+    /// use themis::secure_cell::SecureCell;
+    ///
+    /// let cell = SecureCell::with_passphrase("correct horse battery staple").seal();
+    ///
+    /// let encrypted = cell.encrypt(b"source data")?;
+    /// let decrypted = cell.decrypt(&encrypted)?;
+    /// assert_eq!(decrypted, b"source data");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(themis_secure_cell_passphrase)]
+    pub fn with_passphrase<P: AsRef<str>>(passphrase: P) -> SecureCellWithPassphrase {
+        SecureCellWithPassphrase(Self {
+            master_key: KeyBytes::copy_slice(passphrase.as_ref().as_bytes()),
+            user_context: KeyBytes::empty(),
+        })
+    }
+
+    /// Constructs a new cell secured by a passphrase and arbitrary “context information”.
+    ///
+    /// See [`with_passphrase`] and [`with_key_and_context`] for details.
+    ///
+    /// [`with_passphrase`]: #method.with_passphrase
+    /// [`with_key_and_context`]: #method.with_key_and_context
+    #[cfg(themis_secure_cell_passphrase)]
+    pub fn with_passphrase_and_context<P, C>(
+        passphrase: P,
+        user_context: C,
+    ) -> SecureCellWithPassphrase
+    where
+        P: AsRef<str>,
+        C: AsRef<[u8]>,
+    {
+        SecureCellWithPassphrase(Self {
+            master_key: KeyBytes::copy_slice(passphrase.as_ref().as_bytes()),
+            user_context: KeyBytes::copy_slice(user_context.as_ref()),
+        })
+    }
+
     /// Switches this Secure Cell to the _sealing_ operation mode.
     pub fn seal(self) -> SecureCellSeal {
         SecureCellSeal(self)
@@ -335,9 +393,9 @@ fn encrypt_seal(master_key: &[u8], user_context: &[u8], message: &[u8]) -> Resul
             ptr::null_mut(),
             &mut encrypted_message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::BufferTooSmall {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
         }
     }
 
@@ -354,9 +412,9 @@ fn encrypt_seal(master_key: &[u8], user_context: &[u8], message: &[u8]) -> Resul
             encrypted_message.as_mut_ptr(),
             &mut encrypted_message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::Success {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
         }
         debug_assert!(encrypted_message_len <= encrypted_message.capacity());
         encrypted_message.set_len(encrypted_message_len as usize);
@@ -385,9 +443,9 @@ fn decrypt_seal(master_key: &[u8], user_context: &[u8], message: &[u8]) -> Resul
             ptr::null_mut(),
             &mut decrypted_message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::BufferTooSmall {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
         }
     }
 
@@ -404,9 +462,233 @@ fn decrypt_seal(master_key: &[u8], user_context: &[u8], message: &[u8]) -> Resul
             decrypted_message.as_mut_ptr(),
             &mut decrypted_message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::Success {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
+        }
+        debug_assert!(decrypted_message_len <= decrypted_message.capacity());
+        decrypted_message.set_len(decrypted_message_len as usize);
+    }
+
+    Ok(decrypted_message)
+}
+
+/// Secure Cell in _sealing_ operation mode with key rotation.
+///
+/// Messages are encrypted with the current key of a [`KeyRing`] and are prefixed with its
+/// version. Decryption uses the key version from the message, so data encrypted before key
+/// rotation remains accessible as long as its key stays in the ring. See [`keyring`] module
+/// for an example.
+///
+/// Note that the resulting cells are not compatible with plain [`SecureCellSeal`].
+///
+/// [`KeyRing`]: ../keyring/struct.KeyRing.html
+/// [`keyring`]: ../keyring/index.html
+/// [`SecureCellSeal`]: struct.SecureCellSeal.html
+pub struct SecureCellRing<K> {
+    key_ring: KeyRing<K>,
+    user_context: KeyBytes,
+}
+
+impl<K: AsRef<[u8]>> SecureCellRing<K> {
+    /// Makes a new Secure Cell using keys from the ring.
+    pub fn new(key_ring: KeyRing<K>) -> Self {
+        Self {
+            key_ring,
+            user_context: KeyBytes::empty(),
+        }
+    }
+
+    /// Makes a new Secure Cell using keys from the ring and arbitrary “context information”.
+    pub fn with_context<C: AsRef<[u8]>>(key_ring: KeyRing<K>, user_context: C) -> Self {
+        Self {
+            key_ring,
+            user_context: KeyBytes::copy_slice(user_context.as_ref()),
+        }
+    }
+
+    /// Returns the key ring used by this cell.
+    pub fn key_ring(&self) -> &KeyRing<K> {
+        &self.key_ring
+    }
+
+    /// Returns the key ring used by this cell, allowing to rotate the keys.
+    pub fn key_ring_mut(&mut self) -> &mut KeyRing<K> {
+        &mut self.key_ring
+    }
+
+    /// Encrypts and puts the provided message into a sealed cell using the current key.
+    pub fn encrypt<M: AsRef<[u8]>>(&self, message: M) -> Result<Vec<u8>> {
+        let (version, master_key) = self.key_ring.current_key()?;
+        let sealed = encrypt_seal(
+            master_key.as_ref(),
+            self.user_context.as_bytes(),
+            message.as_ref(),
+        )?;
+        Ok(with_version(version, &sealed))
+    }
+
+    /// Extracts the original message from a sealed cell using the key it was encrypted with.
+    pub fn decrypt<M: AsRef<[u8]>>(&self, message: M) -> Result<Vec<u8>> {
+        let (master_key, sealed) = self.key_ring.key_for(message.as_ref())?;
+        decrypt_seal(master_key.as_ref(), self.user_context.as_bytes(), sealed)
+    }
+}
+
+/// Secure Cell secured by a passphrase.
+///
+/// Constructed by [`SecureCell::with_passphrase`], only _sealing_ mode is available.
+///
+/// [`SecureCell::with_passphrase`]: struct.SecureCell.html#method.with_passphrase
+#[cfg(themis_secure_cell_passphrase)]
+pub struct SecureCellWithPassphrase(SecureCell);
+
+#[cfg(themis_secure_cell_passphrase)]
+impl SecureCellWithPassphrase {
+    /// Switches this Secure Cell to the _sealing_ operation mode.
+    pub fn seal(self) -> SecureCellSealWithPassphrase {
+        SecureCellSealWithPassphrase(self.0)
+    }
+}
+
+/// Secure Cell in _sealing_ operation mode secured by a passphrase.
+///
+/// This is the same as [`SecureCellSeal`], but the key is derived from a passphrase. Data
+/// encrypted with a passphrase cannot be decrypted with the same bytes used as a master key,
+/// and vice versa.
+///
+/// [`SecureCellSeal`]: struct.SecureCellSeal.html
+#[cfg(themis_secure_cell_passphrase)]
+pub struct SecureCellSealWithPassphrase(SecureCell);
+
+#[cfg(themis_secure_cell_passphrase)]
+impl SecureCellSealWithPassphrase {
+    /// Encrypts and puts the provided message into a sealed cell.
+    ///
+    /// The message must not be empty.
+    pub fn encrypt<M: AsRef<[u8]>>(&self, message: M) -> Result<Vec<u8>> {
+        encrypt_seal_with_passphrase(
+            self.0.master_key.as_bytes(),
+            self.0.user_context.as_bytes(),
+            message.as_ref(),
+        )
+    }
+
+    /// Extracts the original message from a sealed cell.
+    ///
+    /// Decryption fails if the passphrase or the context are invalid, or if the data has been
+    /// corrupted.
+    pub fn decrypt<M: AsRef<[u8]>>(&self, message: M) -> Result<Vec<u8>> {
+        decrypt_seal_with_passphrase(
+            self.0.master_key.as_bytes(),
+            self.0.user_context.as_bytes(),
+            message.as_ref(),
+        )
+    }
+}
+
+/// Encrypts `message` with `passphrase` including optional `user_context` for verification.
+#[cfg(themis_secure_cell_passphrase)]
+fn encrypt_seal_with_passphrase(
+    passphrase: &[u8],
+    user_context: &[u8],
+    message: &[u8],
+) -> Result<Vec<u8>> {
+    let (passphrase_ptr, passphrase_len) = into_raw_parts(passphrase);
+    let (user_context_ptr, user_context_len) = into_raw_parts(user_context);
+    let (message_ptr, message_len) = into_raw_parts(message);
+
+    let mut encrypted_message = Vec::new();
+    let mut encrypted_message_len = 0;
+
+    unsafe {
+        let status = themis_secure_cell_encrypt_seal_with_passphrase(
+            passphrase_ptr,
+            passphrase_len,
+            user_context_ptr,
+            user_context_len,
+            message_ptr,
+            message_len,
+            ptr::null_mut(),
+            &mut encrypted_message_len,
+        );
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
+        }
+    }
+
+    encrypted_message.reserve(encrypted_message_len as usize);
+
+    unsafe {
+        let status = themis_secure_cell_encrypt_seal_with_passphrase(
+            passphrase_ptr,
+            passphrase_len,
+            user_context_ptr,
+            user_context_len,
+            message_ptr,
+            message_len,
+            encrypted_message.as_mut_ptr(),
+            &mut encrypted_message_len,
+        );
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
+        }
+        debug_assert!(encrypted_message_len <= encrypted_message.capacity());
+        encrypted_message.set_len(encrypted_message_len as usize);
+    }
+
+    Ok(encrypted_message)
+}
+
+/// Decrypts `message` with `passphrase` and verifies authenticity of `user_context`.
+#[cfg(themis_secure_cell_passphrase)]
+fn decrypt_seal_with_passphrase(
+    passphrase: &[u8],
+    user_context: &[u8],
+    message: &[u8],
+) -> Result<Vec<u8>> {
+    let (passphrase_ptr, passphrase_len) = into_raw_parts(passphrase);
+    let (user_context_ptr, user_context_len) = into_raw_parts(user_context);
+    let (message_ptr, message_len) = into_raw_parts(message);
+
+    let mut decrypted_message = Vec::new();
+    let mut decrypted_message_len = 0;
+
+    unsafe {
+        let status = themis_secure_cell_decrypt_seal_with_passphrase(
+            passphrase_ptr,
+            passphrase_len,
+            user_context_ptr,
+            user_context_len,
+            message_ptr,
+            message_len,
+            ptr::null_mut(),
+            &mut decrypted_message_len,
+        );
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
+        }
+    }
+
+    decrypted_message.reserve(decrypted_message_len as usize);
+
+    unsafe {
+        let status = themis_secure_cell_decrypt_seal_with_passphrase(
+            passphrase_ptr,
+            passphrase_len,
+            user_context_ptr,
+            user_context_len,
+            message_ptr,
+            message_len,
+            decrypted_message.as_mut_ptr(),
+            &mut decrypted_message_len,
+        );
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
         }
         debug_assert!(decrypted_message_len <= decrypted_message.capacity());
         decrypted_message.set_len(decrypted_message_len as usize);
@@ -588,9 +870,9 @@ fn encrypt_token_protect(
             ptr::null_mut(),
             &mut encrypted_message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::BufferTooSmall {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
         }
     }
 
@@ -610,9 +892,9 @@ fn encrypt_token_protect(
             encrypted_message.as_mut_ptr(),
             &mut encrypted_message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::Success {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
         }
         debug_assert!(token_len <= token.capacity());
         token.set_len(token_len as usize);
@@ -651,9 +933,9 @@ fn decrypt_token_protect(
             ptr::null_mut(),
             &mut decrypted_message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::BufferTooSmall {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
         }
     }
 
@@ -672,9 +954,9 @@ fn decrypt_token_protect(
             decrypted_message.as_mut_ptr(),
             &mut decrypted_message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::Success {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
         }
         debug_assert!(decrypted_message_len <= decrypted_message.capacity());
         decrypted_message.set_len(decrypted_message_len as usize);
@@ -849,9 +1131,9 @@ fn encrypt_context_imprint(master_key: &[u8], message: &[u8], context: &[u8]) ->
             ptr::null_mut(),
             &mut encrypted_message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::BufferTooSmall {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
         }
     }
 
@@ -868,9 +1150,9 @@ fn encrypt_context_imprint(master_key: &[u8], message: &[u8], context: &[u8]) ->
             encrypted_message.as_mut_ptr(),
             &mut encrypted_message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::Success {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
         }
         debug_assert!(encrypted_message_len <= encrypted_message.capacity());
         encrypted_message.set_len(encrypted_message_len as usize);
@@ -899,9 +1181,9 @@ fn decrypt_context_imprint(master_key: &[u8], message: &[u8], context: &[u8]) ->
             ptr::null_mut(),
             &mut decrypted_message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::BufferTooSmall {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
         }
     }
 
@@ -918,9 +1200,9 @@ fn decrypt_context_imprint(master_key: &[u8], message: &[u8], context: &[u8]) ->
             decrypted_message.as_mut_ptr(),
             &mut decrypted_message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::Success {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
         }
         debug_assert!(decrypted_message_len <= decrypted_message.capacity());
         decrypted_message.set_len(decrypted_message_len as usize);
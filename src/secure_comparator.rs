@@ -55,7 +55,7 @@
 //! # fn main() -> Result<(), themis::Error> {
 //! # use std::cell::RefCell;
 //! #
-//! # use themis::secure_comparator::SecureComparator;
+//! # use themis::secure_comparator::{CompareStep, SecureComparator};
 //! #
 //! # let mut comparison = SecureComparator::new();
 //! # let mut other_peer = SecureComparator::new();
@@ -65,8 +65,10 @@
 //! #
 //! # let peer_data = RefCell::new(None);
 //! # let mut send = |data: &[u8]| {
-//! #     let reply = other_peer.proceed_compare(data).expect("server comparison");
-//! #     peer_data.replace(Some(reply));
+//! #     let step = other_peer.proceed_compare(data).expect("server comparison");
+//! #     if let CompareStep::SendToPeer(reply) = step {
+//! #         peer_data.replace(Some(reply));
+//! #     }
 //! # };
 //! # let mut receive = || {
 //! #     peer_data.borrow_mut().take().expect("reply data")
@@ -78,7 +80,9 @@
 //!     send(&request);         // This function should send the `request` to the server.
 //!     let reply = receive();  // This function should receive a `reply` from the server.
 //!
-//!     request = comparison.proceed_compare(&reply)?;
+//!     if let CompareStep::SendToPeer(next_request) = comparison.proceed_compare(&reply)? {
+//!         request = next_request;
+//!     }
 //! }
 //!
 //! if !comparison.get_result()? {
@@ -94,7 +98,7 @@
 //! # fn main() -> Result<(), themis::Error> {
 //! # use std::cell::RefCell;
 //! #
-//! # use themis::secure_comparator::SecureComparator;
+//! # use themis::secure_comparator::{CompareStep, SecureComparator};
 //! #
 //! # let mut comparison = SecureComparator::new();
 //! # let mut other_peer = SecureComparator::new();
@@ -105,8 +109,10 @@
 //! #
 //! # let peer_data = RefCell::new(Some(request));
 //! # let mut send = |data: &[u8]| {
-//! #     let reply = other_peer.proceed_compare(data).expect("server comparison");
-//! #     peer_data.replace(Some(reply));
+//! #     let step = other_peer.proceed_compare(data).expect("client comparison");
+//! #     if let CompareStep::SendToPeer(reply) = step {
+//! #         peer_data.replace(Some(reply));
+//! #     }
 //! # };
 //! # let mut receive = || {
 //! #     peer_data.borrow_mut().take().expect("reply data")
@@ -116,9 +122,9 @@
 //!     // This function should receive a `request` from the client.
 //!     let request = receive();
 //!
-//!     let reply = comparison.proceed_compare(&request)?;
-//!
-//!     send(&reply);   // This function should send the `reply` to the client.
+//!     if let CompareStep::SendToPeer(reply) = comparison.proceed_compare(&request)? {
+//!         send(&reply);   // This function should send the `reply` to the client.
+//!     }
 //! }
 //!
 //! if !comparison.get_result()? {
@@ -133,9 +139,35 @@
 //!
 //! [`get_result`]: struct.SecureComparator.html#method.get_result
 //! [`is_complete`]: struct.SecureComparator.html#method.is_complete
+//!
+//! [`ComparatorBuilder`] provides an alternative API which tracks the state of comparison
+//! at compile time. It does not allow to append secrets after the comparison has started
+//! or to reuse a completed comparator.
+//!
+//! [`ComparatorBuilder`]: struct.ComparatorBuilder.html
+//!
+//! # Handling secrets
+//!
+//! Compared data is usually sensitive: passwords, PINs, confirmation codes. Secure Comparator
+//! does not keep copies of appended secrets. They are immediately mixed into a running hash
+//! and only the digest is stored in the comparison context, which is wiped when the
+//! `SecureComparator` is dropped.
+//!
+//! However, the buffers you pass to [`append_secret`] remain yours. Make sure to wipe them
+//! after use, for example with the [`zeroize`] crate.
+//!
+//! [`append_secret`]: struct.SecureComparator.html#method.append_secret
+//! [`zeroize`]: https://crates.io/crates/zeroize
 
+#[cfg(feature = "futures")]
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
 use std::os::raw::c_void;
+#[cfg(feature = "futures")]
+use std::pin::Pin;
 use std::ptr;
+#[cfg(feature = "futures")]
+use std::task::{Context, Poll, Waker};
 
 use bindings::{
     secure_comparator_append_secret, secure_comparator_begin_compare, secure_comparator_create,
@@ -143,7 +175,8 @@ use bindings::{
     secure_comparator_t,
 };
 
-use crate::error::{Error, ErrorKind, Result};
+use crate::error::{Error, ErrorKind, Result, Status};
+use crate::secure_session::{read_frame, write_frame, SecureSession, SecureSessionTransport};
 use crate::utils::into_raw_parts;
 
 /// Secure Comparison context.
@@ -153,6 +186,8 @@ use crate::utils::into_raw_parts;
 /// [secure_comparator]: index.html
 pub struct SecureComparator {
     comp_ctx: *mut secure_comparator_t,
+    rounds: usize,
+    awaiting_peer: bool,
 }
 
 impl SecureComparator {
@@ -168,6 +203,27 @@ impl SecureComparator {
         }
     }
 
+    /// Prepares a new comparison with secrets collected up front.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), themis::Error> {
+    /// use themis::secure_comparator::SecureComparator;
+    ///
+    /// let mut comparison = SecureComparator::builder()
+    ///     .secret(b"999-04-1234")
+    ///     .secret(b"John Doe")
+    ///     .build()?;
+    ///
+    /// let request = comparison.begin_compare()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder() -> ComparatorBuilder {
+        ComparatorBuilder::new()
+    }
+
     /// Prepares for a new comparison.
     fn try_new() -> Result<Self> {
         let comp_ctx = unsafe { secure_comparator_create() };
@@ -178,7 +234,11 @@ impl SecureComparator {
             return Err(Error::with_kind(ErrorKind::NoMemory));
         }
 
-        Ok(Self { comp_ctx })
+        Ok(Self {
+            comp_ctx,
+            rounds: 0,
+            awaiting_peer: false,
+        })
     }
 
     /// Collects the data to be compared.
@@ -187,6 +247,11 @@ impl SecureComparator {
     /// then the comparison will always return `false`. In this case you will need to recreate
     /// a `SecureComparator` to make a new comparison.
     ///
+    /// The secret is not copied, only its hash is updated. The caller is responsible for wiping
+    /// the `secret` buffer afterwards if necessary. See [handling secrets] for details.
+    ///
+    /// [handling secrets]: index.html#handling-secrets
+    ///
     /// You can use this method only before the comparison has been started. That is,
     /// [`append_secret`] is safe call only before [`begin_compare`] or [`proceed_compare`].
     /// It will fail with an error if you try to append more data when you’re in the middle of
@@ -223,9 +288,9 @@ impl SecureComparator {
                 secret_ptr as *const c_void,
                 secret_len,
             );
-            let error = Error::from_compare_status(status);
-            if error.kind() != ErrorKind::Success {
-                return Err(error);
+            let status = Status::from_compare(status);
+            if status != Status::Success {
+                return Err(status.into());
             }
         }
 
@@ -258,9 +323,9 @@ impl SecureComparator {
                 ptr::null_mut(),
                 &mut compare_data_len,
             );
-            let error = Error::from_compare_status(status);
-            if error.kind() != ErrorKind::BufferTooSmall {
-                return Err(error);
+            let status = Status::from_compare(status);
+            if status != Status::BufferTooSmall {
+                return Err(status.into());
             }
         }
 
@@ -272,14 +337,17 @@ impl SecureComparator {
                 compare_data.as_mut_ptr() as *mut c_void,
                 &mut compare_data_len,
             );
-            let error = Error::from_compare_status(status);
-            if error.kind() != ErrorKind::CompareSendOutputToPeer {
-                return Err(error);
+            let status = Status::from_compare(status);
+            if status != Status::SendOutputToPeer {
+                return Err(status.into());
             }
             debug_assert!(compare_data_len <= compare_data.capacity());
             compare_data.set_len(compare_data_len);
         }
 
+        self.rounds += 1;
+        self.awaiting_peer = true;
+
         Ok(compare_data)
     }
 
@@ -291,6 +359,10 @@ impl SecureComparator {
     /// do the same. The process repeats at both sides until [`is_complete`] signals that the
     /// comparison is complete.
     ///
+    /// The result tells whether there is a message to be sent to the peer. Note that the server
+    /// completes the comparison with a final message for the client, so you should check
+    /// [`is_complete`] after sending it.
+    ///
     /// Both peers should have appended all the compared data before using this method, and no
     /// additional data may be appended while the comparison is underway.
     ///
@@ -302,7 +374,11 @@ impl SecureComparator {
     /// Please see [module-level documentation][secure_comparator] for examples.
     ///
     /// [secure_comparator]: index.html
-    pub fn proceed_compare<D: AsRef<[u8]>>(&mut self, peer_data: D) -> Result<Vec<u8>> {
+    pub fn proceed_compare<D: AsRef<[u8]>>(&mut self, peer_data: D) -> Result<CompareStep> {
+        // Whatever happens next, we are not waiting for the peer anymore. Either we are going
+        // to send them a reply, or the comparison is over (successfully or not).
+        self.awaiting_peer = false;
+
         let (peer_compare_data_ptr, peer_compare_data_len) = into_raw_parts(peer_data.as_ref());
 
         let mut compare_data = Vec::new();
@@ -316,9 +392,9 @@ impl SecureComparator {
                 ptr::null_mut(),
                 &mut compare_data_len,
             );
-            let error = Error::from_compare_status(status);
-            if error.kind() != ErrorKind::BufferTooSmall {
-                return Err(error);
+            let status = Status::from_compare(status);
+            if status != Status::BufferTooSmall {
+                return Err(status.into());
             }
         }
 
@@ -332,20 +408,24 @@ impl SecureComparator {
                 compare_data.as_mut_ptr() as *mut c_void,
                 &mut compare_data_len,
             );
-            let error = Error::from_compare_status(status);
-            match error.kind() {
-                ErrorKind::CompareSendOutputToPeer => {}
-                // TODO: signal that this does not need to be sent
-                ErrorKind::Success => {}
-                _ => {
-                    return Err(error);
+            match Status::from_compare(status) {
+                Status::SendOutputToPeer => {}
+                Status::Success => {
+                    self.rounds += 1;
+                    return Ok(CompareStep::Complete);
+                }
+                status => {
+                    return Err(status.into());
                 }
             }
             debug_assert!(compare_data_len <= compare_data.capacity());
             compare_data.set_len(compare_data_len);
         }
 
-        Ok(compare_data)
+        self.rounds += 1;
+        self.awaiting_peer = !self.is_complete();
+
+        Ok(CompareStep::SendToPeer(compare_data))
     }
 
     /// Returns the result of comparison.
@@ -362,7 +442,7 @@ impl SecureComparator {
     /// # fn main() -> Result<(), themis::Error> {
     /// use themis::secure_comparator::SecureComparator;
     /// #
-    /// # use std::cell::RefCell;
+    /// # use themis::secure_comparator::CompareStep;
     ///
     /// let mut comparison = SecureComparator::new();
     /// # let mut other_peer = SecureComparator::new();
@@ -378,8 +458,11 @@ impl SecureComparator {
     /// #
     /// while !comparison.is_complete() {
     ///     // ...
-    /// #   let reply = other_peer.proceed_compare(&request)?;
-    /// #   request = comparison.proceed_compare(&reply)?;
+    /// #   if let CompareStep::SendToPeer(reply) = other_peer.proceed_compare(&request)? {
+    /// #       if let CompareStep::SendToPeer(next) = comparison.proceed_compare(&reply)? {
+    /// #           request = next;
+    /// #       }
+    /// #   }
     /// }
     ///
     /// assert!(comparison.get_result().is_ok());
@@ -388,11 +471,10 @@ impl SecureComparator {
     /// ```
     pub fn get_result(&self) -> Result<bool> {
         let status = unsafe { secure_comparator_get_result(self.comp_ctx) };
-        let error = Error::from_match_status(status);
-        match error.kind() {
-            ErrorKind::CompareMatch => Ok(true),
-            ErrorKind::CompareNoMatch => Ok(false),
-            _ => Err(error),
+        match Status::from_match(status) {
+            Status::Match => Ok(true),
+            Status::NoMatch => Ok(false),
+            status => Err(status.into()),
         }
     }
 
@@ -423,6 +505,507 @@ impl SecureComparator {
             _ => true,
         }
     }
+
+    /// Returns the number of protocol rounds completed by this peer.
+    ///
+    /// Each successful call to [`begin_compare`] or [`proceed_compare`] completes one round.
+    /// The client completes three rounds over a full comparison, the server completes two.
+    /// This is useful for displaying progress and for finding out where a failed comparison
+    /// has stopped.
+    ///
+    /// [`begin_compare`]: struct.SecureComparator.html#method.begin_compare
+    /// [`proceed_compare`]: struct.SecureComparator.html#method.proceed_compare
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), themis::Error> {
+    /// use themis::secure_comparator::SecureComparator;
+    ///
+    /// let mut comparison = SecureComparator::new();
+    /// comparison.append_secret(b"999-04-1234")?;
+    ///
+    /// assert_eq!(comparison.rounds_completed(), 0);
+    ///
+    /// let request = comparison.begin_compare()?;
+    ///
+    /// assert_eq!(comparison.rounds_completed(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rounds_completed(&self) -> usize {
+        self.rounds
+    }
+
+    /// Checks whether this peer has sent a message and now waits for a reply.
+    ///
+    /// Returns `false` before the comparison has been started, after it is complete, and after
+    /// a failed round. In other cases the next thing to do is to receive a message from the peer
+    /// and pass it to [`proceed_compare`].
+    ///
+    /// [`proceed_compare`]: struct.SecureComparator.html#method.proceed_compare
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), themis::Error> {
+    /// use themis::secure_comparator::SecureComparator;
+    ///
+    /// let mut comparison = SecureComparator::new();
+    /// comparison.append_secret(b"999-04-1234")?;
+    ///
+    /// assert!(!comparison.is_awaiting_peer());
+    ///
+    /// let request = comparison.begin_compare()?;
+    ///
+    /// assert!(comparison.is_awaiting_peer());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_awaiting_peer(&self) -> bool {
+        self.awaiting_peer
+    }
+
+    /// Runs the whole comparison over a byte stream, returns the result.
+    ///
+    /// This method takes care of the message exchange with the peer. One side of the stream
+    /// should use `Role::Client` and the other one should use `Role::Server`. Messages are
+    /// framed with [`write_frame`] and [`read_frame`]. Both peers should have appended all
+    /// the compared data before using this method.
+    ///
+    /// Comparison errors are reported as I/O errors of kind `Other`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> std::io::Result<()> {
+    /// use std::net::TcpStream;
+    ///
+    /// use themis::secure_comparator::{Role, SecureComparator};
+    ///
+    /// let mut comparison = SecureComparator::new();
+    /// comparison.append_secret(b"999-04-1234").expect("append secret");
+    ///
+    /// let mut server = TcpStream::connect("localhost:7575")?;
+    ///
+    /// if comparison.compare_over(&mut server, Role::Client)? {
+    ///     println!("[+] match OK");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`write_frame`]: ../secure_session/fn.write_frame.html
+    /// [`read_frame`]: ../secure_session/fn.read_frame.html
+    pub fn compare_over<S>(&mut self, stream: &mut S, role: Role) -> io::Result<bool>
+    where
+        S: Read + Write + ?Sized,
+    {
+        if role == Role::Client {
+            let request = self.begin_compare()?;
+            write_frame(stream, &request)?;
+            stream.flush()?;
+        }
+
+        while !self.is_complete() {
+            let request = read_frame(stream)?;
+            let step = self.proceed_compare(&request)?;
+            if let CompareStep::SendToPeer(reply) = step {
+                write_frame(stream, &reply)?;
+                stream.flush()?;
+            }
+        }
+
+        Ok(self.get_result()?)
+    }
+
+    /// Runs the whole comparison inside an established Secure Session, returns the result.
+    ///
+    /// This is similar to [`compare_over`], but the comparison messages are sent and received
+    /// with [`SecureSession::send`] and [`SecureSession::receive`]. That is, they are protected
+    /// by the session and transferred by its transport. One peer should use `Role::Client` and
+    /// the other one should use `Role::Server`. Both peers should have appended all the compared
+    /// data before using this method.
+    ///
+    /// This is useful for confirming that the peers of an authenticated connection share some
+    /// low-entropy secret, like a pairing code displayed to the user.
+    ///
+    /// [`compare_over`]: struct.SecureComparator.html#method.compare_over
+    /// [`SecureSession::send`]: ../secure_session/struct.SecureSession.html#method.send
+    /// [`SecureSession::receive`]: ../secure_session/struct.SecureSession.html#method.receive
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), themis::Error> {
+    /// use std::net::TcpStream;
+    ///
+    /// use themis::keygen::gen_ec_key_pair;
+    /// use themis::secure_comparator::{Role, SecureComparator};
+    /// use themis::secure_session::{SecureSession, StaticKeyResolver, TcpTransport};
+    ///
+    /// # let (secret_key, public_key) = gen_ec_key_pair().split();
+    /// let mut keys = StaticKeyResolver::new();
+    /// keys.insert("server", public_key);
+    ///
+    /// let stream = TcpStream::connect("localhost:7575").expect("connect");
+    /// let transport = TcpTransport::new(stream, keys);
+    /// let mut session = SecureSession::with_transport("client", &secret_key, transport)?;
+    ///
+    /// session.connect()?;
+    /// while !session.is_established() {
+    ///     session.negotiate_transport()?;
+    /// }
+    ///
+    /// let mut comparison = SecureComparator::new();
+    /// comparison.append_secret(b"318-142")?;
+    ///
+    /// if comparison.compare_over_session(&mut session, Role::Client)? {
+    ///     println!("[+] pairing confirmed");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compare_over_session<T>(
+        &mut self,
+        session: &mut SecureSession<T>,
+        role: Role,
+    ) -> Result<bool>
+    where
+        T: SecureSessionTransport,
+    {
+        if role == Role::Client {
+            let request = self.begin_compare()?;
+            session.send(&request)?;
+        }
+
+        while !self.is_complete() {
+            let request = session.receive(MAX_COMPARATOR_MESSAGE_SIZE)?;
+            if let CompareStep::SendToPeer(reply) = self.proceed_compare(&request)? {
+                session.send(&reply)?;
+            }
+        }
+
+        self.get_result()
+    }
+}
+
+// Secure Comparator messages are a few hundred bytes long, this is more than enough.
+const MAX_COMPARATOR_MESSAGE_SIZE: usize = 4096;
+
+/// Role of a peer in Secure Comparator protocol.
+///
+/// Used by [`SecureComparator::compare_over`].
+///
+/// [`SecureComparator::compare_over`]: struct.SecureComparator.html#method.compare_over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// The peer which initiates the comparison.
+    Client,
+    /// The peer which responds to the client.
+    Server,
+}
+
+/// Secure Comparator as a stream of outgoing messages and a sink of incoming ones.
+///
+/// This adapter allows to run comparison over any framed asynchronous transport. Forward all
+/// messages from the `Stream` to the peer, and feed all messages from the peer into the `Sink`.
+/// The stream ends when the comparison is complete. After that, use [`result`] to get the
+/// outcome of the comparison.
+///
+/// This adapter is available with `futures` crate feature.
+///
+/// [`result`]: struct.ComparisonChannel.html#method.result
+#[cfg(feature = "futures")]
+pub struct ComparisonChannel {
+    comparator: SecureComparator,
+    outgoing: VecDeque<Vec<u8>>,
+    failed: bool,
+    waker: Option<Waker>,
+}
+
+#[cfg(feature = "futures")]
+impl ComparisonChannel {
+    /// Wraps a comparator which should have all the secrets appended.
+    ///
+    /// The client immediately begins the comparison.
+    pub fn new(mut comparator: SecureComparator, role: Role) -> Result<Self> {
+        let mut outgoing = VecDeque::new();
+        if role == Role::Client {
+            outgoing.push_back(comparator.begin_compare()?);
+        }
+        Ok(Self {
+            comparator,
+            outgoing,
+            failed: false,
+            waker: None,
+        })
+    }
+
+    /// Returns the result of comparison.
+    ///
+    /// See [`SecureComparator::get_result`] for details.
+    ///
+    /// [`SecureComparator::get_result`]: struct.SecureComparator.html#method.get_result
+    pub fn result(&self) -> Result<bool> {
+        self.comparator.get_result()
+    }
+
+    /// Unwraps this adapter, returning the underlying comparator.
+    pub fn into_inner(self) -> SecureComparator {
+        self.comparator
+    }
+}
+
+#[cfg(feature = "futures")]
+impl futures::Stream for ComparisonChannel {
+    type Item = Vec<u8>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Vec<u8>>> {
+        let this = self.get_mut();
+        if let Some(message) = this.outgoing.pop_front() {
+            return Poll::Ready(Some(message));
+        }
+        if this.failed || this.comparator.is_complete() {
+            return Poll::Ready(None);
+        }
+        this.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(feature = "futures")]
+impl futures::Sink<Vec<u8>> for ComparisonChannel {
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, message: Vec<u8>) -> Result<()> {
+        let this = self.get_mut();
+        let step = this.comparator.proceed_compare(&message);
+        // Wake up the stream in any case: it may have something to send or be complete now.
+        if let Some(waker) = this.waker.take() {
+            waker.wake();
+        }
+        match step {
+            Ok(CompareStep::SendToPeer(reply)) => {
+                this.outgoing.push_back(reply);
+                Ok(())
+            }
+            Ok(CompareStep::Complete) => Ok(()),
+            Err(e) => {
+                this.failed = true;
+                Err(e)
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Outcome of [`SecureComparator::proceed_compare`].
+///
+/// [`SecureComparator::proceed_compare`]: struct.SecureComparator.html#method.proceed_compare
+#[derive(Debug, PartialEq, Eq)]
+pub enum CompareStep {
+    /// The message must be transferred to the peer.
+    SendToPeer(Vec<u8>),
+    /// Comparison is complete, nothing needs to be sent to the peer.
+    Complete,
+}
+
+/// Secure Comparator collecting secrets before comparison.
+///
+/// This is the first state of Secure Comparator with compile-time state tracking:
+///
+///   - `ComparatorBuilder` collects the secrets.
+///   - [`ComparisonInProgress`] exchanges the messages with the peer.
+///   - [`ComparisonResult`] holds the outcome.
+///
+/// Each transition consumes the previous state, so it is not possible to append secrets after
+/// the comparison has been started, or to reuse a comparator for another comparison.
+///
+/// ```compile_fail
+/// # fn main() -> Result<(), themis::Error> {
+/// use themis::secure_comparator::ComparatorBuilder;
+///
+/// let mut comparison = ComparatorBuilder::new().secret(b"999-04-1234");
+/// let (in_progress, request) = comparison.begin_compare()?;
+///
+/// // error[E0382]: use of moved value: `comparison`
+/// comparison = comparison.secret(b"oops");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Examples
+///
+/// The client runs a loop like this (the server does the same, starting with
+/// [`accept_compare`] instead):
+///
+/// ```
+/// # fn main() -> Result<(), themis::Error> {
+/// use themis::secure_comparator::{ComparatorBuilder, ComparisonStep};
+/// #
+/// # use themis::secure_comparator::{CompareStep, SecureComparator};
+/// #
+/// # let mut other_peer = SecureComparator::new();
+/// # other_peer.append_secret(b"999-04-1234")?;
+/// # let mut exchange = |data: &[u8]| match other_peer.proceed_compare(data).expect("server") {
+/// #     CompareStep::SendToPeer(reply) => reply,
+/// #     CompareStep::Complete => unreachable!(),
+/// # };
+///
+/// let comparison = ComparatorBuilder::new().secret(b"999-04-1234");
+///
+/// let (mut comparison, mut request) = comparison.begin_compare()?;
+///
+/// let result = loop {
+///     // This function should send the `request` to the server and receive a `reply`.
+///     let reply = exchange(&request);
+///
+///     match comparison.proceed_compare(&reply)? {
+///         ComparisonStep::Continue(next, next_request) => {
+///             comparison = next;
+///             request = next_request;
+///         }
+///         ComparisonStep::Complete(result, _) => break result,
+///     }
+/// };
+///
+/// assert!(result.matches());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`accept_compare`]: struct.ComparatorBuilder.html#method.accept_compare
+/// [`ComparisonInProgress`]: struct.ComparisonInProgress.html
+/// [`ComparisonResult`]: struct.ComparisonResult.html
+pub struct ComparatorBuilder {
+    comparator: SecureComparator,
+    error: Option<Error>,
+}
+
+/// Secure Comparator exchanging messages with the peer.
+///
+/// Use [`proceed_compare`] to process messages from the peer until the comparison is complete.
+///
+/// [`proceed_compare`]: struct.ComparisonInProgress.html#method.proceed_compare
+pub struct ComparisonInProgress {
+    comparator: SecureComparator,
+}
+
+/// Outcome of a single step of comparison.
+pub enum ComparisonStep {
+    /// Comparison continues. Send the message to the peer and wait for a reply.
+    Continue(ComparisonInProgress, Vec<u8>),
+    /// Comparison is complete. Send the final message to the peer, if there is any.
+    Complete(ComparisonResult, Option<Vec<u8>>),
+}
+
+/// Result of a completed comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComparisonResult {
+    matches: bool,
+}
+
+impl ComparatorBuilder {
+    /// Prepares a new comparison.
+    ///
+    /// # Panics
+    ///
+    /// May panic on internal unrecoverable errors (e.g., out-of-memory).
+    pub fn new() -> Self {
+        Self {
+            comparator: SecureComparator::new(),
+            error: None,
+        }
+    }
+
+    /// Appends more data to be compared.
+    ///
+    /// See [`SecureComparator::append_secret`] for details. If appending fails then the error
+    /// is reported when the comparison begins.
+    ///
+    /// Note that owned secrets (like `Vec<u8>` or `String`) are dropped without being wiped.
+    /// Pass a reference if you need to wipe the secret afterwards.
+    ///
+    /// [`SecureComparator::append_secret`]: struct.SecureComparator.html#method.append_secret
+    pub fn secret<S: AsRef<[u8]>>(mut self, secret: S) -> Self {
+        if self.error.is_none() {
+            self.error = self.comparator.append_secret(secret).err();
+        }
+        self
+    }
+
+    /// Starts comparison on the client, returning the first message to be sent to the server.
+    pub fn begin_compare(self) -> Result<(ComparisonInProgress, Vec<u8>)> {
+        let mut comparator = self.check_secrets()?;
+        let request = comparator.begin_compare()?;
+        Ok((ComparisonInProgress { comparator }, request))
+    }
+
+    /// Starts comparison on the server which waits for the first message from the client.
+    pub fn accept_compare(self) -> Result<ComparisonInProgress> {
+        let comparator = self.check_secrets()?;
+        Ok(ComparisonInProgress { comparator })
+    }
+
+    /// Returns a comparator with all the secrets appended, ready to start the comparison.
+    ///
+    /// Reports the first error encountered while appending the secrets, if any.
+    pub fn build(self) -> Result<SecureComparator> {
+        self.check_secrets()
+    }
+
+    fn check_secrets(self) -> Result<SecureComparator> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.comparator),
+        }
+    }
+}
+
+impl Default for ComparatorBuilder {
+    fn default() -> Self {
+        ComparatorBuilder::new()
+    }
+}
+
+impl ComparisonInProgress {
+    /// Processes a message from the peer.
+    ///
+    /// Returns either the next state of comparison along with a message for the peer, or the
+    /// result of the comparison if it is complete. Comparison is aborted on errors.
+    pub fn proceed_compare<D: AsRef<[u8]>>(mut self, peer_data: D) -> Result<ComparisonStep> {
+        let reply = match self.comparator.proceed_compare(peer_data)? {
+            CompareStep::SendToPeer(reply) => Some(reply),
+            CompareStep::Complete => None,
+        };
+        if !self.comparator.is_complete() {
+            let reply = reply.expect("Secure Comparator must reply while in progress");
+            return Ok(ComparisonStep::Continue(self, reply));
+        }
+        let result = ComparisonResult {
+            matches: self.comparator.get_result()?,
+        };
+        Ok(ComparisonStep::Complete(result, reply))
+    }
+}
+
+impl ComparisonResult {
+    /// Returns `true` if the data is found to be equal on both peers.
+    pub fn matches(&self) -> bool {
+        self.matches
+    }
 }
 
 impl Default for SecureComparator {
@@ -434,11 +1017,15 @@ impl Default for SecureComparator {
 #[doc(hidden)]
 impl Drop for SecureComparator {
     fn drop(&mut self) {
+        // This also wipes the hash of the secrets held in the context.
         unsafe {
             let status = secure_comparator_destroy(self.comp_ctx);
-            let error = Error::from_themis_status(status);
-            if (cfg!(debug) || cfg!(test)) && error.kind() != ErrorKind::Success {
-                panic!("secure_comparator_destroy() failed: {}", error);
+            let status = Status::from_themis(status);
+            if (cfg!(debug) || cfg!(test)) && status != Status::Success {
+                panic!(
+                    "secure_comparator_destroy() failed: {}",
+                    Error::from(status)
+                );
             }
         }
     }
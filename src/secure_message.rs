@@ -63,11 +63,13 @@
 //! You can find more examples for each operation mode in their respective documentation.
 
 use std::ptr;
+use std::sync::Arc;
 
 use bindings::{themis_secure_message_unwrap, themis_secure_message_wrap};
 
-use crate::error::{Error, ErrorKind, Result};
-use crate::keys::{KeyPair, PublicKey, SecretKey};
+use crate::error::{Result, Status};
+use crate::keyring::{with_version, KeyRing};
+use crate::keys::{KeyPair, PrivateKeyHandle, PublicKey, SecretKey};
 use crate::utils::into_raw_parts;
 
 /// Secure Message encryption and decryption.
@@ -208,7 +210,13 @@ impl SecureMessage {
 /// ```
 #[derive(Clone)]
 pub struct SecureSign {
-    secret_key: SecretKey,
+    secret_key: SigningKey,
+}
+
+#[derive(Clone)]
+enum SigningKey {
+    Key(SecretKey),
+    Handle(Arc<dyn PrivateKeyHandle + Send + Sync>),
 }
 
 impl SecureSign {
@@ -217,7 +225,20 @@ impl SecureSign {
     /// Both ECDSA and RSA keys are supported.
     pub fn new<S: Into<SecretKey>>(secret_key: S) -> Self {
         Self {
-            secret_key: secret_key.into(),
+            secret_key: SigningKey::Key(secret_key.into()),
+        }
+    }
+
+    /// Makes a new Secure Message using a secret key provided by a handle.
+    ///
+    /// The handle is asked for the key each time a message is signed, errors are passed through.
+    /// Both ECDSA and RSA keys are supported.
+    pub fn with_key_handle<H>(handle: H) -> Self
+    where
+        H: PrivateKeyHandle + Send + Sync + 'static,
+    {
+        Self {
+            secret_key: SigningKey::Handle(Arc::new(handle)),
         }
     }
 
@@ -243,7 +264,13 @@ impl SecureSign {
     /// # }
     /// ```
     pub fn sign<M: AsRef<[u8]>>(&self, message: M) -> Result<Vec<u8>> {
-        wrap(self.secret_key.as_ref(), &[], message.as_ref())
+        match self.secret_key {
+            SigningKey::Key(ref secret_key) => wrap(secret_key.as_ref(), &[], message.as_ref()),
+            SigningKey::Handle(ref handle) => {
+                let secret_key = handle.secret_key()?;
+                wrap(secret_key.as_ref(), &[], message.as_ref())
+            }
+        }
     }
 }
 
@@ -335,8 +362,136 @@ impl SecureVerify {
     }
 }
 
+/// Secure Message encryption with key rotation.
+///
+/// Messages are wrapped with the current key pair of a [`KeyRing`] and are prefixed with its
+/// version. Unwrapping uses the key pair version from the message, so both peers need to keep
+/// their rings in sync: version _N_ of Alice’s ring should contain her secret key and Bob’s
+/// public key, while version _N_ of Bob’s ring contains his secret key and Alice’s public key.
+///
+/// Note that the resulting messages are not compatible with plain [`SecureMessage`].
+///
+/// [`KeyRing`]: ../keyring/struct.KeyRing.html
+/// [`SecureMessage`]: struct.SecureMessage.html
+#[derive(Clone)]
+pub struct SecureMessageRing {
+    key_ring: KeyRing<KeyPair>,
+}
+
+impl SecureMessageRing {
+    /// Makes a new Secure Message using key pairs from the ring.
+    pub fn new(key_ring: KeyRing<KeyPair>) -> Self {
+        Self { key_ring }
+    }
+
+    /// Returns the key ring used by this Secure Message.
+    pub fn key_ring(&self) -> &KeyRing<KeyPair> {
+        &self.key_ring
+    }
+
+    /// Returns the key ring used by this Secure Message, allowing to rotate the keys.
+    pub fn key_ring_mut(&mut self) -> &mut KeyRing<KeyPair> {
+        &mut self.key_ring
+    }
+
+    /// Wraps the provided message using the current key pair.
+    pub fn wrap<M: AsRef<[u8]>>(&self, message: M) -> Result<Vec<u8>> {
+        let (version, key_pair) = self.key_ring.current_key()?;
+        let wrapped = wrap(
+            key_pair.secret_key_bytes(),
+            key_pair.public_key_bytes(),
+            message.as_ref(),
+        )?;
+        Ok(with_version(version, &wrapped))
+    }
+
+    /// Unwraps an encrypted message using the key pair it was wrapped with.
+    pub fn unwrap<M: AsRef<[u8]>>(&self, wrapped: M) -> Result<Vec<u8>> {
+        let (key_pair, wrapped) = self.key_ring.key_for(wrapped.as_ref())?;
+        unwrap(
+            key_pair.secret_key_bytes(),
+            key_pair.public_key_bytes(),
+            wrapped,
+        )
+    }
+}
+
+/// Secure Message signing with key rotation.
+///
+/// Messages are signed with the current secret key of a [`KeyRing`] and are prefixed with its
+/// version. Signatures can be checked with [`SecureVerifyRing`] holding public keys with the
+/// same versions.
+///
+/// Note that the resulting messages are not compatible with plain [`SecureVerify`].
+///
+/// [`KeyRing`]: ../keyring/struct.KeyRing.html
+/// [`SecureVerifyRing`]: struct.SecureVerifyRing.html
+/// [`SecureVerify`]: struct.SecureVerify.html
+#[derive(Clone)]
+pub struct SecureSignRing {
+    key_ring: KeyRing<SecretKey>,
+}
+
+impl SecureSignRing {
+    /// Makes a new Secure Message using secret keys from the ring.
+    pub fn new(key_ring: KeyRing<SecretKey>) -> Self {
+        Self { key_ring }
+    }
+
+    /// Returns the key ring used by this Secure Message.
+    pub fn key_ring(&self) -> &KeyRing<SecretKey> {
+        &self.key_ring
+    }
+
+    /// Returns the key ring used by this Secure Message, allowing to rotate the keys.
+    pub fn key_ring_mut(&mut self) -> &mut KeyRing<SecretKey> {
+        &mut self.key_ring
+    }
+
+    /// Securely signs a message using the current secret key.
+    pub fn sign<M: AsRef<[u8]>>(&self, message: M) -> Result<Vec<u8>> {
+        let (version, secret_key) = self.key_ring.current_key()?;
+        let signed = wrap(secret_key.as_ref(), &[], message.as_ref())?;
+        Ok(with_version(version, &signed))
+    }
+}
+
+/// Secure Message verification with key rotation.
+///
+/// Verifies signatures produced by [`SecureSignRing`], using the public key version from the
+/// message. Expired keys are still used for verification until they are removed from the ring.
+///
+/// [`SecureSignRing`]: struct.SecureSignRing.html
+#[derive(Clone)]
+pub struct SecureVerifyRing {
+    key_ring: KeyRing<PublicKey>,
+}
+
+impl SecureVerifyRing {
+    /// Makes a new Secure Message using public keys from the ring.
+    pub fn new(key_ring: KeyRing<PublicKey>) -> Self {
+        Self { key_ring }
+    }
+
+    /// Returns the key ring used by this Secure Message.
+    pub fn key_ring(&self) -> &KeyRing<PublicKey> {
+        &self.key_ring
+    }
+
+    /// Returns the key ring used by this Secure Message, allowing to rotate the keys.
+    pub fn key_ring_mut(&mut self) -> &mut KeyRing<PublicKey> {
+        &mut self.key_ring
+    }
+
+    /// Verifies the signature and returns the original message.
+    pub fn verify<M: AsRef<[u8]>>(&self, message: M) -> Result<Vec<u8>> {
+        let (public_key, signed) = self.key_ring.key_for(message.as_ref())?;
+        unwrap(&[], public_key.as_ref(), signed)
+    }
+}
+
 /// Wrap a message into a secure message.
-fn wrap(secret_key: &[u8], public_key: &[u8], message: &[u8]) -> Result<Vec<u8>> {
+pub(crate) fn wrap(secret_key: &[u8], public_key: &[u8], message: &[u8]) -> Result<Vec<u8>> {
     let (secret_key_ptr, secret_key_len) = into_raw_parts(secret_key);
     let (public_key_ptr, public_key_len) = into_raw_parts(public_key);
     let (message_ptr, message_len) = into_raw_parts(message);
@@ -355,9 +510,9 @@ fn wrap(secret_key: &[u8], public_key: &[u8], message: &[u8]) -> Result<Vec<u8>>
             ptr::null_mut(),
             &mut wrapped_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::BufferTooSmall {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
         }
     }
 
@@ -374,9 +529,9 @@ fn wrap(secret_key: &[u8], public_key: &[u8], message: &[u8]) -> Result<Vec<u8>>
             wrapped.as_mut_ptr(),
             &mut wrapped_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::Success {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
         }
         debug_assert!(wrapped_len <= wrapped.capacity());
         wrapped.set_len(wrapped_len as usize);
@@ -386,7 +541,7 @@ fn wrap(secret_key: &[u8], public_key: &[u8], message: &[u8]) -> Result<Vec<u8>>
 }
 
 /// Unwrap a secure message into a message.
-fn unwrap(secret_key: &[u8], public_key: &[u8], wrapped: &[u8]) -> Result<Vec<u8>> {
+pub(crate) fn unwrap(secret_key: &[u8], public_key: &[u8], wrapped: &[u8]) -> Result<Vec<u8>> {
     let (secret_key_ptr, secret_key_len) = into_raw_parts(secret_key);
     let (public_key_ptr, public_key_len) = into_raw_parts(public_key);
     let (wrapped_ptr, wrapped_len) = into_raw_parts(wrapped);
@@ -405,9 +560,9 @@ fn unwrap(secret_key: &[u8], public_key: &[u8], wrapped: &[u8]) -> Result<Vec<u8
             ptr::null_mut(),
             &mut message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::BufferTooSmall {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::BufferTooSmall {
+            return Err(status.into());
         }
     }
 
@@ -424,9 +579,9 @@ fn unwrap(secret_key: &[u8], public_key: &[u8], wrapped: &[u8]) -> Result<Vec<u8
             message.as_mut_ptr(),
             &mut message_len,
         );
-        let error = Error::from_themis_status(status);
-        if error.kind() != ErrorKind::Success {
-            return Err(error);
+        let status = Status::from_themis(status);
+        if status != Status::Success {
+            return Err(status.into());
         }
         debug_assert!(message_len <= message.capacity());
         message.set_len(message_len as usize);
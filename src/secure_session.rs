@@ -16,9 +16,27 @@
 //!
 //! **Secure Session** is a lightweight mechanism for securing any kind of network communication
 //! (both private and public networks, including the Internet).
-
+//!
+//! Enable `tracing` crate feature to get diagnostic spans and events for Secure Session
+//! connection lifecycle: creation, negotiation steps, establishment, and unwrap failures.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::{self, Read, Write};
+use std::iter::FromIterator;
+use std::net::TcpStream;
 use std::os::raw::{c_int, c_void};
-use std::{ptr, result, slice};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::rc::Rc;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use std::{ptr, slice};
+
+#[cfg(feature = "futures")]
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
 
 use bindings::{
     secure_session_connect, secure_session_create, secure_session_destroy,
@@ -28,14 +46,30 @@ use bindings::{
     STATE_IDLE, STATE_NEGOTIATING,
 };
 
-use crate::error::{themis_status_t, Error, ErrorKind, Result};
-use crate::keys::{EcdsaPublicKey, EcdsaSecretKey};
+use crate::error::{themis_status_t, Error, ErrorKind, Result, Status};
+use crate::keys::{
+    EcdsaKeyPair, EcdsaPublicKey, EcdsaSecretKey, KeyKind, PrivateKeyHandle, SecretKey,
+};
+use crate::trace::SessionTrace;
 use crate::utils::into_raw_parts;
 
 /// Secure Session context.
+///
+/// Secure Session takes ownership of its [transport]. You can access it later with
+/// [`transport`] and [`transport_mut`] methods. If you need to share transport state with
+/// the rest of your application then wrap the transport into `Rc<RefCell<T>>` or
+/// `Arc<Mutex<T>>` and keep a clone of it around: these types implement
+/// `SecureSessionTransport` too.
+///
+/// [transport]: trait.SecureSessionTransport.html
+/// [`transport`]: struct.SecureSession.html#method.transport
+/// [`transport_mut`]: struct.SecureSession.html#method.transport_mut
 pub struct SecureSession<T> {
     session_ctx: *mut secure_session_t,
-    _delegate: Box<SecureSessionDelegate<T>>,
+    delegate: *mut SecureSessionDelegate<T>,
+    trace: SessionTrace,
+    created: Instant,
+    negotiation_timeout: Option<Duration>,
 }
 
 /// Transport delegate for Secure Session.
@@ -49,57 +83,1832 @@ pub struct SecureSession<T> {
 /// [`get_public_key_for_id`]: trait.SecureSessionTransport.html#tymethod.get_public_key_for_id
 #[allow(unused_variables)]
 pub trait SecureSessionTransport {
-    // TODO: consider send/receive use std::io::Error for errors (or a custom type)
-
     /// Send the provided data to the peer, return the number of bytes transferred.
     ///
     /// This callback will be called when Secure Session needs to send some data to its peer.
     /// The whole message is expected to be transferred so returning anything other than
     /// `Ok(data.len())` is considered an error.
     ///
+    /// Returned I/O errors are attached as the source of [`SessionTransportError`] reported
+    /// by Secure Session.
+    ///
     /// This method is used by the transport API ([`connect`], [`negotiate_transport`], [`send`]).
     /// You need to implement it in order to use this API.
     ///
-    /// [`connect`]: struct.SecureSession.html#method.connect
-    /// [`negotiate_transport`]: struct.SecureSession.html#method.negotiate_transport
-    /// [`send`]: struct.SecureSession.html#method.send
-    fn send_data(&mut self, data: &[u8]) -> result::Result<usize, ()> {
-        Err(())
+    /// [`connect`]: struct.SecureSession.html#method.connect
+    /// [`negotiate_transport`]: struct.SecureSession.html#method.negotiate_transport
+    /// [`send`]: struct.SecureSession.html#method.send
+    /// [`SessionTransportError`]: ../enum.ErrorKind.html#variant.SessionTransportError
+    fn send_data(&mut self, data: &[u8]) -> io::Result<usize> {
+        Err(not_implemented("send_data"))
+    }
+
+    /// Receive some data from the peer into the provided buffer, return the number of bytes.
+    ///
+    /// This callback will be called when Secure Session expects to receive some data. The length
+    /// of the buffer indicates the maximum amount of data expected. Put the received data into
+    /// the provided buffer and return the number of bytes that you used.
+    ///
+    /// Returned I/O errors are attached as the source of [`SessionTransportError`] reported
+    /// by Secure Session.
+    ///
+    /// This method is used by the transport API ([`negotiate_transport`], [`receive`]).
+    /// You need to implement it in order to use this API.
+    ///
+    /// [`negotiate_transport`]: struct.SecureSession.html#method.negotiate_transport
+    /// [`receive`]: struct.SecureSession.html#method.receive
+    /// [`SessionTransportError`]: ../enum.ErrorKind.html#variant.SessionTransportError
+    fn receive_data(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        Err(not_implemented("receive_data"))
+    }
+
+    /// Notification about connection state of Secure Session.
+    ///
+    /// This method is truly optional and has no effect on Secure Session operation.
+    fn state_changed(&mut self, state: SecureSessionState) {}
+
+    /// Get a public key corresponding to a peer ID.
+    ///
+    /// Return `None` if you are unable to find a corresponding public key.
+    fn get_public_key_for_id(&mut self, id: &[u8]) -> Option<EcdsaPublicKey>;
+}
+
+impl<T: SecureSessionTransport + ?Sized> SecureSessionTransport for Box<T> {
+    fn send_data(&mut self, data: &[u8]) -> io::Result<usize> {
+        (**self).send_data(data)
+    }
+
+    fn receive_data(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        (**self).receive_data(data)
+    }
+
+    fn state_changed(&mut self, state: SecureSessionState) {
+        (**self).state_changed(state)
+    }
+
+    fn get_public_key_for_id(&mut self, id: &[u8]) -> Option<EcdsaPublicKey> {
+        (**self).get_public_key_for_id(id)
+    }
+}
+
+// Shared transports are borrowed only for the duration of a single callback. Make sure that
+// you do not hold a borrow (or a lock) while calling Secure Session methods which may invoke
+// the transport, otherwise the callback will panic (or deadlock).
+
+impl<T: SecureSessionTransport + ?Sized> SecureSessionTransport for Rc<RefCell<T>> {
+    fn send_data(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.borrow_mut().send_data(data)
+    }
+
+    fn receive_data(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        self.borrow_mut().receive_data(data)
+    }
+
+    fn state_changed(&mut self, state: SecureSessionState) {
+        self.borrow_mut().state_changed(state)
+    }
+
+    fn get_public_key_for_id(&mut self, id: &[u8]) -> Option<EcdsaPublicKey> {
+        self.borrow_mut().get_public_key_for_id(id)
+    }
+}
+
+// A poisoned mutex means that some other thread has panicked while using the transport.
+// Its state is questionable so we report failures to Themis instead of propagating panics.
+
+impl<T: SecureSessionTransport + ?Sized> SecureSessionTransport for Arc<Mutex<T>> {
+    fn send_data(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.lock().map_err(|_| poisoned())?.send_data(data)
+    }
+
+    fn receive_data(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        self.lock().map_err(|_| poisoned())?.receive_data(data)
+    }
+
+    fn state_changed(&mut self, state: SecureSessionState) {
+        if let Ok(mut transport) = self.lock() {
+            transport.state_changed(state)
+        }
+    }
+
+    fn get_public_key_for_id(&mut self, id: &[u8]) -> Option<EcdsaPublicKey> {
+        self.lock().ok()?.get_public_key_for_id(id)
+    }
+}
+
+fn not_implemented(method: &str) -> io::Error {
+    let message = format!("{}() is not implemented by transport", method);
+    io::Error::new(io::ErrorKind::NotConnected, message)
+}
+
+fn poisoned() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "transport mutex is poisoned")
+}
+
+fn too_large(message: &[u8], buffer: &[u8]) -> io::Error {
+    let message = format!(
+        "received message is too large: {} bytes, expected at most {}",
+        message.len(),
+        buffer.len()
+    );
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Static mapping of peer IDs onto their public keys.
+///
+/// This is a ready-made implementation of [`get_public_key_for_id`] for the common case when
+/// all peers are known beforehand. You can use `StaticKeyResolver` as a transport on its own
+/// (if you use only [`negotiate`] and [`unwrap`] methods), or delegate to it from your transport.
+///
+/// By default the resolver operates in allow-list mode: peers with unknown IDs are rejected.
+/// You can set a [default key] to be used for all unknown IDs instead.
+///
+/// # Examples
+///
+/// ```
+/// use themis::keygen::gen_ec_key_pair;
+/// use themis::secure_session::StaticKeyResolver;
+///
+/// let (_, public_alice) = gen_ec_key_pair().split();
+/// let (_, public_bob) = gen_ec_key_pair().split();
+///
+/// let resolver: StaticKeyResolver = vec![("alice", public_alice), ("bob", public_bob)]
+///     .into_iter()
+///     .collect();
+///
+/// assert!(resolver.resolve(b"alice").is_some());
+/// assert!(resolver.resolve(b"mallory").is_none());
+/// ```
+///
+/// [`get_public_key_for_id`]: trait.SecureSessionTransport.html#tymethod.get_public_key_for_id
+/// [`negotiate`]: struct.SecureSession.html#method.negotiate
+/// [`unwrap`]: struct.SecureSession.html#method.unwrap
+/// [default key]: struct.StaticKeyResolver.html#method.set_default_key
+#[derive(Clone, Default)]
+pub struct StaticKeyResolver {
+    keys: HashMap<Vec<u8>, EcdsaPublicKey>,
+    default_key: Option<EcdsaPublicKey>,
+}
+
+impl StaticKeyResolver {
+    /// Makes a new empty resolver which rejects all IDs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a public key for the given peer ID.
+    ///
+    /// Previously added key for the same ID is replaced.
+    pub fn insert<I: AsRef<[u8]>>(&mut self, id: I, key: EcdsaPublicKey) {
+        self.keys.insert(id.as_ref().to_vec(), key);
+    }
+
+    /// Removes a public key of the given peer ID, returns the key if it was known.
+    pub fn remove<I: AsRef<[u8]>>(&mut self, id: I) -> Option<EcdsaPublicKey> {
+        self.keys.remove(id.as_ref())
+    }
+
+    /// Sets a public key used for peer IDs without a key of their own.
+    ///
+    /// Set it to `None` (the default) in order to reject unknown IDs.
+    pub fn set_default_key(&mut self, key: Option<EcdsaPublicKey>) {
+        self.default_key = key;
+    }
+
+    /// Returns a public key for the given peer ID, or `None` if the ID is not allowed.
+    pub fn resolve<I: AsRef<[u8]>>(&self, id: I) -> Option<EcdsaPublicKey> {
+        self.keys
+            .get(id.as_ref())
+            .or(self.default_key.as_ref())
+            .cloned()
+    }
+}
+
+impl From<HashMap<Vec<u8>, EcdsaPublicKey>> for StaticKeyResolver {
+    fn from(keys: HashMap<Vec<u8>, EcdsaPublicKey>) -> Self {
+        Self {
+            keys,
+            default_key: None,
+        }
+    }
+}
+
+impl<I: AsRef<[u8]>> FromIterator<(I, EcdsaPublicKey)> for StaticKeyResolver {
+    fn from_iter<T: IntoIterator<Item = (I, EcdsaPublicKey)>>(iter: T) -> Self {
+        let mut resolver = Self::new();
+        for (id, key) in iter {
+            resolver.insert(id, key);
+        }
+        resolver
+    }
+}
+
+impl SecureSessionTransport for StaticKeyResolver {
+    fn get_public_key_for_id(&mut self, id: &[u8]) -> Option<EcdsaPublicKey> {
+        self.resolve(id)
+    }
+}
+
+/// In-memory transport for Secure Session.
+///
+/// Channel transports are created in connected pairs by [`channel_pair`]. Whatever is sent
+/// by one transport of the pair is received by the other one. This is useful for testing
+/// and for communication between threads of the same process.
+///
+/// Public keys of the peers need to be registered with [`add_public_key`] before use.
+///
+/// [`channel_pair`]: fn.channel_pair.html
+/// [`add_public_key`]: struct.ChannelTransport.html#method.add_public_key
+pub struct ChannelTransport {
+    tx: Sender<Vec<u8>>,
+    rx: Receiver<Vec<u8>>,
+    public_keys: StaticKeyResolver,
+}
+
+/// Creates a pair of connected in-memory transports.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), themis::Error> {
+/// use themis::keygen::gen_ec_key_pair;
+/// use themis::secure_session::{channel_pair, SecureSession};
+///
+/// let (secret_client, public_client) = gen_ec_key_pair().split();
+/// let (secret_server, public_server) = gen_ec_key_pair().split();
+///
+/// let (mut transport_client, mut transport_server) = channel_pair();
+/// transport_client.add_public_key("server", public_server);
+/// transport_server.add_public_key("client", public_client);
+///
+/// let mut client = SecureSession::with_transport("client", &secret_client, transport_client)?;
+/// let mut server = SecureSession::with_transport("server", &secret_server, transport_server)?;
+///
+/// client.connect()?;
+/// while !client.is_established() {
+///     server.negotiate_transport()?;
+///     client.negotiate_transport()?;
+/// }
+///
+/// client.send(b"test message please ignore")?;
+/// assert_eq!(server.receive(1024)?, b"test message please ignore");
+/// # Ok(())
+/// # }
+/// ```
+pub fn channel_pair() -> (ChannelTransport, ChannelTransport) {
+    let (tx12, rx21) = channel();
+    let (tx21, rx12) = channel();
+
+    let transport1 = ChannelTransport {
+        tx: tx12,
+        rx: rx12,
+        public_keys: StaticKeyResolver::new(),
+    };
+    let transport2 = ChannelTransport {
+        tx: tx21,
+        rx: rx21,
+        public_keys: StaticKeyResolver::new(),
+    };
+
+    (transport1, transport2)
+}
+
+impl ChannelTransport {
+    /// Registers a public key of the peer with given ID.
+    ///
+    /// Previously registered key for the same ID is replaced.
+    pub fn add_public_key<I: AsRef<[u8]>>(&mut self, id: I, key: EcdsaPublicKey) {
+        self.public_keys.insert(id, key);
+    }
+}
+
+impl SecureSessionTransport for ChannelTransport {
+    fn send_data(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.tx
+            .send(data.to_vec())
+            .map(|_| data.len())
+            .map_err(|_| {
+                io::Error::new(io::ErrorKind::BrokenPipe, "peer transport has been dropped")
+            })
+    }
+
+    fn receive_data(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        let message = self.rx.recv().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "peer transport has been dropped",
+            )
+        })?;
+        if message.len() > data.len() {
+            return Err(too_large(&message, data));
+        }
+        data[0..message.len()].copy_from_slice(&message);
+        Ok(message.len())
+    }
+
+    fn get_public_key_for_id(&mut self, id: &[u8]) -> Option<EcdsaPublicKey> {
+        self.public_keys.resolve(id)
+    }
+}
+
+/// Size of the frame header written by [`write_frame`].
+///
+/// [`write_frame`]: fn.write_frame.html
+pub const FRAME_HEADER_SIZE: usize = 4;
+
+/// Maximum length of a message accepted by [`write_frame`] and [`read_frame`].
+///
+/// [`write_frame`]: fn.write_frame.html
+/// [`read_frame`]: fn.read_frame.html
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Writes a message into a byte stream, prefixed with its length.
+///
+/// Wrapped messages are not self-delimiting so you need to frame them if you transfer them
+/// over a byte stream like TCP. This function prefixes the message with its length serialized
+/// as a 4-byte little-endian integer. Use [`read_frame`] on the other side to read it back.
+///
+/// Messages longer than [`MAX_FRAME_SIZE`] are rejected with `InvalidInput` error.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> std::io::Result<()> {
+/// use themis::secure_session::{read_frame, write_frame};
+///
+/// let mut stream = Vec::new();
+/// write_frame(&mut stream, b"first")?;
+/// write_frame(&mut stream, b"second")?;
+///
+/// let mut stream = &stream[..];
+/// assert_eq!(read_frame(&mut stream)?, b"first");
+/// assert_eq!(read_frame(&mut stream)?, b"second");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`read_frame`]: fn.read_frame.html
+/// [`MAX_FRAME_SIZE`]: constant.MAX_FRAME_SIZE.html
+pub fn write_frame<W: Write + ?Sized>(writer: &mut W, message: &[u8]) -> io::Result<()> {
+    let header = frame_header(message)?;
+    writer.write_all(&header)?;
+    writer.write_all(message)
+}
+
+/// Reads a message written by [`write_frame`] from a byte stream.
+///
+/// Frames longer than [`MAX_FRAME_SIZE`] are rejected with `InvalidData` error. If the stream
+/// ends in the middle of a frame then `UnexpectedEof` error is returned.
+///
+/// [`write_frame`]: fn.write_frame.html
+/// [`MAX_FRAME_SIZE`]: constant.MAX_FRAME_SIZE.html
+pub fn read_frame<R: Read + ?Sized>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut header = [0; FRAME_HEADER_SIZE];
+    reader.read_exact(&mut header)?;
+    let mut message = vec![0; frame_length(&header)?];
+    reader.read_exact(&mut message)?;
+    Ok(message)
+}
+
+/// Makes a frame header for the message.
+fn frame_header(message: &[u8]) -> io::Result<[u8; FRAME_HEADER_SIZE]> {
+    if message.len() > MAX_FRAME_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "message too long for a frame",
+        ));
+    }
+    let len = message.len() as u32;
+    Ok([
+        len as u8,
+        (len >> 8) as u8,
+        (len >> 16) as u8,
+        (len >> 24) as u8,
+    ])
+}
+
+/// Parses a frame header, returns the length of the message.
+fn frame_length(header: &[u8; FRAME_HEADER_SIZE]) -> io::Result<usize> {
+    let len = (header[0] as usize)
+        | (header[1] as usize) << 8
+        | (header[2] as usize) << 16
+        | (header[3] as usize) << 24;
+    if len > MAX_FRAME_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame length exceeds limit",
+        ));
+    }
+    Ok(len)
+}
+
+/// Secure Session transport over a byte stream.
+///
+/// This transport sends and receives messages via any `Read + Write` stream, such as a TCP
+/// socket. Messages are framed with [`write_frame`] and [`read_frame`]. Public keys of
+/// the peers are looked up in the provided [`StaticKeyResolver`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::net::TcpStream;
+///
+/// use themis::keygen::gen_ec_key_pair;
+/// use themis::secure_session::{SecureSession, StaticKeyResolver, TcpTransport};
+///
+/// let (secret_key, _) = gen_ec_key_pair().split();
+/// # let (_, server_public_key) = gen_ec_key_pair().split();
+///
+/// let mut keys = StaticKeyResolver::new();
+/// keys.insert("server", server_public_key);
+///
+/// let stream = TcpStream::connect("localhost:7575")?;
+/// let transport = TcpTransport::new(stream, keys);
+///
+/// let mut session = SecureSession::with_transport("client", &secret_key, transport)?;
+/// session.connect()?;
+/// while !session.is_established() {
+///     session.negotiate_transport()?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`write_frame`]: fn.write_frame.html
+/// [`read_frame`]: fn.read_frame.html
+/// [`StaticKeyResolver`]: struct.StaticKeyResolver.html
+pub struct StreamTransport<S> {
+    stream: S,
+    keys: StaticKeyResolver,
+}
+
+/// Secure Session transport over TCP.
+pub type TcpTransport = StreamTransport<TcpStream>;
+
+/// Secure Session transport over Unix domain sockets.
+#[cfg(unix)]
+pub type UnixTransport = StreamTransport<UnixStream>;
+
+impl<S> StreamTransport<S> {
+    /// Makes a new transport over the given stream.
+    pub fn new(stream: S, keys: StaticKeyResolver) -> Self {
+        Self { stream, keys }
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    ///
+    /// Reading or writing the stream directly will likely break the framing.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Returns a mutable reference to the public key resolver.
+    pub fn keys_mut(&mut self) -> &mut StaticKeyResolver {
+        &mut self.keys
+    }
+
+    /// Unwraps this transport, returning the underlying stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Read + Write> SecureSessionTransport for StreamTransport<S> {
+    fn send_data(&mut self, data: &[u8]) -> io::Result<usize> {
+        write_frame(&mut self.stream, data)?;
+        self.stream.flush()?;
+        Ok(data.len())
+    }
+
+    fn receive_data(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        let message = read_frame(&mut self.stream)?;
+        if message.len() > data.len() {
+            return Err(too_large(&message, data));
+        }
+        data[0..message.len()].copy_from_slice(&message);
+        Ok(message.len())
+    }
+
+    fn get_public_key_for_id(&mut self, id: &[u8]) -> Option<EcdsaPublicKey> {
+        self.keys.resolve(id)
+    }
+}
+
+/// Secure Session transport over WebSocket.
+///
+/// This transport sends and receives Secure Session messages as binary WebSocket messages.
+/// Control messages are handled by the WebSocket automatically, text messages are considered
+/// an error. Public keys of the peers are looked up in the provided [`StaticKeyResolver`].
+///
+/// This transport is available with `tungstenite` crate feature.
+///
+/// [`StaticKeyResolver`]: struct.StaticKeyResolver.html
+#[cfg(feature = "tungstenite")]
+pub struct WebSocketTransport<S> {
+    socket: tungstenite::WebSocket<S>,
+    keys: StaticKeyResolver,
+}
+
+#[cfg(feature = "tungstenite")]
+impl<S> WebSocketTransport<S> {
+    /// Makes a new transport over an established WebSocket connection.
+    pub fn new(socket: tungstenite::WebSocket<S>, keys: StaticKeyResolver) -> Self {
+        Self { socket, keys }
+    }
+
+    /// Returns a reference to the underlying WebSocket.
+    pub fn get_ref(&self) -> &tungstenite::WebSocket<S> {
+        &self.socket
+    }
+
+    /// Returns a mutable reference to the underlying WebSocket.
+    pub fn get_mut(&mut self) -> &mut tungstenite::WebSocket<S> {
+        &mut self.socket
+    }
+
+    /// Returns a mutable reference to the public key resolver.
+    pub fn keys_mut(&mut self) -> &mut StaticKeyResolver {
+        &mut self.keys
+    }
+
+    /// Unwraps this transport, returning the underlying WebSocket.
+    pub fn into_inner(self) -> tungstenite::WebSocket<S> {
+        self.socket
+    }
+}
+
+#[cfg(feature = "tungstenite")]
+impl<S: Read + Write> SecureSessionTransport for WebSocketTransport<S> {
+    fn send_data(&mut self, data: &[u8]) -> io::Result<usize> {
+        let message = tungstenite::Message::Binary(data.to_vec());
+        self.socket.send(message).map_err(websocket_error)?;
+        Ok(data.len())
+    }
+
+    fn receive_data(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.socket.read().map_err(websocket_error)? {
+                tungstenite::Message::Binary(message) => {
+                    if message.len() > data.len() {
+                        return Err(too_large(&message, data));
+                    }
+                    data[0..message.len()].copy_from_slice(&message);
+                    return Ok(message.len());
+                }
+                tungstenite::Message::Ping(_) | tungstenite::Message::Pong(_) => continue,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "unexpected WebSocket message",
+                    ))
+                }
+            }
+        }
+    }
+
+    fn get_public_key_for_id(&mut self, id: &[u8]) -> Option<EcdsaPublicKey> {
+        self.keys.resolve(id)
+    }
+}
+
+#[cfg(feature = "tungstenite")]
+fn websocket_error(error: tungstenite::Error) -> io::Error {
+    match error {
+        tungstenite::Error::Io(error) => error,
+        tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => {
+            io::Error::new(io::ErrorKind::ConnectionAborted, error)
+        }
+        error => io::Error::new(io::ErrorKind::InvalidData, error),
+    }
+}
+
+// Stream adapters send application data in records. Each record is a single Secure Session
+// message framed like with `write_frame()`. The first byte of the plaintext is the record
+// type, it is followed by the record payload. Control records have empty payload.
+const RECORD_DATA: u8 = 0;
+const RECORD_CLOSE: u8 = 1;
+const RECORD_KEEP_ALIVE: u8 = 2;
+
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
+fn malformed_record() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "malformed Secure Session record",
+    )
+}
+
+fn stream_shut_down() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "stream has been shut down")
+}
+
+fn peer_timed_out() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::TimedOut,
+        "peer has not sent anything in time",
+    )
+}
+
+fn lock_session<'a, T>(
+    session: &'a Mutex<StreamSession<T>>,
+) -> io::Result<MutexGuard<'a, StreamSession<T>>> {
+    session
+        .lock()
+        .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "session mutex is poisoned"))
+}
+
+// Secure Session of a stream adapter, along with the state of the connection.
+struct StreamSession<T> {
+    session: SecureSession<T>,
+    peer_closed: bool,
+    last_received: Instant,
+}
+
+impl<T> StreamSession<T> {
+    fn new(session: SecureSession<T>) -> Self {
+        Self {
+            session,
+            peer_closed: false,
+            last_received: Instant::now(),
+        }
+    }
+}
+
+impl<T: SecureSessionTransport> StreamSession<T> {
+    fn seal(&mut self, kind: u8, payload: &[u8], output: &mut Vec<u8>) -> io::Result<()> {
+        let mut record = Vec::with_capacity(1 + payload.len());
+        record.push(kind);
+        record.extend_from_slice(payload);
+        let wrapped = self.session.wrap(&record)?;
+        output.extend_from_slice(&frame_header(&wrapped)?);
+        output.extend_from_slice(&wrapped);
+        Ok(())
+    }
+}
+
+// Bytes received from the stream which do not make a complete frame yet.
+//
+// Stream adapters keep partially received frames here, so reading can be resumed after
+// an error like `WouldBlock` or `TimedOut`, or after a future has been cancelled.
+struct StreamReader {
+    buffer: Vec<u8>,
+}
+
+impl StreamReader {
+    fn new() -> Self {
+        Self { buffer: Vec::new() }
+    }
+
+    fn next_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.buffer.len() < FRAME_HEADER_SIZE {
+            return Ok(None);
+        }
+        let mut header = [0; FRAME_HEADER_SIZE];
+        header.copy_from_slice(&self.buffer[..FRAME_HEADER_SIZE]);
+        let end = FRAME_HEADER_SIZE + frame_length(&header)?;
+        if self.buffer.len() < end {
+            return Ok(None);
+        }
+        let frame = self.buffer[FRAME_HEADER_SIZE..end].to_vec();
+        self.buffer.drain(..end);
+        Ok(Some(frame))
+    }
+
+    // Returns `Some(Some(message))` for application data, `Some(None)` if the peer has
+    // closed the connection, and `None` if more data needs to be read from the stream.
+    fn next_message<T: SecureSessionTransport>(
+        &mut self,
+        session: &mut StreamSession<T>,
+    ) -> io::Result<Option<Option<Vec<u8>>>> {
+        loop {
+            if session.peer_closed {
+                return Ok(Some(None));
+            }
+            let frame = match self.next_frame()? {
+                Some(frame) => frame,
+                None => return Ok(None),
+            };
+            session.last_received = Instant::now();
+            let mut record = session.session.unwrap(&frame)?;
+            match (record.first().cloned(), record.len()) {
+                (Some(RECORD_DATA), _) => {
+                    record.remove(0);
+                    return Ok(Some(Some(record)));
+                }
+                (Some(RECORD_CLOSE), 1) => session.peer_closed = true,
+                (Some(RECORD_KEEP_ALIVE), 1) => continue,
+                _ => return Err(malformed_record()),
+            }
+        }
+    }
+
+    fn fill<R: Read + ?Sized>(&mut self, stream: &mut R) -> io::Result<()> {
+        let mut chunk = [0; READ_CHUNK_SIZE];
+        match stream.read(&mut chunk) {
+            Ok(0) => Err(self.unexpected_eof()),
+            Ok(n) => {
+                self.buffer.extend_from_slice(&chunk[..n]);
+                Ok(())
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg(feature = "futures")]
+    async fn fill_async<R: AsyncRead + Unpin + ?Sized>(
+        &mut self,
+        stream: &mut R,
+    ) -> io::Result<()> {
+        let mut chunk = [0; READ_CHUNK_SIZE];
+        match stream.read(&mut chunk).await {
+            Ok(0) => Err(self.unexpected_eof()),
+            Ok(n) => {
+                self.buffer.extend_from_slice(&chunk[..n]);
+                Ok(())
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Peers always announce that they are closing the connection. If the stream ends
+    // without that then someone may have cut the connection short, truncating the data.
+    fn unexpected_eof(&self) -> io::Error {
+        let message = if self.buffer.is_empty() {
+            "connection closed without close notification"
+        } else {
+            "connection closed in the middle of a frame"
+        };
+        io::Error::new(io::ErrorKind::UnexpectedEof, message)
+    }
+}
+
+// Bytes which have not been written into the stream yet.
+//
+// Stream adapters keep unwritten data here, so writing can be resumed after an error like
+// `WouldBlock` or `TimedOut`, or after a future has been cancelled. With coalescing enabled
+// (non-zero `capacity`) messages are accumulated here too, until there is enough of them.
+struct StreamWriter {
+    buffer: Vec<u8>,
+    closed: bool,
+    last_sent: Instant,
+    keep_alive: Option<Duration>,
+    peer_timeout: Option<Duration>,
+    capacity: usize,
+    delay: Option<Duration>,
+    pending_since: Option<Instant>,
+}
+
+impl StreamWriter {
+    fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            closed: false,
+            last_sent: Instant::now(),
+            keep_alive: None,
+            peer_timeout: None,
+            capacity: 0,
+            delay: None,
+            pending_since: None,
+        }
+    }
+
+    fn is_due(&self) -> bool {
+        if self.capacity == 0 || self.buffer.len() >= self.capacity {
+            return true;
+        }
+        match (self.pending_since, self.delay) {
+            (Some(since), Some(delay)) => since.elapsed() >= delay,
+            _ => false,
+        }
+    }
+
+    fn push_frame(&mut self, message: &[u8]) -> io::Result<()> {
+        self.buffer.extend_from_slice(&frame_header(message)?);
+        self.buffer.extend_from_slice(message);
+        Ok(())
+    }
+
+    fn push_data<T: SecureSessionTransport>(
+        &mut self,
+        session: &mut StreamSession<T>,
+        message: &[u8],
+    ) -> io::Result<()> {
+        if self.closed {
+            return Err(stream_shut_down());
+        }
+        if self.pending_since.is_none() {
+            self.pending_since = Some(Instant::now());
+        }
+        session.seal(RECORD_DATA, message, &mut self.buffer)
+    }
+
+    fn push_close<T: SecureSessionTransport>(
+        &mut self,
+        session: &mut StreamSession<T>,
+    ) -> io::Result<()> {
+        if !self.closed {
+            session.seal(RECORD_CLOSE, &[], &mut self.buffer)?;
+            self.closed = true;
+        }
+        Ok(())
+    }
+
+    // Returns true if a keep-alive message needs to be sent right away.
+    fn push_keep_alive<T: SecureSessionTransport>(
+        &mut self,
+        session: &mut StreamSession<T>,
+    ) -> io::Result<bool> {
+        if !session.session.is_established() {
+            return Ok(false);
+        }
+        if let Some(timeout) = self.peer_timeout {
+            if !session.peer_closed && session.last_received.elapsed() >= timeout {
+                return Err(peer_timed_out());
+            }
+        }
+        if let Some(interval) = self.keep_alive {
+            let idle = self.buffer.is_empty() && self.last_sent.elapsed() >= interval;
+            if !self.closed && idle {
+                session.seal(RECORD_KEEP_ALIVE, &[], &mut self.buffer)?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn flush<W: Write + ?Sized>(&mut self, stream: &mut W) -> io::Result<()> {
+        while !self.buffer.is_empty() {
+            match stream.write(&self.buffer) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.buffer.drain(..n);
+                    self.last_sent = Instant::now();
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.pending_since = None;
+        stream.flush()
+    }
+
+    #[cfg(feature = "futures")]
+    async fn flush_async<W: AsyncWrite + Unpin + ?Sized>(
+        &mut self,
+        stream: &mut W,
+    ) -> io::Result<()> {
+        while !self.buffer.is_empty() {
+            match stream.write(&self.buffer).await {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.buffer.drain(..n);
+                    self.last_sent = Instant::now();
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.pending_since = None;
+        stream.flush().await
+    }
+}
+
+/// Secure Session over a blocking byte stream.
+///
+/// This adapter runs Secure Session over any stream implementing `Read` and `Write`, like
+/// `TcpStream` or `UnixStream`. It completes the negotiation for you and sends messages in
+/// records, providing an authenticated close of the connection:
+///
+///   - [`shutdown`] tells the peer that no more messages will be sent;
+///   - [`receive`] returns `None` once the peer has shut down its side of the connection;
+///   - if the stream ends without a shutdown then [`receive`] returns an `UnexpectedEof`
+///     error. Someone might have cut the connection short, the data may be truncated.
+///
+/// Dropping the adapter does not shut down the connection: call [`shutdown`] explicitly
+/// once you are done sending.
+///
+/// Dead peers can be detected with keep-alive messages, see [`heartbeat`]. Small messages
+/// can be coalesced into larger writes, see [`set_write_buffer`].
+///
+/// Records are carried by Secure Session messages framed like with [`write_frame`], but
+/// the adapter cannot talk to peers using plain [`read_frame`] and [`StreamTransport`].
+/// Use this adapter or [`AsyncSecureSessionStream`] on both sides. The Secure Session
+/// uses only [`get_public_key_for_id`] method of its transport, a [`StaticKeyResolver`]
+/// is enough.
+///
+/// Incomplete reads and writes are buffered by the adapter. If an operation fails with
+/// a transient error like `WouldBlock` or `TimedOut` (say, the stream has a read timeout)
+/// then it can be safely retried.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> {
+/// use std::net::TcpStream;
+///
+/// use themis::keygen::gen_ec_key_pair;
+/// use themis::secure_session::{SecureSession, SecureSessionStream, StaticKeyResolver};
+///
+/// let (secret_key, _) = gen_ec_key_pair().split();
+/// # let (_, server_public_key) = gen_ec_key_pair().split();
+///
+/// let mut keys = StaticKeyResolver::new();
+/// keys.insert("server", server_public_key);
+///
+/// let session = SecureSession::with_transport("client", &secret_key, keys)?;
+/// let stream = TcpStream::connect("localhost:7575")?;
+/// let mut session = SecureSessionStream::new(session, stream);
+///
+/// session.connect()?;
+/// session.send(b"hello")?;
+/// session.shutdown()?;
+///
+/// while let Some(reply) = session.receive()? {
+///     println!("{}", String::from_utf8_lossy(&reply));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`shutdown`]: struct.SecureSessionStream.html#method.shutdown
+/// [`receive`]: struct.SecureSessionStream.html#method.receive
+/// [`heartbeat`]: struct.SecureSessionStream.html#method.heartbeat
+/// [`set_write_buffer`]: struct.SecureSessionStream.html#method.set_write_buffer
+/// [`write_frame`]: fn.write_frame.html
+/// [`read_frame`]: fn.read_frame.html
+/// [`StreamTransport`]: struct.StreamTransport.html
+/// [`AsyncSecureSessionStream`]: struct.AsyncSecureSessionStream.html
+/// [`get_public_key_for_id`]: trait.SecureSessionTransport.html#tymethod.get_public_key_for_id
+/// [`StaticKeyResolver`]: struct.StaticKeyResolver.html
+pub struct SecureSessionStream<S, T> {
+    session: StreamSession<T>,
+    stream: S,
+    reader: StreamReader,
+    writer: StreamWriter,
+}
+
+impl<S, T> SecureSessionStream<S, T>
+where
+    S: Read + Write,
+    T: SecureSessionTransport,
+{
+    /// Makes a new adapter for the session over the given stream.
+    pub fn new(session: SecureSession<T>, stream: S) -> Self {
+        Self {
+            session: StreamSession::new(session),
+            stream,
+            reader: StreamReader::new(),
+            writer: StreamWriter::new(),
+        }
+    }
+
+    /// Establishes connection with the server.
+    ///
+    /// This is the method for the client. It completes the whole negotiation.
+    pub fn connect(&mut self) -> io::Result<()> {
+        let request = self.session.session.generate_connect_request()?;
+        self.writer.push_frame(&request)?;
+        self.negotiate()
+    }
+
+    /// Accepts connection from the client.
+    ///
+    /// This is the method for the server. It completes the whole negotiation.
+    pub fn accept(&mut self) -> io::Result<()> {
+        self.negotiate()
+    }
+
+    fn negotiate(&mut self) -> io::Result<()> {
+        self.writer.flush(&mut self.stream)?;
+        while !self.session.session.is_established() {
+            match self.reader.next_frame()? {
+                Some(request) => {
+                    let reply = self.session.session.negotiate(&request)?;
+                    if !reply.is_empty() {
+                        self.writer.push_frame(&reply)?;
+                        self.writer.flush(&mut self.stream)?;
+                    }
+                }
+                None => self.reader.fill(&mut self.stream)?,
+            }
+        }
+        self.session.last_received = Instant::now();
+        Ok(())
+    }
+
+    /// Sends a message to the peer.
+    ///
+    /// This method will fail if a secure connection has not been established yet,
+    /// or if the stream has been shut down.
+    pub fn send<M: AsRef<[u8]>>(&mut self, message: M) -> io::Result<()> {
+        self.writer.push_data(&mut self.session, message.as_ref())?;
+        if self.writer.is_due() {
+            self.writer.flush(&mut self.stream)?;
+        }
+        Ok(())
+    }
+
+    /// Writes out all buffered data.
+    ///
+    /// Call this method to send messages held by [coalescing] without waiting.
+    /// If sending has failed with a transient error, call this method to retry.
+    ///
+    /// [coalescing]: struct.SecureSessionStream.html#method.set_write_buffer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush(&mut self.stream)
+    }
+
+    /// Receives a message from the peer.
+    ///
+    /// Returns `None` if the peer has shut down the connection. Returns an `UnexpectedEof`
+    /// error if the stream has ended without that.
+    ///
+    /// This method will fail if a secure connection has not been established yet.
+    pub fn receive(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            if let Some(message) = self.reader.next_message(&mut self.session)? {
+                return Ok(message);
+            }
+            self.reader.fill(&mut self.stream)?;
+        }
+    }
+
+    /// Shuts down the outgoing side of the connection.
+    ///
+    /// The peer is notified that no more messages will be sent, this makes the peer's
+    /// [`receive`] return `None`. You can still receive messages from the peer after
+    /// shutdown, but you can no longer send them. Calling this method again does nothing.
+    ///
+    /// The underlying stream is not closed. For example, call [`TcpStream::shutdown`]
+    /// on the stream afterwards if needed.
+    ///
+    /// [`receive`]: struct.SecureSessionStream.html#method.receive
+    /// [`TcpStream::shutdown`]: https://doc.rust-lang.org/std/net/struct.TcpStream.html#method.shutdown
+    pub fn shutdown(&mut self) -> io::Result<()> {
+        self.writer.push_close(&mut self.session)?;
+        self.writer.flush(&mut self.stream)
+    }
+
+    /// Sends keep-alive messages to the peer and checks that the peer is alive.
+    ///
+    /// Call this method periodically, for example when [`receive`] fails with `WouldBlock`
+    /// or `TimedOut` because of a read timeout on the stream. It sends a keep-alive message
+    /// if nothing has been sent for the [keep-alive interval], and returns a `TimedOut` error
+    /// if nothing has been received from the peer within the [peer timeout].
+    ///
+    /// Keep-alive messages are consumed by [`receive`] and never returned from it.
+    /// This method also writes out coalesced messages which have waited long enough.
+    ///
+    /// [`receive`]: struct.SecureSessionStream.html#method.receive
+    /// [keep-alive interval]: struct.SecureSessionStream.html#method.set_keep_alive
+    /// [peer timeout]: struct.SecureSessionStream.html#method.set_peer_timeout
+    pub fn heartbeat(&mut self) -> io::Result<()> {
+        if self.writer.push_keep_alive(&mut self.session)? || self.writer.is_due() {
+            self.writer.flush(&mut self.stream)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S, T> SecureSessionStream<S, T> {
+    /// Returns a reference to the Secure Session.
+    pub fn session(&self) -> &SecureSession<T> {
+        &self.session.session
+    }
+
+    /// Returns a mutable reference to the Secure Session.
+    pub fn session_mut(&mut self) -> &mut SecureSession<T> {
+        &mut self.session.session
+    }
+
+    /// Sets interval of keep-alive messages sent by [`heartbeat`].
+    ///
+    /// Keep-alive messages are not sent by default. Enable them on both sides of
+    /// the connection to detect dead peers with [`set_peer_timeout`].
+    ///
+    /// [`heartbeat`]: struct.SecureSessionStream.html#method.heartbeat
+    /// [`set_peer_timeout`]: struct.SecureSessionStream.html#method.set_peer_timeout
+    pub fn set_keep_alive(&mut self, interval: Option<Duration>) {
+        self.writer.keep_alive = interval;
+    }
+
+    /// Sets the time after which a silent peer is considered dead by [`heartbeat`].
+    ///
+    /// There is no timeout by default. The timeout should be longer than the keep-alive
+    /// interval used by the peer.
+    ///
+    /// [`heartbeat`]: struct.SecureSessionStream.html#method.heartbeat
+    pub fn set_peer_timeout(&mut self, timeout: Option<Duration>) {
+        self.writer.peer_timeout = timeout;
+    }
+
+    /// Enables coalescing of small messages into larger writes.
+    ///
+    /// With coalescing, [`send`] puts messages into a buffer and writes them into the stream
+    /// together, once there are at least `capacity` bytes of them, or once the oldest one
+    /// has been waiting for the [write delay]. Call [`flush`] to write out the buffer now.
+    /// [`shutdown`] writes out the buffer as well.
+    ///
+    /// Coalescing is disabled by default, use zero capacity to disable it.
+    ///
+    /// [`send`]: struct.SecureSessionStream.html#method.send
+    /// [write delay]: struct.SecureSessionStream.html#method.set_write_delay
+    /// [`flush`]: struct.SecureSessionStream.html#method.flush
+    /// [`shutdown`]: struct.SecureSessionStream.html#method.shutdown
+    pub fn set_write_buffer(&mut self, capacity: usize) {
+        self.writer.capacity = capacity;
+    }
+
+    /// Sets how long messages may wait in the coalescing buffer.
+    ///
+    /// There is no timer: the delay is checked by [`send`] and [`heartbeat`]. If the delay
+    /// is not set then messages wait until the buffer fills up or is flushed explicitly.
+    ///
+    /// [`send`]: struct.SecureSessionStream.html#method.send
+    /// [`heartbeat`]: struct.SecureSessionStream.html#method.heartbeat
+    pub fn set_write_delay(&mut self, delay: Option<Duration>) {
+        self.writer.delay = delay;
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    ///
+    /// Reading or writing the stream directly will likely break the framing.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Unwraps this adapter, returning the session and the stream.
+    ///
+    /// Any buffered data which has not been written or processed yet is lost.
+    pub fn into_inner(self) -> (SecureSession<T>, S) {
+        (self.session.session, self.stream)
+    }
+}
+
+impl<S, T> SecureSessionStream<S, T>
+where
+    for<'a> &'a S: Read + Write,
+    T: SecureSessionTransport,
+{
+    /// Splits this adapter into independent halves for receiving and sending.
+    ///
+    /// This is useful for receiving and sending from different threads. The halves share
+    /// the Secure Session, but the stream is not locked while a half is waiting for I/O,
+    /// so receiving does not block sending. The stream needs to support reading and writing
+    /// via a shared reference, like `TcpStream` and `UnixStream` do.
+    ///
+    /// Split the adapter after the connection has been established.
+    pub fn into_split(self) -> (SecureSessionReadHalf<S, T>, SecureSessionWriteHalf<S, T>) {
+        let session = Arc::new(Mutex::new(self.session));
+        let stream = Arc::new(self.stream);
+        let read_half = SecureSessionReadHalf {
+            session: session.clone(),
+            stream: stream.clone(),
+            reader: self.reader,
+        };
+        let write_half = SecureSessionWriteHalf {
+            session,
+            stream,
+            writer: self.writer,
+        };
+        (read_half, write_half)
+    }
+}
+
+/// Receiving half of a [`SecureSessionStream`].
+///
+/// Created by [`into_split`] method.
+///
+/// [`SecureSessionStream`]: struct.SecureSessionStream.html
+/// [`into_split`]: struct.SecureSessionStream.html#method.into_split
+pub struct SecureSessionReadHalf<S, T> {
+    session: Arc<Mutex<StreamSession<T>>>,
+    stream: Arc<S>,
+    reader: StreamReader,
+}
+
+impl<S, T> SecureSessionReadHalf<S, T>
+where
+    for<'a> &'a S: Read,
+    T: SecureSessionTransport,
+{
+    /// Receives a message from the peer.
+    ///
+    /// See [`SecureSessionStream::receive`].
+    ///
+    /// [`SecureSessionStream::receive`]: struct.SecureSessionStream.html#method.receive
+    pub fn receive(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            let next = self
+                .reader
+                .next_message(&mut *lock_session(&self.session)?)?;
+            if let Some(message) = next {
+                return Ok(message);
+            }
+            self.reader.fill(&mut &*self.stream)?;
+        }
+    }
+}
+
+impl<S, T> SecureSessionReadHalf<S, T> {
+    /// Returns a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+}
+
+/// Sending half of a [`SecureSessionStream`].
+///
+/// Created by [`into_split`] method.
+///
+/// [`SecureSessionStream`]: struct.SecureSessionStream.html
+/// [`into_split`]: struct.SecureSessionStream.html#method.into_split
+pub struct SecureSessionWriteHalf<S, T> {
+    session: Arc<Mutex<StreamSession<T>>>,
+    stream: Arc<S>,
+    writer: StreamWriter,
+}
+
+impl<S, T> SecureSessionWriteHalf<S, T>
+where
+    for<'a> &'a S: Write,
+    T: SecureSessionTransport,
+{
+    /// Sends a message to the peer.
+    ///
+    /// See [`SecureSessionStream::send`].
+    ///
+    /// [`SecureSessionStream::send`]: struct.SecureSessionStream.html#method.send
+    pub fn send<M: AsRef<[u8]>>(&mut self, message: M) -> io::Result<()> {
+        self.writer
+            .push_data(&mut *lock_session(&self.session)?, message.as_ref())?;
+        if self.writer.is_due() {
+            self.writer.flush(&mut &*self.stream)?;
+        }
+        Ok(())
+    }
+
+    /// Writes out all buffered data.
+    ///
+    /// See [`SecureSessionStream::flush`].
+    ///
+    /// [`SecureSessionStream::flush`]: struct.SecureSessionStream.html#method.flush
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush(&mut &*self.stream)
+    }
+
+    /// Shuts down the outgoing side of the connection.
+    ///
+    /// See [`SecureSessionStream::shutdown`].
+    ///
+    /// [`SecureSessionStream::shutdown`]: struct.SecureSessionStream.html#method.shutdown
+    pub fn shutdown(&mut self) -> io::Result<()> {
+        self.writer.push_close(&mut *lock_session(&self.session)?)?;
+        self.writer.flush(&mut &*self.stream)
+    }
+
+    /// Sends keep-alive messages to the peer and checks that the peer is alive.
+    ///
+    /// See [`SecureSessionStream::heartbeat`]. The peer is considered alive as long as
+    /// the read half keeps receiving messages from it.
+    ///
+    /// [`SecureSessionStream::heartbeat`]: struct.SecureSessionStream.html#method.heartbeat
+    pub fn heartbeat(&mut self) -> io::Result<()> {
+        let keep_alive = self
+            .writer
+            .push_keep_alive(&mut *lock_session(&self.session)?)?;
+        if keep_alive || self.writer.is_due() {
+            self.writer.flush(&mut &*self.stream)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S, T> SecureSessionWriteHalf<S, T> {
+    /// Sets interval of keep-alive messages sent by [`heartbeat`].
+    ///
+    /// [`heartbeat`]: struct.SecureSessionWriteHalf.html#method.heartbeat
+    pub fn set_keep_alive(&mut self, interval: Option<Duration>) {
+        self.writer.keep_alive = interval;
+    }
+
+    /// Sets the time after which a silent peer is considered dead by [`heartbeat`].
+    ///
+    /// [`heartbeat`]: struct.SecureSessionWriteHalf.html#method.heartbeat
+    pub fn set_peer_timeout(&mut self, timeout: Option<Duration>) {
+        self.writer.peer_timeout = timeout;
+    }
+
+    /// Enables coalescing of small messages into larger writes.
+    ///
+    /// See [`SecureSessionStream::set_write_buffer`].
+    ///
+    /// [`SecureSessionStream::set_write_buffer`]: struct.SecureSessionStream.html#method.set_write_buffer
+    pub fn set_write_buffer(&mut self, capacity: usize) {
+        self.writer.capacity = capacity;
+    }
+
+    /// Sets how long messages may wait in the coalescing buffer.
+    ///
+    /// See [`SecureSessionStream::set_write_delay`].
+    ///
+    /// [`SecureSessionStream::set_write_delay`]: struct.SecureSessionStream.html#method.set_write_delay
+    pub fn set_write_delay(&mut self, delay: Option<Duration>) {
+        self.writer.delay = delay;
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+}
+
+/// Secure Session over an asynchronous byte stream.
+///
+/// This adapter runs Secure Session over any stream implementing `AsyncRead` and `AsyncWrite`
+/// traits of the `futures` crate, without tying you to a particular executor. Streams of
+/// async-std and smol implement these traits directly. Tokio streams can be adapted with
+/// `tokio_util::compat`:
+///
+/// ```ignore
+/// use tokio_util::compat::TokioAsyncReadCompatExt;
+///
+/// let stream = tokio::net::TcpStream::connect("localhost:7575").await?.compat();
+/// ```
+///
+/// This is an asynchronous counterpart of [`SecureSessionStream`], it uses the same records
+/// and provides an authenticated close of the connection with [`shutdown`], as well as
+/// keep-alive messages to detect dead peers with [`heartbeat`], and coalescing of small
+/// messages with [`set_write_buffer`]. Blocking and asynchronous adapters can talk to each
+/// other. The Secure Session uses only [`get_public_key_for_id`] method of its transport,
+/// a [`StaticKeyResolver`] is enough.
+///
+/// Incomplete reads and writes are buffered by the adapter, so it is safe to cancel its
+/// futures (say, on a timeout) and call the method again.
+///
+/// This adapter is available with `futures` crate feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example<S>(stream: S) -> std::io::Result<()>
+/// # where S: futures::io::AsyncRead + futures::io::AsyncWrite + Unpin {
+/// use themis::keygen::gen_ec_key_pair;
+/// use themis::secure_session::{AsyncSecureSessionStream, SecureSession, StaticKeyResolver};
+///
+/// let (secret_key, _) = gen_ec_key_pair().split();
+/// # let (_, server_public_key) = gen_ec_key_pair().split();
+///
+/// let mut keys = StaticKeyResolver::new();
+/// keys.insert("server", server_public_key);
+///
+/// let session = SecureSession::with_transport("client", &secret_key, keys)?;
+/// let mut session = AsyncSecureSessionStream::new(session, stream);
+///
+/// session.connect().await?;
+/// session.send(b"hello").await?;
+/// session.shutdown().await?;
+///
+/// while let Some(reply) = session.receive().await? {
+///     println!("{}", String::from_utf8_lossy(&reply));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`SecureSessionStream`]: struct.SecureSessionStream.html
+/// [`shutdown`]: struct.AsyncSecureSessionStream.html#method.shutdown
+/// [`heartbeat`]: struct.AsyncSecureSessionStream.html#method.heartbeat
+/// [`set_write_buffer`]: struct.AsyncSecureSessionStream.html#method.set_write_buffer
+/// [`get_public_key_for_id`]: trait.SecureSessionTransport.html#tymethod.get_public_key_for_id
+/// [`StaticKeyResolver`]: struct.StaticKeyResolver.html
+#[cfg(feature = "futures")]
+pub struct AsyncSecureSessionStream<S, T> {
+    session: StreamSession<T>,
+    stream: S,
+    reader: StreamReader,
+    writer: StreamWriter,
+}
+
+#[cfg(feature = "futures")]
+impl<S, T> AsyncSecureSessionStream<S, T>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: SecureSessionTransport,
+{
+    /// Makes a new adapter for the session over the given stream.
+    pub fn new(session: SecureSession<T>, stream: S) -> Self {
+        Self {
+            session: StreamSession::new(session),
+            stream,
+            reader: StreamReader::new(),
+            writer: StreamWriter::new(),
+        }
+    }
+
+    /// Establishes connection with the server.
+    ///
+    /// This is the method for the client. It completes the whole negotiation.
+    pub async fn connect(&mut self) -> io::Result<()> {
+        let request = self.session.session.generate_connect_request()?;
+        self.writer.push_frame(&request)?;
+        self.negotiate().await
+    }
+
+    /// Accepts connection from the client.
+    ///
+    /// This is the method for the server. It completes the whole negotiation.
+    pub async fn accept(&mut self) -> io::Result<()> {
+        self.negotiate().await
+    }
+
+    async fn negotiate(&mut self) -> io::Result<()> {
+        self.writer.flush_async(&mut self.stream).await?;
+        while !self.session.session.is_established() {
+            match self.reader.next_frame()? {
+                Some(request) => {
+                    let reply = self.session.session.negotiate(&request)?;
+                    if !reply.is_empty() {
+                        self.writer.push_frame(&reply)?;
+                        self.writer.flush_async(&mut self.stream).await?;
+                    }
+                }
+                None => self.reader.fill_async(&mut self.stream).await?,
+            }
+        }
+        self.session.last_received = Instant::now();
+        Ok(())
+    }
+
+    /// Sends a message to the peer.
+    ///
+    /// This method will fail if a secure connection has not been established yet,
+    /// or if the stream has been shut down.
+    pub async fn send<M: AsRef<[u8]>>(&mut self, message: M) -> io::Result<()> {
+        self.writer.push_data(&mut self.session, message.as_ref())?;
+        if self.writer.is_due() {
+            self.writer.flush_async(&mut self.stream).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes out all buffered data.
+    ///
+    /// Call this method to send messages held by [coalescing] without waiting.
+    /// If sending has failed or has been cancelled, call this method to retry.
+    ///
+    /// [coalescing]: struct.AsyncSecureSessionStream.html#method.set_write_buffer
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush_async(&mut self.stream).await
+    }
+
+    /// Receives a message from the peer.
+    ///
+    /// Returns `None` if the peer has shut down the connection. Returns an `UnexpectedEof`
+    /// error if the stream has ended without that.
+    ///
+    /// This method will fail if a secure connection has not been established yet.
+    pub async fn receive(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            if let Some(message) = self.reader.next_message(&mut self.session)? {
+                return Ok(message);
+            }
+            self.reader.fill_async(&mut self.stream).await?;
+        }
+    }
+
+    /// Sends keep-alive messages to the peer and checks that the peer is alive.
+    ///
+    /// Call this method periodically, for example from a timer. Receiving is safe to cancel,
+    /// so you can race [`receive`] against a timer and call this method when it fires.
+    /// It sends a keep-alive message if nothing has been sent for the [keep-alive interval],
+    /// and returns a `TimedOut` error if nothing has been received from the peer within
+    /// the [peer timeout].
+    ///
+    /// Keep-alive messages are consumed by [`receive`] and never returned from it.
+    /// This method also writes out coalesced messages which have waited long enough.
+    ///
+    /// [`receive`]: struct.AsyncSecureSessionStream.html#method.receive
+    /// [keep-alive interval]: struct.AsyncSecureSessionStream.html#method.set_keep_alive
+    /// [peer timeout]: struct.AsyncSecureSessionStream.html#method.set_peer_timeout
+    pub async fn heartbeat(&mut self) -> io::Result<()> {
+        if self.writer.push_keep_alive(&mut self.session)? || self.writer.is_due() {
+            self.writer.flush_async(&mut self.stream).await?;
+        }
+        Ok(())
+    }
+
+    /// Shuts down the outgoing side of the connection.
+    ///
+    /// The peer is notified that no more messages will be sent, this makes the peer's
+    /// [`receive`] return `None`. Then the underlying stream is closed for writing.
+    /// You can still receive messages from the peer after shutdown, but you can no longer
+    /// send them.
+    ///
+    /// [`receive`]: struct.AsyncSecureSessionStream.html#method.receive
+    pub async fn shutdown(&mut self) -> io::Result<()> {
+        self.writer.push_close(&mut self.session)?;
+        self.writer.flush_async(&mut self.stream).await?;
+        self.stream.close().await
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<S, T> AsyncSecureSessionStream<S, T> {
+    /// Returns a reference to the Secure Session.
+    pub fn session(&self) -> &SecureSession<T> {
+        &self.session.session
+    }
+
+    /// Returns a mutable reference to the Secure Session.
+    pub fn session_mut(&mut self) -> &mut SecureSession<T> {
+        &mut self.session.session
+    }
+
+    /// Sets interval of keep-alive messages sent by [`heartbeat`].
+    ///
+    /// Keep-alive messages are not sent by default. Enable them on both sides of
+    /// the connection to detect dead peers with [`set_peer_timeout`].
+    ///
+    /// [`heartbeat`]: struct.AsyncSecureSessionStream.html#method.heartbeat
+    /// [`set_peer_timeout`]: struct.AsyncSecureSessionStream.html#method.set_peer_timeout
+    pub fn set_keep_alive(&mut self, interval: Option<Duration>) {
+        self.writer.keep_alive = interval;
+    }
+
+    /// Sets the time after which a silent peer is considered dead by [`heartbeat`].
+    ///
+    /// There is no timeout by default. The timeout should be longer than the keep-alive
+    /// interval used by the peer.
+    ///
+    /// [`heartbeat`]: struct.AsyncSecureSessionStream.html#method.heartbeat
+    pub fn set_peer_timeout(&mut self, timeout: Option<Duration>) {
+        self.writer.peer_timeout = timeout;
+    }
+
+    /// Enables coalescing of small messages into larger writes.
+    ///
+    /// With coalescing, [`send`] puts messages into a buffer and writes them into the stream
+    /// together, once there are at least `capacity` bytes of them, or once the oldest one
+    /// has been waiting for the [write delay]. Call [`flush`] to write out the buffer now.
+    /// [`shutdown`] writes out the buffer as well.
+    ///
+    /// Coalescing is disabled by default, use zero capacity to disable it.
+    ///
+    /// [`send`]: struct.AsyncSecureSessionStream.html#method.send
+    /// [write delay]: struct.AsyncSecureSessionStream.html#method.set_write_delay
+    /// [`flush`]: struct.AsyncSecureSessionStream.html#method.flush
+    /// [`shutdown`]: struct.AsyncSecureSessionStream.html#method.shutdown
+    pub fn set_write_buffer(&mut self, capacity: usize) {
+        self.writer.capacity = capacity;
+    }
+
+    /// Sets how long messages may wait in the coalescing buffer.
+    ///
+    /// There is no timer: the delay is checked by [`send`] and [`heartbeat`]. If the delay
+    /// is not set then messages wait until the buffer fills up or is flushed explicitly.
+    ///
+    /// [`send`]: struct.AsyncSecureSessionStream.html#method.send
+    /// [`heartbeat`]: struct.AsyncSecureSessionStream.html#method.heartbeat
+    pub fn set_write_delay(&mut self, delay: Option<Duration>) {
+        self.writer.delay = delay;
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    ///
+    /// Reading or writing the stream directly will likely break the framing.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Unwraps this adapter, returning the session and the stream.
+    ///
+    /// Any buffered data which has not been written or processed yet is lost.
+    pub fn into_inner(self) -> (SecureSession<T>, S) {
+        (self.session.session, self.stream)
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<S, T> AsyncSecureSessionStream<S, T>
+where
+    S: AsyncRead + AsyncWrite,
+    T: SecureSessionTransport,
+{
+    /// Splits this adapter into independent halves for receiving and sending.
+    ///
+    /// This is useful for receiving and sending from different tasks. The halves share
+    /// the Secure Session, but the session is never locked while a half is waiting for I/O,
+    /// so receiving does not block sending.
+    ///
+    /// Split the adapter after the connection has been established.
+    pub fn into_split(
+        self,
+    ) -> (
+        AsyncSecureSessionReadHalf<S, T>,
+        AsyncSecureSessionWriteHalf<S, T>,
+    ) {
+        let session = Arc::new(Mutex::new(self.session));
+        let (read_stream, write_stream) = self.stream.split();
+        let read_half = AsyncSecureSessionReadHalf {
+            session: session.clone(),
+            stream: read_stream,
+            reader: self.reader,
+        };
+        let write_half = AsyncSecureSessionWriteHalf {
+            session,
+            stream: write_stream,
+            writer: self.writer,
+        };
+        (read_half, write_half)
+    }
+}
+
+/// Receiving half of an [`AsyncSecureSessionStream`].
+///
+/// Created by [`into_split`] method.
+///
+/// [`AsyncSecureSessionStream`]: struct.AsyncSecureSessionStream.html
+/// [`into_split`]: struct.AsyncSecureSessionStream.html#method.into_split
+#[cfg(feature = "futures")]
+pub struct AsyncSecureSessionReadHalf<S, T> {
+    session: Arc<Mutex<StreamSession<T>>>,
+    stream: ReadHalf<S>,
+    reader: StreamReader,
+}
+
+#[cfg(feature = "futures")]
+impl<S, T> AsyncSecureSessionReadHalf<S, T>
+where
+    S: AsyncRead,
+    T: SecureSessionTransport,
+{
+    /// Receives a message from the peer.
+    ///
+    /// See [`AsyncSecureSessionStream::receive`].
+    ///
+    /// [`AsyncSecureSessionStream::receive`]: struct.AsyncSecureSessionStream.html#method.receive
+    pub async fn receive(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            let next = self
+                .reader
+                .next_message(&mut *lock_session(&self.session)?)?;
+            if let Some(message) = next {
+                return Ok(message);
+            }
+            self.reader.fill_async(&mut self.stream).await?;
+        }
+    }
+}
+
+/// Sending half of an [`AsyncSecureSessionStream`].
+///
+/// Created by [`into_split`] method.
+///
+/// [`AsyncSecureSessionStream`]: struct.AsyncSecureSessionStream.html
+/// [`into_split`]: struct.AsyncSecureSessionStream.html#method.into_split
+#[cfg(feature = "futures")]
+pub struct AsyncSecureSessionWriteHalf<S, T> {
+    session: Arc<Mutex<StreamSession<T>>>,
+    stream: WriteHalf<S>,
+    writer: StreamWriter,
+}
+
+#[cfg(feature = "futures")]
+impl<S, T> AsyncSecureSessionWriteHalf<S, T>
+where
+    S: AsyncWrite,
+    T: SecureSessionTransport,
+{
+    /// Sends a message to the peer.
+    ///
+    /// See [`AsyncSecureSessionStream::send`].
+    ///
+    /// [`AsyncSecureSessionStream::send`]: struct.AsyncSecureSessionStream.html#method.send
+    pub async fn send<M: AsRef<[u8]>>(&mut self, message: M) -> io::Result<()> {
+        self.writer
+            .push_data(&mut *lock_session(&self.session)?, message.as_ref())?;
+        if self.writer.is_due() {
+            self.writer.flush_async(&mut self.stream).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes out all buffered data.
+    ///
+    /// See [`AsyncSecureSessionStream::flush`].
+    ///
+    /// [`AsyncSecureSessionStream::flush`]: struct.AsyncSecureSessionStream.html#method.flush
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush_async(&mut self.stream).await
+    }
+
+    /// Shuts down the outgoing side of the connection.
+    ///
+    /// See [`AsyncSecureSessionStream::shutdown`].
+    ///
+    /// [`AsyncSecureSessionStream::shutdown`]: struct.AsyncSecureSessionStream.html#method.shutdown
+    pub async fn shutdown(&mut self) -> io::Result<()> {
+        self.writer.push_close(&mut *lock_session(&self.session)?)?;
+        self.writer.flush_async(&mut self.stream).await?;
+        self.stream.close().await
     }
 
-    /// Receive some data from the peer into the provided buffer, return the number of bytes.
+    /// Sends keep-alive messages to the peer and checks that the peer is alive.
     ///
-    /// This callback will be called when Secure Session expects to receive some data. The length
-    /// of the buffer indicates the maximum amount of data expected. Put the received data into
-    /// the provided buffer and return the number of bytes that you used.
+    /// See [`AsyncSecureSessionStream::heartbeat`]. The peer is considered alive as long as
+    /// the read half keeps receiving messages from it.
     ///
-    /// This method is used by the transport API ([`negotiate_transport`], [`receive`]).
-    /// You need to implement it in order to use this API.
+    /// [`AsyncSecureSessionStream::heartbeat`]: struct.AsyncSecureSessionStream.html#method.heartbeat
+    pub async fn heartbeat(&mut self) -> io::Result<()> {
+        let keep_alive = self
+            .writer
+            .push_keep_alive(&mut *lock_session(&self.session)?)?;
+        if keep_alive || self.writer.is_due() {
+            self.writer.flush_async(&mut self.stream).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<S, T> AsyncSecureSessionWriteHalf<S, T> {
+    /// Sets interval of keep-alive messages sent by [`heartbeat`].
     ///
-    /// [`negotiate_transport`]: struct.SecureSession.html#method.negotiate_transport
-    /// [`receive`]: struct.SecureSession.html#method.receive
-    fn receive_data(&mut self, data: &mut [u8]) -> result::Result<usize, ()> {
-        Err(())
+    /// [`heartbeat`]: struct.AsyncSecureSessionWriteHalf.html#method.heartbeat
+    pub fn set_keep_alive(&mut self, interval: Option<Duration>) {
+        self.writer.keep_alive = interval;
     }
 
-    /// Notification about connection state of Secure Session.
+    /// Sets the time after which a silent peer is considered dead by [`heartbeat`].
     ///
-    /// This method is truly optional and has no effect on Secure Session operation.
-    fn state_changed(&mut self, state: SecureSessionState) {}
+    /// [`heartbeat`]: struct.AsyncSecureSessionWriteHalf.html#method.heartbeat
+    pub fn set_peer_timeout(&mut self, timeout: Option<Duration>) {
+        self.writer.peer_timeout = timeout;
+    }
 
-    /// Get a public key corresponding to a peer ID.
+    /// Enables coalescing of small messages into larger writes.
     ///
-    /// Return `None` if you are unable to find a corresponding public key.
-    fn get_public_key_for_id(&mut self, id: &[u8]) -> Option<EcdsaPublicKey>;
+    /// See [`AsyncSecureSessionStream::set_write_buffer`].
+    ///
+    /// [`AsyncSecureSessionStream::set_write_buffer`]: struct.AsyncSecureSessionStream.html#method.set_write_buffer
+    pub fn set_write_buffer(&mut self, capacity: usize) {
+        self.writer.capacity = capacity;
+    }
+
+    /// Sets how long messages may wait in the coalescing buffer.
+    ///
+    /// See [`AsyncSecureSessionStream::set_write_delay`].
+    ///
+    /// [`AsyncSecureSessionStream::set_write_delay`]: struct.AsyncSecureSessionStream.html#method.set_write_delay
+    pub fn set_write_delay(&mut self, delay: Option<Duration>) {
+        self.writer.delay = delay;
+    }
 }
 
-// We keep this struct in a box so that it has fixed address. Themis does *not* copy
+// We keep this struct on the heap so that it has fixed address. Themis does *not* copy
 // the callback struct into session context, it keeps a pointer to it. The callback
-// structure itself also stores a `user_data` pointer to itself, so it's important
-// to have this structure pinned in memory.
+// structure itself also stores a `user_data` pointer to the whole struct, which is
+// how the callbacks find the transport.
+//
+// Themis keeps these pointers for the whole session lifetime, so the delegate must not be
+// owned by a `Box`: a box asserts unique access to its content, and any access through it
+// (say, `&mut self.delegate.transport`) would invalidate the pointers held by Themis.
+// Instead, SecureSession keeps the raw pointer returned by `Box::into_raw()`, frees it only
+// in `drop()`, and *all* accesses go through this single raw pointer, from the callbacks and
+// from SecureSession methods alike. Callbacks borrow only the fields they need, never the
+// callback struct itself, which Themis may read at any time.
+//
+// References produced from the raw pointer must not overlap. Themis invokes the callbacks
+// only from within SecureSession methods that take `&mut self`, so references to the
+// transport handed out by methods which borrow the session cannot be alive at that time:
+// the borrow checker makes sure of that.
+//
+// Themis reports only a generic transport error when callbacks fail, so we keep the last
+// error returned by the transport and attach it to the error returned from the session.
+#[repr(C)]
 struct SecureSessionDelegate<T> {
     callbacks: secure_session_user_callbacks_t,
     transport: T,
+    last_error: Option<io::Error>,
 }
 
 /// State of Secure Session connection.
@@ -113,6 +1922,19 @@ pub enum SecureSessionState {
     Established,
 }
 
+/// Result of unwrapping an incoming message with [`unwrap_message`].
+///
+/// [`unwrap_message`]: struct.SecureSession.html#method.unwrap_message
+#[derive(Debug, PartialEq, Eq)]
+pub enum Unwrapped {
+    /// Application data sent by the peer.
+    Data(Vec<u8>),
+    /// Negotiation message which must be transferred to the peer.
+    SendToPeer(Vec<u8>),
+    /// Negotiation is complete, nothing needs to be sent to the peer.
+    Established,
+}
+
 impl SecureSessionState {
     fn from_int(state: c_int) -> Option<Self> {
         match state as u32 {
@@ -139,11 +1961,92 @@ where
     where
         I: AsRef<[u8]>,
     {
-        let (id_ptr, id_len) = into_raw_parts(id.as_ref());
-        let (key_ptr, key_len) = into_raw_parts(key.as_ref());
+        Self::new(id.as_ref(), key.as_ref(), transport)
+    }
+
+    /// Creates a new Secure Session using the secret key from a key pair.
+    ///
+    /// ID is an arbitrary byte sequence used to identify this peer. Only the secret part of the
+    /// key pair is used by this Secure Session, the public key should be distributed to peers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), themis::Error> {
+    /// use themis::keygen::gen_ec_key_pair;
+    /// use themis::keys::EcdsaPublicKey;
+    /// use themis::secure_session::{SecureSession, SecureSessionTransport};
+    ///
+    /// struct NoTransport;
+    ///
+    /// impl SecureSessionTransport for NoTransport {
+    ///     fn get_public_key_for_id(&mut self, id: &[u8]) -> Option<EcdsaPublicKey> {
+    ///         None
+    ///     }
+    /// }
+    ///
+    /// let key_pair = gen_ec_key_pair();
+    ///
+    /// let session = SecureSession::with_key_pair("client", &key_pair, NoTransport)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_key_pair<I>(id: I, key_pair: &EcdsaKeyPair, transport: T) -> Result<Self>
+    where
+        I: AsRef<[u8]>,
+    {
+        Self::new(id.as_ref(), key_pair.secret_key_bytes(), transport)
+    }
+
+    /// Creates a new Secure Session with a secret key of any kind.
+    ///
+    /// ID is an arbitrary byte sequence used to identify this peer.
+    ///
+    /// Secure Session supports only ECDSA keys. This method returns an error of kind
+    /// [`NotSupported`] if an RSA key is provided. Prefer [`with_transport`] which checks
+    /// the key kind at compile time.
+    ///
+    /// [`NotSupported`]: ../enum.ErrorKind.html#variant.NotSupported
+    /// [`with_transport`]: struct.SecureSession.html#method.with_transport
+    pub fn with_secret_key<I>(id: I, key: &SecretKey, transport: T) -> Result<Self>
+    where
+        I: AsRef<[u8]>,
+    {
+        Self::new(id.as_ref(), key.as_ref(), transport)
+    }
+
+    /// Creates a new Secure Session using a secret key provided by a handle.
+    ///
+    /// ID is an arbitrary byte sequence used to identify this peer.
+    ///
+    /// Secure Session keeps its own copy of the key for the whole session lifetime, so the handle
+    /// is asked for the key only once, here. Only ECDSA keys are supported, see
+    /// [`with_secret_key`] for details.
+    ///
+    /// [`with_secret_key`]: struct.SecureSession.html#method.with_secret_key
+    pub fn with_key_handle<I, H>(id: I, handle: &H, transport: T) -> Result<Self>
+    where
+        I: AsRef<[u8]>,
+        H: PrivateKeyHandle + ?Sized,
+    {
+        let key = handle.secret_key()?;
+        Self::new(id.as_ref(), key.as_ref(), transport)
+    }
+
+    fn new(id: &[u8], key: &[u8], transport: T) -> Result<Self> {
+        // Themis happily accepts RSA keys here, but the resulting session is not functional.
+        // Check the key kind beforehand so that the users get a meaningful error.
+        match SecretKey::try_from_slice(key)?.kind() {
+            KeyKind::EcdsaSecret => {}
+            KeyKind::RsaSecret => return Err(Error::with_kind(ErrorKind::NotSupported)),
+            _ => return Err(Error::with_kind(ErrorKind::InvalidParameter)),
+        }
+
+        let (id_ptr, id_len) = into_raw_parts(id);
+        let (key_ptr, key_len) = into_raw_parts(key);
         let delegate = SecureSessionDelegate::new(transport);
 
-        let user_callbacks = delegate.user_callbacks();
+        let user_callbacks = SecureSessionDelegate::user_callbacks(delegate);
         let session_ctx = unsafe {
             secure_session_create(
                 id_ptr as *const c_void,
@@ -155,6 +2058,9 @@ where
         };
 
         if session_ctx.is_null() {
+            unsafe {
+                SecureSessionDelegate::free(delegate);
+            }
             // Technically, this may be an allocation error but we have no way to know so just
             // assume that the user messed up and provided invalid keys (which is more likely).
             return Err(Error::with_kind(ErrorKind::InvalidParameter));
@@ -162,15 +2068,60 @@ where
 
         Ok(Self {
             session_ctx,
-            _delegate: delegate,
+            delegate,
+            trace: SessionTrace::new(id),
+            created: Instant::now(),
+            negotiation_timeout: None,
         })
     }
 
+    /// Returns a reference to the transport used by this Secure Session.
+    pub fn transport(&self) -> &T {
+        unsafe { &(*self.delegate).transport }
+    }
+
+    /// Returns a mutable reference to the transport used by this Secure Session.
+    ///
+    /// Note that Secure Session keeps its own state. Replacing the transport in the middle
+    /// of a connection may confuse it.
+    pub fn transport_mut(&mut self) -> &mut T {
+        unsafe { &mut (*self.delegate).transport }
+    }
+
     /// Returns `true` if this Secure Session may be used for data transfer.
     pub fn is_established(&self) -> bool {
         unsafe { secure_session_is_established(self.session_ctx) }
     }
 
+    /// Sets a deadline for connection negotiation.
+    ///
+    /// The timeout is counted from the moment of Secure Session creation. If the connection
+    /// is not established in time then negotiation methods return an error of kind
+    /// [`SessionHandshakeTimeout`] and the session should be discarded. This protects you from
+    /// peers that stall in the middle of negotiation. There is no timeout by default.
+    ///
+    /// [`SessionHandshakeTimeout`]: ../enum.ErrorKind.html#variant.SessionHandshakeTimeout
+    pub fn set_negotiation_timeout(&mut self, timeout: Option<Duration>) {
+        self.negotiation_timeout = timeout;
+    }
+
+    /// Returns `true` if negotiation has not been completed within the [timeout].
+    ///
+    /// [timeout]: struct.SecureSession.html#method.set_negotiation_timeout
+    pub fn is_negotiation_expired(&self) -> bool {
+        match self.negotiation_timeout {
+            Some(timeout) => !self.is_established() && self.created.elapsed() >= timeout,
+            None => false,
+        }
+    }
+
+    fn check_negotiation_timeout(&self) -> Result<()> {
+        if self.is_negotiation_expired() {
+            return Err(Error::with_kind(ErrorKind::SessionHandshakeTimeout));
+        }
+        Ok(())
+    }
+
     // TODO: abstract out the 'check-allocate-leap' pattern
     //
     // This is really common here to call a C function to get a size of the buffer, then allocate
@@ -187,9 +2138,9 @@ where
         unsafe {
             let status =
                 secure_session_get_remote_id(self.session_ctx, ptr::null_mut(), &mut id_len);
-            let error = Error::from_session_status(status);
-            if error.kind() != ErrorKind::BufferTooSmall {
-                return Err(error);
+            let status = Status::from_session(status);
+            if status != Status::BufferTooSmall {
+                return Err(status.into());
             }
         }
 
@@ -198,9 +2149,9 @@ where
         unsafe {
             let status =
                 secure_session_get_remote_id(self.session_ctx, id.as_mut_ptr(), &mut id_len);
-            let error = Error::from_session_status(status);
-            if error.kind() != ErrorKind::Success {
-                return Err(error);
+            let status = Status::from_session(status);
+            if status != Status::Success {
+                return Err(status.into());
             }
             debug_assert!(id_len <= id.capacity());
             id.set_len(id_len);
@@ -225,11 +2176,13 @@ where
     /// [`is_established`]: struct.SecureSession.html#method.is_established
     /// [`send_data`]: trait.SecureSessionTransport.html#method.send_data
     pub fn connect(&mut self) -> Result<()> {
+        self.check_negotiation_timeout()?;
+        self.trace.connect_requested();
         unsafe {
             let status = secure_session_connect(self.session_ctx);
-            let error = Error::from_session_status(status);
-            if error.kind() != ErrorKind::Success {
-                return Err(error);
+            let status = Status::from_session(status);
+            if status != Status::Success {
+                return Err(self.transport_error(status));
             }
         }
         Ok(())
@@ -250,6 +2203,8 @@ where
     /// [`state_changed`]: trait.SecureSessionTransport.html#method.state_changed
     /// [`is_established`]: struct.SecureSession.html#method.is_established
     pub fn generate_connect_request(&mut self) -> Result<Vec<u8>> {
+        self.check_negotiation_timeout()?;
+        self.trace.connect_requested();
         let mut output = Vec::new();
         let mut output_len = 0;
 
@@ -259,9 +2214,9 @@ where
                 ptr::null_mut(),
                 &mut output_len,
             );
-            let error = Error::from_session_status(status);
-            if error.kind() != ErrorKind::BufferTooSmall {
-                return Err(error);
+            let status = Status::from_session(status);
+            if status != Status::BufferTooSmall {
+                return Err(status.into());
             }
         }
 
@@ -273,9 +2228,9 @@ where
                 output.as_mut_ptr() as *mut c_void,
                 &mut output_len,
             );
-            let error = Error::from_session_status(status);
-            if error.kind() != ErrorKind::Success {
-                return Err(error);
+            let status = Status::from_session(status);
+            if status != Status::Success {
+                return Err(status.into());
             }
             debug_assert!(output_len <= output.capacity());
             output.set_len(output_len);
@@ -295,9 +2250,24 @@ where
     ///
     /// [`unwrap`]: struct.SecureSession.html#method.unwrap
     pub fn wrap<M: AsRef<[u8]>>(&mut self, message: M) -> Result<Vec<u8>> {
+        let mut wrapped = Vec::new();
+        self.wrap_into(message, &mut wrapped)?;
+        Ok(wrapped)
+    }
+
+    /// Wraps a message into the provided buffer.
+    ///
+    /// This is the same as [`wrap`], but the result is placed into `wrapped` instead of a newly
+    /// allocated vector. The buffer is cleared first, then its capacity is reused. This avoids
+    /// memory allocation when processing many messages with the same buffer.
+    ///
+    /// The buffer is left empty in case of an error.
+    ///
+    /// [`wrap`]: struct.SecureSession.html#method.wrap
+    pub fn wrap_into<M: AsRef<[u8]>>(&mut self, message: M, wrapped: &mut Vec<u8>) -> Result<()> {
         let (message_ptr, message_len) = into_raw_parts(message.as_ref());
 
-        let mut wrapped = Vec::new();
+        wrapped.clear();
         let mut wrapped_len = 0;
 
         unsafe {
@@ -308,9 +2278,9 @@ where
                 ptr::null_mut(),
                 &mut wrapped_len,
             );
-            let error = Error::from_session_status(status);
-            if error.kind() != ErrorKind::BufferTooSmall {
-                return Err(error);
+            let status = Status::from_session(status);
+            if status != Status::BufferTooSmall {
+                return Err(status.into());
             }
         }
 
@@ -324,15 +2294,15 @@ where
                 wrapped.as_mut_ptr() as *mut c_void,
                 &mut wrapped_len,
             );
-            let error = Error::from_session_status(status);
-            if error.kind() != ErrorKind::Success {
-                return Err(error);
+            let status = Status::from_session(status);
+            if status != Status::Success {
+                return Err(status.into());
             }
             debug_assert!(wrapped_len <= wrapped.capacity());
             wrapped.set_len(wrapped_len);
         }
 
-        Ok(wrapped)
+        Ok(())
     }
 
     /// Unwraps a message and returns it.
@@ -343,9 +2313,32 @@ where
     ///
     /// [wrapped]: struct.SecureSession.html#method.wrap
     pub fn unwrap<M: AsRef<[u8]>>(&mut self, wrapped: M) -> Result<Vec<u8>> {
-        let (wrapped_ptr, wrapped_len) = into_raw_parts(wrapped.as_ref());
-
         let mut message = Vec::new();
+        self.unwrap_into(wrapped, &mut message)?;
+        Ok(message)
+    }
+
+    /// Unwraps a message into the provided buffer.
+    ///
+    /// This is the same as [`unwrap`], but the result is placed into `message` instead of
+    /// a newly allocated vector. The buffer is cleared first, then its capacity is reused.
+    /// This avoids memory allocation when processing many messages with the same buffer.
+    ///
+    /// The buffer is left empty in case of an error.
+    ///
+    /// [`unwrap`]: struct.SecureSession.html#method.unwrap
+    pub fn unwrap_into<M: AsRef<[u8]>>(&mut self, wrapped: M, message: &mut Vec<u8>) -> Result<()> {
+        let result = self.unwrap_impl(wrapped.as_ref(), message);
+        if let Err(ref error) = result {
+            self.trace.unwrap_failed(error);
+        }
+        result
+    }
+
+    fn unwrap_impl(&mut self, wrapped: &[u8], message: &mut Vec<u8>) -> Result<()> {
+        let (wrapped_ptr, wrapped_len) = into_raw_parts(wrapped);
+
+        message.clear();
         let mut message_len = 0;
 
         unsafe {
@@ -356,9 +2349,9 @@ where
                 ptr::null_mut(),
                 &mut message_len,
             );
-            let error = Error::from_session_status(status);
-            if error.kind() != ErrorKind::BufferTooSmall {
-                return Err(error);
+            let status = Status::from_session(status);
+            if status != Status::BufferTooSmall {
+                return Err(status.into());
             }
         }
 
@@ -372,15 +2365,84 @@ where
                 message.as_mut_ptr() as *mut c_void,
                 &mut message_len,
             );
-            let error = Error::from_session_status(status);
-            if error.kind() != ErrorKind::Success {
-                return Err(error);
+            let status = Status::from_session(status);
+            if status != Status::Success {
+                return Err(status.into());
             }
             debug_assert!(message_len <= message.capacity());
             message.set_len(message_len);
         }
 
-        Ok(message)
+        Ok(())
+    }
+
+    /// Unwraps a message of any kind.
+    ///
+    /// This method combines [`negotiate`] and [`unwrap`]. It accepts both negotiation messages
+    /// and wrapped data messages, and tells you what to do with the result. This is convenient
+    /// when you receive all messages from the peer in a single loop.
+    ///
+    /// [`negotiate`]: struct.SecureSession.html#method.negotiate
+    /// [`unwrap`]: struct.SecureSession.html#method.unwrap
+    pub fn unwrap_message<M: AsRef<[u8]>>(&mut self, wrapped: M) -> Result<Unwrapped> {
+        let result = self.unwrap_message_impl(wrapped.as_ref());
+        match result {
+            Ok(Unwrapped::Data(_)) => {}
+            Ok(_) => self.trace_negotiation_step(),
+            Err(ref error) => self.trace.unwrap_failed(error),
+        }
+        result
+    }
+
+    fn unwrap_message_impl(&mut self, wrapped: &[u8]) -> Result<Unwrapped> {
+        self.check_negotiation_timeout()?;
+
+        let (wrapped_ptr, wrapped_len) = into_raw_parts(wrapped);
+
+        let mut message = Vec::new();
+        let mut message_len = 0;
+
+        unsafe {
+            let status = secure_session_unwrap(
+                self.session_ctx,
+                wrapped_ptr as *const c_void,
+                wrapped_len,
+                ptr::null_mut(),
+                &mut message_len,
+            );
+            let status = Status::from_session(status);
+            if status == Status::Success {
+                return Ok(Unwrapped::Established);
+            }
+            if status != Status::BufferTooSmall {
+                return Err(status.into());
+            }
+        }
+
+        message.reserve(message_len);
+
+        unsafe {
+            let status = secure_session_unwrap(
+                self.session_ctx,
+                wrapped_ptr as *const c_void,
+                wrapped_len,
+                message.as_mut_ptr() as *mut c_void,
+                &mut message_len,
+            );
+            let send_to_peer = match Status::from_session(status) {
+                Status::Success => false,
+                Status::SendOutputToPeer => true,
+                status => return Err(status.into()),
+            };
+            debug_assert!(message_len <= message.capacity());
+            message.set_len(message_len);
+
+            if send_to_peer {
+                Ok(Unwrapped::SendToPeer(message))
+            } else {
+                Ok(Unwrapped::Data(message))
+            }
+        }
     }
 
     /// Continues connection negotiation with given message.
@@ -395,7 +2457,18 @@ where
     /// [`negotiate`]: struct.SecureSession.html#method.negotiate
     /// [`generate_connect_request`]: struct.SecureSession.html#method.generate_connect_request
     pub fn negotiate<M: AsRef<[u8]>>(&mut self, wrapped: M) -> Result<Vec<u8>> {
-        let (wrapped_ptr, wrapped_len) = into_raw_parts(wrapped.as_ref());
+        let result = self.negotiate_impl(wrapped.as_ref());
+        match result {
+            Ok(_) => self.trace_negotiation_step(),
+            Err(ref error) => self.trace.unwrap_failed(error),
+        }
+        result
+    }
+
+    fn negotiate_impl(&mut self, wrapped: &[u8]) -> Result<Vec<u8>> {
+        self.check_negotiation_timeout()?;
+
+        let (wrapped_ptr, wrapped_len) = into_raw_parts(wrapped);
 
         let mut message = Vec::new();
         let mut message_len = 0;
@@ -408,12 +2481,12 @@ where
                 ptr::null_mut(),
                 &mut message_len,
             );
-            let error = Error::from_session_status(status);
-            if error.kind() == ErrorKind::Success {
+            let status = Status::from_session(status);
+            if status == Status::Success {
                 return Ok(message);
             }
-            if error.kind() != ErrorKind::BufferTooSmall {
-                return Err(error);
+            if status != Status::BufferTooSmall {
+                return Err(status.into());
             }
         }
 
@@ -427,10 +2500,10 @@ where
                 message.as_mut_ptr() as *mut c_void,
                 &mut message_len,
             );
-            let error = Error::from_session_status(status);
-            if error.kind() != ErrorKind::SessionSendOutputToPeer {
-                assert_ne!(error.kind(), ErrorKind::Success);
-                return Err(error);
+            let status = Status::from_session(status);
+            if status != Status::SendOutputToPeer {
+                assert_ne!(status, Status::Success);
+                return Err(status.into());
             }
             debug_assert!(message_len <= message.capacity());
             message.set_len(message_len);
@@ -464,7 +2537,8 @@ where
             let length =
                 secure_session_send(self.session_ctx, message_ptr as *const c_void, message_len);
             if length <= 21 {
-                return Err(Error::from_session_status(length as themis_status_t));
+                let status = Status::from_session(length as themis_status_t);
+                return Err(self.transport_error(status));
             }
         }
 
@@ -492,7 +2566,8 @@ where
                 message.capacity(),
             );
             if length <= 21 {
-                return Err(Error::from_session_status(length as themis_status_t));
+                let status = Status::from_session(length as themis_status_t);
+                return Err(self.transport_error(status));
             }
             debug_assert!(length as usize <= message.capacity());
             message.set_len(length as usize);
@@ -515,25 +2590,248 @@ where
     /// [`send_data`]: trait.SecureSessionTransport.html#method.send_data
     /// [`receive_data`]: trait.SecureSessionTransport.html#method.receive_data
     pub fn negotiate_transport(&mut self) -> Result<()> {
+        self.check_negotiation_timeout()?;
+
         unsafe {
             let result = secure_session_receive(self.session_ctx, ptr::null_mut(), 0);
-            let error = Error::from_session_status(result as themis_status_t);
-            if error.kind() != ErrorKind::Success {
+            let status = Status::from_session(result as themis_status_t);
+            if status != Status::Success {
+                let error = self.transport_error(status);
+                self.trace.unwrap_failed(&error);
                 return Err(error);
             }
         }
 
+        self.trace_negotiation_step();
+
+        Ok(())
+    }
+
+    /// Converts a failed status of transport API, attaching the error reported by transport.
+    fn transport_error(&mut self, status: Status) -> Error {
+        let error = Error::from(status);
+        match unsafe { SecureSessionDelegate::take_error(self.delegate) } {
+            Some(source) => error.caused_by(source),
+            None => error,
+        }
+    }
+
+    fn trace_negotiation_step(&mut self) {
+        if self.is_established() && !self.trace.is_established() {
+            let peer_id = self.get_remote_id().unwrap_or_default();
+            self.trace.established(&peer_id);
+        } else {
+            self.trace.negotiation_step();
+        }
+    }
+}
+
+/// A collection of Secure Sessions with many peers.
+///
+/// Registry owns Secure Sessions and routes incoming messages to them by key. The key is
+/// typically a peer ID or a network address of the peer (e.g., when receiving UDP datagrams).
+///
+/// Registry limits the number of concurrent sessions and expires the ones that have been idle
+/// for too long. Sessions are considered idle if they have not been accessed via registry.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), themis::Error> {
+/// use std::time::Duration;
+///
+/// use themis::keygen::gen_ec_key_pair;
+/// use themis::keys::EcdsaPublicKey;
+/// use themis::secure_session::{
+///     SecureSession, SecureSessionRegistry, SecureSessionTransport, Unwrapped,
+/// };
+///
+/// struct Transport(Option<EcdsaPublicKey>);
+///
+/// impl SecureSessionTransport for Transport {
+///     fn get_public_key_for_id(&mut self, _id: &[u8]) -> Option<EcdsaPublicKey> {
+///         self.0.clone()
+///     }
+/// }
+///
+/// let (secret_client, public_client) = gen_ec_key_pair().split();
+/// let (secret_server, public_server) = gen_ec_key_pair().split();
+///
+/// let transport = Transport(Some(public_server));
+/// let mut client = SecureSession::with_transport("client", &secret_client, transport)?;
+/// let mut registry = SecureSessionRegistry::new(100, Duration::from_secs(60));
+///
+/// let mut request = client.generate_connect_request()?;
+/// while !client.is_established() {
+///     // Route the message from the client to its own session, creating it if needed.
+///     let reply = registry.unwrap_message("127.0.0.1:1234", &request, || {
+///         let transport = Transport(Some(public_client.clone()));
+///         SecureSession::with_transport("server", &secret_server, transport)
+///     })?;
+///     if let Unwrapped::SendToPeer(reply) = reply {
+///         request = client.negotiate(&reply)?;
+///     }
+/// }
+///
+/// let wrapped = client.wrap(b"test message please ignore")?;
+/// let unwrapped = registry.unwrap_message("127.0.0.1:1234", &wrapped, || unreachable!())?;
+/// assert_eq!(unwrapped, Unwrapped::Data(b"test message please ignore".to_vec()));
+/// # Ok(())
+/// # }
+/// ```
+pub struct SecureSessionRegistry<K, T> {
+    sessions: HashMap<K, RegistryEntry<T>>,
+    max_sessions: usize,
+    idle_timeout: Duration,
+}
+
+struct RegistryEntry<T> {
+    session: SecureSession<T>,
+    last_used: Instant,
+}
+
+impl<K, T> SecureSessionRegistry<K, T>
+where
+    K: Eq + Hash,
+    T: SecureSessionTransport,
+{
+    /// Makes a new empty registry.
+    ///
+    /// The registry will hold at most `max_sessions` sessions at a time. Sessions which are not
+    /// used for `idle_timeout` are removed.
+    pub fn new(max_sessions: usize, idle_timeout: Duration) -> Self {
+        Self {
+            sessions: HashMap::new(),
+            max_sessions,
+            idle_timeout,
+        }
+    }
+
+    /// Returns the number of sessions in the registry.
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Returns `true` if the registry has no sessions.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Returns `true` if the registry has a session for the given key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.sessions.contains_key(key)
+    }
+
+    /// Returns a session for the given key, if any.
+    ///
+    /// This counts as session activity and resets its idle timer.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut SecureSession<T>> {
+        self.sessions.get_mut(key).map(|entry| {
+            entry.last_used = Instant::now();
+            &mut entry.session
+        })
+    }
+
+    /// Adds a session to the registry.
+    ///
+    /// An existing session with the same key is replaced. Idle sessions are expired before
+    /// adding a new one. If the registry is still full then an error of kind
+    /// [`SessionLimitExceeded`] is returned.
+    ///
+    /// [`SessionLimitExceeded`]: ../enum.ErrorKind.html#variant.SessionLimitExceeded
+    pub fn insert(&mut self, key: K, session: SecureSession<T>) -> Result<()> {
+        if !self.sessions.contains_key(&key) {
+            self.reserve_slot()?;
+        }
+        self.sessions.insert(
+            key,
+            RegistryEntry {
+                session,
+                last_used: Instant::now(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Removes a session from the registry and returns it.
+    pub fn remove(&mut self, key: &K) -> Option<SecureSession<T>> {
+        self.sessions.remove(key).map(|entry| entry.session)
+    }
+
+    /// Removes all sessions which have been idle for too long, returns the number of them.
+    ///
+    /// Sessions which failed to complete negotiation in time are removed as well
+    /// (see [`set_negotiation_timeout`]).
+    ///
+    /// [`set_negotiation_timeout`]: struct.SecureSession.html#method.set_negotiation_timeout
+    pub fn expire_idle(&mut self) -> usize {
+        let idle_timeout = self.idle_timeout;
+        let before = self.sessions.len();
+        self.sessions.retain(|_, entry| {
+            entry.last_used.elapsed() < idle_timeout && !entry.session.is_negotiation_expired()
+        });
+        before - self.sessions.len()
+    }
+
+    /// Unwraps a message received from the peer with the given key.
+    ///
+    /// The message is passed to [`unwrap_message`] of the corresponding session. If there is no
+    /// session for this key yet, a new one is made with `new_session` and added to the registry
+    /// if it accepts the message. This way malformed messages do not occupy registry slots.
+    ///
+    /// Returns an error of kind [`SessionLimitExceeded`] if a new session is needed but the
+    /// registry is full.
+    ///
+    /// [`unwrap_message`]: struct.SecureSession.html#method.unwrap_message
+    /// [`SessionLimitExceeded`]: ../enum.ErrorKind.html#variant.SessionLimitExceeded
+    pub fn unwrap_message<M, F>(&mut self, key: K, message: M, new_session: F) -> Result<Unwrapped>
+    where
+        M: AsRef<[u8]>,
+        F: FnOnce() -> Result<SecureSession<T>>,
+    {
+        if let Some(session) = self.get_mut(&key) {
+            return session.unwrap_message(message);
+        }
+
+        self.reserve_slot()?;
+
+        let mut session = new_session()?;
+        let result = session.unwrap_message(message)?;
+        self.sessions.insert(
+            key,
+            RegistryEntry {
+                session,
+                last_used: Instant::now(),
+            },
+        );
+        Ok(result)
+    }
+
+    fn reserve_slot(&mut self) -> Result<()> {
+        if self.sessions.len() >= self.max_sessions {
+            self.expire_idle();
+        }
+        if self.sessions.len() >= self.max_sessions {
+            return Err(Error::with_kind(ErrorKind::SessionLimitExceeded));
+        }
         Ok(())
     }
 }
 
+impl<T> SecureSessionDelegate<T> {
+    /// Frees the delegate allocated by `new()`.
+    pub unsafe fn free(delegate: *mut Self) {
+        drop(Box::from_raw(delegate));
+    }
+}
+
 impl<T> SecureSessionDelegate<T>
 where
     T: SecureSessionTransport,
 {
     #[allow(clippy::new_ret_no_self)]
-    pub fn new(transport: T) -> Box<Self> {
-        let mut delegate = Box::new(Self {
+    pub fn new(transport: T) -> *mut Self {
+        let delegate = Box::into_raw(Box::new(Self {
             callbacks: secure_session_user_callbacks_t {
                 send_data: Some(Self::send_data),
                 receive_data: Some(Self::receive_data),
@@ -542,23 +2840,47 @@ where
                 user_data: ptr::null_mut(),
             },
             transport,
-        });
-        delegate.callbacks.user_data = delegate.transport_ptr();
+            last_error: None,
+        }));
+        unsafe {
+            (*delegate).callbacks.user_data = delegate as *mut c_void;
+        }
         delegate
     }
 
-    pub fn user_callbacks(&self) -> *const secure_session_user_callbacks_t {
-        &self.callbacks
+    pub fn user_callbacks(delegate: *mut Self) -> *const secure_session_user_callbacks_t {
+        // Callbacks are the first field of a #[repr(C)] struct. Cast the pointer instead of
+        // taking a reference so that Themis gets a pointer derived from the original one.
+        delegate as *const secure_session_user_callbacks_t
     }
 
     // These functions are unsafe. They should be used only for `user_data` conversion.
 
-    fn transport_ptr(&mut self) -> *mut c_void {
-        &mut self.transport as *mut T as *mut c_void
+    unsafe fn transport<'a>(ptr: *mut c_void) -> &'a mut T {
+        &mut (*(ptr as *mut Self)).transport
+    }
+
+    unsafe fn last_error<'a>(ptr: *mut c_void) -> &'a mut Option<io::Error> {
+        &mut (*(ptr as *mut Self)).last_error
     }
 
-    fn transport<'a>(ptr: *mut c_void) -> &'a mut T {
-        unsafe { &mut *(ptr as *mut T) }
+    unsafe fn report(ptr: *mut c_void, result: io::Result<usize>) -> isize {
+        let last_error = Self::last_error(ptr);
+        match result {
+            Ok(length) => {
+                *last_error = None;
+                as_isize(length).unwrap_or(-1)
+            }
+            Err(error) => {
+                *last_error = Some(error);
+                -1
+            }
+        }
+    }
+
+    /// Takes the error returned by the transport callbacks, if any.
+    pub unsafe fn take_error(delegate: *mut Self) -> Option<io::Error> {
+        Self::last_error(delegate as *mut c_void).take()
     }
 
     unsafe extern "C" fn send_data(
@@ -567,13 +2889,9 @@ where
         user_data: *mut c_void,
     ) -> isize {
         let data = byte_slice_from_ptr(data_ptr, data_len);
-        let transport = Self::transport(user_data);
 
-        transport
-            .send_data(data)
-            .ok()
-            .and_then(as_isize)
-            .unwrap_or(-1)
+        let result = Self::transport(user_data).send_data(data);
+        Self::report(user_data, result)
     }
 
     unsafe extern "C" fn receive_data(
@@ -582,13 +2900,9 @@ where
         user_data: *mut c_void,
     ) -> isize {
         let data = byte_slice_from_ptr_mut(data_ptr, data_len);
-        let transport = Self::transport(user_data);
 
-        transport
-            .receive_data(data)
-            .ok()
-            .and_then(as_isize)
-            .unwrap_or(-1)
+        let result = Self::transport(user_data).receive_data(data);
+        Self::report(user_data, result)
     }
 
     unsafe extern "C" fn state_changed(event: c_int, user_data: *mut c_void) {
@@ -626,14 +2940,21 @@ impl<D> Drop for SecureSession<D> {
     fn drop(&mut self) {
         unsafe {
             let status = secure_session_destroy(self.session_ctx);
-            let error = Error::from_session_status(status);
-            if (cfg!(debug) || cfg!(test)) && error.kind() != ErrorKind::Success {
-                panic!("secure_session_destroy() failed: {}", error);
+            // Themis does not use the callbacks after the session has been destroyed.
+            SecureSessionDelegate::free(self.delegate);
+            let status = Status::from_session(status);
+            if (cfg!(debug) || cfg!(test)) && status != Status::Success {
+                panic!("secure_session_destroy() failed: {}", Error::from(status));
             }
         }
     }
 }
 
+// Session context is not tied to any particular thread, it is just some memory. Themis calls
+// the transport only from within methods taking `&mut self`, so the transport moves along
+// with the session.
+unsafe impl<T: Send> Send for SecureSession<T> {}
+
 fn as_isize(n: usize) -> Option<isize> {
     if n <= isize::max_value() as usize {
         Some(n as isize)
@@ -0,0 +1,475 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serde support for keys.
+//!
+//! Keys are serialized as structures with format version, key kind, and key data:
+//!
+//! ```text
+//! { "version": 1, "kind": "ecdsa-public", "key": "VUVDMgAAAC0Ti98MAh8JiDnZczqEj6hQ2SvtPTjPHdDO9K7bz6/La6VKCBEh" }
+//! ```
+//!
+//! Key pairs have `secret` and `public` fields instead of `key`. Key data is encoded with
+//! base64 for human-readable formats (like JSON) and stored as raw bytes otherwise.
+//!
+//! Managed keys add `id`, `created`, and `expires` fields to the wrapped key stored in `key`.
+//! Timestamps are represented as the number of seconds since UNIX epoch, `expires` is optional.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_crate::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_crate::ser::{Serialize, SerializeStruct, Serializer};
+use zeroize::Zeroize;
+
+use crate::keys::{
+    EcdsaKeyPair, EcdsaPublicKey, EcdsaSecretKey, KeyKind, KeyPair, PublicKey, RsaKeyPair,
+    RsaPublicKey, RsaSecretKey, SecretKey,
+};
+use crate::keystore::ManagedKey;
+
+/// Current version of serialization format.
+const FORMAT_VERSION: u32 = 1;
+
+const KEY_FIELDS: &[&str] = &["version", "kind", "key"];
+const KEY_PAIR_FIELDS: &[&str] = &["version", "kind", "secret", "public"];
+
+fn key_kind_name(kind: KeyKind) -> &'static str {
+    match kind {
+        KeyKind::RsaSecret => "rsa-secret",
+        KeyKind::RsaPublic => "rsa-public",
+        KeyKind::EcdsaSecret => "ecdsa-secret",
+        KeyKind::EcdsaPublic => "ecdsa-public",
+    }
+}
+
+fn key_pair_kind_name(kind: KeyKind) -> &'static str {
+    match kind {
+        KeyKind::RsaSecret | KeyKind::RsaPublic => "rsa-pair",
+        KeyKind::EcdsaSecret | KeyKind::EcdsaPublic => "ecdsa-pair",
+    }
+}
+
+//
+// Serialization
+//
+
+struct KeyData<'a>(&'a [u8]);
+
+impl<'a> Serialize for KeyData<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let mut encoded = base64::encode(self.0);
+            let result = serializer.serialize_str(&encoded);
+            encoded.zeroize();
+            result
+        } else {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+}
+
+fn serialize_key<S>(
+    serializer: S,
+    name: &'static str,
+    kind: KeyKind,
+    key: &[u8],
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut state = serializer.serialize_struct(name, KEY_FIELDS.len())?;
+    state.serialize_field("version", &FORMAT_VERSION)?;
+    state.serialize_field("kind", key_kind_name(kind))?;
+    state.serialize_field("key", &KeyData(key))?;
+    state.end()
+}
+
+fn serialize_key_pair<S>(
+    serializer: S,
+    name: &'static str,
+    kind: KeyKind,
+    secret_key: &[u8],
+    public_key: &[u8],
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut state = serializer.serialize_struct(name, KEY_PAIR_FIELDS.len())?;
+    state.serialize_field("version", &FORMAT_VERSION)?;
+    state.serialize_field("kind", key_pair_kind_name(kind))?;
+    state.serialize_field("secret", &KeyData(secret_key))?;
+    state.serialize_field("public", &KeyData(public_key))?;
+    state.end()
+}
+
+//
+// Deserialization
+//
+
+/// Key data buffer which is wiped when no longer needed.
+struct KeyBuffer(Vec<u8>);
+
+impl Drop for KeyBuffer {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+struct KeyBufferVisitor;
+
+impl<'de> Visitor<'de> for KeyBufferVisitor {
+    type Value = KeyBuffer;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("key data")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<KeyBuffer, E> {
+        base64::decode(value)
+            .map(KeyBuffer)
+            .map_err(|_| E::invalid_value(de::Unexpected::Str("<redacted>"), &"base64 string"))
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<KeyBuffer, E> {
+        Ok(KeyBuffer(value.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<KeyBuffer, E> {
+        Ok(KeyBuffer(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<KeyBuffer, A::Error> {
+        let mut buffer = KeyBuffer(Vec::with_capacity(seq.size_hint().unwrap_or(0)));
+        while let Some(byte) = seq.next_element()? {
+            buffer.0.push(byte);
+        }
+        Ok(buffer)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBuffer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(KeyBufferVisitor)
+        } else {
+            deserializer.deserialize_byte_buf(KeyBufferVisitor)
+        }
+    }
+}
+
+/// Deserialized key structure: its kind and key data fields in order.
+struct KeyRepr {
+    kind: String,
+    data: Vec<KeyBuffer>,
+}
+
+struct KeyReprVisitor {
+    name: &'static str,
+    fields: &'static [&'static str],
+}
+
+fn check_version<E: de::Error>(version: u32) -> Result<(), E> {
+    if version != FORMAT_VERSION {
+        return Err(E::custom(format!(
+            "unsupported key format version: {}",
+            version
+        )));
+    }
+    Ok(())
+}
+
+impl<'de> Visitor<'de> for KeyReprVisitor {
+    type Value = KeyRepr;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "struct {}", self.name)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<KeyRepr, A::Error> {
+        let version: u32 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        check_version(version)?;
+        let kind = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let mut data = Vec::with_capacity(self.fields.len() - 2);
+        for i in 2..self.fields.len() {
+            let key = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+            data.push(key);
+        }
+        Ok(KeyRepr { kind, data })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<KeyRepr, A::Error> {
+        let mut version = None;
+        let mut kind = None;
+        let mut data: Vec<Option<KeyBuffer>> = self.fields[2..].iter().map(|_| None).collect();
+        while let Some(field) = map.next_key::<String>()? {
+            match self.fields.iter().position(|&name| name == field) {
+                Some(0) => version = Some(map.next_value()?),
+                Some(1) => kind = Some(map.next_value()?),
+                Some(i) => data[i - 2] = Some(map.next_value()?),
+                None => return Err(de::Error::unknown_field(&field, self.fields)),
+            }
+        }
+        let version = version.ok_or_else(|| de::Error::missing_field("version"))?;
+        check_version(version)?;
+        let kind = kind.ok_or_else(|| de::Error::missing_field("kind"))?;
+        let data = data
+            .into_iter()
+            .zip(self.fields[2..].iter())
+            .map(|(key, &name)| key.ok_or_else(|| de::Error::missing_field(name)))
+            .collect::<Result<_, _>>()?;
+        Ok(KeyRepr { kind, data })
+    }
+}
+
+fn deserialize_key<'de, D>(deserializer: D, name: &'static str) -> Result<KeyRepr, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let visitor = KeyReprVisitor {
+        name,
+        fields: KEY_FIELDS,
+    };
+    deserializer.deserialize_struct(name, KEY_FIELDS, visitor)
+}
+
+fn deserialize_key_pair<'de, D>(deserializer: D, name: &'static str) -> Result<KeyRepr, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let visitor = KeyReprVisitor {
+        name,
+        fields: KEY_PAIR_FIELDS,
+    };
+    deserializer.deserialize_struct(name, KEY_PAIR_FIELDS, visitor)
+}
+
+fn check_kind<E: de::Error>(repr: &KeyRepr, expected: &'static str) -> Result<(), E> {
+    if repr.kind != expected {
+        return Err(E::invalid_value(de::Unexpected::Str(&repr.kind), &expected));
+    }
+    Ok(())
+}
+
+//
+// Key types
+//
+
+macro_rules! impl_serde_for_key {
+    ($key:ident, $kind:expr) => {
+        impl Serialize for $key {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serialize_key(serializer, stringify!($key), $kind, self.as_ref())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $key {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let repr = deserialize_key(deserializer, stringify!($key))?;
+                check_kind(&repr, key_kind_name($kind))?;
+                $key::try_from_slice(&repr.data[0].0).map_err(de::Error::custom)
+            }
+        }
+    };
+}
+
+impl_serde_for_key!(RsaSecretKey, KeyKind::RsaSecret);
+impl_serde_for_key!(RsaPublicKey, KeyKind::RsaPublic);
+impl_serde_for_key!(EcdsaSecretKey, KeyKind::EcdsaSecret);
+impl_serde_for_key!(EcdsaPublicKey, KeyKind::EcdsaPublic);
+
+macro_rules! impl_serde_for_generic_key {
+    ($key:ident) => {
+        impl Serialize for $key {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serialize_key(serializer, stringify!($key), self.kind(), self.as_ref())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $key {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let repr = deserialize_key(deserializer, stringify!($key))?;
+                let key = $key::try_from_slice(&repr.data[0].0).map_err(de::Error::custom)?;
+                check_kind(&repr, key_kind_name(key.kind()))?;
+                Ok(key)
+            }
+        }
+    };
+}
+
+impl_serde_for_generic_key!(SecretKey);
+impl_serde_for_generic_key!(PublicKey);
+
+//
+// Key pairs
+//
+
+macro_rules! impl_serde_for_key_pair {
+    ($pair:ident, $secret:ident, $public:ident, $kind:expr) => {
+        impl Serialize for $pair {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serialize_key_pair(
+                    serializer,
+                    stringify!($pair),
+                    $kind,
+                    self.secret_key_bytes(),
+                    self.public_key_bytes(),
+                )
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $pair {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let repr = deserialize_key_pair(deserializer, stringify!($pair))?;
+                check_kind(&repr, key_pair_kind_name($kind))?;
+                let secret_key =
+                    $secret::try_from_slice(&repr.data[0].0).map_err(de::Error::custom)?;
+                let public_key =
+                    $public::try_from_slice(&repr.data[1].0).map_err(de::Error::custom)?;
+                Ok($pair::join(secret_key, public_key))
+            }
+        }
+    };
+}
+
+impl_serde_for_key_pair!(RsaKeyPair, RsaSecretKey, RsaPublicKey, KeyKind::RsaSecret);
+impl_serde_for_key_pair!(
+    EcdsaKeyPair,
+    EcdsaSecretKey,
+    EcdsaPublicKey,
+    KeyKind::EcdsaSecret
+);
+
+impl Serialize for KeyPair {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_key_pair(
+            serializer,
+            "KeyPair",
+            self.kind(),
+            self.secret_key_bytes(),
+            self.public_key_bytes(),
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyPair {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = deserialize_key_pair(deserializer, "KeyPair")?;
+        let secret_key = SecretKey::try_from_slice(&repr.data[0].0).map_err(de::Error::custom)?;
+        let public_key = PublicKey::try_from_slice(&repr.data[1].0).map_err(de::Error::custom)?;
+        check_kind(&repr, key_pair_kind_name(secret_key.kind()))?;
+        KeyPair::try_join(secret_key, public_key).map_err(de::Error::custom)
+    }
+}
+
+//
+// Managed keys
+//
+
+const MANAGED_KEY_FIELDS: &[&str] = &["version", "id", "created", "expires", "key"];
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn managed_key<K>(id: String, created: u64, expires: Option<u64>, key: K) -> ManagedKey<K> {
+    let created = UNIX_EPOCH + Duration::from_secs(created);
+    let managed = ManagedKey::with_creation_time(id, key, created);
+    match expires {
+        Some(expires) => managed.expires_at(UNIX_EPOCH + Duration::from_secs(expires)),
+        None => managed,
+    }
+}
+
+impl<K: Serialize> Serialize for ManagedKey<K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ManagedKey", MANAGED_KEY_FIELDS.len())?;
+        state.serialize_field("version", &FORMAT_VERSION)?;
+        state.serialize_field("id", self.id())?;
+        state.serialize_field("created", &unix_seconds(self.created()))?;
+        state.serialize_field("expires", &self.expires().map(unix_seconds))?;
+        state.serialize_field("key", self.key())?;
+        state.end()
+    }
+}
+
+struct ManagedKeyVisitor<K>(PhantomData<K>);
+
+impl<'de, K: Deserialize<'de>> Visitor<'de> for ManagedKeyVisitor<K> {
+    type Value = ManagedKey<K>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("struct ManagedKey")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ManagedKey<K>, A::Error> {
+        let version: u32 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        check_version(version)?;
+        let id = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let created = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        let expires = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+        let key = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(4, &self))?;
+        Ok(managed_key(id, created, expires, key))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<ManagedKey<K>, A::Error> {
+        let mut version = None;
+        let mut id = None;
+        let mut created = None;
+        let mut expires = None;
+        let mut key = None;
+        while let Some(field) = map.next_key::<String>()? {
+            match field.as_str() {
+                "version" => version = Some(map.next_value()?),
+                "id" => id = Some(map.next_value()?),
+                "created" => created = Some(map.next_value()?),
+                "expires" => expires = Some(map.next_value()?),
+                "key" => key = Some(map.next_value()?),
+                _ => return Err(de::Error::unknown_field(&field, MANAGED_KEY_FIELDS)),
+            }
+        }
+        let version = version.ok_or_else(|| de::Error::missing_field("version"))?;
+        check_version(version)?;
+        let id = id.ok_or_else(|| de::Error::missing_field("id"))?;
+        let created = created.ok_or_else(|| de::Error::missing_field("created"))?;
+        let key = key.ok_or_else(|| de::Error::missing_field("key"))?;
+        Ok(managed_key(id, created, expires.unwrap_or(None), key))
+    }
+}
+
+impl<'de, K: Deserialize<'de>> Deserialize<'de> for ManagedKey<K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let visitor = ManagedKeyVisitor(PhantomData);
+        deserializer.deserialize_struct("ManagedKey", MANAGED_KEY_FIELDS, visitor)
+    }
+}
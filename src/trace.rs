@@ -0,0 +1,87 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Diagnostic tracing.
+//!
+//! With `tracing` feature enabled Themis emits `tracing` spans and events for notable things
+//! that happen to Secure Session. Without it all of this compiles down to nothing.
+
+use crate::error::Error;
+
+#[cfg(feature = "tracing")]
+pub struct SessionTrace {
+    span: tracing::Span,
+    established: bool,
+}
+
+#[cfg(feature = "tracing")]
+impl SessionTrace {
+    pub fn new(id: &[u8]) -> Self {
+        let span = tracing::debug_span!("secure_session", id = %String::from_utf8_lossy(id));
+        span.in_scope(|| tracing::debug!("session created"));
+        Self {
+            span,
+            established: false,
+        }
+    }
+
+    pub fn connect_requested(&self) {
+        self.span
+            .in_scope(|| tracing::debug!("connection requested"));
+    }
+
+    pub fn negotiation_step(&self) {
+        self.span.in_scope(|| tracing::trace!("negotiation step"));
+    }
+
+    pub fn is_established(&self) -> bool {
+        self.established
+    }
+
+    pub fn established(&mut self, peer_id: &[u8]) {
+        self.established = true;
+        self.span.in_scope(
+            || tracing::debug!(peer_id = %String::from_utf8_lossy(peer_id), "session established"),
+        );
+    }
+
+    pub fn unwrap_failed(&self, error: &Error) {
+        self.span
+            .in_scope(|| tracing::warn!(error = %error, "failed to unwrap message"));
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub struct SessionTrace;
+
+#[cfg(not(feature = "tracing"))]
+impl SessionTrace {
+    pub fn new(_id: &[u8]) -> Self {
+        SessionTrace
+    }
+
+    pub fn connect_requested(&self) {}
+
+    pub fn negotiation_step(&self) {}
+
+    // Pretend that we have already reported establishment so that the caller does not bother
+    // with looking up the peer ID.
+    pub fn is_established(&self) -> bool {
+        true
+    }
+
+    pub fn established(&mut self, _peer_id: &[u8]) {}
+
+    pub fn unwrap_failed(&self, _error: &Error) {}
+}
@@ -28,3 +28,14 @@ pub fn into_raw_parts(slice: &[u8]) -> (*const u8, usize) {
     };
     (ptr, len)
 }
+
+/// Compares two byte slices in constant time.
+///
+/// The time depends only on the length of the slices, not on their contents.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let difference = a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y));
+    difference == 0
+}
@@ -0,0 +1,67 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Version of native Themis library.
+//!
+//! These functions report the version of Themis and Soter which the application is actually
+//! running with. It may differ from the one Rust code was compiled against if Themis is
+//! linked dynamically.
+//!
+//! # Examples
+//!
+//! ```
+//! use themis::version;
+//!
+//! println!("using {}", version::soter_version());
+//! ```
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+#[cfg(not(feature = "soter-only"))]
+use std::sync::Once;
+
+use bindings::soter_version as soter_version_ptr;
+#[cfg(not(feature = "soter-only"))]
+use bindings::themis_version as themis_version_ptr;
+
+/// Returns version string of Themis library, including Soter version.
+///
+/// The format of the string is not specified and may change between Themis releases.
+/// Currently it looks like `"themis 0.9: soter 0.9: "`.
+#[cfg(not(feature = "soter-only"))]
+pub fn themis_version() -> &'static str {
+    static INIT: Once = Once::new();
+    static mut VERSION: *const c_char = std::ptr::null();
+    // Themis assembles the string in a static buffer on each call, so make sure that
+    // it happens only once and concurrent callers do not race with each other.
+    unsafe {
+        INIT.call_once(|| VERSION = themis_version_ptr());
+        static_str(VERSION)
+    }
+}
+
+/// Returns version string of Soter library.
+///
+/// The format of the string is not specified and may change between Themis releases.
+/// Currently it looks like `"soter 0.9: "`.
+pub fn soter_version() -> &'static str {
+    unsafe { static_str(soter_version_ptr()) }
+}
+
+unsafe fn static_str(ptr: *const c_char) -> &'static str {
+    if ptr.is_null() {
+        return "";
+    }
+    CStr::from_ptr(ptr).to_str().unwrap_or("")
+}
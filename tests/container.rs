@@ -0,0 +1,104 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use themis::container::{self, Container, Header, HEADER_LEN};
+use themis::keygen::gen_ec_key_pair;
+use themis::ErrorKind;
+
+const TEST_CONTAINER: &[u8] = b"TEST\x00\x00\x00\x13\x07\x9b\x3f\xf1payload";
+
+#[test]
+fn build_known_container() {
+    let bytes = container::build(b"TEST", b"payload").unwrap();
+
+    assert_eq!(bytes, TEST_CONTAINER);
+}
+
+#[test]
+fn parse_known_container() {
+    let container = Container::parse(TEST_CONTAINER).unwrap();
+
+    assert_eq!(container.tag(), b"TEST");
+    assert_eq!(container.payload(), b"payload");
+    assert_eq!(container.as_bytes(), TEST_CONTAINER);
+    assert_eq!(container.header().size(), TEST_CONTAINER.len());
+    assert_eq!(container.header().payload_len(), 7);
+    assert_eq!(container.header().checksum(), 0x079b_3ff1);
+}
+
+#[test]
+fn empty_payload() {
+    let bytes = container::build(b"NONE", b"").unwrap();
+    let container = Container::parse(&bytes).unwrap();
+
+    assert_eq!(bytes.len(), HEADER_LEN);
+    assert!(container.payload().is_empty());
+}
+
+#[test]
+fn parse_keys() {
+    let (_, public) = gen_ec_key_pair().split();
+
+    let container = Container::parse(public.as_ref()).unwrap();
+
+    assert_eq!(container.tag(), b"UEC2");
+}
+
+#[test]
+fn parse_prefix() {
+    let mut bytes = TEST_CONTAINER.to_vec();
+    bytes.extend_from_slice(b"trailing data");
+
+    let (container, rest) = Container::parse_prefix(&bytes).unwrap();
+
+    assert_eq!(container.payload(), b"payload");
+    assert_eq!(rest, b"trailing data");
+
+    let error = Container::parse(&bytes).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+}
+
+#[test]
+fn corrupted_container() {
+    let mut bytes = TEST_CONTAINER.to_vec();
+    bytes[15] ^= 0x01;
+
+    let error = Container::parse(&bytes).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::DataCorrupt);
+}
+
+#[test]
+fn truncated_container() {
+    let bytes = &TEST_CONTAINER[..TEST_CONTAINER.len() - 1];
+
+    let header = Header::read(bytes).unwrap();
+    assert_eq!(header.size(), TEST_CONTAINER.len());
+
+    let error = Container::parse(bytes).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+
+    let error = Header::read(&bytes[..HEADER_LEN - 1]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+}
+
+#[test]
+fn invalid_size() {
+    let mut bytes = TEST_CONTAINER.to_vec();
+    bytes[7] = 0x0B;
+
+    let error = Header::read(&bytes).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+}
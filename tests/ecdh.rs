@@ -0,0 +1,73 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use themis::container::{self, Container};
+use themis::ecdh::derive_shared_key;
+use themis::kdf::DERIVED_KEY_LEN;
+use themis::keygen::gen_ec_key_pair;
+use themis::keys::EcdsaPublicKey;
+use themis::ErrorKind;
+
+const NO_CONTEXT: &[&[u8]] = &[];
+
+#[test]
+fn both_parties_agree() {
+    let (secret_a, public_a) = gen_ec_key_pair().split();
+    let (secret_b, public_b) = gen_ec_key_pair().split();
+
+    let key_a = derive_shared_key(&secret_a, &public_b, "test", &[b"context"]).unwrap();
+    let key_b = derive_shared_key(&secret_b, &public_a, "test", &[b"context"]).unwrap();
+
+    assert_eq!(key_a, key_b);
+    assert_eq!(key_a.len(), DERIVED_KEY_LEN);
+}
+
+#[test]
+fn different_purposes() {
+    let (secret_a, _) = gen_ec_key_pair().split();
+    let (_, public_b) = gen_ec_key_pair().split();
+
+    let key_1 = derive_shared_key(&secret_a, &public_b, "one", NO_CONTEXT).unwrap();
+    let key_2 = derive_shared_key(&secret_a, &public_b, "two", NO_CONTEXT).unwrap();
+    let key_3 = derive_shared_key(&secret_a, &public_b, "one", &[b"context"]).unwrap();
+
+    assert_ne!(key_1, key_2);
+    assert_ne!(key_1, key_3);
+}
+
+#[test]
+fn different_peers() {
+    let (secret_a, _) = gen_ec_key_pair().split();
+    let (_, public_b) = gen_ec_key_pair().split();
+    let (_, public_c) = gen_ec_key_pair().split();
+
+    let key_b = derive_shared_key(&secret_a, &public_b, "test", NO_CONTEXT).unwrap();
+    let key_c = derive_shared_key(&secret_a, &public_c, "test", NO_CONTEXT).unwrap();
+
+    assert_ne!(key_b, key_c);
+}
+
+#[test]
+fn mismatched_curves() {
+    let (secret_a, _) = gen_ec_key_pair().split();
+    let (_, public_b) = gen_ec_key_pair().split();
+
+    // P-384 tag with P-256 data is rejected before looking at the data.
+    let payload = Container::parse(public_b.as_ref()).unwrap().payload();
+    let bytes = container::build(b"UEC3", payload).unwrap();
+    let public_b = EcdsaPublicKey::try_from_slice(&bytes).unwrap();
+
+    let error = derive_shared_key(&secret_a, &public_b, "test", NO_CONTEXT).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+}
@@ -0,0 +1,50 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+
+use themis::{Error, ErrorKind};
+
+#[test]
+fn error_is_thread_safe() {
+    fn assert_send_sync_static<T: Send + Sync + 'static>() {}
+
+    assert_send_sync_static::<Error>();
+}
+
+#[test]
+fn convert_into_io_error() {
+    let io_error: io::Error = Error::from(ErrorKind::DataCorrupt).into();
+    assert_eq!(io_error.kind(), io::ErrorKind::InvalidData);
+
+    let inner = io_error.get_ref().and_then(|e| e.downcast_ref::<Error>());
+    assert_eq!(inner.map(Error::kind), Some(ErrorKind::DataCorrupt));
+
+    let io_error: io::Error = Error::from(ErrorKind::InvalidParameter).into();
+    assert_eq!(io_error.kind(), io::ErrorKind::InvalidInput);
+
+    let io_error: io::Error = Error::from(ErrorKind::NoMemory).into();
+    assert_eq!(io_error.kind(), io::ErrorKind::Other);
+}
+
+#[test]
+fn propagate_with_question_mark() {
+    fn decrypt() -> io::Result<Vec<u8>> {
+        let cell = themis::secure_cell::SecureCell::with_key(b"key").seal();
+        Ok(cell.decrypt(b"garbage")?)
+    }
+
+    let error = decrypt().expect_err("decryption failure");
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,98 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{self, Write};
+
+use themis::hash::{HashAlgorithm, HashWriter, SecureHash};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn sha256_test_vector() {
+    let digest = SecureHash::digest(HashAlgorithm::Sha256, b"abc").unwrap();
+    assert_eq!(
+        hex(&digest),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
+
+#[test]
+fn sha512_test_vector() {
+    let digest = SecureHash::digest(HashAlgorithm::Sha512, b"abc").unwrap();
+    assert_eq!(
+        hex(&digest),
+        "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+         2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+    );
+}
+
+#[test]
+fn incremental_update() {
+    let mut hash = SecureHash::sha512();
+    hash.update(b"a").unwrap();
+    hash.update(b"").unwrap();
+    hash.update(b"bc").unwrap();
+    assert_eq!(hash.algorithm(), HashAlgorithm::Sha512);
+
+    let digest = hash.finalize().unwrap();
+    assert_eq!(digest.len(), HashAlgorithm::Sha512.output_len());
+    assert_eq!(
+        digest,
+        SecureHash::digest(HashAlgorithm::Sha512, b"abc").unwrap()
+    );
+}
+
+// Writer which accepts at most two bytes at a time.
+struct SlowWriter(Vec<u8>);
+
+impl Write for SlowWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(2);
+        self.0.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn hash_writer() {
+    let mut writer = HashWriter::new(SlowWriter(Vec::new()), SecureHash::sha256());
+    writer.write_all(b"ab").unwrap();
+    writer.write_all(b"c").unwrap();
+    assert_eq!(writer.get_ref().0, b"abc");
+
+    let (inner, digest) = writer.finalize().unwrap();
+    assert_eq!(inner.0, b"abc");
+    assert_eq!(
+        digest,
+        SecureHash::digest(HashAlgorithm::Sha256, b"abc").unwrap()
+    );
+}
+
+#[test]
+fn hash_writer_counts_accepted_bytes() {
+    let mut writer = HashWriter::new(SlowWriter(Vec::new()), SecureHash::sha256());
+    assert_eq!(writer.write(b"abc").unwrap(), 2);
+
+    let (_, digest) = writer.finalize().unwrap();
+    assert_eq!(
+        digest,
+        SecureHash::digest(HashAlgorithm::Sha256, b"ab").unwrap()
+    );
+}
@@ -0,0 +1,88 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{self, Write};
+
+use themis::hmac::{HmacWriter, SecureHmac};
+use themis::ErrorKind;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// RFC 4231, test case 2
+const KEY: &[u8] = b"Jefe";
+const DATA: &[u8] = b"what do ya want for nothing?";
+
+#[test]
+fn hmac_sha256_test_vector() {
+    let mut hmac = SecureHmac::sha256(KEY).unwrap();
+    hmac.update(&DATA[..10]).unwrap();
+    hmac.update(&DATA[10..]).unwrap();
+    assert_eq!(
+        hex(&hmac.finalize().unwrap()),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+#[test]
+fn hmac_sha512_test_vector() {
+    let mut hmac = SecureHmac::sha512(KEY).unwrap();
+    hmac.update(DATA).unwrap();
+    assert_eq!(
+        hex(&hmac.finalize().unwrap()),
+        "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+         9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+    );
+}
+
+#[test]
+fn verify_tag() {
+    let mut hmac = SecureHmac::sha256(KEY).unwrap();
+    hmac.update(DATA).unwrap();
+    let mut tag = hmac.finalize().unwrap();
+
+    let mut hmac = SecureHmac::sha256(KEY).unwrap();
+    hmac.update(DATA).unwrap();
+    assert!(hmac.verify(&tag).is_ok());
+
+    tag[5] ^= 0x01;
+    let mut hmac = SecureHmac::sha256(KEY).unwrap();
+    hmac.update(DATA).unwrap();
+    let error = hmac.verify(&tag).expect_err("corrupted tag");
+    assert_eq!(error.kind(), ErrorKind::InvalidSignature);
+
+    let hmac = SecureHmac::sha256(KEY).unwrap();
+    let error = hmac.verify(&tag[..16]).expect_err("truncated tag");
+    assert_eq!(error.kind(), ErrorKind::InvalidSignature);
+}
+
+#[test]
+fn empty_key() {
+    let error = SecureHmac::sha256(b"").err().expect("empty key");
+    assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+}
+
+#[test]
+fn hmac_writer() {
+    let mut writer = HmacWriter::new(io::sink(), SecureHmac::sha256(KEY).unwrap());
+    writer.write_all(&DATA[..10]).unwrap();
+    writer.write_all(&DATA[10..]).unwrap();
+
+    let (_, tag) = writer.finalize().unwrap();
+    assert_eq!(
+        hex(&tag),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
@@ -0,0 +1,149 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(not(feature = "soter-only"))]
+use themis::kdf::KeyDerivation;
+#[cfg(all(soter_pbkdf2, not(feature = "soter-only")))]
+use themis::kdf::PassphraseParams;
+use themis::kdf::{derive_key, DERIVED_KEY_LEN};
+#[cfg(not(feature = "soter-only"))]
+use themis::keys::SymmetricKey;
+use themis::ErrorKind;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn derived_key_is_hmac_of_label_and_context() {
+    // HMAC-SHA-256(key, 00000001 || label || 00 || context...)
+    let context: [&[u8]; 2] = [b"user 42", b"session"];
+    let key = derive_key(b"master secret", "encryption", &context).unwrap();
+    assert_eq!(key.len(), DERIVED_KEY_LEN);
+    assert_eq!(
+        hex(&key),
+        "f6e51883005da869c68ada38a3a76459ff44b61f41da03547af4d1feba96f287"
+    );
+}
+
+#[test]
+fn implicit_key() {
+    let key = derive_key(b"", "identifier", &[b"abc"]).unwrap();
+    assert_eq!(
+        hex(&key),
+        "eacbb78cad1ccbe0080baedccfc3b4ca91413475ca53b7b9848456e94921ca10"
+    );
+}
+
+#[test]
+fn different_purposes() {
+    let no_context: &[&[u8]] = &[];
+    let key1 = derive_key(b"master secret", "one", no_context).unwrap();
+    let key2 = derive_key(b"master secret", "two", no_context).unwrap();
+    let key3 = derive_key(b"master secret", "one", &[b"context"]).unwrap();
+    assert_ne!(key1, key2);
+    assert_ne!(key1, key3);
+    assert_eq!(
+        key1,
+        derive_key(b"master secret", "one", no_context).unwrap()
+    );
+}
+
+#[test]
+fn invalid_label() {
+    let error = derive_key(b"master secret", "bad\0label", &[b"context"]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+}
+
+#[test]
+#[cfg(not(feature = "soter-only"))]
+fn derived_symmetric_keys_are_stable() {
+    let master = KeyDerivation::new(b"master secret").unwrap();
+    let key: SymmetricKey = master.derive("storage").unwrap();
+    assert_eq!(
+        hex(key.as_ref()),
+        "7c8c1b4266bfcf9ca5cbf3c5d00143354607d00e99df8c61f26913beea6e9242"
+    );
+    assert_eq!(
+        key.as_ref(),
+        &derive_key(b"master secret", "storage", &["SymmetricKey"]).unwrap()[..]
+    );
+}
+
+#[test]
+#[cfg(not(feature = "soter-only"))]
+fn derived_symmetric_keys_are_purpose_bound() {
+    let master = KeyDerivation::new(b"master secret").unwrap();
+    let key1: SymmetricKey = master.derive("one").unwrap();
+    let key2: SymmetricKey = master.derive("two").unwrap();
+    assert_ne!(key1, key2);
+    assert_eq!(key1, master.derive("one").unwrap());
+}
+
+#[test]
+#[cfg(not(feature = "soter-only"))]
+fn empty_master_key() {
+    let error = KeyDerivation::new(b"").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+}
+
+#[cfg(all(soter_pbkdf2, not(feature = "soter-only")))]
+mod passphrase {
+    use super::*;
+
+    #[test]
+    fn derived_keys_are_pbkdf2() {
+        // Test vectors from RFC 7914, truncated to DERIVED_KEY_LEN.
+        let params = PassphraseParams::with_iterations(1);
+        let key = SymmetricKey::derive_from_passphrase("passwd", b"salt", params).unwrap();
+        assert_eq!(
+            hex(key.as_ref()),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc"
+        );
+
+        let params = PassphraseParams::with_iterations(80_000);
+        let key = SymmetricKey::derive_from_passphrase("Password", b"NaCl", params).unwrap();
+        assert_eq!(
+            hex(key.as_ref()),
+            "4ddcd8f60b98be21830cee5ef22701f9641a4418d04c0414aeff08876b34ab56"
+        );
+    }
+
+    #[test]
+    fn default_params() {
+        let params = PassphraseParams::default();
+        assert_eq!(params.iterations(), PassphraseParams::DEFAULT_ITERATIONS);
+    }
+
+    #[test]
+    fn salt_matters() {
+        let params = PassphraseParams::with_iterations(10);
+        let key1 = SymmetricKey::derive_from_passphrase("passphrase", b"salt 1", params).unwrap();
+        let key2 = SymmetricKey::derive_from_passphrase("passphrase", b"salt 2", params).unwrap();
+        assert_ne!(key1, key2);
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        let params = PassphraseParams::with_iterations(10);
+        let error = SymmetricKey::derive_from_passphrase("", b"salt", params).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+        let error = SymmetricKey::derive_from_passphrase("passphrase", b"", params).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+        let params = PassphraseParams::with_iterations(0);
+        let error =
+            SymmetricKey::derive_from_passphrase("passphrase", b"salt", params).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use themis::keygen::gen_ec_key_pair;
+use themis::keyring::KeyRing;
+use themis::keys::{KeyPair, SecretKey};
+use themis::keystore::{KeyStorage, ManagedKey, MemoryKeyStorage};
+use themis::secure_cell::{SecureCell, SecureCellRing};
+use themis::secure_message::{SecureMessageRing, SecureSignRing, SecureVerifyRing};
+use themis::ErrorKind;
+
+#[test]
+fn current_key_skips_expired() {
+    let mut ring = KeyRing::new();
+    assert!(ring.current().is_none());
+
+    let long_ago = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    let v1 = ring.add(ManagedKey::new("first", 1));
+    let v2 = ring.add(ManagedKey::new("second", 2).expires_at(long_ago));
+    assert_eq!((v1, v2), (1, 2));
+
+    let (version, key) = ring.current().unwrap();
+    assert_eq!(version, 1);
+    assert_eq!(key.id(), "first");
+
+    assert_eq!(ring.remove_expired(), 1);
+    assert!(ring.get(2).is_none());
+    assert_eq!(ring.len(), 1);
+
+    ring.insert(10, ManagedKey::new("restored", 10));
+    assert_eq!(ring.add(ManagedKey::new("next", 11)), 11);
+    assert_eq!(ring.iter().map(|(v, _)| v).collect::<Vec<_>>(), [1, 10, 11]);
+}
+
+#[test]
+fn secure_cell_rotation() {
+    let mut ring = KeyRing::new();
+    ring.add(ManagedKey::new("old", b"old password".to_vec()));
+    let mut cell = SecureCellRing::with_context(ring, b"context");
+
+    let old_data = cell.encrypt(b"old data").unwrap();
+    cell.key_ring_mut()
+        .add(ManagedKey::new("new", b"new password".to_vec()));
+    let new_data = cell.encrypt(b"new data").unwrap();
+
+    assert_eq!(cell.decrypt(&old_data).unwrap(), b"old data");
+    assert_eq!(cell.decrypt(&new_data).unwrap(), b"new data");
+
+    // Versioned cell is a Secure Cell prefixed with version.
+    assert_eq!(&new_data[..4], &[0, 0, 0, 2]);
+    let plain = SecureCell::with_key_and_context(b"new password", b"context").seal();
+    assert_eq!(plain.decrypt(&new_data[4..]).unwrap(), b"new data");
+
+    cell.key_ring_mut().remove(1);
+    let error = cell.decrypt(&old_data).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::KeyRingUnknownVersion);
+
+    let error = cell.decrypt([0, 0]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::DataCorrupt);
+}
+
+#[test]
+fn expired_ring_cannot_encrypt() {
+    let mut ring = KeyRing::new();
+    ring.add(ManagedKey::new("key", b"password".to_vec()).expires_at(SystemTime::now()));
+    let cell = SecureCellRing::new(ring);
+
+    let error = cell.encrypt(b"data").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::KeyRingNoCurrentKey);
+}
+
+#[test]
+fn secure_message_rotation() {
+    let (secret_a1, public_a1) = gen_ec_key_pair().split();
+    let (secret_b1, public_b1) = gen_ec_key_pair().split();
+    let (secret_a2, public_a2) = gen_ec_key_pair().split();
+    let (secret_b2, public_b2) = gen_ec_key_pair().split();
+
+    let mut ring_a = KeyRing::new();
+    let mut ring_b = KeyRing::new();
+    ring_a.add(ManagedKey::new(
+        "1",
+        KeyPair::try_join(secret_a1, public_b1).unwrap(),
+    ));
+    ring_b.add(ManagedKey::new(
+        "1",
+        KeyPair::try_join(secret_b1, public_a1).unwrap(),
+    ));
+    let mut alice = SecureMessageRing::new(ring_a);
+    let mut bob = SecureMessageRing::new(ring_b);
+
+    let old_message = alice.wrap(b"old message").unwrap();
+
+    alice.key_ring_mut().add(ManagedKey::new(
+        "2",
+        KeyPair::try_join(secret_a2, public_b2).unwrap(),
+    ));
+    bob.key_ring_mut().add(ManagedKey::new(
+        "2",
+        KeyPair::try_join(secret_b2, public_a2).unwrap(),
+    ));
+
+    let new_message = alice.wrap(b"new message").unwrap();
+    assert_eq!(bob.unwrap(&old_message).unwrap(), b"old message");
+    assert_eq!(bob.unwrap(&new_message).unwrap(), b"new message");
+}
+
+#[test]
+fn secure_sign_rotation() {
+    let (secret_1, public_1) = gen_ec_key_pair().split();
+    let (secret_2, public_2) = gen_ec_key_pair().split();
+
+    let mut secret_ring = KeyRing::new();
+    let mut public_ring = KeyRing::new();
+    secret_ring.add(ManagedKey::new("1", SecretKey::from(secret_1)));
+    public_ring.add(ManagedKey::new("1", public_1.into()));
+    let mut sign = SecureSignRing::new(secret_ring);
+    let mut verify = SecureVerifyRing::new(public_ring);
+
+    let old_message = sign.sign(b"old message").unwrap();
+    sign.key_ring_mut()
+        .add(ManagedKey::new("2", secret_2.into()));
+    let new_message = sign.sign(b"new message").unwrap();
+
+    let error = verify.verify(&new_message).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::KeyRingUnknownVersion);
+
+    verify
+        .key_ring_mut()
+        .add(ManagedKey::new("2", public_2.into()));
+    assert_eq!(verify.verify(&old_message).unwrap(), b"old message");
+    assert_eq!(verify.verify(&new_message).unwrap(), b"new message");
+}
+
+#[test]
+fn key_ring_storage() {
+    let created = UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+    let mut ring = KeyRing::new();
+    ring.add(ManagedKey::with_creation_time(
+        "old",
+        gen_ec_key_pair().split().0,
+        created,
+    ));
+    ring.add(
+        ManagedKey::with_creation_time("new", gen_ec_key_pair().split().0, created)
+            .expires_after(Duration::from_secs(60)),
+    );
+
+    let mut storage = MemoryKeyStorage::new();
+    ring.store(&mut storage, "ring").unwrap();
+
+    let loaded = KeyRing::load(&mut storage, "ring").unwrap().unwrap();
+    let loaded = loaded
+        .try_map(|bytes| SecretKey::try_from_slice(bytes))
+        .unwrap();
+    assert_eq!(loaded.len(), 2);
+    for (version, key) in ring.iter() {
+        let other = loaded.get(version).unwrap();
+        assert_eq!(other.id(), key.id());
+        assert_eq!(other.created(), key.created());
+        assert_eq!(other.expires(), key.expires());
+        assert_eq!(other.key().as_ref(), key.key().as_ref());
+    }
+
+    assert!(KeyRing::load(&mut storage, "missing").unwrap().is_none());
+
+    storage.store("ring", b"TKR1garbage").unwrap();
+    let error = KeyRing::load(&mut storage, "ring").unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn debug_output_is_redacted() {
+    let created = UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+    let mut ring = KeyRing::new();
+    ring.add(ManagedKey::with_creation_time(
+        "raw",
+        b"secret".to_vec(),
+        created,
+    ));
+    assert!(!format!("{:?}", ring).contains("115"));
+
+    let mut storage = MemoryKeyStorage::new();
+    ring.store(&mut storage, "ring").unwrap();
+    let loaded = KeyRing::load(&mut storage, "ring").unwrap().unwrap();
+    let debug = format!("{:?}", loaded.get(1).unwrap());
+    assert!(debug.starts_with("ManagedKey { id: \"raw\""));
+    assert!(!debug.contains("115"));
+    assert_eq!(
+        format!("{:?}", loaded.get(1).unwrap().key()),
+        "SecretBytes(6 bytes)"
+    );
+}
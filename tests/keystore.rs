@@ -0,0 +1,153 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+use std::time::{Duration, UNIX_EPOCH};
+
+use themis::keygen::gen_ec_key_pair;
+use themis::keystore::{FileKeyStore, KeyStorage, ManagedKey, MemoryKeyStorage};
+
+#[test]
+fn memory_storage() {
+    let (secret, public) = gen_ec_key_pair().split();
+    let mut storage = MemoryKeyStorage::new();
+
+    storage.store("secret", secret.as_ref()).unwrap();
+    storage.store("public", public.as_ref()).unwrap();
+    assert_eq!(storage.len(), 2);
+
+    let loaded_secret = storage.load_secret_key("secret").unwrap().unwrap();
+    let loaded_public = storage.load_public_key("public").unwrap().unwrap();
+    assert_eq!(loaded_secret.as_ref(), secret.as_ref());
+    assert_eq!(loaded_public.as_ref(), public.as_ref());
+
+    assert!(storage.load("missing").unwrap().is_none());
+    assert!(storage.load_secret_key("missing").unwrap().is_none());
+
+    let error = storage.load_secret_key("public").unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    storage.store("secret", b"replaced").unwrap();
+    assert_eq!(storage.load("secret").unwrap().unwrap(), b"replaced");
+
+    assert!(storage.delete("secret").unwrap());
+    assert!(!storage.delete("secret").unwrap());
+    assert_eq!(storage.len(), 1);
+}
+
+#[test]
+fn file_key_store() {
+    let dir = std::env::temp_dir().join(format!("themis-key-store-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("keys.bin");
+
+    let (secret, public) = gen_ec_key_pair().split();
+
+    let mut store = FileKeyStore::create(&path, b"master key").unwrap();
+    store.add("secret", secret.as_ref()).unwrap();
+    store.add("public", public.as_ref()).unwrap();
+
+    let error = store.add("secret", b"again").expect_err("duplicate key");
+    assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+
+    let error = FileKeyStore::create(&path, b"master key")
+        .err()
+        .expect("file exists");
+    assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+
+    let mut store = FileKeyStore::open(&path, b"master key").unwrap();
+    assert_eq!(store.list().collect::<Vec<_>>(), vec!["public", "secret"]);
+    assert_eq!(store.get("secret"), Some(secret.as_ref()));
+    assert_eq!(
+        store.load_public_key("public").unwrap().unwrap().as_ref(),
+        public.as_ref()
+    );
+
+    assert!(store.remove("public").unwrap());
+    assert!(!store.remove("public").unwrap());
+
+    let store = FileKeyStore::open(&path, b"master key").unwrap();
+    assert_eq!(store.list().collect::<Vec<_>>(), vec!["secret"]);
+
+    let error = FileKeyStore::open(&path, b"wrong key")
+        .err()
+        .expect("wrong key");
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // Stale temporary file with loose permissions is not reused.
+        let temp_path = dir.join("keys.bin.tmp");
+        std::fs::write(&temp_path, b"stale").unwrap();
+        std::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let mut store = FileKeyStore::open(&path, b"master key").unwrap();
+        store.add("public", public.as_ref()).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!temp_path.exists());
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn managed_key_expiry() {
+    let created = UNIX_EPOCH + Duration::from_millis(1_500_000_000_500);
+    let key = ManagedKey::with_creation_time("key", gen_ec_key_pair(), created);
+
+    assert_eq!(key.id(), "key");
+    assert_eq!(
+        key.created(),
+        UNIX_EPOCH + Duration::from_secs(1_500_000_000)
+    );
+    assert_eq!(key.expires(), None);
+    assert!(!key.is_expired());
+
+    let key = key.expires_after(Duration::from_secs(60));
+    let expires = UNIX_EPOCH + Duration::from_secs(1_500_000_060);
+    assert_eq!(key.expires(), Some(expires));
+    assert!(!key.is_expired_at(expires - Duration::from_secs(1)));
+    assert!(key.is_expired_at(expires));
+    assert!(key.is_expired());
+
+    let fresh = ManagedKey::new("fresh", ()).expires_after(Duration::from_secs(60));
+    assert!(!fresh.is_expired());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn managed_key_serde() {
+    let created = UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+    let (secret, _) = gen_ec_key_pair().split();
+    let key = ManagedKey::with_creation_time("key", secret, created)
+        .expires_after(Duration::from_secs(60));
+
+    let json = serde_json::to_string(&key).unwrap();
+    assert!(json.contains(r#""created":1500000000,"expires":1500000060"#));
+    let decoded: ManagedKey<_> = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, key);
+
+    let binary = bincode::serialize(&key).unwrap();
+    let decoded: ManagedKey<_> = bincode::deserialize(&binary).unwrap();
+    assert_eq!(decoded, key);
+
+    let permanent = ManagedKey::with_creation_time("key", (), created);
+    let json = serde_json::to_string(&permanent).unwrap();
+    let decoded: ManagedKey<()> = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, permanent);
+}
@@ -0,0 +1,161 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use themis::keygen::{gen_ec_key_pair, gen_rsa_key_pair};
+use themis::low_level::asym::{rsa_decrypt, rsa_encrypt, Signer, Verifier};
+use themis::low_level::sym::{AesCtr, AesGcmDecryptor, AesGcmEncryptor};
+use themis::ErrorKind;
+
+fn unhex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+// "The Galois/Counter Mode of Operation (GCM)", test case 4
+const GCM_KEY: &str = "feffe9928665731c6d6a8f9467308308";
+const GCM_NONCE: &str = "cafebabefacedbaddecaf888";
+const GCM_AAD: &str = "feedfacedeadbeeffeedfacedeadbeefabaddad2";
+const GCM_PLAINTEXT: &str = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+                             1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39";
+const GCM_CIPHERTEXT: &str = "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+                              21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091";
+const GCM_TAG: &str = "5bc94fbc3221a5db94fae95ae7121a47";
+
+#[test]
+fn aes_gcm_test_vector() {
+    let plaintext = unhex(GCM_PLAINTEXT);
+
+    let mut encryptor = AesGcmEncryptor::new(unhex(GCM_KEY), unhex(GCM_NONCE)).unwrap();
+    encryptor.update_aad(unhex(GCM_AAD)).unwrap();
+    let mut ciphertext = encryptor.update(&plaintext[..20]).unwrap();
+    ciphertext.extend(encryptor.update(&plaintext[20..]).unwrap());
+    let tag = encryptor.finalize().unwrap();
+
+    assert_eq!(ciphertext, unhex(GCM_CIPHERTEXT));
+    assert_eq!(tag, unhex(GCM_TAG));
+
+    let mut decryptor = AesGcmDecryptor::new(unhex(GCM_KEY), unhex(GCM_NONCE)).unwrap();
+    decryptor.update_aad(unhex(GCM_AAD)).unwrap();
+    let decrypted = decryptor.update(&ciphertext).unwrap();
+    decryptor.finalize(&tag).unwrap();
+
+    assert_eq!(decrypted, plaintext);
+}
+
+#[test]
+fn aes_gcm_corrupted_tag() {
+    let mut tag = unhex(GCM_TAG);
+    tag[0] ^= 0x80;
+
+    let mut decryptor = AesGcmDecryptor::new(unhex(GCM_KEY), unhex(GCM_NONCE)).unwrap();
+    decryptor.update_aad(unhex(GCM_AAD)).unwrap();
+    decryptor.update(unhex(GCM_CIPHERTEXT)).unwrap();
+    assert!(decryptor.finalize(&tag).is_err());
+
+    let decryptor = AesGcmDecryptor::new(unhex(GCM_KEY), unhex(GCM_NONCE)).unwrap();
+    let error = decryptor.finalize(&tag[..8]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+}
+
+#[test]
+fn aes_gcm_invalid_parameters() {
+    let error = AesGcmEncryptor::new([0; 20], unhex(GCM_NONCE))
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+
+    let error = AesGcmEncryptor::new(unhex(GCM_KEY), [0; 16]).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::InvalidParameter);
+}
+
+#[test]
+fn aes_ctr_test_vector() {
+    // NIST SP 800-38A, F.5.1 CTR-AES128.Encrypt
+    let key = unhex("2b7e151628aed2a6abf7158809cf4f3c");
+    let iv = unhex("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff");
+    let plaintext = unhex("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51");
+    let ciphertext = unhex("874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff");
+
+    let mut cipher = AesCtr::new(&key, &iv).unwrap();
+    assert_eq!(cipher.apply(&plaintext).unwrap(), ciphertext);
+
+    let mut cipher = AesCtr::new(&key, &iv).unwrap();
+    let mut decrypted = cipher.apply(&ciphertext[..7]).unwrap();
+    decrypted.extend(cipher.apply(&ciphertext[7..]).unwrap());
+    assert_eq!(decrypted, plaintext);
+}
+
+#[test]
+fn rsa_encrypt_decrypt() {
+    let (secret, public) = gen_rsa_key_pair().split();
+    let message = b"short secret message";
+
+    let ciphertext = rsa_encrypt(&public, message).unwrap();
+    let decrypted = rsa_decrypt(&secret, &ciphertext).unwrap();
+
+    assert_eq!(decrypted, message);
+}
+
+#[test]
+fn rsa_decrypt_with_wrong_key() {
+    let (_, public) = gen_rsa_key_pair().split();
+    let (other_secret, _) = gen_rsa_key_pair().split();
+
+    let ciphertext = rsa_encrypt(&public, b"short secret message").unwrap();
+    let error = rsa_decrypt(&other_secret, &ciphertext).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::Fail);
+}
+
+#[test]
+fn ecdsa_sign_verify() {
+    let (secret, public) = gen_ec_key_pair().split();
+
+    let mut signer = Signer::new(secret).unwrap();
+    signer.update(b"some data, ").unwrap();
+    signer.update(b"and more data").unwrap();
+    let signature = signer.finalize().unwrap();
+
+    let mut verifier = Verifier::new(public.clone()).unwrap();
+    verifier.update(b"some data, and more data").unwrap();
+    verifier.finalize(&signature).unwrap();
+
+    let mut verifier = Verifier::new(public).unwrap();
+    verifier.update(b"some data, and MORE data").unwrap();
+    let error = verifier.finalize(&signature).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidSignature);
+}
+
+#[test]
+fn rsa_sign_verify() {
+    let (secret, public) = gen_rsa_key_pair().split();
+    let (_, other_public) = gen_rsa_key_pair().split();
+
+    let mut signer = Signer::new(secret).unwrap();
+    signer.update(b"some data").unwrap();
+    let signature = signer.finalize().unwrap();
+
+    let mut verifier = Verifier::new(public).unwrap();
+    verifier.update(b"some data").unwrap();
+    verifier.finalize(&signature).unwrap();
+
+    let mut verifier = Verifier::new(other_public).unwrap();
+    verifier.update(b"some data").unwrap();
+    let error = verifier.finalize(&signature).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidSignature);
+}
@@ -0,0 +1,52 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use themis::rand;
+
+#[test]
+fn secure_bytes() {
+    let bytes1 = rand::secure_bytes(32).unwrap();
+    let bytes2 = rand::secure_bytes(32).unwrap();
+    assert_eq!(bytes1.len(), 32);
+    assert_ne!(bytes1, bytes2);
+
+    assert!(rand::secure_bytes(0).unwrap().is_empty());
+}
+
+#[test]
+fn fill_buffer() {
+    let mut buffer = [0; 64];
+    rand::fill(&mut buffer).unwrap();
+    assert!(buffer.iter().any(|&b| b != 0));
+
+    rand::fill(&mut []).unwrap();
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn rng_core() {
+    use rand_core::RngCore;
+    use themis::rand::SoterRng;
+
+    fn takes_crypto_rng<R: RngCore + rand_core::CryptoRng>(rng: &mut R) -> u64 {
+        rng.next_u64()
+    }
+
+    let mut rng = SoterRng;
+    assert_ne!(takes_crypto_rng(&mut rng), takes_crypto_rng(&mut rng));
+
+    let mut buffer = [0; 64];
+    rng.try_fill_bytes(&mut buffer).unwrap();
+    assert!(buffer.iter().any(|&b| b != 0));
+}
@@ -192,3 +192,42 @@ mod token_protect {
         assert_eq!(error.kind(), ErrorKind::InvalidParameter);
     }
 }
+
+#[cfg(themis_secure_cell_passphrase)]
+mod seal_with_passphrase {
+    use super::*;
+
+    #[test]
+    fn happy_path() {
+        let cell = SecureCell::with_passphrase_and_context("open sesame", b"123").seal();
+
+        let plaintext = b"example plaintext";
+        let ciphertext = cell.encrypt(&plaintext).unwrap();
+        let recovered = cell.decrypt(&ciphertext).unwrap();
+
+        assert_eq!(recovered, plaintext);
+    }
+
+    #[test]
+    fn invalid_passphrase() {
+        let cell1 = SecureCell::with_passphrase("open sesame").seal();
+        let cell2 = SecureCell::with_passphrase("OPEN SESAME").seal();
+
+        let plaintext = b"example plaintext";
+        let ciphertext = cell1.encrypt(&plaintext).unwrap();
+        let error = cell2.decrypt(&ciphertext).unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::Fail);
+    }
+
+    #[test]
+    fn passphrase_is_not_key() {
+        let cell1 = SecureCell::with_passphrase("open sesame").seal();
+        let cell2 = SecureCell::with_key("open sesame").seal();
+
+        let plaintext = b"example plaintext";
+        let ciphertext = cell1.encrypt(&plaintext).unwrap();
+
+        assert!(cell2.decrypt(&ciphertext).is_err());
+    }
+}
@@ -12,7 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use themis::{secure_comparator::SecureComparator, ErrorKind};
+use std::net::{TcpListener, TcpStream};
+
+use themis::keygen::gen_ec_key_pair;
+use themis::secure_comparator::{
+    ComparatorBuilder, CompareStep, ComparisonInProgress, ComparisonResult, ComparisonStep, Role,
+    SecureComparator,
+};
+use themis::secure_session::{channel_pair, SecureSession};
+use themis::ErrorKind;
 
 #[test]
 fn compare_matching_data() {
@@ -23,10 +31,13 @@ fn compare_matching_data() {
     comparator2.append_secret(b"se-e-ecrets").unwrap();
 
     let data = comparator1.begin_compare().unwrap();
-    let data = comparator2.proceed_compare(&data).unwrap();
-    let data = comparator1.proceed_compare(&data).unwrap();
-    let data = comparator2.proceed_compare(&data).unwrap();
-    let _ata = comparator1.proceed_compare(&data).unwrap();
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    let data = reply(comparator1.proceed_compare(&data).unwrap());
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    assert_eq!(
+        comparator1.proceed_compare(&data).unwrap(),
+        CompareStep::Complete
+    );
 
     assert!(comparator1.is_complete());
     assert!(comparator2.is_complete());
@@ -51,10 +62,13 @@ fn compare_different_data() {
         .unwrap();
 
     let data = comparator1.begin_compare().unwrap();
-    let data = comparator2.proceed_compare(&data).unwrap();
-    let data = comparator1.proceed_compare(&data).unwrap();
-    let data = comparator2.proceed_compare(&data).unwrap();
-    let _ata = comparator1.proceed_compare(&data).unwrap();
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    let data = reply(comparator1.proceed_compare(&data).unwrap());
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    assert_eq!(
+        comparator1.proceed_compare(&data).unwrap(),
+        CompareStep::Complete
+    );
 
     assert!(!comparator1.get_result().unwrap());
     assert!(!comparator2.get_result().unwrap());
@@ -70,10 +84,13 @@ fn split_secrets() {
     comparator2.append_secret(b"123456").unwrap();
 
     let data = comparator1.begin_compare().unwrap();
-    let data = comparator2.proceed_compare(&data).unwrap();
-    let data = comparator1.proceed_compare(&data).unwrap();
-    let data = comparator2.proceed_compare(&data).unwrap();
-    let _ata = comparator1.proceed_compare(&data).unwrap();
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    let data = reply(comparator1.proceed_compare(&data).unwrap());
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    assert_eq!(
+        comparator1.proceed_compare(&data).unwrap(),
+        CompareStep::Complete
+    );
 
     assert!(comparator1.get_result().unwrap());
     assert!(comparator2.get_result().unwrap());
@@ -115,11 +132,14 @@ fn data_corruption() {
     comparator2.append_secret(b"se-e-ecrets").unwrap();
 
     let data = comparator1.begin_compare().unwrap();
-    let mut data = comparator2.proceed_compare(&data).unwrap();
+    let mut data = reply(comparator2.proceed_compare(&data).unwrap());
     data[20] = 42;
-    let data = comparator1.proceed_compare(&data).unwrap();
-    let data = comparator2.proceed_compare(&data).unwrap();
-    let _ata = comparator1.proceed_compare(&data).unwrap();
+    let data = reply(comparator1.proceed_compare(&data).unwrap());
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    assert_eq!(
+        comparator1.proceed_compare(&data).unwrap(),
+        CompareStep::Complete
+    );
 
     assert!(comparator1.get_result().unwrap());
     assert!(comparator2.get_result().unwrap());
@@ -135,10 +155,13 @@ fn reusing_comparators() {
     comparator2.append_secret(b"data").unwrap();
 
     let data = comparator1.begin_compare().unwrap();
-    let data = comparator2.proceed_compare(&data).unwrap();
-    let data = comparator1.proceed_compare(&data).unwrap();
-    let data = comparator2.proceed_compare(&data).unwrap();
-    let _ata = comparator1.proceed_compare(&data).unwrap();
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    let data = reply(comparator1.proceed_compare(&data).unwrap());
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    assert_eq!(
+        comparator1.proceed_compare(&data).unwrap(),
+        CompareStep::Complete
+    );
 
     assert!(!comparator1.get_result().unwrap());
     assert!(!comparator2.get_result().unwrap());
@@ -153,3 +176,214 @@ fn reusing_comparators() {
     assert!(!comparator1.get_result().unwrap());
     assert!(!comparator2.get_result().unwrap());
 }
+
+#[test]
+fn progress_introspection() {
+    let mut comparator1 = SecureComparator::new();
+    let mut comparator2 = SecureComparator::new();
+
+    comparator1.append_secret(b"se-e-ecrets").unwrap();
+    comparator2.append_secret(b"se-e-ecrets").unwrap();
+
+    assert_eq!(comparator1.rounds_completed(), 0);
+    assert!(!comparator1.is_awaiting_peer());
+
+    let data = comparator1.begin_compare().unwrap();
+    assert_eq!(comparator1.rounds_completed(), 1);
+    assert!(comparator1.is_awaiting_peer());
+
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    assert_eq!(comparator2.rounds_completed(), 1);
+    assert!(comparator2.is_awaiting_peer());
+
+    let data = reply(comparator1.proceed_compare(&data).unwrap());
+    assert_eq!(comparator1.rounds_completed(), 2);
+    assert!(comparator1.is_awaiting_peer());
+
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    assert_eq!(comparator2.rounds_completed(), 2);
+    assert!(!comparator2.is_awaiting_peer());
+
+    let mut corrupted = data.clone();
+    corrupted.truncate(10);
+    assert!(comparator1.proceed_compare(&corrupted).is_err());
+    assert_eq!(comparator1.rounds_completed(), 2);
+    assert!(!comparator1.is_awaiting_peer());
+}
+
+#[test]
+fn typestate_comparison() {
+    let client = ComparatorBuilder::new().secret(b"se-e-").secret(b"ecrets");
+    let server = ComparatorBuilder::new().secret(b"se-e-ecrets");
+
+    let (client, data) = client.begin_compare().unwrap();
+    let server = server.accept_compare().unwrap();
+
+    let (server, data) = continue_step(server.proceed_compare(&data).unwrap());
+    let (client, data) = continue_step(client.proceed_compare(&data).unwrap());
+
+    let (server_result, data) = complete_step(server.proceed_compare(&data).unwrap());
+    let (client_result, nothing) = complete_step(client.proceed_compare(data.unwrap()).unwrap());
+
+    assert!(nothing.is_none());
+    assert!(server_result.matches());
+    assert!(client_result.matches());
+}
+
+#[test]
+fn typestate_different_data() {
+    let (client, data) = ComparatorBuilder::new()
+        .secret(b"test")
+        .begin_compare()
+        .unwrap();
+    let server = ComparatorBuilder::new()
+        .secret(b"data")
+        .accept_compare()
+        .unwrap();
+
+    let (server, data) = continue_step(server.proceed_compare(&data).unwrap());
+    let (client, data) = continue_step(client.proceed_compare(&data).unwrap());
+    let (server_result, data) = complete_step(server.proceed_compare(&data).unwrap());
+    let (client_result, _) = complete_step(client.proceed_compare(data.unwrap()).unwrap());
+
+    assert!(!server_result.matches());
+    assert!(!client_result.matches());
+}
+
+fn continue_step(step: ComparisonStep) -> (ComparisonInProgress, Vec<u8>) {
+    match step {
+        ComparisonStep::Continue(comparison, data) => (comparison, data),
+        ComparisonStep::Complete(..) => panic!("comparison completed too early"),
+    }
+}
+
+fn complete_step(step: ComparisonStep) -> (ComparisonResult, Option<Vec<u8>>) {
+    match step {
+        ComparisonStep::Complete(result, data) => (result, data),
+        ComparisonStep::Continue(..) => panic!("comparison not complete"),
+    }
+}
+
+fn reply(step: CompareStep) -> Vec<u8> {
+    match step {
+        CompareStep::SendToPeer(data) => data,
+        CompareStep::Complete => panic!("comparison completed too early"),
+    }
+}
+
+#[test]
+fn compare_over_stream() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut comparator = SecureComparator::new();
+        comparator.append_secret(b"se-e-ecrets").unwrap();
+        comparator.compare_over(&mut stream, Role::Server).unwrap()
+    });
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    let mut comparator = SecureComparator::new();
+    comparator.append_secret(b"se-e-ecrets").unwrap();
+    let client_result = comparator.compare_over(&mut stream, Role::Client).unwrap();
+
+    assert!(client_result);
+    assert!(server.join().unwrap());
+}
+
+#[test]
+fn compare_over_session() {
+    let (secret_client, public_client) = gen_ec_key_pair().split();
+    let (secret_server, public_server) = gen_ec_key_pair().split();
+
+    let (mut transport_client, mut transport_server) = channel_pair();
+    transport_client.add_public_key("server", public_server);
+    transport_server.add_public_key("client", public_client);
+
+    let server = std::thread::spawn(move || {
+        let mut session =
+            SecureSession::with_transport("server", &secret_server, transport_server).unwrap();
+        while !session.is_established() {
+            session.negotiate_transport().expect("server negotiation");
+        }
+        let mut comparator = SecureComparator::new();
+        comparator.append_secret(b"318-142").unwrap();
+        comparator
+            .compare_over_session(&mut session, Role::Server)
+            .unwrap()
+    });
+
+    let mut session =
+        SecureSession::with_transport("client", &secret_client, transport_client).unwrap();
+    session.connect().expect("client-side connection");
+    while !session.is_established() {
+        session.negotiate_transport().expect("client negotiation");
+    }
+    let mut comparator = SecureComparator::new();
+    comparator.append_secret(b"318-142").unwrap();
+    let client_result = comparator
+        .compare_over_session(&mut session, Role::Client)
+        .unwrap();
+
+    assert!(client_result);
+    assert!(server.join().unwrap());
+}
+
+#[test]
+fn builder_with_secrets() {
+    let mut comparator1 = SecureComparator::builder()
+        .secret(b"123")
+        .secret(b"456")
+        .build()
+        .unwrap();
+    let mut comparator2 = SecureComparator::builder()
+        .secret(b"123456")
+        .build()
+        .unwrap();
+
+    let data = comparator1.begin_compare().unwrap();
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    let data = reply(comparator1.proceed_compare(&data).unwrap());
+    let data = reply(comparator2.proceed_compare(&data).unwrap());
+    assert_eq!(
+        comparator1.proceed_compare(&data).unwrap(),
+        CompareStep::Complete
+    );
+
+    assert!(comparator1.get_result().unwrap());
+    assert!(comparator2.get_result().unwrap());
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn comparison_channel() {
+    use futures::executor::block_on;
+    use futures::{SinkExt, StreamExt};
+    use themis::secure_comparator::ComparisonChannel;
+
+    let comparator1 = SecureComparator::builder()
+        .secret(b"se-e-ecrets")
+        .build()
+        .unwrap();
+    let comparator2 = SecureComparator::builder()
+        .secret(b"se-e-ecrets")
+        .build()
+        .unwrap();
+
+    let mut client = ComparisonChannel::new(comparator1, Role::Client).unwrap();
+    let mut server = ComparisonChannel::new(comparator2, Role::Server).unwrap();
+
+    block_on(async {
+        while let Some(request) = client.next().await {
+            server.send(request).await.unwrap();
+            if let Some(reply) = server.next().await {
+                client.send(reply).await.unwrap();
+            }
+        }
+        assert!(server.next().await.is_none());
+    });
+
+    assert!(client.result().unwrap());
+    assert!(server.result().unwrap());
+}
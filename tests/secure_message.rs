@@ -12,9 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
 use themis::keygen::{gen_ec_key_pair, gen_rsa_key_pair};
+use themis::keys::{PrivateKeyHandle, SecretKey};
 use themis::secure_message::{SecureMessage, SecureSign, SecureVerify};
-use themis::ErrorKind;
+use themis::{Error, ErrorKind};
 
 #[test]
 fn mode_encrypt_decrypt() {
@@ -40,6 +44,44 @@ fn mode_sign_verify() {
     assert_eq!(recovered_message, plaintext);
 }
 
+struct CountingHandle {
+    key: Option<SecretKey>,
+    uses: Arc<AtomicUsize>,
+}
+
+impl PrivateKeyHandle for CountingHandle {
+    fn secret_key(&self) -> themis::Result<SecretKey> {
+        self.uses.fetch_add(1, Ordering::SeqCst);
+        self.key
+            .clone()
+            .ok_or_else(|| Error::from(ErrorKind::NotSupported))
+    }
+}
+
+#[test]
+fn sign_with_key_handle() {
+    let (secret, public) = gen_ec_key_pair().split();
+    let uses = Arc::new(AtomicUsize::new(0));
+    let sign = SecureSign::with_key_handle(CountingHandle {
+        key: Some(secret.into()),
+        uses: uses.clone(),
+    });
+    let verify = SecureVerify::new(public);
+
+    let plaintext = b"test message please ignore";
+    let signed_message = sign.sign(plaintext).unwrap();
+    assert_eq!(verify.verify(&signed_message).unwrap(), plaintext);
+    sign.clone().sign(plaintext).unwrap();
+    assert_eq!(uses.load(Ordering::SeqCst), 2);
+
+    let sign = SecureSign::with_key_handle(CountingHandle {
+        key: None,
+        uses: uses.clone(),
+    });
+    let error = sign.sign(plaintext).expect_err("key handle error");
+    assert_eq!(error.kind(), ErrorKind::NotSupported);
+}
+
 #[test]
 fn invalid_key() {
     let secure1 = SecureMessage::new(gen_ec_key_pair());
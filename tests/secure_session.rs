@@ -12,103 +12,65 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::BTreeMap;
+use std::cell::RefCell;
+use std::error::Error;
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
 use std::rc::Rc;
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use themis::keygen::gen_ec_key_pair;
-use themis::keys::EcdsaPublicKey;
-use themis::secure_session::{SecureSession, SecureSessionTransport};
+use themis::keygen::{gen_ec_key_pair, gen_rsa_key_pair};
+use themis::keys::{EcdsaPublicKey, SecretKey};
+use themis::secure_session::{
+    channel_pair, read_frame, write_frame, SecureSession, SecureSessionRegistry,
+    SecureSessionState, SecureSessionStream, SecureSessionTransport, StaticKeyResolver,
+    TcpTransport, Unwrapped, FRAME_HEADER_SIZE, MAX_FRAME_SIZE,
+};
+use themis::ErrorKind;
 
-struct DummyTransport {
-    key_map: Rc<BTreeMap<Vec<u8>, EcdsaPublicKey>>,
+struct RecordingTransport {
+    key_map: StaticKeyResolver,
+    states: Vec<SecureSessionState>,
 }
 
-impl DummyTransport {
-    fn new(key_map: &Rc<BTreeMap<Vec<u8>, EcdsaPublicKey>>) -> Self {
+impl RecordingTransport {
+    fn new(key_map: &StaticKeyResolver) -> Self {
         Self {
             key_map: key_map.clone(),
+            states: Vec::new(),
         }
     }
 }
 
-impl SecureSessionTransport for DummyTransport {
-    fn get_public_key_for_id(&mut self, id: &[u8]) -> Option<EcdsaPublicKey> {
-        self.key_map.get(id).cloned()
-    }
-}
-
-struct ChannelTransport {
-    key_map: Rc<BTreeMap<Vec<u8>, EcdsaPublicKey>>,
-    tx: Sender<Vec<u8>>,
-    rx: Receiver<Vec<u8>>,
-}
-
-impl ChannelTransport {
-    #[allow(clippy::new_ret_no_self)]
-    fn new(key_map: &Rc<BTreeMap<Vec<u8>, EcdsaPublicKey>>) -> (Self, Self) {
-        let (tx12, rx21) = channel();
-        let (tx21, rx12) = channel();
-
-        let transport1 = Self {
-            key_map: key_map.clone(),
-            tx: tx12,
-            rx: rx12,
-        };
-        let transport2 = Self {
-            key_map: key_map.clone(),
-            tx: tx21,
-            rx: rx21,
-        };
-
-        (transport1, transport2)
-    }
-}
-
-impl SecureSessionTransport for ChannelTransport {
-    fn send_data(&mut self, data: &[u8]) -> Result<usize, ()> {
-        self.tx
-            .send(data.to_vec())
-            .map(|_| data.len())
-            .map_err(|_| ())
-    }
-
-    fn receive_data(&mut self, data: &mut [u8]) -> Result<usize, ()> {
-        let msg = self.rx.recv().map_err(|_| ())?;
-        if msg.len() > data.len() {
-            return Err(());
-        }
-        data[0..msg.len()].copy_from_slice(&msg);
-        Ok(msg.len())
+impl SecureSessionTransport for RecordingTransport {
+    fn state_changed(&mut self, state: SecureSessionState) {
+        self.states.push(state);
     }
 
     fn get_public_key_for_id(&mut self, id: &[u8]) -> Option<EcdsaPublicKey> {
-        self.key_map.get(id).cloned()
+        self.key_map.resolve(id)
     }
 }
 
 #[test]
 fn no_transport() {
     // Peer credentials. Secure Session supports only ECDSA.
-    // TODO: tests that confirm RSA failure
     let (secret_client, public_client) = gen_ec_key_pair().split();
     let (secret_server, public_server) = gen_ec_key_pair().split();
     let (name_client, name_server) = ("client", "server");
 
     // Shared storage of public peer credentials. These should be communicated between
     // the peers beforehand in some unspecified trusted way.
-    let mut key_map = BTreeMap::new();
-    key_map.insert(name_client.as_bytes().to_vec(), public_client);
-    key_map.insert(name_server.as_bytes().to_vec(), public_server);
-    let key_map = Rc::new(key_map);
+    let mut key_map = StaticKeyResolver::new();
+    key_map.insert(name_client, public_client);
+    key_map.insert(name_server, public_server);
 
     // The client and the server.
     let mut client =
-        SecureSession::with_transport(name_client, &secret_client, DummyTransport::new(&key_map))
-            .unwrap();
+        SecureSession::with_transport(name_client, &secret_client, key_map.clone()).unwrap();
     let mut server =
-        SecureSession::with_transport(name_server, &secret_server, DummyTransport::new(&key_map))
-            .unwrap();
+        SecureSession::with_transport(name_server, &secret_server, key_map.clone()).unwrap();
 
     assert!(!client.is_established());
     assert!(!server.is_established());
@@ -159,20 +121,15 @@ fn no_transport() {
 #[test]
 fn with_transport() {
     // Peer credentials. Secure Session supports only ECDSA.
-    // TODO: tests that confirm RSA failure
     let (secret_client, public_client) = gen_ec_key_pair().split();
     let (secret_server, public_server) = gen_ec_key_pair().split();
     let (name_client, name_server) = ("client", "server");
 
-    // Shared storage of public peer credentials. These should be communicated between
-    // the peers beforehand in some unspecified trusted way.
-    let mut key_map = BTreeMap::new();
-    key_map.insert(name_client.as_bytes().to_vec(), public_client);
-    key_map.insert(name_server.as_bytes().to_vec(), public_server);
-    let key_map = Rc::new(key_map);
-
-    // The client and the server.
-    let (transport_client, transport_server) = ChannelTransport::new(&key_map);
+    // Public peer credentials. These should be communicated between the peers beforehand
+    // in some unspecified trusted way.
+    let (mut transport_client, mut transport_server) = channel_pair();
+    transport_client.add_public_key(name_server, public_server);
+    transport_server.add_public_key(name_client, public_client);
     let mut client =
         SecureSession::with_transport(name_client, &secret_client, transport_client).unwrap();
     let mut server =
@@ -199,3 +156,948 @@ fn with_transport() {
 
     assert_eq!(received, message);
 }
+
+#[test]
+fn with_key_pair() {
+    let key_pair = gen_ec_key_pair();
+    let key_map = StaticKeyResolver::new();
+
+    let session =
+        SecureSession::with_key_pair("client", &key_pair, key_map.clone()).expect("ECDSA key pair");
+
+    assert!(!session.is_established());
+}
+
+#[test]
+fn rsa_keys_not_supported() {
+    let (secret_rsa, _) = gen_rsa_key_pair().split();
+    let (secret_ec, _) = gen_ec_key_pair().split();
+    let key_map = StaticKeyResolver::new();
+
+    let error =
+        SecureSession::with_secret_key("client", &SecretKey::from(secret_rsa), key_map.clone())
+            .err()
+            .expect("RSA key");
+    assert_eq!(error.kind(), ErrorKind::NotSupported);
+
+    SecureSession::with_secret_key("client", &SecretKey::from(secret_ec), key_map.clone())
+        .expect("ECDSA key");
+}
+
+#[test]
+fn shared_transport() {
+    let (secret_client, public_client) = gen_ec_key_pair().split();
+    let (secret_server, public_server) = gen_ec_key_pair().split();
+    let (name_client, name_server) = ("client", "server");
+
+    let mut key_map = StaticKeyResolver::new();
+    key_map.insert(name_client, public_client);
+    key_map.insert(name_server, public_server);
+
+    let transport_client = Rc::new(RefCell::new(RecordingTransport::new(&key_map)));
+    let transport_server = Arc::new(Mutex::new(RecordingTransport::new(&key_map)));
+
+    let mut client =
+        SecureSession::with_transport(name_client, &secret_client, transport_client.clone())
+            .unwrap();
+    let mut server =
+        SecureSession::with_transport(name_server, &secret_server, transport_server.clone())
+            .unwrap();
+
+    let connect_request = client.generate_connect_request().expect("connect request");
+    let connect_reply = server.negotiate(&connect_request).expect("connect reply");
+    let key_proposed = client.negotiate(&connect_reply).expect("key proposed");
+    let key_accepted = server.negotiate(&key_proposed).expect("key accepted");
+    let key_confirmed = client.negotiate(&key_accepted).expect("key confirmed");
+    assert!(key_confirmed.is_empty());
+
+    assert!(transport_client.borrow().states.last() == Some(&SecureSessionState::Established));
+    assert!(
+        transport_server.lock().unwrap().states.last() == Some(&SecureSessionState::Established)
+    );
+
+    // The same transport state is accessible via the session.
+    assert!(Rc::ptr_eq(client.transport(), &transport_client));
+    server.transport_mut().lock().unwrap().states.clear();
+    assert!(transport_server.lock().unwrap().states.is_empty());
+}
+
+#[test]
+fn channel_transport_across_threads() {
+    let (secret_client, public_client) = gen_ec_key_pair().split();
+    let (secret_server, public_server) = gen_ec_key_pair().split();
+
+    let (mut transport_client, mut transport_server) = channel_pair();
+    transport_client.add_public_key("server", public_server);
+    transport_server.add_public_key("client", public_client);
+
+    let server = std::thread::spawn(move || {
+        let mut server =
+            SecureSession::with_transport("server", &secret_server, transport_server).unwrap();
+        while !server.is_established() {
+            server.negotiate_transport().expect("server negotiation");
+        }
+        let message = server.receive(1024).expect("receive message");
+        server.send(&message).expect("send reply");
+    });
+
+    let mut client =
+        SecureSession::with_transport("client", &secret_client, transport_client).unwrap();
+    client.connect().expect("client-side connection");
+    while !client.is_established() {
+        client.negotiate_transport().expect("client negotiation");
+    }
+    client
+        .send(b"echo message please ignore")
+        .expect("send message");
+    assert_eq!(
+        client.receive(1024).expect("receive reply"),
+        b"echo message please ignore"
+    );
+
+    server.join().unwrap();
+}
+
+#[test]
+fn transport_error_source() {
+    let (secret_client, _) = gen_ec_key_pair().split();
+    let (transport_client, transport_server) = channel_pair();
+    drop(transport_server);
+
+    let mut client =
+        SecureSession::with_transport("client", &secret_client, transport_client).unwrap();
+    let error = client.connect().expect_err("peer is gone");
+    assert_eq!(error.kind(), ErrorKind::SessionTransportError);
+
+    let source = error.source().expect("transport error");
+    let source = source.downcast_ref::<io::Error>().expect("I/O error");
+    assert_eq!(source.kind(), io::ErrorKind::BrokenPipe);
+
+    let error: io::Error = error.into();
+    assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn unwrap_message() {
+    let (secret_client, public_client) = gen_ec_key_pair().split();
+    let (secret_server, public_server) = gen_ec_key_pair().split();
+    let (name_client, name_server) = ("client", "server");
+
+    let mut key_map = StaticKeyResolver::new();
+    key_map.insert(name_client, public_client);
+    key_map.insert(name_server, public_server);
+
+    let mut client =
+        SecureSession::with_transport(name_client, &secret_client, key_map.clone()).unwrap();
+    let mut server =
+        SecureSession::with_transport(name_server, &secret_server, key_map.clone()).unwrap();
+
+    // Bounce messages between the peers until both of them have nothing to say.
+    let mut message = client.generate_connect_request().expect("connect request");
+    let mut peers = [&mut server, &mut client];
+    loop {
+        match peers[0].unwrap_message(&message).expect("negotiation") {
+            Unwrapped::SendToPeer(reply) => message = reply,
+            Unwrapped::Established => break,
+            Unwrapped::Data(_) => panic!("unexpected data during negotiation"),
+        }
+        peers.swap(0, 1);
+    }
+
+    assert!(client.is_established());
+    assert!(server.is_established());
+
+    let wrapped = client.wrap(b"test message please ignore").unwrap();
+    assert_eq!(
+        server.unwrap_message(&wrapped),
+        Ok(Unwrapped::Data(b"test message please ignore".to_vec()))
+    );
+}
+
+#[test]
+fn registry_routing_and_limits() {
+    let (secret_client, public_client) = gen_ec_key_pair().split();
+    let (secret_server, public_server) = gen_ec_key_pair().split();
+
+    let mut key_map = StaticKeyResolver::new();
+    key_map.insert("client", public_client);
+    key_map.insert("server", public_server);
+
+    let new_client =
+        || SecureSession::with_transport("client", &secret_client, key_map.clone()).unwrap();
+    let new_server = || SecureSession::with_transport("server", &secret_server, key_map.clone());
+
+    let mut registry = SecureSessionRegistry::new(1, Duration::from_secs(3600));
+
+    // Garbage does not occupy registry slots.
+    let error = registry
+        .unwrap_message(1, b"garbage", new_server)
+        .expect_err("garbage");
+    assert_ne!(error.kind(), ErrorKind::SessionLimitExceeded);
+    assert!(registry.is_empty());
+
+    let mut client = new_client();
+    let mut message = client.generate_connect_request().unwrap();
+    while !client.is_established() {
+        match registry.unwrap_message(1, &message, new_server).unwrap() {
+            Unwrapped::SendToPeer(reply) => message = client.negotiate(&reply).unwrap(),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+    assert!(client.is_established());
+    assert_eq!(registry.len(), 1);
+
+    let wrapped = client.wrap(b"test message please ignore").unwrap();
+    let unwrapped = registry.unwrap_message(1, &wrapped, new_server).unwrap();
+    assert_eq!(
+        unwrapped,
+        Unwrapped::Data(b"test message please ignore".to_vec())
+    );
+
+    // The registry is full, new peers are not accepted.
+    let mut other_client = new_client();
+    let request = other_client.generate_connect_request().unwrap();
+    let error = registry
+        .unwrap_message(2, &request, new_server)
+        .expect_err("session limit");
+    assert_eq!(error.kind(), ErrorKind::SessionLimitExceeded);
+
+    // But they are after the old session is gone.
+    assert!(registry.remove(&1).is_some());
+    registry.unwrap_message(2, &request, new_server).unwrap();
+    assert!(registry.contains_key(&2));
+}
+
+#[test]
+fn registry_expires_idle_sessions() {
+    let (secret, _) = gen_ec_key_pair().split();
+    let key_map = StaticKeyResolver::new();
+    let new_session = || SecureSession::with_transport("server", &secret, key_map.clone()).unwrap();
+
+    let mut registry = SecureSessionRegistry::new(1, Duration::from_secs(0));
+    registry.insert("first", new_session()).unwrap();
+    assert!(registry.get_mut(&"first").is_some());
+
+    // Zero timeout: everything is idle. The old session is expired to make room.
+    registry.insert("second", new_session()).unwrap();
+    assert!(!registry.contains_key(&"first"));
+    assert!(registry.contains_key(&"second"));
+
+    assert_eq!(registry.expire_idle(), 1);
+    assert!(registry.is_empty());
+}
+
+#[test]
+fn static_key_resolver() {
+    let (_, public_alice) = gen_ec_key_pair().split();
+    let (_, public_bob) = gen_ec_key_pair().split();
+
+    let mut resolver = StaticKeyResolver::new();
+    resolver.insert("alice", public_alice.clone());
+    assert_eq!(resolver.resolve("alice"), Some(public_alice.clone()));
+    assert_eq!(resolver.resolve("bob"), None);
+
+    resolver.set_default_key(Some(public_bob.clone()));
+    assert_eq!(resolver.resolve("alice"), Some(public_alice.clone()));
+    assert_eq!(resolver.resolve("bob"), Some(public_bob.clone()));
+
+    resolver.set_default_key(None);
+    assert_eq!(resolver.remove("alice"), Some(public_alice));
+    assert_eq!(resolver.resolve("alice"), None);
+}
+
+#[test]
+fn framing() {
+    let mut stream = Vec::new();
+    write_frame(&mut stream, b"").unwrap();
+    write_frame(&mut stream, b"test message please ignore").unwrap();
+    assert_eq!(stream.len(), 2 * FRAME_HEADER_SIZE + 26);
+
+    let mut reader = &stream[..];
+    assert_eq!(read_frame(&mut reader).unwrap(), b"");
+    assert_eq!(
+        read_frame(&mut reader).unwrap(),
+        b"test message please ignore"
+    );
+    let error = read_frame(&mut reader).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+    let truncated = &stream[FRAME_HEADER_SIZE..stream.len() - 1];
+    let error = read_frame(&mut &truncated[..]).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+    let oversized = vec![0; MAX_FRAME_SIZE + 1];
+    let error = write_frame(&mut Vec::new(), &oversized).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+    let error = read_frame(&mut &[0xFF, 0xFF, 0xFF, 0xFF][..]).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn negotiation_timeout() {
+    let (secret_client, public_client) = gen_ec_key_pair().split();
+    let (secret_server, public_server) = gen_ec_key_pair().split();
+
+    let mut key_map = StaticKeyResolver::new();
+    key_map.insert("client", public_client);
+    key_map.insert("server", public_server);
+
+    let mut client =
+        SecureSession::with_transport("client", &secret_client, key_map.clone()).unwrap();
+    let mut server =
+        SecureSession::with_transport("server", &secret_server, key_map.clone()).unwrap();
+    let mut stalled =
+        SecureSession::with_transport("server", &secret_server, key_map.clone()).unwrap();
+
+    let connect_request = client.generate_connect_request().unwrap();
+
+    // Zero timeout expires immediately.
+    stalled.set_negotiation_timeout(Some(Duration::from_secs(0)));
+    assert!(stalled.is_negotiation_expired());
+    let error = stalled.negotiate(&connect_request).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SessionHandshakeTimeout);
+
+    let connect_reply = server.negotiate(&connect_request).unwrap();
+    let key_proposed = client.negotiate(&connect_reply).unwrap();
+    let key_accepted = server.negotiate(&key_proposed).unwrap();
+    client.negotiate(&key_accepted).unwrap();
+
+    // Established sessions are not affected by the timeout.
+    server.set_negotiation_timeout(Some(Duration::from_secs(0)));
+    assert!(!server.is_negotiation_expired());
+    let wrapped = client.wrap(b"test message please ignore").unwrap();
+    assert_eq!(
+        server.unwrap_message(&wrapped).unwrap(),
+        Unwrapped::Data(b"test message please ignore".to_vec())
+    );
+}
+
+#[test]
+fn wrap_into_reuses_buffers() {
+    let (secret_client, public_client) = gen_ec_key_pair().split();
+    let (secret_server, public_server) = gen_ec_key_pair().split();
+
+    let mut key_map = StaticKeyResolver::new();
+    key_map.insert("client", public_client);
+    key_map.insert("server", public_server);
+
+    let mut client =
+        SecureSession::with_transport("client", &secret_client, key_map.clone()).unwrap();
+    let mut server =
+        SecureSession::with_transport("server", &secret_server, key_map.clone()).unwrap();
+
+    let connect_request = client.generate_connect_request().unwrap();
+    let connect_reply = server.negotiate(&connect_request).unwrap();
+    let key_proposed = client.negotiate(&connect_reply).unwrap();
+    let key_accepted = server.negotiate(&key_proposed).unwrap();
+    client.negotiate(&key_accepted).unwrap();
+
+    let mut wrapped = Vec::new();
+    let mut unwrapped = Vec::new();
+
+    client.wrap_into(b"first message", &mut wrapped).unwrap();
+    server.unwrap_into(&wrapped, &mut unwrapped).unwrap();
+    assert_eq!(unwrapped, b"first message");
+
+    // Buffers are overwritten with new data, not appended to.
+    let capacity = wrapped.capacity();
+    client.wrap_into(b"second", &mut wrapped).unwrap();
+    server.unwrap_into(&wrapped, &mut unwrapped).unwrap();
+    assert_eq!(unwrapped, b"second");
+    assert_eq!(wrapped.capacity(), capacity);
+
+    // Buffer is empty after an error.
+    let error = server.unwrap_into(b"garbage", &mut unwrapped);
+    assert!(error.is_err());
+    assert!(unwrapped.is_empty());
+}
+
+#[test]
+fn tcp_transport() {
+    let (secret_client, public_client) = gen_ec_key_pair().split();
+    let (secret_server, public_server) = gen_ec_key_pair().split();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut keys = StaticKeyResolver::new();
+        keys.insert("client", public_client);
+
+        let transport = TcpTransport::new(stream, keys);
+        let mut server =
+            SecureSession::with_transport("server", &secret_server, transport).unwrap();
+        while !server.is_established() {
+            server.negotiate_transport().expect("server negotiation");
+        }
+        let message = server.receive(1024).expect("receive message");
+        server.send(&message).expect("send reply");
+    });
+
+    let mut keys = StaticKeyResolver::new();
+    keys.insert("server", public_server);
+
+    let transport = TcpTransport::new(TcpStream::connect(address).unwrap(), keys);
+    let mut client = SecureSession::with_transport("client", &secret_client, transport).unwrap();
+    client.connect().expect("client-side connection");
+    while !client.is_established() {
+        client.negotiate_transport().expect("client negotiation");
+    }
+    client
+        .send(b"echo message please ignore")
+        .expect("send message");
+    assert_eq!(
+        client.receive(1024).expect("receive reply"),
+        b"echo message please ignore"
+    );
+
+    server.join().unwrap();
+}
+
+fn connected_streams() -> (
+    SecureSessionStream<TcpStream, StaticKeyResolver>,
+    SecureSessionStream<TcpStream, StaticKeyResolver>,
+) {
+    let (secret_client, public_client) = gen_ec_key_pair().split();
+    let (secret_server, public_server) = gen_ec_key_pair().split();
+
+    let mut keys = StaticKeyResolver::new();
+    keys.insert("client", public_client);
+    keys.insert("server", public_server);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let stream_client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (stream_server, _) = listener.accept().unwrap();
+
+    let session = SecureSession::with_transport("server", &secret_server, keys.clone()).unwrap();
+    let mut server = SecureSessionStream::new(session, stream_server);
+    let server = std::thread::spawn(move || {
+        server.accept().expect("server connection");
+        server
+    });
+
+    let session = SecureSession::with_transport("client", &secret_client, keys).unwrap();
+    let mut client = SecureSessionStream::new(session, stream_client);
+    client.connect().expect("client connection");
+
+    (client, server.join().unwrap())
+}
+
+#[test]
+fn session_stream() {
+    let (mut client, mut server) = connected_streams();
+
+    assert!(client.session().is_established());
+    assert!(server.session().is_established());
+
+    client.send(b"ping").expect("send ping");
+    client.send(b"").expect("send empty message");
+    assert_eq!(
+        server.receive().expect("receive ping"),
+        Some(b"ping".to_vec())
+    );
+    assert_eq!(server.receive().expect("receive empty"), Some(Vec::new()));
+    server.send(b"pong").expect("send pong");
+    assert_eq!(
+        client.receive().expect("receive pong"),
+        Some(b"pong".to_vec())
+    );
+}
+
+#[test]
+fn session_stream_shutdown() {
+    let (mut client, mut server) = connected_streams();
+
+    client.send(b"last message").expect("send message");
+    client.shutdown().expect("client shutdown");
+    client.shutdown().expect("repeated shutdown");
+
+    let error = client.send(b"one more").expect_err("send after shutdown");
+    assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+
+    assert_eq!(
+        server.receive().expect("receive message"),
+        Some(b"last message".to_vec())
+    );
+    assert_eq!(server.receive().expect("receive close"), None);
+    assert_eq!(server.receive().expect("receive close again"), None);
+
+    // Shutdown closes only one direction of the connection.
+    server.send(b"reply").expect("send reply");
+    server.shutdown().expect("server shutdown");
+    assert_eq!(
+        client.receive().expect("receive reply"),
+        Some(b"reply".to_vec())
+    );
+    assert_eq!(client.receive().expect("receive close"), None);
+}
+
+#[test]
+fn session_stream_truncated() {
+    let (mut client, mut server) = connected_streams();
+
+    client.send(b"message").expect("send message");
+    drop(client);
+
+    assert_eq!(
+        server.receive().expect("receive message"),
+        Some(b"message".to_vec())
+    );
+    let error = server.receive().expect_err("truncated stream");
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn session_stream_truncated_frame() {
+    let (client, mut server) = connected_streams();
+
+    let (_, mut stream) = client.into_inner();
+    stream.write_all(&[0x10, 0x00, 0x00, 0x00, 0x01]).unwrap();
+    drop(stream);
+
+    let error = server.receive().expect_err("truncated stream");
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn session_stream_unknown_record() {
+    let (client, mut server) = connected_streams();
+
+    let (mut session, mut stream) = client.into_inner();
+    let wrapped = session.wrap(b"\x07").unwrap();
+    write_frame(&mut stream, &wrapped).unwrap();
+
+    let error = server.receive().expect_err("unknown record type");
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+}
+
+fn is_timeout(error: &io::Error) -> bool {
+    let kind = error.kind();
+    kind == io::ErrorKind::WouldBlock || kind == io::ErrorKind::TimedOut
+}
+
+#[test]
+fn session_stream_keep_alive() {
+    let (mut client, mut server) = connected_streams();
+
+    let server = std::thread::spawn(move || {
+        server.set_keep_alive(Some(Duration::from_millis(10)));
+        let started = Instant::now();
+        while started.elapsed() < Duration::from_millis(500) {
+            server.heartbeat().expect("server heartbeat");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        server.send(b"still alive").expect("send message");
+        server.shutdown().expect("server shutdown");
+    });
+
+    client.set_peer_timeout(Some(Duration::from_millis(200)));
+    client
+        .get_ref()
+        .set_read_timeout(Some(Duration::from_millis(20)))
+        .unwrap();
+
+    let mut messages = Vec::new();
+    loop {
+        match client.receive() {
+            Ok(Some(message)) => messages.push(message),
+            Ok(None) => break,
+            Err(ref e) if is_timeout(e) => client.heartbeat().expect("peer is alive"),
+            Err(e) => panic!("receive failed: {}", e),
+        }
+    }
+    assert_eq!(messages, vec![b"still alive".to_vec()]);
+
+    server.join().unwrap();
+}
+
+#[test]
+fn session_stream_dead_peer() {
+    let (mut client, _server) = connected_streams();
+
+    client.set_peer_timeout(Some(Duration::from_millis(100)));
+    client
+        .get_ref()
+        .set_read_timeout(Some(Duration::from_millis(20)))
+        .unwrap();
+
+    let started = Instant::now();
+    let error = loop {
+        assert!(started.elapsed() < Duration::from_secs(5));
+        match client.receive() {
+            Ok(message) => panic!("unexpected message: {:?}", message),
+            Err(ref e) if is_timeout(e) => {}
+            Err(e) => panic!("receive failed: {}", e),
+        }
+        if let Err(e) = client.heartbeat() {
+            break e;
+        }
+    };
+    assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    assert!(started.elapsed() >= Duration::from_millis(100));
+}
+
+#[test]
+fn session_stream_coalescing() {
+    let (mut client, mut server) = connected_streams();
+
+    server
+        .get_ref()
+        .set_read_timeout(Some(Duration::from_millis(50)))
+        .unwrap();
+
+    // Small messages are held until flushed.
+    client.set_write_buffer(1024);
+    client.send(b"first").expect("send first");
+    client.send(b"second").expect("send second");
+    let error = server.receive().expect_err("nothing sent yet");
+    assert!(is_timeout(&error));
+
+    client.flush().expect("flush");
+    assert_eq!(server.receive().unwrap(), Some(b"first".to_vec()));
+    assert_eq!(server.receive().unwrap(), Some(b"second".to_vec()));
+
+    // Full buffer is written out right away.
+    client.send(vec![0xAA; 1024]).expect("send large message");
+    assert_eq!(server.receive().unwrap(), Some(vec![0xAA; 1024]));
+
+    // Messages are written out once they have waited for long enough.
+    client.set_write_delay(Some(Duration::from_millis(10)));
+    client.send(b"delayed").expect("send delayed");
+    let error = server.receive().expect_err("nothing sent yet");
+    assert!(is_timeout(&error));
+    client.heartbeat().expect("heartbeat");
+    assert_eq!(server.receive().unwrap(), Some(b"delayed".to_vec()));
+
+    // Shutdown writes out everything.
+    client.send(b"last").expect("send last");
+    client.shutdown().expect("shutdown");
+    assert_eq!(server.receive().unwrap(), Some(b"last".to_vec()));
+    assert_eq!(server.receive().unwrap(), None);
+}
+
+#[test]
+fn session_stream_split() {
+    let (client, mut server) = connected_streams();
+    let (mut client_reader, mut client_writer) = client.into_split();
+
+    // Echo server, it shuts down when the client does.
+    let server = std::thread::spawn(move || {
+        while let Some(message) = server.receive().expect("receive message") {
+            server.send(&message).expect("send reply");
+        }
+        server.shutdown().expect("server shutdown");
+    });
+
+    let receiver = std::thread::spawn(move || {
+        let mut replies = Vec::new();
+        while let Some(reply) = client_reader.receive().expect("receive reply") {
+            replies.push(reply);
+        }
+        replies
+    });
+
+    let messages: Vec<Vec<u8>> = (0..100u8).map(|i| vec![i; i as usize]).collect();
+    for message in &messages {
+        client_writer.send(message).expect("send message");
+    }
+    client_writer.shutdown().expect("client shutdown");
+
+    assert_eq!(receiver.join().unwrap(), messages);
+    server.join().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn unix_transport() {
+    use std::os::unix::net::UnixStream;
+    use themis::secure_session::UnixTransport;
+
+    let (secret_client, public_client) = gen_ec_key_pair().split();
+    let (secret_server, public_server) = gen_ec_key_pair().split();
+    let (stream_client, stream_server) = UnixStream::pair().unwrap();
+
+    let mut keys = StaticKeyResolver::new();
+    keys.insert("client", public_client);
+    keys.insert("server", public_server);
+
+    let transport = UnixTransport::new(stream_client, keys.clone());
+    let mut client = SecureSession::with_transport("client", &secret_client, transport).unwrap();
+    let transport = UnixTransport::new(stream_server, keys);
+    let mut server = SecureSession::with_transport("server", &secret_server, transport).unwrap();
+
+    client.connect().expect("client-side connection");
+    server.negotiate_transport().expect("connect reply");
+    client.negotiate_transport().expect("key proposed");
+    server.negotiate_transport().expect("key accepted");
+    client.negotiate_transport().expect("key confirmed");
+
+    assert!(client.is_established());
+    assert!(server.is_established());
+
+    client
+        .send(b"test message please ignore")
+        .expect("send message");
+    assert_eq!(
+        server.receive(1024).expect("receive message"),
+        b"test message please ignore"
+    );
+}
+
+#[cfg(all(unix, feature = "tungstenite"))]
+#[test]
+fn websocket_transport() {
+    use std::os::unix::net::UnixStream;
+    use themis::secure_session::WebSocketTransport;
+    use tungstenite::protocol::{Role, WebSocket};
+
+    let (secret_client, public_client) = gen_ec_key_pair().split();
+    let (secret_server, public_server) = gen_ec_key_pair().split();
+    let (stream_client, stream_server) = UnixStream::pair().unwrap();
+
+    let mut keys = StaticKeyResolver::new();
+    keys.insert("client", public_client);
+    keys.insert("server", public_server);
+
+    // Pretend that the WebSocket handshake has already happened.
+    let socket = WebSocket::from_raw_socket(stream_client, Role::Client, None);
+    let transport = WebSocketTransport::new(socket, keys.clone());
+    let mut client = SecureSession::with_transport("client", &secret_client, transport).unwrap();
+    let socket = WebSocket::from_raw_socket(stream_server, Role::Server, None);
+    let transport = WebSocketTransport::new(socket, keys);
+    let mut server = SecureSession::with_transport("server", &secret_server, transport).unwrap();
+
+    client.connect().expect("client-side connection");
+    server.negotiate_transport().expect("connect reply");
+    client.negotiate_transport().expect("key proposed");
+    server.negotiate_transport().expect("key accepted");
+    client.negotiate_transport().expect("key confirmed");
+
+    assert!(client.is_established());
+    assert!(server.is_established());
+
+    client
+        .send(b"test message please ignore")
+        .expect("send message");
+    assert_eq!(
+        server.receive(1024).expect("receive message"),
+        b"test message please ignore"
+    );
+}
+
+#[cfg(feature = "futures")]
+mod async_pipe {
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+    use futures::executor::block_on;
+    use futures::future::join;
+    use futures::io::{AsyncRead, AsyncWrite};
+    use futures::stream::IntoAsyncRead;
+    use futures::TryStreamExt;
+    use themis::keygen::gen_ec_key_pair;
+    use themis::secure_session::{AsyncSecureSessionStream, SecureSession, StaticKeyResolver};
+
+    // In-memory async byte stream, one direction of it.
+    pub struct Pipe {
+        reader: IntoAsyncRead<UnboundedReceiver<io::Result<Vec<u8>>>>,
+        writer: UnboundedSender<io::Result<Vec<u8>>>,
+    }
+
+    impl AsyncRead for Pipe {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.reader).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for Pipe {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let sent = self.writer.unbounded_send(Ok(buf.to_vec()));
+            Poll::Ready(
+                sent.map(|_| buf.len())
+                    .map_err(|_| io::ErrorKind::BrokenPipe.into()),
+            )
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    pub type Stream = AsyncSecureSessionStream<Pipe, StaticKeyResolver>;
+
+    pub fn connected_streams() -> (Stream, Stream) {
+        let (client_writer, server_reader) = unbounded();
+        let (server_writer, client_reader) = unbounded();
+        let client_stream = Pipe {
+            reader: client_reader.into_async_read(),
+            writer: client_writer,
+        };
+        let server_stream = Pipe {
+            reader: server_reader.into_async_read(),
+            writer: server_writer,
+        };
+
+        let (secret_client, public_client) = gen_ec_key_pair().split();
+        let (secret_server, public_server) = gen_ec_key_pair().split();
+
+        let mut keys = StaticKeyResolver::new();
+        keys.insert("client", public_client);
+        keys.insert("server", public_server);
+
+        let client = SecureSession::with_transport("client", &secret_client, keys.clone()).unwrap();
+        let server = SecureSession::with_transport("server", &secret_server, keys).unwrap();
+        let mut client = AsyncSecureSessionStream::new(client, client_stream);
+        let mut server = AsyncSecureSessionStream::new(server, server_stream);
+
+        let (client_result, server_result) = block_on(join(client.connect(), server.accept()));
+        client_result.expect("client connection");
+        server_result.expect("server connection");
+
+        (client, server)
+    }
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn async_stream() {
+    use futures::executor::block_on;
+    use futures::future::FutureExt;
+
+    let (mut client, mut server) = async_pipe::connected_streams();
+
+    assert!(client.session().is_established());
+    assert!(server.session().is_established());
+
+    block_on(async {
+        client.send(b"ping").await.expect("send ping");
+        assert_eq!(
+            server.receive().await.expect("receive ping"),
+            Some(b"ping".to_vec())
+        );
+        server.send(b"pong").await.expect("send pong");
+        assert_eq!(
+            client.receive().await.expect("receive pong"),
+            Some(b"pong".to_vec())
+        );
+
+        client.shutdown().await.expect("client shutdown");
+        assert!(client.send(b"ping").await.is_err());
+        assert_eq!(server.receive().await.expect("receive close"), None);
+        assert_eq!(server.receive().await.expect("receive close again"), None);
+
+        // Peer can still send messages after receiving shutdown.
+        server.send(b"bye").await.expect("send bye");
+        assert_eq!(
+            client.receive().await.expect("receive bye"),
+            Some(b"bye".to_vec())
+        );
+    });
+
+    block_on(async {
+        // Keep-alive messages are not returned to the application.
+        server.set_keep_alive(Some(Duration::from_millis(0)));
+        server.heartbeat().await.expect("send keep-alive");
+        server
+            .send(b"after keep-alive")
+            .await
+            .expect("send message");
+        assert_eq!(
+            client.receive().await.expect("receive message"),
+            Some(b"after keep-alive".to_vec())
+        );
+
+        // Peer which does not send anything is considered dead.
+        client.set_peer_timeout(Some(Duration::from_millis(10)));
+        client.heartbeat().await.expect("peer is alive");
+        std::thread::sleep(Duration::from_millis(20));
+        let error = client.heartbeat().await.expect_err("peer is dead");
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    });
+
+    block_on(async {
+        // Coalesced messages are not sent until flushed.
+        server.set_write_buffer(1024);
+        server.send(b"first").await.expect("send first");
+        server.send(b"second").await.expect("send second");
+        assert!(client.receive().now_or_never().is_none());
+        server.flush().await.expect("flush");
+        assert_eq!(client.receive().await.unwrap(), Some(b"first".to_vec()));
+        assert_eq!(client.receive().await.unwrap(), Some(b"second".to_vec()));
+    });
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn async_stream_split() {
+    use futures::executor::block_on;
+    use futures::future::join;
+
+    let (client, server) = async_pipe::connected_streams();
+
+    // Halves can be used concurrently.
+    let (mut server_reader, mut server_writer) = server.into_split();
+    let (mut client_reader, mut client_writer) = client.into_split();
+    let receive = async {
+        assert_eq!(
+            server_reader.receive().await.expect("receive request"),
+            Some(b"request".to_vec())
+        );
+        assert_eq!(server_reader.receive().await.expect("receive close"), None);
+        server_writer.send(b"reply").await.expect("send reply");
+        server_writer.shutdown().await.expect("server shutdown");
+        assert_eq!(
+            client_reader.receive().await.expect("receive reply"),
+            Some(b"reply".to_vec())
+        );
+        assert_eq!(client_reader.receive().await.expect("receive close"), None);
+    };
+    let send = async {
+        client_writer.send(b"request").await.expect("send request");
+        client_writer.shutdown().await.expect("client shutdown");
+    };
+    block_on(join(receive, send));
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn async_stream_truncated() {
+    use futures::executor::block_on;
+
+    let (mut client, server) = async_pipe::connected_streams();
+
+    // Stream ending without shutdown is reported as truncation.
+    drop(server);
+    let error = block_on(client.receive()).expect_err("truncated stream");
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn session_with_key_handle() {
+    let (secret_rsa, _) = gen_rsa_key_pair().split();
+    let (secret_ec, _) = gen_ec_key_pair().split();
+    let key_map = StaticKeyResolver::new();
+
+    let error = SecureSession::with_key_handle("client", &secret_rsa, key_map.clone())
+        .err()
+        .expect("RSA key");
+    assert_eq!(error.kind(), ErrorKind::NotSupported);
+
+    SecureSession::with_key_handle("client", &secret_ec, key_map.clone()).expect("ECDSA key");
+}
@@ -0,0 +1,30 @@
+// Copyright 2018 (c) rust-themis developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use themis::version;
+
+#[test]
+fn soter_version() {
+    assert!(version::soter_version().starts_with("soter "));
+}
+
+#[cfg(not(feature = "soter-only"))]
+#[test]
+fn themis_version() {
+    let themis = version::themis_version();
+    assert!(themis.starts_with("themis "));
+    assert!(themis.contains(version::soter_version()));
+    // Subsequent calls return the same string.
+    assert_eq!(version::themis_version(), themis);
+}